@@ -0,0 +1,45 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+include!("src/util/hash.rs");
+
+// Embeds a fingerprint of the sources and the git revision into the binary, see src/util/buildinfo.rs.
+fn main() {
+    let mut files = vec![];
+    collect_files(Path::new("src"), &mut files);
+    files.sort();
+
+    // FNV-1a over the paths and contents, so renaming a file changes the fingerprint as well.
+    let hash = fnv1a(files.iter().flat_map(|file| {
+        let path: Vec<u8> = file.to_string_lossy().bytes().collect();
+        path.into_iter().chain(fs::read(file).unwrap_or_default())
+    }));
+    println!("cargo:rustc-env=AOC_SOURCE_HASH={:016x}", hash);
+
+    let revision = Command::new("git").args(["rev-parse", "--short", "HEAD"]).output().ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or("unknown".to_string());
+    println!("cargo:rustc-env=AOC_GIT_REVISION={}", revision);
+
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    if let Ok(head) = fs::read_to_string(".git/HEAD") {
+        if let Some(reference) = head.trim().strip_prefix("ref: ") {
+            println!("cargo:rerun-if-changed=.git/{}", reference);
+        }
+    }
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use std::time::{Duration, Instant};
+use crate::days::Puzzle;
+use crate::util::answer::Answer;
+use crate::util::panic::catch_panic;
+use crate::util::results::RunRecord;
+
+/// The durations of a puzzle run a number of times on the same input.
+#[derive(Debug)]
+pub struct Benchmark {
+    pub day: i32,
+    pub part: usize,
+    /// The answer of the runs, or why they failed. Runs giving different answers count as a failure as well.
+    pub answer: Result<Answer, String>,
+    pub runs: usize,
+    pub min: Duration,
+    pub avg: Duration,
+    pub max: Duration,
+}
+
+impl Benchmark {
+    /// Runs the puzzle `runs` times (at least once), stopping at the first failure.
+    pub fn run(day: i32, part: usize, puzzle: Puzzle, input: &str, runs: usize) -> Self {
+        let mut durations = vec![];
+        let mut answer: Option<Result<Answer, String>> = None;
+        for _ in 0..runs.max(1) {
+            let start = Instant::now();
+            let result = catch_panic(|| puzzle(input)).map_err(|e| e.to_string()).and_then(|r| r.map_err(String::from));
+            durations.push(start.elapsed());
+
+            answer = match (answer, result) {
+                (Some(Ok(previous)), Ok(current)) if previous != current =>
+                    Some(Err(format!("Answers differ between runs: {} and {}", previous, current))),
+                (_, result) => Some(result),
+            };
+            if answer.as_ref().is_some_and(|a| a.is_err()) {
+                break;
+            }
+        }
+
+        let total: Duration = durations.iter().sum();
+        Benchmark {
+            day,
+            part,
+            answer: answer.unwrap_or(Err("Not run".to_string())),
+            runs: durations.len(),
+            min: durations.iter().copied().min().unwrap_or_default(),
+            avg: total / durations.len() as u32,
+            max: durations.iter().copied().max().unwrap_or_default(),
+        }
+    }
+}
+
+/// The latest successful recorded run of the puzzle, to compare a benchmark with.
+fn latest_recorded(records: &[RunRecord], day: i32, part: usize) -> Option<Duration> {
+    records.iter()
+        .filter(|r| r.day == day && r.part == part && r.success)
+        .max_by_key(|r| r.timestamp)
+        .map(|r| r.duration)
+}
+
+fn millis(duration: Duration) -> String {
+    format!("{:.3}", duration.as_secs_f64() * 1000.0)
+}
+
+/// A table of the benchmarks, with the latest recorded run of every puzzle next to them.
+pub fn format_table(benchmarks: &[Benchmark], records: &[RunRecord]) -> String {
+    let mut table = format!("{:>5} | {:>4} | {:>4} | {:>10} | {:>10} | {:>10} | {:>13} | {}\n",
+                            "Day", "Part", "Runs", "Min (ms)", "Avg (ms)", "Max (ms)", "Recorded (ms)", "Answer");
+    for benchmark in benchmarks {
+        let recorded = latest_recorded(records, benchmark.day, benchmark.part).map(millis).unwrap_or("-".to_string());
+        let answer = match &benchmark.answer {
+            Ok(answer) if answer.to_string().contains('\n') => format!("({} lines)", answer.to_string().lines().count()),
+            Ok(answer) => answer.to_string(),
+            Err(e) => format!("FAILED: {}", e),
+        };
+        table += &format!("{:>5} | {:>4} | {:>4} | {:>10} | {:>10} | {:>10} | {:>13} | {}\n",
+                          benchmark.day, benchmark.part, benchmark.runs,
+                          millis(benchmark.min), millis(benchmark.avg), millis(benchmark.max), recorded, answer);
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use crate::bench::{Benchmark, format_table};
+    use crate::util::answer::Answer;
+    use crate::util::error::AocError;
+    use crate::util::results::RunRecord;
+
+    fn constant(_: &str) -> Result<Answer, AocError> {
+        Ok(42.into())
+    }
+
+    fn failing(input: &str) -> Result<Answer, AocError> {
+        Err(AocError::parse(format!("Cannot parse '{}'", input)))
+    }
+
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    fn changing(_: &str) -> Result<Answer, AocError> {
+        Ok(CALLS.fetch_add(1, Ordering::SeqCst).into())
+    }
+
+    #[test]
+    fn test_run() {
+        let benchmark = Benchmark::run(1, 2, constant, "", 3);
+        assert_eq!(Ok(Answer::from(42)), benchmark.answer);
+        assert_eq!(3, benchmark.runs);
+        assert!(benchmark.min <= benchmark.avg && benchmark.avg <= benchmark.max);
+
+        // Puzzles run at least once, and failures stop the benchmark.
+        assert_eq!(1, Benchmark::run(1, 1, constant, "", 0).runs);
+        let benchmark = Benchmark::run(1, 1, failing, "x", 5);
+        assert_eq!((Err("Cannot parse 'x'".to_string()), 1), (benchmark.answer, benchmark.runs));
+
+        let benchmark = Benchmark::run(1, 1, changing, "", 5);
+        assert!(benchmark.answer.is_err_and(|e| e.starts_with("Answers differ between runs")));
+        assert_eq!(2, benchmark.runs);
+    }
+
+    #[test]
+    fn test_format_table() {
+        let benchmarks = [
+            Benchmark { day: 1, part: 1, answer: Ok(24000.into()), runs: 2, min: Duration::from_micros(1500), avg: Duration::from_micros(2000), max: Duration::from_micros(2500) },
+            Benchmark { day: 1, part: 2, answer: Err("Oops".to_string()), runs: 1, min: Duration::ZERO, avg: Duration::ZERO, max: Duration::ZERO },
+        ];
+        let record = |timestamp, millis| RunRecord { day: 1, part: 1, timestamp, duration: Duration::from_millis(millis), success: true, build: None };
+        assert_eq!("  Day | Part | Runs |   Min (ms) |   Avg (ms) |   Max (ms) | Recorded (ms) | Answer
+    1 |    1 |    2 |      1.500 |      2.000 |      2.500 |         3.000 | 24000
+    1 |    2 |    1 |      0.000 |      0.000 |      0.000 |             - | FAILED: Oops
+", format_table(&benchmarks, &[record(2, 3), record(1, 5)]));
+    }
+}
@@ -1,88 +1,362 @@
+#[cfg(feature = "day01")]
 mod day01;
+#[cfg(feature = "day01")]
 use day01::DAY1;
+#[cfg(feature = "day02")]
 mod day02;
+#[cfg(feature = "day02")]
 use day02::DAY2;
+#[cfg(feature = "day03")]
 mod day03;
+#[cfg(feature = "day03")]
 use day03::DAY3;
+#[cfg(feature = "day04")]
 mod day04;
+#[cfg(feature = "day04")]
 use day04::DAY4;
+#[cfg(feature = "day05")]
 mod day05;
+#[cfg(feature = "day05")]
 use day05::DAY5;
+#[cfg(feature = "day06")]
 mod day06;
+#[cfg(feature = "day06")]
 use day06::DAY6;
+#[cfg(feature = "day07")]
 mod day07;
+#[cfg(feature = "day07")]
 use day07::DAY7;
+#[cfg(feature = "day08")]
 mod day08;
+#[cfg(feature = "day08")]
 use day08::DAY8;
+#[cfg(feature = "day09")]
 mod day09;
+#[cfg(feature = "day09")]
 use day09::DAY9;
+#[cfg(feature = "day10")]
 mod day10;
+#[cfg(feature = "day10")]
 use day10::DAY10;
+#[cfg(feature = "day11")]
 mod day11;
+#[cfg(feature = "day11")]
 use day11::DAY11;
+#[cfg(feature = "day12")]
 mod day12;
+#[cfg(feature = "day12")]
 use day12::DAY12;
+#[cfg(feature = "day13")]
 mod day13;
+#[cfg(feature = "day13")]
 use day13::DAY13;
+#[cfg(feature = "day14")]
 mod day14;
+#[cfg(feature = "day14")]
 use day14::DAY14;
+#[cfg(feature = "day15")]
 mod day15;
+#[cfg(feature = "day15")]
 use day15::DAY15;
+#[cfg(feature = "day16")]
 mod day16;
+#[cfg(feature = "day16")]
 use day16::DAY16;
+#[cfg(feature = "day17")]
 mod day17;
+#[cfg(feature = "day17")]
 use day17::DAY17;
+#[cfg(feature = "day18")]
 mod day18;
+#[cfg(feature = "day18")]
 use day18::DAY18;
+#[cfg(feature = "day19")]
 mod day19;
+#[cfg(feature = "day19")]
 use day19::DAY19;
+#[cfg(feature = "day20")]
 mod day20;
+#[cfg(feature = "day20")]
 use day20::DAY20;
+#[cfg(feature = "day21")]
 mod day21;
+#[cfg(feature = "day21")]
 use day21::DAY21;
+#[cfg(feature = "day22")]
 mod day22;
+#[cfg(feature = "day22")]
 use day22::DAY22;
+#[cfg(feature = "day23")]
 mod day23;
+#[cfg(feature = "day23")]
 use day23::DAY23;
+#[cfg(feature = "day24")]
 mod day24;
+#[cfg(feature = "day24")]
 use day24::DAY24;
+#[cfg(feature = "day25")]
 mod day25;
+#[cfg(feature = "day25")]
 use day25::DAY25;
 // « add day import »
 
+use std::fmt;
+use crate::util::answer::Answer;
+use crate::util::error::AocError;
+
+/// A puzzle solution, returning the answer for the given input. Anything else a puzzle prints (notes, verbose
+/// output) is extra; the runner prints the answer itself.
+pub type Puzzle = fn(input: &str) -> Result<Answer, AocError>;
+
+/// A check of a solution against a slower, more direct way to solve the input. Returns what was checked.
+pub type SelfTest = fn(input: &str) -> Result<String, AocError>;
+
+/// A day's solution, with answers of the type that fits the puzzle. `solve_part1` and `solve_part2` turn its parts
+/// into the `Puzzle`s of a `Day`, so answers are printed, recorded and compared the same way for every day.
+pub trait Solver: Default {
+    type Output: fmt::Display + Into<Answer>;
+
+    fn part1(&self, input: &str) -> Result<Self::Output, AocError>;
+    fn part2(&self, input: &str) -> Result<Self::Output, AocError>;
+    /// Only parses the input, to check it without solving anything (see `--dry-run`).
+    fn parse(&self, input: &str) -> Result<ParseSummary, AocError>;
+}
+
+pub fn solve_part1<S: Solver>(input: &str) -> Result<Answer, AocError> {
+    S::default().part1(input).map(Into::into)
+}
+
+pub fn solve_part2<S: Solver>(input: &str) -> Result<Answer, AocError> {
+    S::default().part2(input).map(Into::into)
+}
+
+pub fn parse_only<S: Solver>(input: &str) -> Result<ParseSummary, AocError> {
+    S::default().parse(input)
+}
+
+/// What a day's parser found in its input, as the number of each kind of element, e.g. "3 elves, 14 food items".
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct ParseSummary {
+    pub counts: Vec<(usize, &'static str)>
+}
+
+impl ParseSummary {
+    pub fn new() -> Self {
+        ParseSummary::default()
+    }
+
+    pub fn count(mut self, count: usize, what: &'static str) -> Self {
+        self.counts.push((count, what));
+        self
+    }
+}
+
+impl fmt::Display for ParseSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.counts.is_empty() {
+            return f.write_str("nothing counted");
+        }
+        let counts: Vec<_> = self.counts.iter().map(|(count, what)| format!("{} {}", count, what)).collect();
+        f.write_str(&counts.join(", "))
+    }
+}
+
 pub struct Day {
-    pub puzzle1: fn(input: &String),
-    pub puzzle2: fn(input: &String)
+    pub puzzle1: Puzzle,
+    pub puzzle2: Puzzle,
+    /// Parses the input without solving, for `--dry-run`.
+    pub parse: fn(input: &str) -> Result<ParseSummary, AocError>,
+    /// Puzzle title and a one-line summary, e.g. "Calorie Counting: find the elves carrying the most calories"
+    pub describe: &'static str,
+    /// Notes on the approach of the solution, printed by `--explain N`.
+    pub explain: Option<fn() -> String>,
+    /// Checks the solution for the input, run by `selftest`.
+    pub selftest: Option<SelfTest>,
+    /// Names of the solvers to choose from with `AOC_DAY<NN>_ALGORITHM` (see `config::Choice`), the default first.
+    /// Empty when there is only one.
+    pub algorithms: &'static [&'static str]
+}
+
+/// How a day is solved, rendered as the notes returned by a day's `explain`.
+pub struct Explanation {
+    pub approach: &'static str,
+    pub complexity: &'static str,
+    /// Other solvers available for the day, or approaches worth trying.
+    pub alternatives: &'static [&'static str]
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Approach:\n  {}", self.approach)?;
+        writeln!(f, "Complexity:\n  {}", self.complexity)?;
+        writeln!(f, "Alternatives:")?;
+        if self.alternatives.is_empty() {
+            writeln!(f, "  none")?;
+        }
+        for alternative in self.alternatives {
+            writeln!(f, "  - {}", alternative)?;
+        }
+        Ok(())
+    }
 }
 
+/// Days taking minutes (or what looks like forever) without optimizations, so they should be run in release builds.
+pub const SLOW_DAYS: [i32; 4] = [16, 19, 23, 24];
+
 pub fn get_day(day: i32) -> Result<Day, String> {
     match day {
+        #[cfg(feature = "day01")]
         1 => Ok(DAY1),
+        #[cfg(feature = "day02")]
         2 => Ok(DAY2),
+        #[cfg(feature = "day03")]
         3 => Ok(DAY3),
+        #[cfg(feature = "day04")]
         4 => Ok(DAY4),
+        #[cfg(feature = "day05")]
         5 => Ok(DAY5),
+        #[cfg(feature = "day06")]
         6 => Ok(DAY6),
+        #[cfg(feature = "day07")]
         7 => Ok(DAY7),
+        #[cfg(feature = "day08")]
         8 => Ok(DAY8),
+        #[cfg(feature = "day09")]
         9 => Ok(DAY9),
+        #[cfg(feature = "day10")]
         10 => Ok(DAY10),
+        #[cfg(feature = "day11")]
         11 => Ok(DAY11),
+        #[cfg(feature = "day12")]
         12 => Ok(DAY12),
+        #[cfg(feature = "day13")]
         13 => Ok(DAY13),
+        #[cfg(feature = "day14")]
         14 => Ok(DAY14),
+        #[cfg(feature = "day15")]
         15 => Ok(DAY15),
+        #[cfg(feature = "day16")]
         16 => Ok(DAY16),
+        #[cfg(feature = "day17")]
         17 => Ok(DAY17),
+        #[cfg(feature = "day18")]
         18 => Ok(DAY18),
+        #[cfg(feature = "day19")]
         19 => Ok(DAY19),
+        #[cfg(feature = "day20")]
         20 => Ok(DAY20),
+        #[cfg(feature = "day21")]
         21 => Ok(DAY21),
+        #[cfg(feature = "day22")]
         22 => Ok(DAY22),
+        #[cfg(feature = "day23")]
         23 => Ok(DAY23),
+        #[cfg(feature = "day24")]
         24 => Ok(DAY24),
+        #[cfg(feature = "day25")]
         25 => Ok(DAY25),
         // « add day match »
+        // Every day up to 25 is implemented, but can be left out of the build by disabling its feature.
+        #[allow(unreachable_patterns)]
+        1..=25 => Err(format!("Day {} is not compiled in, enable it with `--features day{:02}`", day, day)),
         _ => Err(format!("No implementation yet for day {}", day))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+    use crate::days::{get_day, Explanation, ParseSummary, SLOW_DAYS, Solver, solve_part1, solve_part2};
+    use crate::util::answer::Answer;
+    use crate::util::error::AocError;
+
+    #[test]
+    fn test_every_day_has_examples() {
+        for day in (1..=25).filter(|d| get_day(*d).is_ok()) {
+            for file in ["input.txt", "part1.expected"] {
+                let path = format!("examples/day{:02}/{}", day, file);
+                assert!(Path::new(&path).exists(), "Missing example file {}", path);
+            }
+        }
+    }
+
+    /// Puzzles whose solution does not handle their example: day 22 only folds the cube of the real input.
+    const UNSUPPORTED_EXAMPLES: [(i32, usize); 1] = [(22, 2)];
+
+    #[test]
+    fn test_examples_give_expected_answers() {
+        // The slow days take too long in a debug build, even for their examples.
+        for day_num in (1..=25).filter(|d| !cfg!(debug_assertions) || !SLOW_DAYS.contains(d)) {
+            let Ok(day) = get_day(day_num) else { continue };
+            let input = fs::read_to_string(format!("examples/day{:02}/input.txt", day_num)).unwrap();
+            for (part, puzzle) in [(1, day.puzzle1), (2, day.puzzle2)] {
+                let expected = fs::read_to_string(format!("examples/day{:02}/part{}.expected", day_num, part)).unwrap_or_default();
+                if expected.trim().is_empty() || UNSUPPORTED_EXAMPLES.contains(&(day_num, part)) {
+                    continue;
+                }
+                match puzzle(&input) {
+                    Ok(answer) => assert!(answer.matches(&expected), "Day {} puzzle {}: expected {}, got {}", day_num, part, expected.trim(), answer),
+                    Err(e) => panic!("Day {} puzzle {} failed: {}", day_num, part, e),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_days_not_compiled_in() {
+        for day in 1..=25 {
+            if let Err(e) = get_day(day) {
+                assert_eq!(format!("Day {} is not compiled in, enable it with `--features day{:02}`", day, day), e);
+            }
+        }
+        assert_eq!(Some("No implementation yet for day 26".to_string()), get_day(26).err());
+    }
+
+    #[test]
+    fn test_every_day_explains() {
+        for day in (1..=25).filter_map(|d| get_day(d).ok()) {
+            assert!(day.explain.is_some_and(|explain| !explain().trim().is_empty()), "Missing notes for '{}'", day.describe);
+        }
+    }
+
+    #[test]
+    fn test_explanation_display() {
+        let explanation = Explanation { approach: "Sort.", complexity: "O(n log n).", alternatives: &[] };
+        assert_eq!("Approach:\n  Sort.\nComplexity:\n  O(n log n).\nAlternatives:\n  none\n", explanation.to_string());
+
+        let explanation = Explanation { approach: "BFS.", complexity: "O(n).", alternatives: &["DFS.", "A*."] };
+        assert_eq!("Approach:\n  BFS.\nComplexity:\n  O(n).\nAlternatives:\n  - DFS.\n  - A*.\n", explanation.to_string());
+    }
+
+    #[derive(Default)]
+    struct Counter;
+
+    impl Solver for Counter {
+        type Output = usize;
+
+        fn part1(&self, input: &str) -> Result<Self::Output, AocError> {
+            Ok(input.lines().count())
+        }
+
+        fn part2(&self, input: &str) -> Result<Self::Output, AocError> {
+            Err(format!("Cannot count '{}'", input).into())
+        }
+
+        fn parse(&self, input: &str) -> Result<ParseSummary, AocError> {
+            Ok(ParseSummary::new().count(input.lines().count(), "lines"))
+        }
+    }
+
+    #[test]
+    fn test_solver() {
+        assert_eq!(Ok(Answer::from(2usize)), solve_part1::<Counter>("a\nb\n"));
+        assert_eq!(Err(AocError::Solve("Cannot count 'x'".to_string())), solve_part2::<Counter>("x"));
+    }
+
+    #[test]
+    fn test_parse_summary() {
+        assert_eq!("nothing counted", ParseSummary::new().to_string());
+        assert_eq!("3 elves, 14 food items", ParseSummary::new().count(3, "elves").count(14, "food items").to_string());
+    }
+}
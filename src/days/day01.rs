@@ -1,53 +1,70 @@
-use crate::days::Day;
-use crate::util::number::parse_i32;
+use std::env;
+use crate::days::{Day, Explanation, Solver, solve_part1, solve_part2, parse_only, ParseSummary};
+use crate::util::error::AocError;
+use crate::util::collection::CollectionExtension;
+use crate::util::input::parse_lines;
+use crate::util::number::{parse_i32, percentile};
+use crate::util::vis::Histogram;
 
 pub const DAY1: Day = Day {
-    puzzle1,
-    puzzle2
+    puzzle1: solve_part1::<Day01>,
+    puzzle2: solve_part2::<Day01>,
+    parse: parse_only::<Day01>,
+    describe: "Calorie Counting: find the elves carrying the most calories",
+    explain: Some(explain),
+    selftest: None,
+    algorithms: &[]
 };
 
-fn puzzle1(input: &String) {
-    let backpacks = parse_input(input).unwrap();
-
-    let result = find_most_calories(backpacks).unwrap();
-    println!("Most total calories carried: {}", result);
+fn explain() -> String {
+    Explanation {
+        approach: "Split the input on blank lines into backpacks, sum every backpack, and take the largest (or the three largest after sorting).",
+        complexity: "O(n log n) for the sort over the backpacks, linear otherwise.",
+        alternatives: &[]
+    }.to_string()
 }
-fn puzzle2(input: &String) {
-    let backpacks = parse_input(input).unwrap();
 
-    let result = get_top_three_calories(backpacks);
-    println!("Top three calories summed: {}", result);
-}
+#[derive(Default)]
+struct Day01;
 
-#[derive(Clone, Debug, Eq, PartialEq)]
-struct Backpack {
-    food_calories: Vec<i32>
-}
+impl Solver for Day01 {
+    type Output = i32;
 
-fn parse_input(input: &str) -> Result<Vec<Backpack>, String> {
-    let mut result: Vec<Backpack> = vec![];
-    let mut current: Vec<i32> = vec![];
-
-    for line in input.lines() {
-        if line.trim().is_empty() {
-            if !current.is_empty() {
-                result.push(Backpack { food_calories: current });
-                current = vec![];
-            }
-            continue;
+    fn part1(&self, input: &str) -> Result<Self::Output, AocError> {
+        let backpacks = parse_input(input)?;
+        if env::var("AOC_VERBOSE").is_ok() {
+            print!("{}", describe_distribution(&backpacks));
         }
 
-        match parse_i32(line) {
-            Ok(num) => current.push(num),
-            Err(e) => return Err(e)
-        }
+        let result = find_most_calories(backpacks).ok_or("No backpacks in the input")?;
+        Ok(result)
+    }
+
+    fn part2(&self, input: &str) -> Result<Self::Output, AocError> {
+        let backpacks = parse_input(input)?;
+
+        Ok(get_top_three_calories(backpacks))
     }
 
-    if !current.is_empty() {
-        result.push(Backpack { food_calories: current });
+    fn parse(&self, input: &str) -> Result<ParseSummary, AocError> {
+        let backpacks = parse_input(input)?;
+        Ok(ParseSummary::new()
+            .count(backpacks.len(), "elves")
+            .count(backpacks.iter().map(|b| b.food_calories.len()).sum(), "food items"))
     }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Backpack {
+    food_calories: Vec<i32>
+}
 
-    Ok(result)
+fn parse_input(input: &str) -> Result<Vec<Backpack>, AocError> {
+    // Blank lines (None) separate the backpacks.
+    let lines = parse_lines(input, |line| if line.trim().is_empty() { Ok(None) } else { parse_i32(line).map(Some) })?;
+    Ok(lines.split_when(|line| line.is_none()).into_iter()
+        .map(|calories| Backpack { food_calories: calories.into_iter().flatten().collect() })
+        .collect())
 }
 
 fn find_most_calories(backpacks: Vec<Backpack>) -> Option<i32> {
@@ -61,25 +78,20 @@ fn get_top_three_calories(backpacks: Vec<Backpack>) -> i32 {
     totals.into_iter().take(3).sum()
 }
 
+/// A histogram of the calories carried per elf, with some percentiles.
+fn describe_distribution(backpacks: &[Backpack]) -> String {
+    let totals: Vec<i64> = backpacks.iter().map(|bp| bp.food_calories.iter().map(|c| *c as i64).sum()).collect();
+    let (Some(p50), Some(p90), Some(max)) = (percentile(&totals, 50), percentile(&totals, 90), totals.iter().max()) else {
+        return "No elves to describe\n".to_string();
+    };
+    format!("Calories per elf, for {} elves:\n{}p50: {}, p90: {}, max: {}\n", totals.len(), Histogram::create(&totals, 10).render(40), p50, p90, max)
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::days::day01::{find_most_calories, get_top_three_calories, parse_input};
-
-    const TEST_INPUT: &str = &"\
-    1000\n\
-    2000\n\
-    3000\n\
-    \n\
-    4000\n\
-    \n\
-    5000\n\
-    6000\n\
-    \n\
-    7000\n\
-    8000\n\
-    9000\n\
-    \n\
-    10000\n";
+    use crate::days::day01::{describe_distribution, find_most_calories, get_top_three_calories, parse_input};
+
+    const TEST_INPUT: &str = include_str!("../../examples/day01/input.txt");
 
     #[test]
     fn test_parse_input() {
@@ -108,4 +120,14 @@ mod tests {
 
         assert_eq!(result, 45000);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_describe_distribution() {
+        let description = describe_distribution(&parse_input(TEST_INPUT).unwrap());
+
+        assert!(description.starts_with("Calories per elf, for 5 elves:\n"), "{}", description);
+        assert!(description.ends_with("p50: 10000, p90: 24000, max: 24000\n"), "{}", description);
+        assert_eq!(Some("  4000..=5999 | #################### 1"), description.lines().nth(1));
+        assert_eq!("No elves to describe\n", describe_distribution(&[]));
+    }
+}
@@ -1,21 +1,92 @@
-use crate::days::Day;
+use crate::days::{Day, Explanation, Solver, solve_part1, solve_part2, parse_only, ParseSummary};
+use crate::util::error::AocError;
 
 pub const DAY2: Day = Day {
-    puzzle1,
-    puzzle2
+    puzzle1: solve_part1::<Day02>,
+    puzzle2: solve_part2::<Day02>,
+    parse: parse_only::<Day02>,
+    describe: "Rock Paper Scissors: score a rock-paper-scissors strategy guide",
+    explain: Some(explain),
+    selftest: None,
+    algorithms: &[]
 };
 
-fn puzzle1(input: &String) {
-    let rounds = parse_input(input).unwrap();
+fn explain() -> String {
+    Explanation {
+        approach: "Map every round to shapes, derive the shape to play from the wanted outcome for part 2, and sum the shape and outcome scores.",
+        complexity: "O(n) over the rounds.",
+        alternatives: &[]
+    }.to_string()
+}
+
+#[derive(Default)]
+struct Day02;
+
+impl Solver for Day02 {
+    type Output = i64;
+
+    fn part1(&self, input: &str) -> Result<Self::Output, AocError> {
+        let (result, _) = get_scores(input)?;
+        Ok(result)
+    }
 
-    let result: i32 = rounds.iter().map(|r| r.get_score_1()).sum();
-    println!("Total score of the strategy guide: {}", result);
+    fn part2(&self, input: &str) -> Result<Self::Output, AocError> {
+        let (_, result) = get_scores(input)?;
+        Ok(result)
+    }
+
+    fn parse(&self, input: &str) -> Result<ParseSummary, AocError> {
+        let rounds = parse_input(input)?;
+        Ok(ParseSummary::new()
+            .count(rounds.len(), "rounds")
+            .count(rounds.iter().map(|r| r.games.len()).sum(), "games"))
+    }
 }
-fn puzzle2(input: &String) {
-    let rounds = parse_input(input).unwrap();
 
-    let result: i32 = rounds.iter().map(|r| r.get_score_2()).sum();
-    println!("Total score of the correct strategy guide: {}", result);
+/// Above this size, the guide is scored straight from its bytes instead of being parsed into `Round`s first.
+const FAST_PATH_THRESHOLD: usize = 1 << 20;
+
+/// The total scores for puzzle 1 and puzzle 2.
+fn get_scores(input: &str) -> Result<(i64, i64), AocError> {
+    if input.len() > FAST_PATH_THRESHOLD {
+        return score_bytes(input.as_bytes());
+    }
+
+    let rounds = parse_input(input)?;
+    let score_1 = rounds.iter().map(|r| r.get_score_1() as i64).sum();
+    let score_2 = rounds.iter().map(|r| r.get_score_2() as i64).sum();
+    Ok((score_1, score_2))
+}
+
+/// Scores every game line ("A X") through lookup tables indexed by the two letters, without allocating anything.
+/// Blank lines (between rounds) are skipped, as all rounds add up to the total anyway.
+fn score_bytes(input: &[u8]) -> Result<(i64, i64), AocError> {
+    const SHAPES: [RPS; 3] = [RPS::Rock, RPS::Paper, RPS::Scissors];
+    const OUTCOMES: [Outcome; 3] = [Outcome::Lose, Outcome::Draw, Outcome::Win];
+    let mut table_1 = [[0i64; 3]; 3];
+    let mut table_2 = [[0i64; 3]; 3];
+    for (o, opponent) in SHAPES.iter().enumerate() {
+        for c in 0..3 {
+            table_1[o][c] = SHAPES[c].score_against(opponent) as i64;
+            table_2[o][c] = OUTCOMES[c].to_rps(opponent).score_against(opponent) as i64;
+        }
+    }
+
+    let (mut score_1, mut score_2) = (0, 0);
+    for (index, line) in input.split(|b| *b == b'\n').enumerate() {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        match line {
+            [] => continue,
+            [opponent @ b'A'..=b'C', b' ', column @ b'X'..=b'Z'] => {
+                let (o, c) = ((opponent - b'A') as usize, (column - b'X') as usize);
+                score_1 += table_1[o][c];
+                score_2 += table_2[o][c];
+            }
+            _ => return Err(AocError::parse(format!("Invalid game line '{}'", String::from_utf8_lossy(line))).in_line(index + 1))
+        }
+    }
+
+    Ok((score_1, score_2))
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -91,17 +162,17 @@ impl Round {
     }
 }
 
-fn parse_input(input: &str) -> Result<Vec<Round>, String> {
+fn parse_input(input: &str) -> Result<Vec<Round>, AocError> {
     let mut result: Vec<Round> = vec![];
     let mut games: Vec<(RPS, RPS, Outcome)> = vec![];
-    for line in input.lines() {
+    for (index, line) in input.lines().enumerate() {
         if line.trim().is_empty() && !games.is_empty() {
             result.push(Round { games });
             games = vec![];
             continue;
         }
 
-        games.push(parse_game(line)?);
+        games.push(parse_game(line).map_err(|e| e.in_line(index + 1))?);
     }
 
     if !games.is_empty() {
@@ -111,10 +182,10 @@ fn parse_input(input: &str) -> Result<Vec<Round>, String> {
     Ok(result)
 }
 
-fn parse_game(input: &str) -> Result<(RPS, RPS, Outcome), String> {
+fn parse_game(input: &str) -> Result<(RPS, RPS, Outcome), AocError> {
     let parts: Vec<_> = input.split(" ").collect();
     if parts.len() != 2 {
-        return Err(format!("Expected exactly 2 parts in line '{}', but got {}", input, parts.len()));
+        return Err(AocError::parse(format!("Expected exactly 2 parts in line '{}', but got {}", input, parts.len())));
     }
 
     // The first column is what your opponent is going to play: A for Rock, B for Paper, and C for Scissors.
@@ -122,7 +193,7 @@ fn parse_game(input: &str) -> Result<(RPS, RPS, Outcome), String> {
         "A" => RPS::Rock,
         "B" => RPS::Paper,
         "C" => RPS::Scissors,
-        _ => return Err(format!("Invalid RPS value for first column: {}", parts[0]))
+        _ => return Err(AocError::parse(format!("Invalid RPS value for first column: {}", parts[0])))
     };
 
     // The second column, you reason, must be what you should play in response: X for Rock, Y for Paper, and Z for Scissors.
@@ -130,27 +201,26 @@ fn parse_game(input: &str) -> Result<(RPS, RPS, Outcome), String> {
         "X" => RPS::Rock,
         "Y" => RPS::Paper,
         "Z" => RPS::Scissors,
-        _ => return Err(format!("Invalid RPS value for second column: {}", parts[0]))
+        _ => return Err(AocError::parse(format!("Invalid RPS value for second column: {}", parts[1])))
     };
     // X means you need to lose, Y means you need to end the round in a draw, and Z means you need to win.
     let puzzle_2 = match parts[1] {
         "X" => Outcome::Lose,
         "Y" => Outcome::Draw,
         "Z" => Outcome::Win,
-        _ => return Err(format!("Invalid Outcome value for second column: {}", parts[0]))
+        _ => return Err(AocError::parse(format!("Invalid Outcome value for second column: {}", parts[1])))
     };
 
     Ok((opponent, puzzle_1, puzzle_2))
 }
 #[cfg(test)]
 mod tests {
-    use crate::days::day02::{Outcome, parse_input, Round, RPS};
+    use std::time::Instant;
+    use crate::days::day02::{FAST_PATH_THRESHOLD, get_scores, Outcome, parse_input, Round, RPS, score_bytes};
+    use crate::util::error::AocError;
+    use crate::util::snaptest::SeededRng;
 
-    const TEST_INPUT: &str = "\
-        A Y\n\
-        B X\n\
-        C Z\n\
-    ";
+    const TEST_INPUT: &str = include_str!("../../examples/day02/input.txt");
 
     #[test]
     fn test_parse_input() {
@@ -179,4 +249,46 @@ mod tests {
         assert_eq!(round.get_score_1(), 15);
         assert_eq!(round.get_score_2(), 12);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_score_bytes() {
+        assert_eq!(Ok((15, 12)), score_bytes(TEST_INPUT.as_bytes()));
+        assert_eq!(Ok((15, 12)), score_bytes(b"A Y\r\nB X\r\n\r\nC Z"));
+        assert_eq!(Err(AocError::parse("Invalid game line 'A W'").in_line(2)), score_bytes(b"A Y\nA W\n"));
+        assert_eq!(Err(AocError::parse("Invalid game line 'A  Y'").in_line(1)), score_bytes(b"A  Y\n"));
+    }
+
+    fn generate_guide(games: usize) -> String {
+        let mut rng = SeededRng::new(2022);
+        (0..games).map(|_| {
+            let value = rng.next_u64() as u8;
+            format!("{} {}\n", (b'A' + value % 3) as char, (b'X' + value / 3 % 3) as char)
+        }).collect()
+    }
+
+    #[test]
+    fn test_fast_path_matches_parser() {
+        let input = generate_guide(300_000);
+        assert!(input.len() > FAST_PATH_THRESHOLD);
+        let rounds = parse_input(&input).unwrap();
+        let expected = (rounds[0].get_score_1() as i64, rounds[0].get_score_2() as i64);
+        assert_eq!(Ok(expected), get_scores(&input));
+    }
+
+    // `cargo test --release day02::tests::bench_score_bytes -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_score_bytes() {
+        let input = generate_guide(20_000_000);
+
+        let start = Instant::now();
+        let rounds = parse_input(&input).unwrap();
+        let parsed: (i64, i64) = (rounds.iter().map(|r| r.get_score_1() as i64).sum(), rounds.iter().map(|r| r.get_score_2() as i64).sum());
+        println!("Parser: {:?}", start.elapsed());
+
+        let start = Instant::now();
+        let scored = score_bytes(input.as_bytes()).unwrap();
+        println!("Bytes: {:?}", start.elapsed());
+        assert_eq!(parsed, scored);
+    }
+}
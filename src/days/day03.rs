@@ -1,58 +1,124 @@
-use crate::days::Day;
-use crate::util::collection::CollectionExtension;
+use crate::days::{Day, Explanation, Solver, solve_part1, solve_part2, parse_only, ParseSummary};
+use crate::util::error::AocError;
 
 pub const DAY3: Day = Day {
-    puzzle1,
-    puzzle2
+    puzzle1: solve_part1::<Day03>,
+    puzzle2: solve_part2::<Day03>,
+    parse: parse_only::<Day03>,
+    describe: "Rucksack Reorganization: find items shared between compartments and elf groups",
+    explain: Some(explain),
+    selftest: None,
+    algorithms: &[]
 };
 
-fn puzzle1(input: &String) {
-    let duplicates_sum: u32 = parse_input(input).unwrap().iter().map(|r| r.get_duplicate_priority_sum().unwrap()).sum();
+fn explain() -> String {
+    Explanation {
+        approach: "Store each compartment as a 52-bit item set; shared items are the intersection of the sets (within a rucksack, or over a group of three).",
+        complexity: "O(n) over the rucksacks, set operations are single bit operations.",
+        alternatives: &[]
+    }.to_string()
+}
+
+#[derive(Default)]
+struct Day03;
+
+impl Solver for Day03 {
+    type Output = u32;
+
+    fn part1(&self, input: &str) -> Result<Self::Output, AocError> {
+        let duplicates_sum: u32 = parse_input(input)?.iter().map(|r| r.get_duplicate_priority_sum()).sum();
+
+        Ok(duplicates_sum)
+    }
+
+    fn part2(&self, input: &str) -> Result<Self::Output, AocError> {
+        let badge_ids = find_badge_item_ids(&parse_input(input)?, 3)?;
 
-    println!("Sum of duplicate item priorities: {}", duplicates_sum);
+        let result = badge_ids.into_iter().map(get_item_priority).sum::<Result<u32, String>>()?;
+        Ok(result)
+    }
+
+    fn parse(&self, input: &str) -> Result<ParseSummary, AocError> {
+        let rucksacks = parse_input(input)?;
+        Ok(ParseSummary::new().count(rucksacks.len(), "rucksacks"))
+    }
 }
 
-fn puzzle2(input: &String) {
-    let badge_ids = find_badge_item_ids(&parse_input(input).unwrap());
+/// A set of item types, stored as a bitset where bit N is the item with priority N.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+struct ItemSet(u64);
+
+impl ItemSet {
+    fn all() -> Self {
+        ItemSet(((1u64 << 52) - 1) << 1)
+    }
+
+    fn from_items(items: &[char]) -> Result<Self, String> {
+        let mut bits = 0u64;
+        for item in items {
+            bits |= 1 << get_item_priority(*item)?;
+        }
+        Ok(ItemSet(bits))
+    }
+
+    fn intersect(&self, other: &Self) -> Self {
+        ItemSet(self.0 & other.0)
+    }
+
+    fn union(&self, other: &Self) -> Self {
+        ItemSet(self.0 | other.0)
+    }
+
+    fn priority_sum(&self) -> u32 {
+        (1..=52).filter(|p| self.0 & (1 << p) != 0).sum()
+    }
 
-    let result = badge_ids.into_iter().map(|c| get_item_priority(c).unwrap()).sum::<u32>();
-    println!("Sum of badge item types: {}", result);
+    /// The items in this set, ordered by priority.
+    fn items(&self) -> Vec<char> {
+        (1..=52).filter(|p| self.0 & (1 << p) != 0).map(get_item_for_priority).collect()
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 struct Rucksack {
-    compartment_a: Vec<char>,
-    compartment_b: Vec<char>
+    compartments: Vec<ItemSet>
 }
 
 impl Rucksack {
-    fn get_duplicates(&self) -> Vec<char> {
-        self.compartment_a.iter().filter(|c| self.compartment_b.contains(c)).cloned().collect::<Vec<_>>().deduplicate()
+    /// The item types present in every compartment of this rucksack.
+    fn shared_items(&self) -> ItemSet {
+        self.compartments.iter().fold(ItemSet::all(), |acc, c| acc.intersect(c))
     }
 
-    fn get_duplicate_priority_sum(&self) -> Result<u32, String> {
-        self.get_duplicates().iter().cloned().map(|c| get_item_priority(c)).sum()
+    fn get_duplicate_priority_sum(&self) -> u32 {
+        self.shared_items().priority_sum()
     }
 
-    fn get_item_types(&self) -> Vec<char> {
-        self.compartment_a.iter().chain(self.compartment_b.iter()).cloned().collect::<Vec<_>>().deduplicate()
+    fn get_item_types(&self) -> ItemSet {
+        self.compartments.iter().fold(ItemSet::default(), |acc, c| acc.union(c))
     }
 }
 
-fn parse_input(input: &str) -> Result<Vec<Rucksack>, String> {
-    input.lines().map(|l| parse_rucksack(l)).collect()
+fn parse_input(input: &str) -> Result<Vec<Rucksack>, AocError> {
+    parse_input_with_compartments(input, 2)
 }
 
-fn parse_rucksack(input: &str) -> Result<Rucksack, String> {
+fn parse_input_with_compartments(input: &str, compartments: usize) -> Result<Vec<Rucksack>, AocError> {
+    input.lines().enumerate().map(|(index, l)| parse_rucksack(l, compartments).map_err(|e| e.in_line(index + 1))).collect()
+}
+
+fn parse_rucksack(input: &str, compartments: usize) -> Result<Rucksack, AocError> {
     let chars: Vec<_> = input.trim().chars().collect();
-    if chars.len() % 2 != 0 {
-        return Err(format!("Expected an even length of items for rucksack '{}'", input));
+    if chars.is_empty() {
+        return Err(AocError::parse("Expected a rucksack with items, but the line is empty"));
+    }
+    if compartments == 0 || !chars.len().is_multiple_of(compartments) {
+        return Err(AocError::parse(format!("Expected the items of rucksack '{}' to be divisible over {} compartments", input, compartments)));
     }
-    let split_point = chars.len() / 2;
+    let compartment_size = chars.len() / compartments;
 
     Ok(Rucksack {
-        compartment_a: chars[..split_point].to_vec(),
-        compartment_b: chars[split_point..].to_vec()
+        compartments: chars.chunks(compartment_size).map(ItemSet::from_items).collect::<Result<_, _>>()?
     })
 }
 
@@ -64,39 +130,37 @@ fn get_item_priority(item: char) -> Result<u32, String> {
     }
 }
 
-fn find_badge_item_ids(rucksacks: &Vec<Rucksack>) -> Vec<char> {
-    // The rucksacks are ordered and each set of three correspond to a group.
-    // Each group has a badge, that badge should be the only item type shared by all three members
-    // of the group.
-
-    let mut badge_ids = vec![];
+fn get_item_for_priority(priority: u32) -> char {
+    match priority {
+        1..=26 => (b'a' + (priority - 1) as u8) as char,
+        27..=52 => (b'A' + (priority - 27) as u8) as char,
+        _ => panic!("Invalid item priority: {}", priority)
+    }
+}
 
-    for i in (0..rucksacks.len()).step_by(3) {
-        let (first, second, third) = (&rucksacks[i], &rucksacks[i+1], &rucksacks[i+2]);
-        let result = first.get_item_types().into_iter().filter(|t| second.get_item_types().contains(t) && third.get_item_types().contains(t)).collect::<Vec<_>>();
-        if result.len() != 1 {
-            eprintln!("Unexpected situation, not exactly one item shared between group: {} ({}, {}, {})",
-                      result.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(","),
-                      i, i+1, i+2);
-        }
-        badge_ids.push(result[0]);
+fn find_badge_item_ids(rucksacks: &[Rucksack], group_size: usize) -> Result<Vec<char>, String> {
+    // The rucksacks are ordered and each set of `group_size` corresponds to a group.
+    // Each group has a badge, that badge should be the only item type shared by all members
+    // of the group.
+    if group_size == 0 || !rucksacks.len().is_multiple_of(group_size) {
+        return Err(format!("Cannot divide {} rucksacks into groups of {}", rucksacks.len(), group_size));
     }
 
-    badge_ids
+    rucksacks.chunks(group_size).enumerate().map(|(group, members)| {
+        let shared = members.iter().fold(ItemSet::all(), |acc, r| acc.intersect(&r.get_item_types()));
+        match shared.items()[..] {
+            [badge] => Ok(badge),
+            _ => Err(format!("Expected exactly one item shared in group {}, but got [{}]", group + 1,
+                             shared.items().iter().map(|c| c.to_string()).collect::<Vec<_>>().join(",")))
+        }
+    }).collect()
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::days::day03::{find_badge_item_ids, get_item_priority, parse_input};
+    use crate::days::day03::{find_badge_item_ids, get_item_for_priority, get_item_priority, parse_input, parse_input_with_compartments, parse_rucksack};
 
-    const TEST_INPUT: &str = "\
-        vJrwpWtwJgWrhcsFMMfFFhFp\n\
-        jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL\n\
-        PmmdzqPrVvPwwTWBwg\n\
-        wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn\n\
-        ttgJtRGJQctTZtZT\n\
-        CrZsJsPPZsGzwwsLwLmpwMDw\n\
-    ";
+    const TEST_INPUT: &str = include_str!("../../examples/day03/input.txt");
 
     #[test]
     fn test_parse_input() {
@@ -106,9 +170,26 @@ mod tests {
         let rucksacks = result.unwrap();
 
         assert_eq!(6, rucksacks.len(), "Expected to have read 6 rucksacks.");
-        assert_eq!(12, rucksacks[0].compartment_a.len());
-        assert_eq!(12, rucksacks[0].compartment_b.len());
-        assert_eq!(vec!['v','J','r','w','p','W','t','w','J','g','W','r'], rucksacks[0].compartment_a);
+        assert_eq!(2, rucksacks[0].compartments.len());
+        assert_eq!(vec!['g','p','r','t','v','w','J','W'], rucksacks[0].compartments[0].items());
+    }
+
+    #[test]
+    fn test_parse_input_with_compartments() {
+        let rucksacks = parse_input_with_compartments("abcabdabe\nabcdef", 3).unwrap();
+        assert_eq!(vec!['a', 'b'], rucksacks[0].shared_items().items());
+        assert!(rucksacks[1].shared_items().items().is_empty());
+
+        assert!(parse_input_with_compartments("abcd", 3).is_err());
+        assert!(parse_input_with_compartments("abcd", 0).is_err());
+        assert!(parse_input_with_compartments("ab1d", 2).is_err());
+    }
+
+    #[test]
+    fn test_parse_rucksack_errors() {
+        assert!(parse_input("vJrwpWtwJgWrhcsFMMfFFhFp\n\njqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL").is_err(), "Empty rucksack");
+        assert!(parse_rucksack("   ", 2).is_err());
+        assert!(parse_rucksack("abc", 2).is_err(), "Odd number of items");
     }
 
     #[test]
@@ -122,26 +203,34 @@ mod tests {
         assert_eq!(Ok(26), get_item_priority('z'));
         assert_eq!(Ok(27), get_item_priority('A'));
         assert_eq!(Ok(51), get_item_priority('Y'));
+
+        for priority in 1..=52 {
+            assert_eq!(Ok(priority), get_item_priority(get_item_for_priority(priority)));
+        }
     }
 
     #[test]
-    fn test_get_duplicates() {
-        let duplicates: Vec<_> = parse_input(TEST_INPUT).unwrap().iter().flat_map(|r| r.get_duplicates()).collect();
+    fn test_shared_items() {
+        let duplicates: Vec<_> = parse_input(TEST_INPUT).unwrap().iter().flat_map(|r| r.shared_items().items()).collect();
 
         assert_eq!(vec!['p', 'L', 'P', 'v', 't', 's'], duplicates);
     }
 
     #[test]
     fn test_get_duplicate_priority_sum() {
-        let result: Result<u32, _> = parse_input(TEST_INPUT).unwrap().iter().map(|r| r.get_duplicate_priority_sum()).sum();
+        let result: u32 = parse_input(TEST_INPUT).unwrap().iter().map(|r| r.get_duplicate_priority_sum()).sum();
 
-        assert_eq!(Ok(157), result);
+        assert_eq!(157, result);
     }
 
     #[test]
     fn test_find_badge_item_ids() {
-        let result = find_badge_item_ids(&parse_input(TEST_INPUT).unwrap());
+        let rucksacks = parse_input(TEST_INPUT).unwrap();
 
-        assert_eq!(vec!['r', 'Z'], result);
+        assert_eq!(Ok(vec!['r', 'Z']), find_badge_item_ids(&rucksacks, 3));
+        assert_eq!(Ok(vec!['X']), find_badge_item_ids(&parse_input("abXcdX\nefXghX").unwrap(), 2));
+        assert!(find_badge_item_ids(&rucksacks, 1).is_err(), "Rucksacks on their own share more than one item");
+        assert!(find_badge_item_ids(&rucksacks, 4).is_err());
+        assert!(find_badge_item_ids(&rucksacks, 6).is_err());
     }
 }
\ No newline at end of file
@@ -1,23 +1,50 @@
 use std::ops::RangeInclusive;
-use crate::days::Day;
+use crate::days::{Day, Explanation, Solver, solve_part1, solve_part2, parse_only, ParseSummary};
+use crate::util::error::AocError;
+use crate::util::input::parse_lines;
 use crate::util::number::parse_i32;
+use crate::util::range::Interval;
 
 pub const DAY4: Day = Day {
-    puzzle1,
-    puzzle2
+    puzzle1: solve_part1::<Day04>,
+    puzzle2: solve_part2::<Day04>,
+    parse: parse_only::<Day04>,
+    describe: "Camp Cleanup: count fully contained and overlapping section assignments",
+    explain: Some(explain),
+    selftest: None,
+    algorithms: &[]
 };
 
-fn puzzle1(input: &String) {
-    let pairs = parse_input(input).unwrap();
-
-    let result = pairs.iter().filter(|p| p.has_range_fully_contained_in_other()).count();
-    println!("There are {} pairs where one of the elfs can be lazy!", result);
+fn explain() -> String {
+    Explanation {
+        approach: "Parse both assignments as inclusive ranges and check containment and overlap with the interval helpers.",
+        complexity: "O(n) over the pairs.",
+        alternatives: &[]
+    }.to_string()
 }
-fn puzzle2(input: &String) {
-    let pairs = parse_input(input).unwrap();
 
-    let result = pairs.iter().filter(|p| p.has_any_range_overlap()).count();
-    println!("There are {} pairs where any part of the range overlaps.", result);
+#[derive(Default)]
+struct Day04;
+
+impl Solver for Day04 {
+    type Output = usize;
+
+    fn part1(&self, input: &str) -> Result<Self::Output, AocError> {
+        let pairs = parse_input(input)?;
+
+        Ok(pairs.iter().filter(|p| p.has_range_fully_contained_in_other()).count())
+    }
+
+    fn part2(&self, input: &str) -> Result<Self::Output, AocError> {
+        let pairs = parse_input(input)?;
+
+        Ok(pairs.iter().filter(|p| p.has_any_range_overlap()).count())
+    }
+
+    fn parse(&self, input: &str) -> Result<ParseSummary, AocError> {
+        let pairs = parse_input(input)?;
+        Ok(ParseSummary::new().count(pairs.len(), "pairs"))
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -28,24 +55,24 @@ struct CleaningPair {
 
 impl CleaningPair {
     fn has_range_fully_contained_in_other(&self) -> bool {
-        self.left.start() <= self.right.start() && self.left.end() >= self.right.end() ||
-            self.right.start() <= self.left.start() && self.right.end() >= self.left.end()
+        self.left.contains_interval(&self.right) || self.right.contains_interval(&self.left)
     }
 
     fn has_any_range_overlap(&self) -> bool {
-        self.left.contains(self.right.start()) || self.left.contains(self.right.end()) ||
-            self.right.contains(self.left.start()) || self.right.contains(self.left.end())
+        self.left.overlaps(&self.right)
     }
 }
 
-fn parse_input(input: &str) -> Result<Vec<CleaningPair>, String> {
-    input.trim().lines().map(|l| parse_pair(l)).collect()
+fn parse_input(input: &str) -> Result<Vec<CleaningPair>, AocError> {
+    // Blank lines (e.g. around the pairs) are skipped, while errors keep the line number in the input.
+    let pairs = parse_lines(input, |line| if line.trim().is_empty() { Ok(None) } else { parse_pair(line).map(Some) })?;
+    Ok(pairs.into_iter().flatten().collect())
 }
 
-fn parse_pair(input: &str) -> Result<CleaningPair, String> {
+fn parse_pair(input: &str) -> Result<CleaningPair, AocError> {
     let parts = input.trim().split(',').collect::<Vec<_>>();
     if parts.len() != 2 {
-        return Err(format!("Expected line with two ranges, but got '{}'", input))
+        return Err(AocError::parse(format!("Expected line with two ranges, but got '{}'", input)))
     }
 
     let left = parse_assignment(parts[0])?;
@@ -53,10 +80,10 @@ fn parse_pair(input: &str) -> Result<CleaningPair, String> {
     Ok(CleaningPair { left, right })
 }
 
-fn parse_assignment(input: &str) -> Result<RangeInclusive<i32>, String> {
+fn parse_assignment(input: &str) -> Result<RangeInclusive<i32>, AocError> {
     let parts = input.split('-').collect::<Vec<_>>();
     if parts.len() != 2 {
-        return Err(format!("Expected an assignment range, but got '{}'", input));
+        return Err(AocError::parse(format!("Expected an assignment range, but got '{}'", input)));
     }
 
     let start = parse_i32(parts[0])?;
@@ -68,15 +95,9 @@ fn parse_assignment(input: &str) -> Result<RangeInclusive<i32>, String> {
 #[cfg(test)]
 mod tests {
     use crate::days::day04::{CleaningPair, parse_input};
+    use crate::util::error::AocError;
 
-    const TEST_INPUT: &str = "\
-        2-4,6-8\n\
-        2-3,4-5\n\
-        5-7,7-9\n\
-        2-8,3-7\n\
-        6-6,4-6\n\
-        2-6,4-8\n\
-        ";
+    const TEST_INPUT: &str = include_str!("../../examples/day04/input.txt");
 
     #[test]
     fn test_parse_input() {
@@ -91,6 +112,13 @@ mod tests {
         assert_eq!(3..=7, pairs[3].right);
     }
 
+    #[test]
+    fn test_parse_input_blank_lines() {
+        let padded = format!("\n\n{}\n", TEST_INPUT);
+        assert_eq!(parse_input(TEST_INPUT).unwrap(), parse_input(&padded).unwrap());
+        assert_eq!(Err(AocError::parse("Expected line with two ranges, but got '2-4'").in_line(3)), parse_input("\n\n2-4\n"));
+    }
+
     #[test]
     fn test_has_range_fully_contained_in_other() {
         assert_eq!(false, CleaningPair { left: 2..=4, right: 6..=8 }.has_range_fully_contained_in_other());
@@ -1,57 +1,163 @@
 use std::fmt;
-use crate::days::Day;
+use crate::days::{Day, Explanation, Solver, solve_part1, solve_part2, parse_only, ParseSummary};
+use crate::util::error::AocError;
+use crate::util::hash::fnv1a;
 use crate::util::number::parse_usize;
 
 pub const DAY5: Day = Day {
-    puzzle1,
-    puzzle2
+    puzzle1: solve_part1::<Day05>,
+    puzzle2: solve_part2::<Day05>,
+    parse: parse_only::<Day05>,
+    describe: "Supply Stacks: rearrange crate stacks with the CrateMover 9000 and 9001",
+    explain: Some(explain),
+    selftest: None,
+    algorithms: &[]
 };
 
-fn puzzle1(input: &String) {
-    let (mut field, moves) = parse_input(input).unwrap();
+fn explain() -> String {
+    Explanation {
+        approach: "Simulate the moves on the stacks, one crate at a time for the CrateMover 9000 and in order for the 9001. Moves are validated up front.",
+        complexity: "O(total crates moved).",
+        alternatives: &[]
+    }.to_string()
+}
+
+#[derive(Default)]
+struct Day05;
+
+impl Solver for Day05 {
+    type Output = String;
 
-    for m in moves {
-        field.apply_move(&m);
+    fn part1(&self, input: &str) -> Result<Self::Output, AocError> {
+        let (mut field, moves) = parse_input(input)?;
+
+        for m in moves {
+            field.apply_move(&m).map_err(|e| e.to_string())?;
+        }
+
+        println!("{:?}", field);
+        Ok(field.get_items_on_top())
     }
 
-    println!("{:?}", field);
-    println!("Containers on top: {}", field.get_items_on_top());
-}
-fn puzzle2(input: &String) {
-    let (mut field, moves) = parse_input(input).unwrap();
+    fn part2(&self, input: &str) -> Result<Self::Output, AocError> {
+        let (mut field, moves) = parse_input(input)?;
+
+        for m in moves {
+            field.apply_move_with_order(&m).map_err(|e| e.to_string())?;
+        }
 
-    for m in moves {
-        field.apply_move_with_order(&m);
+        println!("{:?}", field);
+        Ok(field.get_items_on_top())
     }
 
-    println!("{:?}", field);
-    println!("Containers on top: {}", field.get_items_on_top());
+    fn parse(&self, input: &str) -> Result<ParseSummary, AocError> {
+        let (field, moves) = parse_input(input)?;
+        Ok(ParseSummary::new()
+            .count(field.stacks.len(), "stacks")
+            .count(field.crate_count, "crates")
+            .count(moves.len(), "moves"))
+    }
 }
 
 #[derive(Clone, Eq, PartialEq)]
 struct Field {
-    stacks: Vec<Vec<char>>
+    stacks: Vec<Vec<char>>,
+    /// The number of crates on the field when it was parsed, which no move should change.
+    crate_count: usize
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum MoveError {
+    UnknownStack(usize),
+    NotEnoughCrates { stack: usize, requested: usize, available: usize },
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::UnknownStack(stack) => write!(f, "stack {} does not exist", stack),
+            MoveError::NotEnoughCrates { stack, requested, available } =>
+                write!(f, "stack {} only holds {} crates, but {} should be moved", stack, available, requested)
+        }
+    }
 }
 
 impl Field {
-    fn apply_move(&mut self, mov: &Move) {
-        for _ in 0..mov.count {
-            if let Some(val) = self.stacks[mov.from-1].pop() {
-                self.stacks[mov.to-1].push(val)
+    fn new(stacks: Vec<Vec<char>>) -> Self {
+        let crate_count = stacks.iter().map(|s| s.len()).sum();
+        Field { stacks, crate_count }
+    }
+
+    fn check_move(&self, mov: &Move) -> Result<(), MoveError> {
+        for stack in [mov.from, mov.to] {
+            if stack == 0 || stack > self.stacks.len() {
+                return Err(MoveError::UnknownStack(stack));
             }
         }
+
+        let available = self.stacks[mov.from-1].len();
+        if available < mov.count {
+            return Err(MoveError::NotEnoughCrates { stack: mov.from, requested: mov.count, available });
+        }
+
+        Ok(())
     }
 
-    fn apply_move_with_order(&mut self, mov: &Move) {
-        let mut to_move: Vec<char> = vec![];
+    /// Replays the moves on a copy of this field, returning an error for the first move that cannot be applied.
+    fn validate_moves(&self, moves: &[Move]) -> Result<(), String> {
+        let mut sim = self.clone();
+        for (index, mov) in moves.iter().enumerate() {
+            sim.apply_move(mov).map_err(|e| format!("Invalid move #{} ('{}'): {}", index + 1, mov, e))?;
+        }
+        Ok(())
+    }
+
+    fn apply_move(&mut self, mov: &Move) -> Result<(), MoveError> {
+        self.check_move(mov)?;
+
         for _ in 0..mov.count {
-            if let Some(val) = self.stacks[mov.from-1].pop() {
-                to_move.push(val);
-            }
+            let val = self.stacks[mov.from-1].pop().unwrap();
+            self.stacks[mov.to-1].push(val)
         }
-        for i in (0..to_move.len()).rev() {
-            self.stacks[mov.to-1].push(to_move[i]);
+        debug_assert_eq!(Ok(()), self.validate_invariants(), "after '{}'", mov);
+        Ok(())
+    }
+
+    fn apply_move_with_order(&mut self, mov: &Move) -> Result<(), MoveError> {
+        self.check_move(mov)?;
+
+        let from = &mut self.stacks[mov.from-1];
+        let to_move = from.split_off(from.len() - mov.count);
+        self.stacks[mov.to-1].extend(to_move);
+        debug_assert_eq!(Ok(()), self.validate_invariants(), "after '{}'", mov);
+        Ok(())
+    }
+
+    /// Checks that no crates were lost or created since the field was parsed.
+    fn validate_invariants(&self) -> Result<(), String> {
+        let current = self.stack_heights().iter().sum::<usize>();
+        if current != self.crate_count {
+            return Err(format!("Field holds {} crates, but started out with {}", current, self.crate_count));
         }
+        Ok(())
+    }
+
+    fn stack_heights(&self) -> Vec<usize> {
+        self.stacks.iter().map(|s| s.len()).collect()
+    }
+
+    /// All stacks, bottom crate first.
+    #[allow(unused)]
+    fn dump(&self) -> Vec<Vec<char>> {
+        self.stacks.clone()
+    }
+
+    /// An (FNV-1a) hash of the full field, to compare large fields cheaply. Stack sizes are part of the hash,
+    /// so moving a crate to a neighbouring stack changes it.
+    #[allow(unused)]
+    fn content_hash(&self) -> u64 {
+        fnv1a(self.stacks.iter()
+            .flat_map(|s| (s.len() as u32).to_le_bytes().into_iter().chain(s.iter().flat_map(|c| (*c as u32).to_le_bytes()))))
     }
 
     fn get_items_on_top(&self) -> String {
@@ -91,22 +197,34 @@ struct Move {
     to: usize
 }
 
-fn parse_input(input: &str) -> Result<(Field, Vec<Move>), String> {
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "move {} from {} to {}", self.count, self.from, self.to)
+    }
+}
+
+fn parse_input(input: &str) -> Result<(Field, Vec<Move>), AocError> {
     let parts = input.split("\n\n").collect::<Vec<_>>();
     if parts.len() != 2 {
-        return Err(format!("Expected a field and move set separated by newline, but got {} parts.", parts.len()));
+        return Err(AocError::parse(format!("Expected a field and move set separated by newline, but got {} parts.", parts.len())));
     }
 
     let field = parse_field(parts[0])?;
-    let moves = parts[1].lines().map(|l| parse_move(l)).collect::<Result<Vec<_>, _>>()?;
+    // The moves start after the lines of the field and the blank line separating them.
+    let first_move_line = parts[0].lines().count() + 2;
+    let moves = parts[1].lines().enumerate()
+        .map(|(index, l)| parse_move(l).map_err(|e| e.in_line(first_move_line + index)))
+        .collect::<Result<Vec<_>, _>>()?;
+    field.validate_moves(&moves).map_err(AocError::parse)?;
     Ok((field, moves))
 }
 
-fn parse_field(input: &str) -> Result<Field, String> {
+fn parse_field(input: &str) -> Result<Field, AocError> {
     let lines = input.lines().collect::<Vec<_>>();
     // Pre-parse the last line to know the amount of stacks to create and handle
     let label_line = lines.last().unwrap();
-    let labels = label_line.split_whitespace().map(|p| parse_usize(p)).collect::<Result<Vec<_>, String>>()?;
+    let labels = label_line.split_whitespace().map(|p| parse_usize(p)).collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.in_line(lines.len()))?;
 
     let mut stacks: Vec<Vec<char>> = vec![];
     for _ in 0..labels.len() {
@@ -129,14 +247,14 @@ fn parse_field(input: &str) -> Result<Field, String> {
         }
     }
 
-    Ok(Field { stacks })
+    Ok(Field::new(stacks))
 }
 
-fn parse_move(input: &str) -> Result<Move, String> {
+fn parse_move(input: &str) -> Result<Move, AocError> {
     // move <count> from <from> to <to>
     let parts = input.split_whitespace().collect::<Vec<_>>();
     if parts.len() != 6 {
-        return Err(format!("Expected line to have 6 parts, got {}. '{}'", parts.len(), input));
+        return Err(AocError::parse(format!("Expected line to have 6 parts, got {}. '{}'", parts.len(), input)));
     }
 
     let count = parse_usize(parts[1])?;
@@ -147,19 +265,10 @@ fn parse_move(input: &str) -> Result<Move, String> {
 
 #[cfg(test)]
 mod tests {
-    use crate::days::day05::{Move, parse_field, parse_input, parse_move};
-
-    const TEST_INPUT: &str = "\
-        \x20   [D]    \n\
-        [N] [C]    \n\
-        [Z] [M] [P]\n\
-        \x201   2   3 \n\
-        \n\
-        move 1 from 2 to 1\n\
-        move 3 from 1 to 3\n\
-        move 2 from 2 to 1\n\
-        move 1 from 1 to 2\n\
-    ";
+    use crate::days::day05::{Field, Move, MoveError, parse_field, parse_input, parse_move};
+    use crate::util::snaptest::SeededRng;
+
+    const TEST_INPUT: &str = include_str!("../../examples/day05/input.txt");
 
     #[test]
     fn test_serde_field() {
@@ -200,17 +309,42 @@ mod tests {
         assert_eq!(4, moves.len());
     }
 
+    #[test]
+    fn test_validate_moves() {
+        let (field, moves) = parse_input(TEST_INPUT).unwrap();
+        assert_eq!(Ok(()), field.validate_moves(&moves));
+
+        assert_eq!(Err("Invalid move #2 ('move 1 from 4 to 1'): stack 4 does not exist".to_string()),
+                   field.validate_moves(&[moves[0].clone(), Move { count: 1, from: 4, to: 1 }]));
+        assert_eq!(Err("Invalid move #1 ('move 1 from 2 to 0'): stack 0 does not exist".to_string()),
+                   field.validate_moves(&[Move { count: 1, from: 2, to: 0 }]));
+        // After the first two moves, stack 1 is empty:
+        assert_eq!(Err("Invalid move #3 ('move 1 from 1 to 2'): stack 1 only holds 0 crates, but 1 should be moved".to_string()),
+                   field.validate_moves(&[moves[0].clone(), moves[1].clone(), Move { count: 1, from: 1, to: 2 }]));
+
+        let invalid_input = TEST_INPUT.replace("move 1 from 1 to 2", "move 5 from 1 to 2");
+        assert!(parse_input(&invalid_input).is_err());
+    }
+
+    #[test]
+    fn test_apply_invalid_move() {
+        let (mut field, _) = parse_input(TEST_INPUT).unwrap();
+        assert_eq!(Err(MoveError::NotEnoughCrates { stack: 3, requested: 2, available: 1 }), field.apply_move(&Move { count: 2, from: 3, to: 1 }));
+        assert_eq!(Err(MoveError::UnknownStack(9)), field.apply_move_with_order(&Move { count: 1, from: 1, to: 9 }));
+        assert_eq!(vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']], field.stacks);
+    }
+
     #[test]
     fn test_run_game() {
         let (mut field, moves) = parse_input(TEST_INPUT).unwrap();
 
-        field.apply_move(&moves[0]);
+        field.apply_move(&moves[0]).unwrap();
         assert_eq!(vec![vec!['Z', 'N', 'D'], vec!['M', 'C'], vec!['P']], field.stacks);
-        field.apply_move(&moves[1]);
+        field.apply_move(&moves[1]).unwrap();
         assert_eq!(vec![vec![], vec!['M', 'C'], vec!['P', 'D', 'N', 'Z']], field.stacks);
-        field.apply_move(&moves[2]);
+        field.apply_move(&moves[2]).unwrap();
         assert_eq!(vec![vec!['C', 'M'], vec![], vec!['P', 'D', 'N', 'Z']], field.stacks);
-        field.apply_move(&moves[3]);
+        field.apply_move(&moves[3]).unwrap();
         assert_eq!(vec![vec!['C'], vec!['M'], vec!['P', 'D', 'N', 'Z']], field.stacks);
 
         assert_eq!("CMZ", field.get_items_on_top());
@@ -220,15 +354,61 @@ mod tests {
     fn test_run_game_2() {
         let (mut field, moves) = parse_input(TEST_INPUT).unwrap();
 
-        field.apply_move_with_order(&moves[0]);
+        field.apply_move_with_order(&moves[0]).unwrap();
         assert_eq!(vec![vec!['Z', 'N', 'D'], vec!['M', 'C'], vec!['P']], field.stacks);
-        field.apply_move_with_order(&moves[1]);
+        field.apply_move_with_order(&moves[1]).unwrap();
         assert_eq!(vec![vec![], vec!['M', 'C'], vec!['P', 'Z', 'N', 'D']], field.stacks);
-        field.apply_move_with_order(&moves[2]);
+        field.apply_move_with_order(&moves[2]).unwrap();
         assert_eq!(vec![vec!['M', 'C'], vec![], vec!['P', 'Z', 'N', 'D']], field.stacks);
-        field.apply_move_with_order(&moves[3]);
+        field.apply_move_with_order(&moves[3]).unwrap();
         assert_eq!(vec![vec!['M'], vec!['C'], vec!['P', 'Z', 'N', 'D']], field.stacks);
 
         assert_eq!("MCD", field.get_items_on_top());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_state_apis() {
+        let (mut field, moves) = parse_input(TEST_INPUT).unwrap();
+        let initial = field.content_hash();
+        assert_eq!(vec![2, 3, 1], field.stack_heights());
+        assert_eq!(initial, field.clone().content_hash());
+
+        for m in &moves {
+            field.apply_move(m).unwrap();
+            assert_eq!(Ok(()), field.validate_invariants());
+        }
+        assert_eq!(vec![vec!['C'], vec!['M'], vec!['P', 'D', 'N', 'Z']], field.dump());
+        assert_eq!(vec![1, 1, 4], field.stack_heights());
+        assert_ne!(initial, field.content_hash());
+        assert_eq!(Field::new(field.dump()).content_hash(), field.content_hash());
+        // Same crates in the same order, but split differently over the stacks:
+        assert_ne!(Field::new(vec![vec!['C', 'M'], vec![], vec!['P', 'D', 'N', 'Z']]).content_hash(), field.content_hash());
+
+        field.stacks[2].pop();
+        assert_eq!(Err("Field holds 5 crates, but started out with 6".to_string()), field.validate_invariants());
+    }
+
+    #[test]
+    fn test_random_moves() {
+        let mut rng = SeededRng::new(2022);
+        let mut next = |bound: usize| rng.below(bound as u64) as usize;
+
+        let stacks: Vec<Vec<char>> = (0..9).map(|_| (0..next(20)).map(|_| (b'A' + next(26) as u8) as char).collect()).collect();
+        let mut field = Field::new(stacks);
+        let mut ordered = field.clone();
+        for _ in 0..10_000 {
+            let from = 1 + next(9);
+            let mov = Move { count: next(field.stacks[from-1].len() + 1), from, to: 1 + next(9) };
+            field.apply_move(&mov).unwrap();
+            ordered.apply_move_with_order(&mov).unwrap();
+            assert_eq!(Ok(()), field.validate_invariants());
+            assert_eq!(Ok(()), ordered.validate_invariants());
+            // Both cranes move the same number of crates, only their order differs.
+            assert_eq!(field.stack_heights(), ordered.stack_heights());
+        }
+
+        let sorted = |field: &Field| { let mut crates = field.dump().concat(); crates.sort(); crates };
+        assert_eq!(sorted(&field), sorted(&ordered));
+        assert_eq!(field.content_hash(), Field::new(field.dump()).content_hash());
+    }
+}
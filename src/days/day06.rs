@@ -1,50 +1,145 @@
-use crate::days::Day;
-use crate::util::collection::CollectionExtension;
+use std::ops::Range;
+use crate::days::{Day, Explanation, Solver, solve_part1, solve_part2, parse_only, ParseSummary};
+use crate::util::config::day06::END_MARKER;
+use crate::util::error::AocError;
 
 pub const DAY6: Day = Day {
-    puzzle1,
-    puzzle2
+    puzzle1: solve_part1::<Day06>,
+    puzzle2: solve_part2::<Day06>,
+    parse: parse_only::<Day06>,
+    describe: "Tuning Trouble: find start-of-packet and start-of-message markers in a datastream",
+    explain: Some(explain),
+    selftest: None,
+    algorithms: &[]
 };
 
-fn puzzle1(input: &String) {
-    let marker = detect_start_of_packet(input).unwrap();
+fn explain() -> String {
+    Explanation {
+        approach: "Slide a window over the stream, shrinking it past the earlier occurrence whenever a character repeats, and report the first position where the window holds enough distinct characters. The same scanner continues from there to find an end marker and extract the message in between.",
+        complexity: "O(n * w) for stream length n and window size w, checking every new character against the window.",
+        alternatives: &["A character count table makes this O(n)."]
+    }.to_string()
+}
+
+#[derive(Default)]
+struct Day06;
+
+impl Solver for Day06 {
+    type Output = usize;
+
+    fn part1(&self, input: &str) -> Result<Self::Output, AocError> {
+        let marker = detect_start_of_packet(input.trim_end()).ok_or("No start-of-packet marker in the stream")?;
+
+        Ok(marker)
+    }
 
-    println!("Start of packet at offset: {}", marker);
+    fn part2(&self, input: &str) -> Result<Self::Output, AocError> {
+        let marker = detect_start_of_message(input.trim_end()).ok_or("No start-of-message marker in the stream")?;
+
+        if let Some(end_marker) = END_MARKER.get()? {
+            match StreamScanner::new(input.trim_end()).extract_message(Marker::Literal(&end_marker)) {
+                Some(message) => println!("Message: {}", message),
+                None => println!("No end marker '{}' after the start of the message", end_marker),
+            }
+        }
+
+        Ok(marker)
+    }
+
+    fn parse(&self, input: &str) -> Result<ParseSummary, AocError> {
+        // The stream is used as it is, there is nothing to parse.
+        Ok(ParseSummary::new().count(input.trim_end().chars().count(), "characters"))
+    }
 }
-fn puzzle2(input: &String) {
-    let marker = detect_start_of_message(input).unwrap();
 
-    println!("Start of message at offset: {}", marker);
+/// A marker in a datastream.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Marker<'a> {
+    /// A sequence of this many characters that are all different.
+    Distinct(usize),
+    /// This exact sequence of characters.
+    Literal(&'a str),
 }
 
 /// To fix the communication system, you need to add a subroutine to the device that detects a
 /// start-of-packet marker in the datastream. In the protocol being used by the Elves, the start
 /// of a packet is indicated by a sequence of four characters that are all different.
-fn detect_start_of_packet(stream: &str) -> Option<usize> {
-    for i in 4..stream.len() {
-        let chars = stream[i-4..i].chars().collect::<Vec<_>>().deduplicate();
-        if chars.len() == 4 {
-            return Some(i)
+const START_OF_PACKET: Marker = Marker::Distinct(4);
+/// Your device's communication system is correctly detecting packets, but still isn't working. It looks like it also needs to look for messages.
+/// A start-of-message marker is just like a start-of-packet marker, except it consists of 14 distinct characters rather than 4.
+const START_OF_MESSAGE: Marker = Marker::Distinct(14);
+
+/// Scans a datastream for markers, every search continuing after the last marker found.
+struct StreamScanner {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl StreamScanner {
+    fn new(stream: &str) -> Self {
+        StreamScanner { chars: stream.chars().collect(), position: 0 }
+    }
+
+    /// The number of characters scanned so far, i.e. the offset right after the last marker found.
+    #[allow(unused)]
+    fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Finds the next marker and continues after it. Returns the character offsets of the marker, or None (without
+    /// moving on) if there is none.
+    fn next_marker(&mut self, marker: Marker) -> Option<Range<usize>> {
+        let found = match marker {
+            Marker::Distinct(size) => self.find_distinct(size),
+            Marker::Literal(literal) => self.find_literal(literal),
+        }?;
+        self.position = found.end;
+        Some(found)
+    }
+
+    fn find_distinct(&self, size: usize) -> Option<Range<usize>> {
+        let mut start = self.position;
+        for end in self.position..self.chars.len() {
+            // Drop the characters up to the earlier occurrence of the new one, keeping the window distinct.
+            if let Some(offset) = self.chars[start..end].iter().position(|c| *c == self.chars[end]) {
+                start += offset + 1;
+            }
+            if end + 1 - start == size {
+                return Some(start..end + 1);
+            }
         }
+        None
     }
-    None
-}
 
-/// Your device's communication system is correctly detecting packets, but still isn't working. It looks like it also needs to look for messages.
-/// A start-of-message marker is just like a start-of-packet marker, except it consists of 14 distinct characters rather than 4.
-fn detect_start_of_message(stream: &str) -> Option<usize> {
-    for i in 14..stream.len() {
-        let chars = stream[i-14..i].chars().collect::<Vec<_>>().deduplicate();
-        if chars.len() == 14 {
-            return Some(i)
+    fn find_literal(&self, literal: &str) -> Option<Range<usize>> {
+        let literal: Vec<_> = literal.chars().collect();
+        if literal.is_empty() {
+            return Some(self.position..self.position);
         }
+        self.chars[self.position..].windows(literal.len())
+            .position(|window| window == literal.as_slice())
+            .map(|offset| self.position + offset..self.position + offset + literal.len())
+    }
+
+    /// Extracts the message payload: the characters after the next start-of-message marker, up to the end marker.
+    fn extract_message(&mut self, end_marker: Marker) -> Option<String> {
+        let start = self.next_marker(START_OF_MESSAGE)?.end;
+        let end = self.next_marker(end_marker)?.start;
+        Some(self.chars[start..end].iter().collect())
     }
-    None
+}
+
+fn detect_start_of_packet(stream: &str) -> Option<usize> {
+    StreamScanner::new(stream).next_marker(START_OF_PACKET).map(|marker| marker.end)
+}
+
+fn detect_start_of_message(stream: &str) -> Option<usize> {
+    StreamScanner::new(stream).next_marker(START_OF_MESSAGE).map(|marker| marker.end)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::days::day06::{detect_start_of_message, detect_start_of_packet};
+    use crate::days::day06::{detect_start_of_message, detect_start_of_packet, Marker, START_OF_PACKET, StreamScanner};
 
     #[test]
     fn test_detect_start_of_packet() {
@@ -63,4 +158,33 @@ mod tests {
         assert_eq!(Some(29), detect_start_of_message("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"));
         assert_eq!(Some(26), detect_start_of_message("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_marker_at_end_of_stream() {
+        assert_eq!(Some(4), detect_start_of_packet("abcd"));
+        assert_eq!(None, detect_start_of_packet("abca"));
+        assert_eq!(None, detect_start_of_message("abcdefghijklm"));
+    }
+
+    #[test]
+    fn test_stream_scanner() {
+        let mut scanner = StreamScanner::new("mjqjpqmgbljsphdztnvjfqwrcgsmlb");
+        assert_eq!(Some(3..7), scanner.next_marker(START_OF_PACKET));
+        assert_eq!(7, scanner.position());
+        // Scanning continues after the previous marker.
+        assert_eq!(Some(7..11), scanner.next_marker(START_OF_PACKET));
+        assert_eq!(Some(15..17), scanner.next_marker(Marker::Literal("zt")));
+        assert_eq!(None, scanner.next_marker(Marker::Literal("mj")));
+        assert_eq!(17, scanner.position());
+    }
+
+    #[test]
+    fn test_extract_message() {
+        let stream = "xxxbcdefghijklmnhello, world!--end--rest";
+        assert_eq!(Some("hello, world!".to_string()), StreamScanner::new(stream).extract_message(Marker::Literal("--end--")));
+        assert_eq!(Some("".to_string()), StreamScanner::new(stream).extract_message(Marker::Literal("")));
+        assert_eq!(None, StreamScanner::new(stream).extract_message(Marker::Literal("--stop--")));
+        // The end marker can be a sequence of distinct characters as well.
+        assert_eq!(Some("hel".to_string()), StreamScanner::new(stream).extract_message(Marker::Distinct(4)));
+    }
+}
@@ -1,33 +1,66 @@
 use std::fmt;
-use crate::days::Day;
+use crate::days::{Day, Explanation, Solver, solve_part1, solve_part2, parse_only, ParseSummary};
+use crate::util::error::AocError;
+use crate::util::config::day07::{DISK_SIZE, FREE_SPACE_NEEDED};
 use crate::util::number::parse_usize;
 
 pub const DAY7: Day = Day {
-    puzzle1,
-    puzzle2
+    puzzle1: solve_part1::<Day07>,
+    puzzle2: solve_part2::<Day07>,
+    parse: parse_only::<Day07>,
+    describe: "No Space Left On Device: size directories from a terminal log and free up space",
+    explain: Some(explain),
+    selftest: None,
+    algorithms: &[]
 };
 
-fn puzzle1(input: &String) {
-    let root = parse_terminal_history(input).unwrap();
-    let dirs_under_100k = get_directories_under_100k(&root);
-    let size_sum = dirs_under_100k.iter().map(|d| d.get_total_size()).sum::<usize>();
-
-    println!("Sum of sizes of dirs < 100k: {}", size_sum);
+fn explain() -> String {
+    Explanation {
+        approach: "Replay the terminal log into a directory tree, resolving cd paths, and sum sizes recursively.",
+        complexity: "O(n * d) for n directories of depth d, as sizes are recomputed per directory.",
+        alternatives: &[]
+    }.to_string()
 }
-fn puzzle2(input: &String) {
-    let root = parse_terminal_history(input).unwrap();
 
-    let disk_size = 70_000_000;
-    let free_space_needed = 30_000_000;
+#[derive(Default)]
+struct Day07;
+
+impl Solver for Day07 {
+    type Output = usize;
+
+    fn part1(&self, input: &str) -> Result<Self::Output, AocError> {
+        let root = parse_terminal_history(input)?;
+        let dirs_under_100k = get_directories_under_100k(&root);
+        let size_sum = dirs_under_100k.iter().map(|d| d.get_total_size()).sum::<usize>();
+
+        Ok(size_sum)
+    }
+
+    fn part2(&self, input: &str) -> Result<Self::Output, AocError> {
+        let root = parse_terminal_history(input)?;
+
+        let disk_size = DISK_SIZE.get()?;
+        let free_space_needed = FREE_SPACE_NEEDED.get()?;
 
-    let used_space = root.get_total_size();
-    let needed_space = used_space - (disk_size - free_space_needed);
+        let used_space = root.get_total_size();
+        let needed_space = used_space - (disk_size - free_space_needed);
 
-    let all_dirs = root.all_dirs();
-    let mut options = all_dirs.iter().filter(|d| d.get_total_size() >= needed_space).collect::<Vec<_>>();
-    options.sort_by(|l, r| l.get_total_size().cmp(&r.get_total_size()));
+        let all_dirs = root.all_dirs();
+        let mut options = all_dirs.iter().filter(|d| d.get_total_size() >= needed_space).collect::<Vec<_>>();
+        options.sort_by(|l, r| l.get_total_size().cmp(&r.get_total_size()));
 
-    println!("Smallest dir to remove = {}, size = {}", options[0].name, options[0].get_total_size());
+        let smallest = options.first().ok_or("No directory is large enough to free up the space needed")?;
+        println!("Smallest dir to remove: {}", smallest.name);
+        Ok(smallest.get_total_size())
+    }
+
+    fn parse(&self, input: &str) -> Result<ParseSummary, AocError> {
+        let root = parse_terminal_history(input)?;
+        let dirs = root.all_dirs();
+        Ok(ParseSummary::new()
+            .count(dirs.len(), "directories")
+            .count(dirs.iter().map(|d| d.files.len()).sum(), "files"))
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -102,43 +135,66 @@ enum ParserState {
     List
 }
 
-fn parse_terminal_history(input: &str) -> Result<Directory, String> {
+/// Resolves the target of a `cd` from the current path. Targets can be absolute ('/a/e'), and can consist
+/// of multiple components ('../..', 'a/e').
+fn resolve_path(current_path: &[String], target: &str) -> Result<Vec<String>, String> {
+    let mut path = if target.starts_with('/') { vec![] } else { current_path.to_vec() };
+
+    for component in target.split('/').filter(|c| !c.is_empty() && *c != ".") {
+        if component == ".." {
+            if path.pop().is_none() {
+                return Err(format!("Tried to go up from the root dir ('cd {}')", target));
+            }
+        } else {
+            path.push(component.to_string());
+        }
+    }
+
+    Ok(path)
+}
+
+fn parse_terminal_history(input: &str) -> Result<Directory, AocError> {
     let mut root_dir = Directory::new("/".to_string());
-    let mut current_path: Vec<&str> = vec![];
+    let mut current_path: Vec<String> = vec![];
     let mut state = ParserState::Ready;
 
-    for line in input.lines() {
+    for (index, line) in input.lines().enumerate() {
+        let error = |message: String| AocError::parse(message).in_line(index + 1);
+
         if state == ParserState::List {
-            let current = root_dir.get_mut(&current_path).ok_or(format!("Missing directory '{}'", current_path.join("/")))?;
+            let path: Vec<_> = current_path.iter().map(|c| c.as_str()).collect();
+            let current = root_dir.get_mut(&path).ok_or_else(|| error(format!("missing directory '/{}'", path.join("/"))))?;
 
             if line.starts_with("$") {
                 state = ParserState::Ready;
             } else if line.starts_with("dir ") {
                 let dirname = &line[4..];
-                current.sub_dirs.push(Directory::new(dirname.to_string()));
+                // The same directory might be listed more than once.
+                if !current.sub_dirs.iter().any(|d| d.name == dirname) {
+                    current.sub_dirs.push(Directory::new(dirname.to_string()));
+                }
             } else if let [fs, name] = line.split(" ").collect::<Vec<_>>()[..] {
-                current.files.push(File { name: name.to_string(), size: parse_usize(fs)? });
+                let size = parse_usize(fs).map_err(|e| e.in_line(index + 1))?;
+                if !current.files.iter().any(|f| f.name == name) {
+                    current.files.push(File { name: name.to_string(), size });
+                }
             } else {
-                return Err(format!("Invalid list line: '{}'", line));
+                return Err(error(format!("invalid list line: '{}'", line)));
             }
         }
 
         if state == ParserState::Ready {
             if line == "$ ls" {
                 state = ParserState::List;
-            } else if line.starts_with("$ cd ") {
-                let folder = &line[5..];
-                if folder == ".." {
-                    if let None = current_path.pop() {
-                        return Err(format!("Tried 'cd ..' from root dir"))
-                    }
-                } else if folder == "/" {
-                    current_path.clear();
-                } else {
-                    current_path.push(folder);
+            } else if let Some(target) = line.strip_prefix("$ cd ") {
+                let path = resolve_path(&current_path, target).map_err(error)?;
+                let components: Vec<_> = path.iter().map(|c| c.as_str()).collect();
+                if root_dir.get_mut(&components).is_none() {
+                    return Err(error(format!("no such directory '/{}' ('{}')", components.join("/"), line)));
                 }
+                current_path = path;
             } else {
-                return Err(format!("Expected to read command, but got: '{}'", line));
+                return Err(error(format!("expected to read command, but got: '{}'", line)));
             }
         }
     }
@@ -160,33 +216,9 @@ fn get_directories_under_100k(root: &Directory) -> Vec<&Directory> {
 
 #[cfg(test)]
 mod tests {
-    use crate::days::day07::{get_directories_under_100k, parse_terminal_history};
-
-    const TEST_INPUT: &str = "\
-        $ cd /\n\
-        $ ls\n\
-        dir a\n\
-        14848514 b.txt\n\
-        8504156 c.dat\n\
-        dir d\n\
-        $ cd a\n\
-        $ ls\n\
-        dir e\n\
-        29116 f\n\
-        2557 g\n\
-        62596 h.lst\n\
-        $ cd e\n\
-        $ ls\n\
-        584 i\n\
-        $ cd ..\n\
-        $ cd ..\n\
-        $ cd d\n\
-        $ ls\n\
-        4060174 j\n\
-        8033020 d.log\n\
-        5626152 d.ext\n\
-        7214296 k\n\
-        ";
+    use crate::days::day07::{get_directories_under_100k, parse_terminal_history, resolve_path};
+
+    const TEST_INPUT: &str = include_str!("../../examples/day07/input.txt");
 
     #[test]
     fn test_parse_history() {
@@ -229,4 +261,63 @@ mod tests {
         assert_eq!("e", result[1].name);
         assert_eq!(584, result[1].get_total_size());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_resolve_path() {
+        let current = vec!["a".to_string(), "e".to_string()];
+        assert_eq!(Ok(vec!["a".to_string()]), resolve_path(&current, ".."));
+        assert_eq!(Ok(vec![]), resolve_path(&current, "../.."));
+        assert_eq!(Ok(vec![]), resolve_path(&current, "/"));
+        assert_eq!(Ok(vec!["d".to_string()]), resolve_path(&current, "/d"));
+        assert_eq!(Ok(vec!["a".to_string(), "e".to_string(), "x".to_string()]), resolve_path(&current, "./x/"));
+        assert_eq!(Ok(vec!["a".to_string(), "f".to_string()]), resolve_path(&current, "../f"));
+        assert!(resolve_path(&current, "../../..").is_err());
+    }
+
+    #[test]
+    fn test_parse_history_with_paths() {
+        let history = "\
+            $ cd /\n\
+            $ ls\n\
+            dir a\n\
+            dir d\n\
+            10 b.txt\n\
+            $ cd a\n\
+            $ ls\n\
+            dir e\n\
+            $ cd /a/e\n\
+            $ ls\n\
+            5 i\n\
+            $ cd ../../d\n\
+            $ ls\n\
+            7 j\n\
+            $ cd /\n\
+            $ ls\n\
+            dir a\n\
+            10 b.txt\n\
+            $ cd /a/e/\n\
+            $ cd ../..\n\
+        ";
+        let root = parse_terminal_history(history).unwrap();
+        assert_eq!([
+            "- / (dir)",
+            "  - a (dir)",
+            "    - e (dir)",
+            "      - i (file, size=5)",
+            "  - d (dir)",
+            "    - j (file, size=7)",
+            "  - b.txt (file, size=10)",
+        ].join("\n"), format!("{}", root).trim());
+        assert_eq!(22, root.get_total_size());
+    }
+
+    #[test]
+    fn test_parse_history_errors() {
+        assert_eq!(Err("Line 3: no such directory '/a/x' ('$ cd /a/x')".to_string()),
+                   parse_terminal_history("$ ls\ndir a\n$ cd /a/x\n").map_err(|e| e.to_string()));
+        assert_eq!(Err("Line 2: Tried to go up from the root dir ('cd ../a')".to_string()),
+                   parse_terminal_history("$ cd /\n$ cd ../a\n").map_err(|e| e.to_string()));
+        assert_eq!(Err("Line 2: expected to read command, but got: 'ls'".to_string()),
+                   parse_terminal_history("$ cd /\nls\n").map_err(|e| e.to_string()));
+    }
+}
@@ -1,24 +1,52 @@
-use crate::days::Day;
+use crate::days::{Day, Explanation, Solver, solve_part1, solve_part2, parse_only, ParseSummary};
+use crate::util::error::AocError;
 use crate::util::collection::CollectionExtension;
-use crate::util::geometry::{Directions, Grid, Point};
+use crate::util::config::day08::{Padding, PADDING};
+use crate::util::geometry::{Bounds, Directions, Grid, Point};
 
 pub const DAY8: Day = Day {
-    puzzle1,
-    puzzle2
+    puzzle1: solve_part1::<Day08>,
+    puzzle2: solve_part2::<Day08>,
+    parse: parse_only::<Day08>,
+    describe: "Treetop Tree House: count visible trees and find the best scenic score",
+    explain: Some(explain),
+    selftest: None,
+    algorithms: &[]
 };
 
-fn puzzle1(input: &String) {
-    let forest = parse_input(input).unwrap();
-
-    let visible_trees = forest.get_visible_tree_count();
-    println!("There are {} visible trees in this forest", visible_trees);
+fn explain() -> String {
+    Explanation {
+        approach: "For every tree, walk each of the four directions until a taller tree blocks the view; this gives both visibility and scenic scores. Missing trees (in a ragged forest padded with none) never block the view.",
+        complexity: "O(n * (w + h)) for n trees in a w by h forest.",
+        alternatives: &[]
+    }.to_string()
 }
 
-fn puzzle2(input: &String) {
-    let forest = parse_input(input).unwrap();
+#[derive(Default)]
+struct Day08;
+
+impl Solver for Day08 {
+    type Output = usize;
+
+    fn part1(&self, input: &str) -> Result<Self::Output, AocError> {
+        let forest = parse_input(input)?;
+
+        Ok(forest.get_visible_tree_count())
+    }
+
+    fn part2(&self, input: &str) -> Result<Self::Output, AocError> {
+        let forest = parse_input(input)?;
 
-    let best_score = forest.get_best_scenic_score().unwrap();
-    println!("Best scenic score in this forest: {}", best_score);
+        let best_score = forest.get_best_scenic_score().ok_or("There are no trees in this forest")?;
+        Ok(best_score)
+    }
+
+    fn parse(&self, input: &str) -> Result<ParseSummary, AocError> {
+        let forest = parse_input(input)?;
+        Ok(ParseSummary::new()
+            .count(forest.trees.bounds.height, "rows")
+            .count(forest.trees.bounds.width, "columns"))
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -28,15 +56,19 @@ struct Forest {
 
 impl Forest {
     fn get_visible_tree_count(&self) -> usize {
-        self.trees.points().iter().filter(|p| self.is_tree_visible(p)).count()
+        self.trees.iter().filter(|(p, _)| self.is_tree_visible(p)).count()
     }
 
     fn is_tree_visible(&self, tree: &Point) -> bool {
+        self.is_tree_visible_from(tree, Directions::Top) ||
+        self.is_tree_visible_from(tree, Directions::Right) ||
+        self.is_tree_visible_from(tree, Directions::Bottom) ||
+        self.is_tree_visible_from(tree, Directions::Left)
+    }
+
+    fn is_tree_visible_from(&self, tree: &Point, direction: Directions) -> bool {
         if let Some(height) = self.trees.get(tree) {
-            self.trees.get_in_direction(tree, Directions::Top).iter().all(|v| v < &height) ||
-            self.trees.get_in_direction(tree, Directions::Right).iter().all(|v| v < &height) ||
-            self.trees.get_in_direction(tree, Directions::Bottom).iter().all(|v| v < &height) ||
-            self.trees.get_in_direction(tree, Directions::Left).iter().all(|v| v < &height)
+            self.trees.iter_in_direction(tree, direction).all(|(_, v)| *v < height)
         } else {
             false
         }
@@ -46,7 +78,7 @@ impl Forest {
         let mut result = vec![];
 
         if let Some(height) = self.trees.get(tree) {
-            for tree in self.trees.get_in_direction(tree, direction) {
+            for (_, &tree) in self.trees.iter_in_direction(tree, direction) {
                 result.push(tree);
                 if tree >= height {
                     break;
@@ -67,31 +99,75 @@ impl Forest {
     }
 
     fn get_best_scenic_score(&self) -> Option<usize> {
-        self.trees.points().iter().map(|p| self.get_scenic_score(p)).max()
+        self.trees.iter().map(|(p, _)| self.get_scenic_score(&p)).max()
     }
 }
 
-fn parse_input(input: &str) -> Result<Forest, String> {
-    let lines = input.lines().collect::<Vec<_>>();
-    let lens = lines.iter().map(|l| l.len()).collect::<Vec<_>>().deduplicate();
-    if lens.len() != 1 {
-        return Err(format!("Expected input lines to all have the same length, but got: {}", lens.iter().map(|l| l.to_string()).collect::<Vec<_>>().join(", ")));
+#[allow(unused)]
+impl Forest {
+    fn visibility_grid(&self) -> Grid<bool> {
+        self.trees.map(|point, _| self.is_tree_visible(&point))
+    }
+
+    fn scenic_grid(&self) -> Grid<usize> {
+        self.trees.map(|point, _| self.get_scenic_score(&point))
+    }
+
+    /// Renders the forest, showing the height of visible trees and a '.' for hidden ones.
+    fn render_visibility(&self) -> String {
+        let visibility = self.visibility_grid();
+        self.trees.bounds.y().map(|y| {
+            self.trees.bounds.x().map(|x| {
+                let point = (x, y).into();
+                match (visibility.get(&point), self.trees.get(&point)) {
+                    (Some(true), Some(height)) => height.to_string(),
+                    _ => ".".to_string()
+                }
+            }).collect::<String>()
+        }).collect::<Vec<_>>().join("\n")
+    }
+}
+
+fn parse_input(input: &str) -> Result<Forest, AocError> {
+    parse_input_with(input, PADDING.get()?)
+}
+
+fn parse_input_with(input: &str, padding: Padding) -> Result<Forest, AocError> {
+    let lines = input.trim_end().lines().collect::<Vec<_>>();
+    let lens = lines.iter().map(|l| l.chars().count()).collect::<Vec<_>>().deduplicate();
+    if lens.len() > 1 && padding == Padding::Reject {
+        return Err(AocError::parse(format!("Expected input lines to all have the same length, but got: {}", lens.iter().map(|l| l.to_string()).collect::<Vec<_>>().join(", "))));
     }
 
-    Ok(Forest { trees: input.parse()? })
+    let width = lens.iter().copied().max().unwrap_or(0);
+    let mut trees = Grid::dense(Bounds::from_size(width, lines.len()));
+    for (y, line) in lines.iter().enumerate() {
+        let heights: Vec<_> = line.chars().map(|c| c.to_digit(10).map(|h| h as i32)).collect();
+        for x in 0..width {
+            let height = match heights.get(x) {
+                Some(Some(height)) => *height,
+                Some(None) => return Err(AocError::parse_at(y + 1, x + 1, format!("Invalid tree height '{}'", line.chars().nth(x).unwrap_or_default()))),
+                None if padding == Padding::Zero => 0,
+                None => continue
+            };
+            trees.set((x, y).try_into()?, height);
+        }
+    }
+    // Padding with none can leave the last column or row empty, those still belong to the forest.
+    trees.bounds.width = width;
+    trees.bounds.height = lines.len();
+
+    Ok(Forest { trees })
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::days::day08::parse_input;
+    use crate::days::day08::{parse_input, parse_input_with};
+    use crate::util::config::day08::Padding;
+    use crate::util::error::AocError;
+    use crate::util::geometry::Directions;
 
-    const TEST_INPUT: &str = "\
-        30373\n\
-        25512\n\
-        65332\n\
-        33549\n\
-        35390\n\
-    ";
+    const TEST_INPUT: &str = include_str!("../../examples/day08/input.txt");
 
     #[test]
     fn test_parse_input() {
@@ -110,6 +186,41 @@ mod tests {
         assert_eq!(false, forest.is_tree_visible(&(3, 3).into()));
     }
 
+    #[test]
+    fn test_is_tree_visible_from() {
+        let forest = parse_input(TEST_INPUT).unwrap();
+        assert!(forest.is_tree_visible_from(&(1, 1).into(), Directions::Top));
+        assert!(forest.is_tree_visible_from(&(1, 1).into(), Directions::Left));
+        assert!(!forest.is_tree_visible_from(&(1, 1).into(), Directions::Right));
+        assert!(!forest.is_tree_visible_from(&(1, 1).into(), Directions::Bottom));
+        assert!(forest.is_tree_visible_from(&(3, 2).into(), Directions::Right));
+        assert!(!forest.is_tree_visible_from(&(3, 2).into(), Directions::Top));
+    }
+
+    #[test]
+    fn test_visibility_grid() {
+        let forest = parse_input(TEST_INPUT).unwrap();
+        let grid = forest.visibility_grid();
+        assert_eq!(grid.bounds, forest.trees.bounds);
+        assert_eq!(21, grid.values().iter().filter(|v| **v).count());
+        assert_eq!("\
+            30373\n\
+            255.2\n\
+            65.32\n\
+            3.5.9\n\
+            35390\
+        ", forest.render_visibility());
+    }
+
+    #[test]
+    fn test_scenic_grid() {
+        let forest = parse_input(TEST_INPUT).unwrap();
+        let grid = forest.scenic_grid();
+        assert_eq!(Some(4), grid.get(&(2, 1).into()));
+        assert_eq!(Some(8), grid.get(&(2, 3).into()));
+        assert_eq!(Some(0), grid.get(&(0, 0).into()));
+    }
+
     #[test]
     fn test_get_visible_tree_count() {
         let forest = parse_input(TEST_INPUT).unwrap();
@@ -128,4 +239,39 @@ mod tests {
         let forest = parse_input(TEST_INPUT).unwrap();
         assert_eq!(Some(8), forest.get_best_scenic_score());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_padding() {
+        // The example, with the second row trimmed to 255 and the last one to 3.
+        let ragged = TEST_INPUT.replacen("25512", "255", 1).replacen("35390", "3", 1);
+
+        assert_eq!(Err(AocError::parse("Expected input lines to all have the same length, but got: 5, 3, 1")), parse_input_with(&ragged, Padding::Reject));
+        assert_eq!(Err(AocError::parse_at(1, 2, "Invalid tree height 'x'")), parse_input_with("3x373\n", Padding::Zero));
+
+        // Short trees fill the gaps, the trees next to them are visible across them.
+        let zero = parse_input_with(&ragged, Padding::Zero).unwrap();
+        assert_eq!(parse_input(TEST_INPUT).unwrap().trees.bounds, zero.trees.bounds);
+        assert_eq!("\
+            30373\n\
+            255.0\n\
+            65.32\n\
+            33549\n\
+            30000\
+        ", zero.render_visibility());
+        assert_eq!(23, zero.get_visible_tree_count());
+        assert_eq!(Some(8), zero.get_best_scenic_score());
+
+        // The gaps are open sky: the trees next to them are visible as well, but missing trees are not counted.
+        let none = parse_input_with(&ragged, Padding::None).unwrap();
+        assert_eq!(zero.trees.bounds, none.trees.bounds);
+        assert_eq!("\
+            30373\n\
+            255..\n\
+            65.32\n\
+            33549\n\
+            3....\
+        ", none.render_visibility());
+        assert_eq!(18, none.get_visible_tree_count());
+        assert_eq!(Some(3), none.get_best_scenic_score());
+    }
+}
@@ -1,28 +1,63 @@
-use std::cmp;
+use std::{cmp, env};
 use std::collections::HashMap;
-use crate::days::Day;
+use crate::days::{Day, Explanation, Solver, solve_part1, solve_part2, parse_only, ParseSummary};
+use crate::util::error::AocError;
 use crate::util::geometry::{Bounds, Directions, Grid, Point};
+use crate::util::moves;
 use crate::util::number::parse_usize;
 
 pub const DAY9: Day = Day {
-    puzzle1,
-    puzzle2
+    puzzle1: solve_part1::<Day09>,
+    puzzle2: solve_part2::<Day09>,
+    parse: parse_only::<Day09>,
+    describe: "Rope Bridge: track the positions visited by the tail of a rope",
+    explain: Some(explain),
+    selftest: None,
+    algorithms: &[]
 };
 
-fn puzzle1(input: &String) {
-    let steps = parse_input(input).unwrap();
-    let mut sim = Simulation::new(2);
-    steps.iter().for_each(|s| sim.apply_step(s));
-    let visited_spots = sim.get_tail_position_count();
-    println!("Tail (2 knots) visited {} different spots in the simulation", visited_spots);
+fn explain() -> String {
+    Explanation {
+        approach: "Simulate the rope knot by knot, every knot following the previous one when it is more than one step away, and record the tail positions in a grid.",
+        complexity: "O(steps * knots).",
+        alternatives: &[]
+    }.to_string()
 }
 
-fn puzzle2(input: &String) {
-    let steps = parse_input(input).unwrap();
-    let mut sim = Simulation::new(10);
-    steps.iter().for_each(|s| sim.apply_step(s));
-    let visited_spots = sim.get_tail_position_count();
-    println!("Tail (10 knots) visited {} different spots in the simulation", visited_spots);
+#[derive(Default)]
+struct Day09;
+
+impl Solver for Day09 {
+    type Output = usize;
+
+    fn part1(&self, input: &str) -> Result<Self::Output, AocError> {
+        let steps = parse_input(input)?;
+        let mut sim = Simulation::new(2);
+        steps.iter().for_each(|s| sim.apply_step(s));
+        Ok(sim.get_tail_position_count())
+    }
+
+    fn part2(&self, input: &str) -> Result<Self::Output, AocError> {
+        let steps = parse_input(input)?;
+        let mut sim = Simulation::new(10);
+        steps.iter().for_each(|s| sim.apply_step(s));
+        if env::var("AOC_VERBOSE").is_ok() {
+            let bounds = sim.get_tail_bounds();
+            println!("Tail area: {}x{} starting at {}", bounds.width, bounds.height, Point::from((bounds.left, bounds.top)));
+            if let Some((point, visits)) = sim.get_most_visited() {
+                println!("Most visited: {} ({} steps)", point, visits);
+            }
+            println!("{}", sim.render_heatmap());
+        }
+        Ok(sim.get_tail_position_count())
+    }
+
+    fn parse(&self, input: &str) -> Result<ParseSummary, AocError> {
+        let steps = parse_input(input)?;
+        Ok(ParseSummary::new()
+            .count(steps.len(), "steps")
+            .count(steps.iter().map(|s| s.amount).sum(), "moves"))
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -39,14 +74,15 @@ struct Step {
 #[derive(Clone, Debug, Eq, PartialEq)]
 struct Simulation {
     knots: Vec<Point>,
-    tail_history: Grid<bool>
+    // Number of steps the tail spent on every position (including its starting position)
+    tail_history: Grid<usize>
 }
 
 impl Simulation {
     fn new(knot_count: usize) -> Self {
         Simulation {
             knots: (0..knot_count).map(|_| (0, 0).into()).collect(),
-            tail_history: Grid::new(HashMap::from([((0, 0).into(), true)]))
+            tail_history: Grid::new(HashMap::from([((0, 0).into(), 1)]))
         }
     }
 
@@ -56,14 +92,13 @@ impl Simulation {
         for _ in 0..step.amount {
             for i in  0..self.knots.len() {
                 if i == 0 {
-                    let knot = self.knots.get_mut(i).unwrap();
                     // Head, we'll just be moving that:
-                    match step.direction {
-                        Direction::Up => { knot.y -= 1 },
-                        Direction::Right => { knot.x += 1 },
-                        Direction::Down => { knot.y += 1 },
-                        Direction::Left => { knot.x -= 1 },
-                    }
+                    self.knots[i] += match step.direction {
+                        Direction::Up => (0, -1),
+                        Direction::Right => (1, 0),
+                        Direction::Down => (0, 1),
+                        Direction::Left => (-1, 0),
+                    };
                 } else {
                     let knot = self.knots[i];
                     // Next knot, follow previous if needed:
@@ -91,19 +126,49 @@ impl Simulation {
             }
 
             // Tail, keep track of visited places:
-            self.tail_history.set(self.knots.last().unwrap().clone(), true);
+            let tail = *self.knots.last().unwrap();
+            let visits = self.tail_history.get(&tail).unwrap_or_default();
+            self.tail_history.set(tail, visits + 1);
         }
     }
 
     fn get_tail_position_count(&self) -> usize {
-        self.tail_history.values().into_iter().filter(|v| v.clone()).count()
+        self.tail_history.count(|v| *v > 0)
+    }
+
+    fn get_tail_bounds(&self) -> Bounds {
+        self.tail_history.bounds
+    }
+
+    fn get_tail_visits(&self) -> &Grid<usize> {
+        &self.tail_history
+    }
+
+    /// Returns the position the tail spent the most steps on, preferring the top-left most position on ties.
+    fn get_most_visited(&self) -> Option<(Point, usize)> {
+        let mut entries = self.get_tail_visits().entries();
+        entries.sort_by(|(lp, lv), (rp, rv)| rv.cmp(lv).then_with(|| lp.cmp(rp)));
+        entries.first().cloned()
+    }
+
+    /// Renders the tail history as a heatmap, from ' ' (never visited) to '@' (most visited).
+    fn render_heatmap(&self) -> String {
+        const SCALE: [char; 10] = [' ', '.', ':', '-', '=', '+', '*', '#', '%', '@'];
+        let max = self.get_most_visited().map(|(_, v)| v).unwrap_or(0).max(1);
+
+        self.tail_history.bounds.y().map(|y| {
+            self.tail_history.bounds.x().map(|x| {
+                let visits = self.tail_history.get(&(x, y).into()).unwrap_or_default();
+                SCALE[(visits * (SCALE.len() - 1)).div_ceil(max)]
+            }).collect::<String>()
+        }).collect::<Vec<_>>().join("\n")
     }
 
     fn _print_tail_history(&self) {
         print!("{}\n", "-".repeat(self.tail_history.bounds.width));
         for y in self.tail_history.bounds.y() {
             for x in self.tail_history.bounds.x() {
-                print!("{}", if self.tail_history.get(&(x, y).into()).unwrap_or_default() { "#" } else { "." });
+                print!("{}", if self.tail_history.get(&(x, y).into()).unwrap_or_default() > 0 { "#" } else { "." });
             }
             print!("\n");
         }
@@ -119,7 +184,7 @@ impl Simulation {
 
         for y in bounds.y() {
             for x in bounds.x() {
-                let hist_token = if self.tail_history.get(&(x, y).into()) == Some(true) { "#" } else { "." };
+                let hist_token = if self.tail_history.get(&(x, y).into()).unwrap_or_default() > 0 { "#" } else { "." };
                 let point = Point::from((x, y));
 
                 if self.knots[0] == point {
@@ -144,35 +209,40 @@ impl Simulation {
     }
 }
 
-fn parse_input(input: &str) -> Result<Vec<Step>, String> {
-    input.lines().map(|l| if let [left, right] = l.split(" ").collect::<Vec<_>>()[..] {
-        let amount = parse_usize(right)?;
-        match left {
-            "U" => Ok(Step{ direction: Direction::Up, amount }),
-            "R" => Ok(Step{ direction: Direction::Right, amount }),
-            "D" => Ok(Step{ direction: Direction::Down, amount }),
-            "L" => Ok(Step{ direction: Direction::Left, amount }),
-            _ => Err(format!("Invalid direction value: {}", left))
+/// Parses either the standard format (a direction and amount per line), or a compact move string like "R4U4L3".
+fn parse_input(input: &str) -> Result<Vec<Step>, AocError> {
+    if moves::is_compact(input) {
+        return moves::letters_with_amounts(input)?.into_iter().map(|(letter, amount)| parse_step(letter, amount)).collect();
+    }
+
+    input.lines().enumerate().map(|(index, l)| if let [left, right] = l.split(" ").collect::<Vec<_>>()[..] {
+        let amount = parse_usize(right).map_err(|e| e.in_line(index + 1))?;
+        match left.chars().collect::<Vec<_>>()[..] {
+            [letter] => parse_step(letter, amount).map_err(|e| e.in_line(index + 1)),
+            _ => Err(AocError::parse(format!("Invalid direction value: {}", left)).in_line(index + 1))
         }
     } else {
-        Err(format!("Invalid direction line: '{}'", l))
+        Err(AocError::parse(format!("Invalid direction line: '{}'", l)).in_line(index + 1))
     }).collect()
 }
 
+fn parse_step(letter: char, amount: usize) -> Result<Step, AocError> {
+    match letter {
+        'U' => Ok(Step{ direction: Direction::Up, amount }),
+        'R' => Ok(Step{ direction: Direction::Right, amount }),
+        'D' => Ok(Step{ direction: Direction::Down, amount }),
+        'L' => Ok(Step{ direction: Direction::Left, amount }),
+        _ => Err(AocError::parse(format!("Invalid direction value: {}", letter)))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::days::day09::{Direction, parse_input, Simulation, Step};
+    use crate::util::error::AocError;
+    use crate::util::geometry::{Bounds, Point};
 
-    const TEST_INPUT: &str = "\
-        R 4\n\
-        U 4\n\
-        L 3\n\
-        D 1\n\
-        R 4\n\
-        D 1\n\
-        L 5\n\
-        R 2\n\
-    ";
+    const TEST_INPUT: &str = include_str!("../../examples/day09/input.txt");
 
     const TEST_INPUT_2: &str = "\
         R 5\n\
@@ -194,6 +264,19 @@ mod tests {
         assert_eq!(Step { direction: Direction::Up, amount: 4 }, steps[1]);
     }
 
+    #[test]
+    fn test_parse_compact_input() {
+        let compact: String = TEST_INPUT.lines().map(|l| l.replace(" ", "")).collect();
+        assert_eq!("R4U4L3D1R4D1L5R2", compact);
+        assert_eq!(parse_input(TEST_INPUT), parse_input(&compact));
+        assert_eq!(parse_input(TEST_INPUT_2), parse_input("R5U8L8D3R17D10L25U20\n"));
+
+        assert_eq!(Err(AocError::parse("Invalid direction value: X")), parse_input("R4X2"));
+        assert_eq!(Err(AocError::parse("Invalid direction value: X").in_line(2)), parse_input("R 4\nX 2"));
+        assert_eq!(Err(AocError::parse("Invalid direction value: RU").in_line(2)), parse_input("R 4\nRU 2"));
+        assert!(parse_input("R4U").is_err());
+    }
+
     #[test]
     fn test_apply_step() {
         let mut sim = Simulation::new(2);
@@ -206,6 +289,19 @@ mod tests {
         assert_eq!(sim.knots[1], (4, -3).into());
     }
 
+    #[test]
+    fn test_tail_statistics() {
+        let mut sim = Simulation::new(2);
+        let steps = parse_input(TEST_INPUT).unwrap();
+        steps.iter().for_each(|s| sim.apply_step(s));
+
+        assert_eq!(Bounds::from_tlbr(-4, 0, 0, 4), sim.get_tail_bounds());
+        assert_eq!(25, sim.get_tail_visits().values().iter().sum::<usize>()); // Starting position + 24 steps
+        assert_eq!(Some(2), sim.get_tail_visits().get(&(0, 0).into()));
+        assert_eq!(Some((Point::from((4, -3)), 6)), sim.get_most_visited());
+        assert_eq!("  *: \n   :@\n +:::\n    :\n-::- ", sim.render_heatmap());
+    }
+
     #[test]
     fn test_get_tail_position_count() {
         let mut sim = Simulation::new(2);
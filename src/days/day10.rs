@@ -1,48 +1,56 @@
-use crate::days::Day;
-use crate::util::number::parse_isize;
+use crate::days::{Day, Explanation, Solver, solve_part1, solve_part2, parse_only, ParseSummary};
+use crate::util::error::AocError;
+use crate::util::answer::Answer;
+use crate::util::vm::{Dialect, execute, parse_program, Program};
 
 pub const DAY10: Day = Day {
-    puzzle1,
-    puzzle2,
+    puzzle1: solve_part1::<Day10>,
+    puzzle2: solve_part2::<Day10>,
+    parse: parse_only::<Day10>,
+    describe: "Cathode-Ray Tube: sample signal strengths and render the CRT output",
+    explain: Some(explain),
+    selftest: None,
+    algorithms: &[]
 };
 
-fn puzzle1(input: &String) {
-    let program = parse_input(input).unwrap();
-    let signals = execute_for_puzzle_1(&program);
-    let signal_sum = signals.iter().take(6).sum::<isize>();
-
-    println!("Sum of 6 target signals = {}", signal_sum);
+fn explain() -> String {
+    Explanation {
+        approach: "Run the program on the shared VM and sample the X register during every cycle, for the signal strengths and the CRT pixels.",
+        complexity: "O(cycles).",
+        alternatives: &[]
+    }.to_string()
 }
 
-fn puzzle2(input: &String) {
-    let program = parse_input(input).unwrap();
-    let pixels = execute_for_puzzle_2(&program);
+#[derive(Default)]
+struct Day10;
 
-    println!("Puzzle 2; screen output:");
-    for line in pixels {
-        for pixel in line {
-            print!("{}", pixel);
-        }
-        print!("\n");
+impl Solver for Day10 {
+    // Puzzle 1 is a number, puzzle 2 the text drawn on the screen.
+    type Output = Answer;
+
+    fn part1(&self, input: &str) -> Result<Self::Output, AocError> {
+        let program = parse_input(input)?;
+        let signals = execute_for_puzzle_1(&program);
+
+        Ok(signals.iter().sum::<isize>().into())
     }
-}
 
-#[derive(Clone, Debug, Eq, PartialEq)]
-enum Operation {
-    Noop,
-    Add(isize),
+    fn part2(&self, input: &str) -> Result<Self::Output, AocError> {
+        let program = parse_input(input)?;
+        let pixels = execute_for_puzzle_2(&program);
+
+        let screen: Vec<String> = pixels.iter().map(|line| line.iter().collect()).collect();
+        Ok(screen.join("\n").into())
+    }
+
+    fn parse(&self, input: &str) -> Result<ParseSummary, AocError> {
+        let program = parse_input(input)?;
+        Ok(ParseSummary::new().count(program.instructions.len(), "instructions"))
+    }
 }
 
-fn parse_input(input: &str) -> Result<Vec<Operation>, String> {
-    input.lines().map(|l|
-        if l == "noop" {
-            Ok(Operation::Noop)
-        } else if l.starts_with("addx ") {
-            Ok(Operation::Add(parse_isize(&l[5..])?))
-        } else {
-            Err(format!("Invalid operation: '{}'", l))
-        }
-    ).collect()
+fn parse_input(input: &str) -> Result<Program, AocError> {
+    parse_program(input, Dialect::Day10)
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -51,46 +59,43 @@ struct CpuState {
     cycle_count: usize,
 }
 
-fn execute_program<C>(program: &Vec<Operation>, mut cycle_callback: C) -> CpuState
+fn execute_program<C>(program: &Program, mut cycle_callback: C) -> CpuState
     where C: FnMut(CpuState) -> ()
 {
     // The CPU has a single register, X, which starts with the value 1. It supports only two instructions:
     //
     // addx V takes two cycles to complete. After two cycles, the X register is increased by the value V. (V can be negative.)
     // noop takes one cycle to complete. It has no other effect.
-    let mut reg_x: isize = 1;
-    let mut cycle_count: usize = 0;
-
-    for operation in program {
-        match operation {
-            Operation::Noop => {
-                cycle_count += 1;
-                cycle_callback(CpuState { reg_x, cycle_count });
-            }
-            Operation::Add(value) => {
-                cycle_count += 1;
-                cycle_callback(CpuState { reg_x, cycle_count });
-                cycle_count += 1;
-                cycle_callback(CpuState { reg_x, cycle_count });
-                reg_x += value;
-            }
-        }
-    }
+    let state = execute(program, |state| {
+        cycle_callback(CpuState { reg_x: state.register('x'), cycle_count: state.cycle_count });
+    });
 
-    CpuState { reg_x, cycle_count }
+    CpuState { reg_x: state.register('x'), cycle_count: state.cycle_count }
 }
 
-fn execute_for_puzzle_1(program: &Vec<Operation>) -> Vec<isize> {
+// (cycle, value of register X during the cycle, signal strength)
+type Sample = (usize, isize, isize);
+
+fn signal_strengths(program: &Program) -> Vec<Sample> {
     let mut results = vec![];
     execute_program(program, |state| {
-        if state.cycle_count >= 20 && (state.cycle_count - 20) % 40 == 0 {
-            results.push(state.cycle_count as isize * state.reg_x);
-        }
+        results.push((state.cycle_count, state.reg_x, state.cycle_count as isize * state.reg_x));
     });
     results
 }
 
-fn execute_for_puzzle_2(program: &Vec<Operation>) -> [[char;40];6] {
+/// Returns the samples for the given cycles, in the order of the cycles. Cycles the program never reached are skipped.
+fn sample_at(samples: &[Sample], cycles: &[usize]) -> Vec<Sample> {
+    cycles.iter().filter_map(|cycle| samples.iter().find(|(c, _, _)| c == cycle)).cloned().collect()
+}
+
+const PUZZLE_1_CYCLES: [usize; 6] = [20, 60, 100, 140, 180, 220];
+
+fn execute_for_puzzle_1(program: &Program) -> Vec<isize> {
+    sample_at(&signal_strengths(program), &PUZZLE_1_CYCLES).iter().map(|(_, _, strength)| *strength).collect()
+}
+
+fn execute_for_puzzle_2(program: &Program) -> [[char;40];6] {
     let mut results = [['.'; 40]; 6];
 
     // It seems like the X register controls the horizontal position of a sprite. Specifically, the
@@ -115,17 +120,20 @@ fn execute_for_puzzle_2(program: &Vec<Operation>) -> [[char;40];6] {
 
 #[cfg(test)]
 mod tests {
-    use crate::days::day10::{execute_for_puzzle_1, execute_for_puzzle_2, Operation, parse_input};
+    use crate::days::day10::{execute_for_puzzle_1, execute_for_puzzle_2, parse_input, sample_at, signal_strengths};
+    use crate::util::answer::Answer;
+    use crate::util::vm::Instruction;
 
     #[test]
     fn test_parse_input() {
-        assert_eq!(Ok(vec![Operation::Noop, Operation::Add(3), Operation::Add(-5)]), parse_input(
+        assert_eq!(Ok(vec![Instruction::Noop, Instruction::Add(3), Instruction::Add(-5)]), parse_input(
             "\
                 noop\n\
                 addx 3\n\
                 addx -5\n\
             "
-        ));
+        ).map(|p| p.instructions));
+        assert!(parse_input("noop\njnz x 0\n").is_err());
     }
 
     #[test]
@@ -136,6 +144,21 @@ mod tests {
         assert_eq!(vec![420, 1140, 1800, 2940, 2880, 3960], result);
     }
 
+    #[test]
+    fn test_signal_strengths() {
+        let program = parse_input("noop\naddx 3\naddx -5\n").unwrap();
+        assert_eq!(vec![(1, 1, 1), (2, 1, 2), (3, 1, 3), (4, 4, 16), (5, 4, 20)], signal_strengths(&program));
+    }
+
+    #[test]
+    fn test_sample_at() {
+        let program = parse_input(TEST_INPUT).unwrap();
+        let samples = signal_strengths(&program);
+
+        assert_eq!(240, samples.len());
+        assert_eq!(vec![(220, 18, 3960), (20, 21, 420), (60, 19, 1140)], sample_at(&samples, &[220, 20, 60, 1000]));
+    }
+
     #[test]
     fn test_execute_for_puzzle_2() {
         let program = parse_input(TEST_INPUT).unwrap();
@@ -151,152 +174,16 @@ mod tests {
         ], result)
     }
 
-    const TEST_INPUT: &str = "\
-        addx 15\n\
-        addx -11\n\
-        addx 6\n\
-        addx -3\n\
-        addx 5\n\
-        addx -1\n\
-        addx -8\n\
-        addx 13\n\
-        addx 4\n\
-        noop\n\
-        addx -1\n\
-        addx 5\n\
-        addx -1\n\
-        addx 5\n\
-        addx -1\n\
-        addx 5\n\
-        addx -1\n\
-        addx 5\n\
-        addx -1\n\
-        addx -35\n\
-        addx 1\n\
-        addx 24\n\
-        addx -19\n\
-        addx 1\n\
-        addx 16\n\
-        addx -11\n\
-        noop\n\
-        noop\n\
-        addx 21\n\
-        addx -15\n\
-        noop\n\
-        noop\n\
-        addx -3\n\
-        addx 9\n\
-        addx 1\n\
-        addx -3\n\
-        addx 8\n\
-        addx 1\n\
-        addx 5\n\
-        noop\n\
-        noop\n\
-        noop\n\
-        noop\n\
-        noop\n\
-        addx -36\n\
-        noop\n\
-        addx 1\n\
-        addx 7\n\
-        noop\n\
-        noop\n\
-        noop\n\
-        addx 2\n\
-        addx 6\n\
-        noop\n\
-        noop\n\
-        noop\n\
-        noop\n\
-        noop\n\
-        addx 1\n\
-        noop\n\
-        noop\n\
-        addx 7\n\
-        addx 1\n\
-        noop\n\
-        addx -13\n\
-        addx 13\n\
-        addx 7\n\
-        noop\n\
-        addx 1\n\
-        addx -33\n\
-        noop\n\
-        noop\n\
-        noop\n\
-        addx 2\n\
-        noop\n\
-        noop\n\
-        noop\n\
-        addx 8\n\
-        noop\n\
-        addx -1\n\
-        addx 2\n\
-        addx 1\n\
-        noop\n\
-        addx 17\n\
-        addx -9\n\
-        addx 1\n\
-        addx 1\n\
-        addx -3\n\
-        addx 11\n\
-        noop\n\
-        noop\n\
-        addx 1\n\
-        noop\n\
-        addx 1\n\
-        noop\n\
-        noop\n\
-        addx -13\n\
-        addx -19\n\
-        addx 1\n\
-        addx 3\n\
-        addx 26\n\
-        addx -30\n\
-        addx 12\n\
-        addx -1\n\
-        addx 3\n\
-        addx 1\n\
-        noop\n\
-        noop\n\
-        noop\n\
-        addx -9\n\
-        addx 18\n\
-        addx 1\n\
-        addx 2\n\
-        noop\n\
-        noop\n\
-        addx 9\n\
-        noop\n\
-        noop\n\
-        noop\n\
-        addx -1\n\
-        addx 2\n\
-        addx -37\n\
-        addx 1\n\
-        addx 3\n\
-        noop\n\
-        addx 15\n\
-        addx -21\n\
-        addx 22\n\
-        addx -6\n\
-        addx 1\n\
-        noop\n\
-        addx 2\n\
-        addx 1\n\
-        noop\n\
-        addx -10\n\
-        noop\n\
-        noop\n\
-        addx 20\n\
-        addx 1\n\
-        addx 2\n\
-        addx 2\n\
-        addx -6\n\
-        addx -11\n\
-        noop\n\
-        noop\n\
-        noop\n\
-    ";
+    #[test]
+    fn test_expected_answers() {
+        let program = parse_input(TEST_INPUT).unwrap();
+
+        let signal_sum: isize = execute_for_puzzle_1(&program).iter().sum();
+        assert!(Answer::from(signal_sum).matches(include_str!("../../examples/day10/part1.expected")));
+
+        let screen: Vec<String> = execute_for_puzzle_2(&program).iter().map(|line| line.iter().collect()).collect();
+        assert!(Answer::from(screen.join("\n")).matches(include_str!("../../examples/day10/part2.expected")));
+    }
+
+    const TEST_INPUT: &str = include_str!("../../examples/day10/input.txt");
 }
\ No newline at end of file
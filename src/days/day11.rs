@@ -1,53 +1,237 @@
+use std::collections::HashMap;
+use std::{env, fmt};
 use std::str::FromStr;
-use crate::days::Day;
+use rayon::prelude::*;
+use crate::days::{Day, Explanation, Solver, solve_part1, solve_part2, parse_only, ParseSummary};
+use crate::util::error::AocError;
+use crate::util::collection::CollectionExtension;
+use crate::util::config::day11::{ALGORITHM, REDUCE_WORRY, ROUNDS_PUZZLE1, ROUNDS_PUZZLE2, WIDEN_WORRY};
 use crate::util::number::{NumberExtensions, parse_usize};
+use crate::util::parser::Parser;
 
 pub const DAY11: Day = Day {
-    puzzle1,
-    puzzle2
+    puzzle1: solve_part1::<Day11>,
+    puzzle2: solve_part2::<Day11>,
+    parse: parse_only::<Day11>,
+    describe: "Monkey in the Middle: simulate monkeys throwing items and find the monkey business",
+    explain: Some(explain),
+    selftest: None,
+    algorithms: ALGORITHM.options
 };
 
-fn puzzle1(input: &String) {
-    let mut simulation = Simulation::create(parse_input(input).unwrap(), SimulationVersion::Puzzle1);
+fn explain() -> String {
+    Explanation {
+        approach: "Simulate the monkeys, keeping worry levels modulo the LCM of all divisors in part 2. Monkeys that only throw within their own group are simulated in parallel. Worry levels are checked for overflow, and continue as u128 once they no longer fit a usize.",
+        complexity: "O(rounds * items).",
+        alternatives: &["Simulate all monkeys one after the other, set AOC_DAY11_ALGORITHM=sequential."]
+    }.to_string()
+}
 
-    let monkey_business = simulation.play_puzzle(20);
+#[derive(Default)]
+struct Day11;
 
-    println!("Monkey business level: {}", monkey_business);
-}
+impl Solver for Day11 {
+    type Output = usize;
 
-fn puzzle2(input: &String) {
-    let mut simulation = Simulation::create(parse_input(input).unwrap(), SimulationVersion::Puzzle2);
+    fn part1(&self, input: &str) -> Result<Self::Output, AocError> {
+        let mut simulation = Simulation::create(parse_input(input)?, SimulationVersion::Puzzle1)
+            .with_config(SimulationConfig::from_settings()?);
+        if env::var("AOC_VERBOSE").is_ok() {
+            println!("{}", ThrowAnalysis::create(&simulation.monkeys));
+        }
 
-    let monkey_business = simulation.play_puzzle(10000);
+        let monkey_business = play(&mut simulation, ROUNDS_PUZZLE1.get()?)?;
+        print_widening(&simulation);
+
+        Ok(monkey_business)
+    }
+
+    fn part2(&self, input: &str) -> Result<Self::Output, AocError> {
+        let mut simulation = Simulation::create(parse_input(input)?, SimulationVersion::Puzzle2)
+            .with_config(SimulationConfig::from_settings()?);
+
+        let monkey_business = play(&mut simulation, ROUNDS_PUZZLE2.get()?)?;
+        print_widening(&simulation);
+
+        Ok(monkey_business)
+    }
 
-    println!("Monkey business level: {}", monkey_business);
+    fn parse(&self, input: &str) -> Result<ParseSummary, AocError> {
+        let monkeys = parse_input(input)?;
+        Ok(ParseSummary::new()
+            .count(monkeys.len(), "monkeys")
+            .count(monkeys.iter().map(|m| m.items.len()).sum(), "items"))
+    }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+/// Plays the rounds with the algorithm chosen with `AOC_DAY11_ALGORITHM`: in parallel groups (default) or sequential.
+fn play(simulation: &mut Simulation, rounds: usize) -> Result<usize, String> {
+    match ALGORITHM.get()? {
+        "sequential" => simulation.play_puzzle(rounds),
+        _ => simulation.play_puzzle_parallel(rounds)
+    }
+}
+
+fn print_widening(simulation: &Simulation) {
+    if let (Some(round), true) = (simulation.widened_in_round, env::var("AOC_VERBOSE").is_ok()) {
+        println!("Worry levels no longer fit a usize in round {}, continued with u128", round);
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 enum SimulationVersion {
     Puzzle1,
     Puzzle2
 }
 
+/// Which monkeys throw to which, and the groups of monkeys that only ever throw items amongst themselves.
+#[derive(Debug, Eq, PartialEq)]
+struct ThrowAnalysis {
+    targets: Vec<(usize, Vec<usize>)>,
+    groups: Vec<Vec<usize>>
+}
+
+impl ThrowAnalysis {
+    fn create(monkeys: &[Monkey]) -> Self {
+        let index_of: HashMap<usize, usize> = monkeys.iter().enumerate().map(|(i, m)| (m.id, i)).collect();
+        let targets: Vec<_> = monkeys.iter().map(|m| {
+            let mut to = vec![m.test.true_to, m.test.false_to];
+            to.dedup();
+            (m.id, to)
+        }).collect();
+
+        // Union-find over the throw edges, the resulting sets are the independent groups.
+        fn find(parents: &mut Vec<usize>, i: usize) -> usize {
+            if parents[i] != i {
+                parents[i] = find(parents, parents[i]);
+            }
+            parents[i]
+        }
+        let mut parents: Vec<usize> = (0..monkeys.len()).collect();
+        for (from, to) in &targets {
+            for target in to.iter().filter_map(|t| index_of.get(t)) {
+                let (a, b) = (find(&mut parents, index_of[from]), find(&mut parents, *target));
+                parents[a.max(b)] = a.min(b);
+            }
+        }
+
+        let mut groups: Vec<Vec<usize>> = vec![];
+        let mut group_of_root: HashMap<usize, usize> = HashMap::new();
+        for (i, monkey) in monkeys.iter().enumerate() {
+            let root = find(&mut parents, i);
+            let group = *group_of_root.entry(root).or_insert_with(|| { groups.push(vec![]); groups.len() - 1 });
+            groups[group].push(monkey.id);
+        }
+
+        ThrowAnalysis { targets, groups }
+    }
+
+    fn is_parallelizable(&self) -> bool {
+        self.groups.len() > 1
+    }
+}
+
+impl fmt::Display for ThrowAnalysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (from, to) in &self.targets {
+            writeln!(f, "Monkey {} throws to {}", from, to.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(", "))?;
+        }
+        let groups: Vec<_> = self.groups.iter()
+            .map(|g| format!("[{}]", g.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(", ")))
+            .collect();
+        write!(f, "{} independent group(s): {}", self.groups.len(), groups.join(" "))
+    }
+}
+
+/// How worry levels are kept in check, see `REDUCE_WORRY` and `WIDEN_WORRY`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct SimulationConfig {
+    reduce: bool,
+    widen: bool
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        SimulationConfig { reduce: true, widen: true }
+    }
+}
+
+impl SimulationConfig {
+    fn from_settings() -> Result<Self, String> {
+        Ok(SimulationConfig { reduce: REDUCE_WORRY.get()?, widen: WIDEN_WORRY.get()? })
+    }
+}
+
 #[derive(Debug)]
 struct Simulation {
     version: SimulationVersion,
-    monkeys: Vec<Monkey>
+    monkeys: Vec<Monkey>,
+    modulus: usize,
+    config: SimulationConfig,
+    round: usize,
+    widened_in_round: Option<usize>
 }
 
 impl Simulation {
     fn create(monkeys: Vec<Monkey>, version: SimulationVersion) -> Self {
-        Self { monkeys, version }
+        // To keep values a bit manageable (and this code fast), we can leverage the following maths:
+        // - n^y mod n = 0
+        // - (a + b) mod n = (a mod n) + (b mod n)
+        // From this, we can see that if we find the LCM of the divisors (X) used by the monkeys,
+        // we get a value that will yield 0 for all `X mod n` operations of the monkeys. As such
+        // we will only need to store the remainder (R) of the new value from that value, as:
+        // (X + R) mod n = (X mod n) + (R mod n) = 0 + (R mod n) = R mod n!
+        let modulus = monkeys.iter().map(|m| m.test.div_by).lcm();
+        Self { monkeys, version, modulus, config: SimulationConfig::default(), round: 0, widened_in_round: None }
     }
 
-    fn play_puzzle(&mut self, rounds: usize) -> usize {
+    fn with_config(self, config: SimulationConfig) -> Self {
+        Simulation { config, ..self }
+    }
+
+    fn play_puzzle(&mut self, rounds: usize) -> Result<usize, String> {
         // Chasing all of the monkeys at once is impossible; you're going to have to focus on the
         // two most active monkeys if you want any hope of getting your stuff back.
         // Count the total number of times each monkey inspects items over 20 rounds:
+        self.play_rounds(rounds)?;
+        Ok(self.get_monkey_business())
+    }
+
+    /// Same as `play_puzzle`, but when the monkeys form independent groups these are simulated in parallel.
+    fn play_puzzle_parallel(&mut self, rounds: usize) -> Result<usize, String> {
+        let analysis = ThrowAnalysis::create(&self.monkeys);
+        if !analysis.is_parallelizable() {
+            return self.play_puzzle(rounds);
+        }
+
+        let group_of: HashMap<usize, usize> = analysis.groups.iter().enumerate()
+            .flat_map(|(group, ids)| ids.iter().map(move |id| (*id, group)))
+            .collect();
+        let mut groups: Vec<Simulation> = analysis.groups.iter()
+            .map(|_| Simulation { monkeys: vec![], ..*self })
+            .collect();
+        for monkey in self.monkeys.drain(..) {
+            groups[group_of[&monkey.id]].monkeys.push(monkey);
+        }
+
+        let results: Vec<_> = groups.par_iter_mut().map(|group| group.play_rounds(rounds)).collect();
+        results.into_iter().collect::<Result<(), String>>()?;
+
+        self.round = groups.iter().map(|g| g.round).max().unwrap_or(self.round);
+        self.widened_in_round = groups.iter().filter_map(|g| g.widened_in_round).min();
+        self.monkeys = groups.into_iter().flat_map(|g| g.monkeys).collect();
+        self.monkeys.sort_by_key(|m| m.id);
+        Ok(self.get_monkey_business())
+    }
+
+    fn play_rounds(&mut self, rounds: usize) -> Result<(), String> {
         for _ in 0..rounds {
-            self.play_round();
+            self.play_round()?;
         }
+        Ok(())
+    }
 
+    fn get_monkey_business(&self) -> usize {
         // The level of monkey business in this situation can be found by multiplying these together:
         let mut monkey_business: Vec<_> = self.monkeys.iter().map(|m| m.inspect_count).collect();
         monkey_business.sort();
@@ -55,24 +239,17 @@ impl Simulation {
         monkey_business[..2].iter().fold(1, |l,r| l*r)
     }
 
-    fn play_round(&mut self) {
+    fn play_round(&mut self) -> Result<(), String> {
+        self.round += 1;
         // For every monkey in order, inspect & yeet all items
         let monkey_ids: Vec<_> = self.monkeys.iter().map(|m| m.id).collect();
         for monkey_id in monkey_ids {
-            self.inspect_and_yeet(monkey_id);
+            self.inspect_and_yeet(monkey_id)?;
         }
+        Ok(())
     }
 
-    fn inspect_and_yeet(&mut self, monkey_id: usize) {
-        // To keep values a bit manageable (and this code fast), we can leverage the following maths:
-        // - n^y mod n = 0
-        // - (a + b) mod n = (a mod n) + (b mod n)
-        // From this, we can see that if we find the LCM of the divisors (X) used by the monkeys,
-        // we get a value that will yield 0 for all `X mod n` operations of the monkeys. As such
-        // we will only need to store the remainder (R) of the new value from that value, as:
-        // (X + R) mod n = (X mod n) + (R mod n) = 0 + (R mod n) = R mod n!
-        let lcm = self.monkeys.iter().map(|m| m.test.div_by).collect::<Vec<_>>().lcm();
-
+    fn inspect_and_yeet(&mut self, monkey_id: usize) -> Result<(), String> {
         let mut yeets = vec![];
 
         if let Some(monkey) = self.monkeys.iter_mut().find(|m| m.id == monkey_id) {
@@ -80,18 +257,24 @@ impl Simulation {
             monkey.items.clear();
             for item in items_to_yeet {
                 // Increase worry value of item based on operation
-                let mut value = monkey.operation.apply(item);
+                let mut value = monkey.operation.apply(item, self.config.widen)
+                    .map_err(|e| format!("Monkey {} in round {}: {}", monkey.id, self.round, e))?;
+                if matches!((item, value), (Worry::Narrow(_), Worry::Wide(_))) && self.widened_in_round.is_none() {
+                    self.widened_in_round = Some(self.round);
+                }
                 monkey.inspect_count += 1;
                 if self.version == SimulationVersion::Puzzle1 {
                     // Divide by three (rounding down) in relief the item is fine
-                    value /= 3;
+                    value = value.map(|v| v / 3, |v| v / 3);
                 }
 
-                value = value % lcm;
+                if self.config.reduce {
+                    value = value.map(|v| v % self.modulus, |v| v % self.modulus as u128);
+                }
 
-                let to_yeet = value.clone();
+                let to_yeet = value;
                 // Decide where to yeet it:
-                let target = if value % monkey.test.div_by == 0 { monkey.test.true_to } else { monkey.test.false_to };
+                let target = if value.is_divisible_by(monkey.test.div_by) { monkey.test.true_to } else { monkey.test.false_to };
                 // Note: I'd really want to just yeet this to the target monkey, but rust doesn't allow
                 // me to get a second mutable monkey in the same scope. Which kinda makes sense, given
                 // this being a loop and all..
@@ -104,18 +287,62 @@ impl Simulation {
                 monkey.items.push(value);
             }
         }
+        Ok(())
     }
 }
 
 #[derive(Debug)]
 struct Monkey {
     id: usize,
-    items: Vec<usize>,
+    items: Vec<Worry>,
     operation: Operation,
     test: Test,
     inspect_count: usize
 }
 
+/// A worry level, kept in a usize until it no longer fits one (when `SimulationConfig::widen` allows it).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Worry {
+    Narrow(usize),
+    Wide(u128)
+}
+
+impl Worry {
+    /// Back to a usize when the value fits one again.
+    fn wide(value: u128) -> Self {
+        usize::try_from(value).map_or(Worry::Wide(value), Worry::Narrow)
+    }
+
+    fn map(self, narrow: impl FnOnce(usize) -> usize, wide: impl FnOnce(u128) -> u128) -> Self {
+        match self {
+            Worry::Narrow(v) => Worry::Narrow(narrow(v)),
+            Worry::Wide(v) => Worry::wide(wide(v))
+        }
+    }
+
+    fn is_divisible_by(&self, div_by: usize) -> bool {
+        match self {
+            Worry::Narrow(v) => v % div_by == 0,
+            Worry::Wide(v) => v % div_by as u128 == 0
+        }
+    }
+}
+
+impl fmt::Display for Worry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Worry::Narrow(v) => write!(f, "{}", v),
+            Worry::Wide(v) => write!(f, "{}", v)
+        }
+    }
+}
+
+impl PartialEq<Worry> for usize {
+    fn eq(&self, other: &Worry) -> bool {
+        Worry::Narrow(*self) == *other
+    }
+}
+
 #[derive(Debug, Eq, PartialEq)]
 enum OperationValue {
     Input,
@@ -126,7 +353,23 @@ impl OperationValue {
     fn get(&self, input: usize) -> usize {
         match self {
             OperationValue::Input => input,
-            OperationValue::Value(val) => val.clone()
+            OperationValue::Value(val) => *val
+        }
+    }
+
+    fn get_wide(&self, input: u128) -> u128 {
+        match self {
+            OperationValue::Input => input,
+            OperationValue::Value(val) => *val as u128
+        }
+    }
+}
+
+impl fmt::Display for OperationValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OperationValue::Input => write!(f, "old"),
+            OperationValue::Value(val) => write!(f, "{}", val)
         }
     }
 }
@@ -149,10 +392,38 @@ enum Operation {
 }
 
 impl Operation {
-    fn apply(&self, input: usize) -> usize {
+    /// The new worry level. When it overflows a usize it continues as a u128 if `widen` allows it, otherwise
+    /// (or when it overflows even a u128) it is an error.
+    fn apply(&self, input: Worry, widen: bool) -> Result<Worry, String> {
+        let wide_input = match input {
+            Worry::Narrow(input) => {
+                let result = match self {
+                    Operation::Add(lhs, rhs) => lhs.get(input).checked_add(rhs.get(input)),
+                    Operation::Multiply(lhs, rhs) => lhs.get(input).checked_mul(rhs.get(input))
+                };
+                if let Some(result) = result {
+                    return Ok(Worry::Narrow(result));
+                }
+                if !widen {
+                    return Err(format!("worry level computing new = {} with old = {} does not fit a usize, and widening is disabled", self, input));
+                }
+                input as u128
+            },
+            Worry::Wide(input) => input
+        };
+        let result = match self {
+            Operation::Add(lhs, rhs) => lhs.get_wide(wide_input).checked_add(rhs.get_wide(wide_input)),
+            Operation::Multiply(lhs, rhs) => lhs.get_wide(wide_input).checked_mul(rhs.get_wide(wide_input))
+        };
+        result.map(Worry::wide).ok_or_else(|| format!("worry level overflows computing new = {} with old = {}", self, input))
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Operation::Add(lhs, rhs) => lhs.get(input) + rhs.get(input),
-            Operation::Multiply(lhs, rhs) => lhs.get(input) * rhs.get(input)
+            Operation::Add(lhs, rhs) => write!(f, "{} + {}", lhs, rhs),
+            Operation::Multiply(lhs, rhs) => write!(f, "{} * {}", lhs, rhs)
         }
     }
 }
@@ -164,35 +435,35 @@ struct Test {
     false_to: usize,
 }
 
-fn parse_input(input: &str) -> Result<Vec<Monkey>, String> {
-    let lines: Vec<_> = input.lines().map(|l| l.trim()).collect();
+fn parse_input(input: &str) -> Result<Vec<Monkey>, AocError> {
+    // Every line with its (1-based) line number, to locate errors. Lines are trimmed when parsed, except for the
+    // starting items, whose parser skips the indentation itself and so reports the right column.
+    let lines: Vec<_> = input.lines().enumerate().map(|(index, l)| (index + 1, l)).collect();
 
     // A monkey is defined by 6 lines. (Seriously, not going to make this more generic :joy:)
     // A line with `Monkey {ID}:`
-    fn get_monkey_id(line: &str) -> Result<usize, String> {
+    fn get_monkey_id(line: &str) -> Result<usize, AocError> {
         if line.starts_with("Monkey ") && line.ends_with(":") {
             Ok(parse_usize(line[7..line.len()-1].trim())?)
         } else {
-            Err(format!("Not a monkey identifier: '{}'", line))
+            Err(AocError::parse(format!("Not a monkey identifier: '{}'", line)))
         }
     }
     // A line with starting items
-    fn get_starting_items(line: &str) -> Result<Vec<usize>, String> {
-        if line.starts_with("Starting items: ") {
-            Ok(line[16..].split(",").map(|i| parse_usize(i.trim())).collect::<Result<Vec<_>, _>>()?)
-        } else {
-            Err(format!("Not a starting items line: '{}'", line))
-        }
+    fn get_starting_items(line: &str) -> Result<Vec<Worry>, AocError> {
+        let mut parser = Parser::new(line);
+        parser.literal("Starting items:").map_err(|_| AocError::parse(format!("Not a starting items line: '{}'", line.trim())))?;
+        parser.separated_list(",", |parser| parser.usize().map(Worry::Narrow))
     }
     // A line with an operation (new = {something} {operation} {something})
-    fn get_operation(line: &str) -> Result<Operation, String> {
+    fn get_operation(line: &str) -> Result<Operation, AocError> {
         if !line.starts_with("Operation: new = ") {
-            return Err(format!("Not an operation line: '{}'", line))
+            return Err(AocError::parse(format!("Not an operation line: '{}'", line)))
         }
 
         let parts: Vec<_> = line[17..].trim().split(" ").collect();
         if parts.len() != 3 {
-            return Err(format!("Not an valid operation line: '{}'", line))
+            return Err(AocError::parse(format!("Not an valid operation line: '{}'", line)))
         }
 
         let left: OperationValue = parts[0].parse()?;
@@ -200,31 +471,44 @@ fn parse_input(input: &str) -> Result<Vec<Monkey>, String> {
         match parts[1] {
             "+" => Ok(Operation::Add(left, right)),
             "*" => Ok(Operation::Multiply(left, right)),
-            _ => Err(format!("Invalid operation: '{}'", parts[1]))
+            _ => Err(AocError::parse(format!("Invalid operation: '{}'", parts[1])))
         }
     }
     // A line with a test (divisible by ##)
     // A line for the true branch (If true: throw to monkey {ID})
     // A line for the false branch (If false: throw to monkey {ID})
-    fn get_test(test_line: &str, true_line: &str, false_line: &str) -> Result<Test, String> {
-        let div_by = if test_line.starts_with("Test: divisible by ") { parse_usize(test_line[19..].trim())? } else { return Err(format!("Not a test line '{}'", test_line)); };
-        let true_to = if true_line.starts_with("If true: throw to monkey ") { parse_usize(true_line[25..].trim())? } else { return Err(format!("Not a true line '{}'", true_line)); };
-        let false_to = if false_line.starts_with("If false: throw to monkey ") { parse_usize(false_line[26..].trim())? } else { return Err(format!("Not a false line '{}'", true_line)); };
-
-        Ok(Test { div_by, true_to, false_to })
+    fn get_divisor(line: &str) -> Result<usize, AocError> {
+        match line.strip_prefix("Test: divisible by ") {
+            Some(value) => parse_usize(value.trim()),
+            None => Err(AocError::parse(format!("Not a test line '{}'", line)))
+        }
+    }
+    fn get_target(line: &str, branch: &str) -> Result<usize, AocError> {
+        match line.strip_prefix(&format!("If {}: throw to monkey ", branch)) {
+            Some(value) => parse_usize(value.trim()),
+            None => Err(AocError::parse(format!("Not a {} line '{}'", branch, line)))
+        }
     }
 
     let mut monkeys = vec![];
 
-    for i in (0..lines.len()).step_by(7) {
-        if lines.len() < i + 5 {
-            break;
+    for monkey_lines in lines.split_when(|(_, l)| l.trim().is_empty()) {
+        if monkey_lines.len() != 6 {
+            let text: Vec<_> = monkey_lines.iter().map(|(_, l)| l.trim()).collect();
+            return Err(AocError::parse(format!("Expected 6 lines for a monkey, but got {}: '{}'", monkey_lines.len(), text.join(" / "))).in_line(monkey_lines[0].0));
         }
-
-        let id = get_monkey_id(lines[i])?;
-        let items = get_starting_items(lines[i+1])?;
-        let operation = get_operation(lines[i+2])?;
-        let test = get_test(lines[i+3], lines[i+4], lines[i+5])?;
+        let at = |index: usize| {
+            let line = monkey_lines[index].0;
+            move |e: AocError| e.in_line(line)
+        };
+
+        let id = get_monkey_id(monkey_lines[0].1.trim()).map_err(at(0))?;
+        let items = get_starting_items(monkey_lines[1].1).map_err(at(1))?;
+        let operation = get_operation(monkey_lines[2].1.trim()).map_err(at(2))?;
+        let div_by = get_divisor(monkey_lines[3].1.trim()).map_err(at(3))?;
+        let true_to = get_target(monkey_lines[4].1.trim(), "true").map_err(at(4))?;
+        let false_to = get_target(monkey_lines[5].1.trim(), "false").map_err(at(5))?;
+        let test = Test { div_by, true_to, false_to };
         monkeys.push(Monkey { id, items, operation, test, inspect_count: 0 })
     }
 
@@ -233,7 +517,7 @@ fn parse_input(input: &str) -> Result<Vec<Monkey>, String> {
 
 #[cfg(test)]
 mod tests {
-    use crate::days::day11::{Operation, OperationValue, parse_input, Simulation, SimulationVersion, Test};
+    use crate::days::day11::{Operation, OperationValue, parse_input, Simulation, SimulationConfig, SimulationVersion, Test, ThrowAnalysis, Worry};
 
     #[test]
     fn test_parse_input() {
@@ -266,7 +550,7 @@ mod tests {
     #[test]
     fn test_inspect_and_yeet() {
         let mut simulation = Simulation::create(parse_input(TEST_INPUT).unwrap(), SimulationVersion::Puzzle1);
-        simulation.inspect_and_yeet(0);
+        simulation.inspect_and_yeet(0).unwrap();
         assert_eq!(0, simulation.monkeys[0].items.len());
         assert_eq!(vec![74, 500, 620], simulation.monkeys[3].items);
     }
@@ -275,7 +559,7 @@ mod tests {
     fn test_play_puzzle1_round() {
         let mut simulation = Simulation::create(parse_input(TEST_INPUT).unwrap(), SimulationVersion::Puzzle1);
 
-        simulation.play_round();
+        simulation.play_round().unwrap();
 
         assert_eq!(vec![20, 23, 27, 26], simulation.monkeys[0].items);
         assert_eq!(vec![2080, 25, 167, 207, 401, 1046], simulation.monkeys[1].items);
@@ -289,7 +573,7 @@ mod tests {
 
         let result = simulation.play_puzzle(20);
 
-        assert_eq!(10605, result);
+        assert_eq!(Ok(10605), result);
     }
 
     #[test]
@@ -298,36 +582,82 @@ mod tests {
 
         let result = simulation.play_puzzle(10000);
 
-        assert_eq!(2713310158, result);
-    }
-
-    const TEST_INPUT: &str = "\
-        Monkey 0:
-          Starting items: 79, 98
-          Operation: new = old * 19
-          Test: divisible by 23
-            If true: throw to monkey 2
-            If false: throw to monkey 3
-
-        Monkey 1:
-          Starting items: 54, 65, 75, 74
-          Operation: new = old + 6
-          Test: divisible by 19
-            If true: throw to monkey 2
-            If false: throw to monkey 0
-
-        Monkey 2:
-          Starting items: 79, 60, 97
-          Operation: new = old * old
-          Test: divisible by 13
-            If true: throw to monkey 1
-            If false: throw to monkey 3
-
-        Monkey 3:
-          Starting items: 74
-          Operation: new = old + 3
-          Test: divisible by 17
-            If true: throw to monkey 0
-            If false: throw to monkey 1
-    ";
+        assert_eq!(Ok(2713310158), result);
+    }
+
+    #[test]
+    fn test_throw_analysis() {
+        let analysis = ThrowAnalysis::create(&parse_input(TEST_INPUT).unwrap());
+        assert_eq!(vec![vec![0, 1, 2, 3]], analysis.groups);
+        assert!(!analysis.is_parallelizable());
+        assert_eq!("Monkey 0 throws to 2, 3\nMonkey 1 throws to 2, 0\nMonkey 2 throws to 1, 3\nMonkey 3 throws to 0, 1\n1 independent group(s): [0, 1, 2, 3]",
+                   analysis.to_string());
+
+        let analysis = ThrowAnalysis::create(&parse_input(&two_groups_input()).unwrap());
+        assert_eq!(vec![vec![0, 1, 2, 3], vec![4, 5, 6, 7]], analysis.groups);
+        assert!(analysis.is_parallelizable());
+    }
+
+    /// The example monkeys twice, with the second set of monkeys only throwing among themselves.
+    fn two_groups_input() -> String {
+        let mut second = TEST_INPUT.to_string();
+        for id in (0..4).rev() {
+            second = second.replace(&format!("monkey {}", id), &format!("monkey {}", id + 4))
+                .replace(&format!("Monkey {}:", id), &format!("Monkey {}:", id + 4));
+        }
+        format!("{}\n\n{}", TEST_INPUT.trim_end(), second)
+    }
+
+    #[test]
+    fn test_play_puzzle_parallel() {
+        for (version, rounds) in [(SimulationVersion::Puzzle1, 20), (SimulationVersion::Puzzle2, 1000)] {
+            let mut sequential = Simulation::create(parse_input(&two_groups_input()).unwrap(), version);
+            let mut parallel = Simulation::create(parse_input(&two_groups_input()).unwrap(), version);
+
+            assert_eq!(sequential.play_puzzle(rounds).unwrap(), parallel.play_puzzle_parallel(rounds).unwrap());
+            assert_eq!(sequential.monkeys.iter().map(|m| m.inspect_count).collect::<Vec<_>>(),
+                       parallel.monkeys.iter().map(|m| m.inspect_count).collect::<Vec<_>>());
+        }
+
+        let mut simulation = Simulation::create(parse_input(TEST_INPUT).unwrap(), SimulationVersion::Puzzle1);
+        assert_eq!(Ok(10605), simulation.play_puzzle_parallel(20));
+    }
+
+    #[test]
+    fn test_operation_overflow() {
+        let square = Operation::Multiply(OperationValue::Input, OperationValue::Input);
+        assert_eq!(Ok(Worry::Narrow(1 << 60)), square.apply(Worry::Narrow(1 << 30), false));
+        assert_eq!(Ok(Worry::Wide(1 << 100)), square.apply(Worry::Narrow(1 << 50), true));
+        assert_eq!(Err("worry level computing new = old * old with old = 1125899906842624 does not fit a usize, and widening is disabled".to_string()),
+                   square.apply(Worry::Narrow(1 << 50), false));
+        assert_eq!(Err("worry level overflows computing new = old * old with old = 18446744073709551616".to_string()), square.apply(Worry::Wide(1 << 64), true));
+        assert_eq!(Ok(Worry::Wide(u128::MAX)), Operation::Add(OperationValue::Input, OperationValue::Value(1)).apply(Worry::Wide(u128::MAX - 1), true));
+        // Results that fit a usize again are narrowed.
+        assert_eq!(Worry::Narrow(6), Worry::Wide(1 << 100).map(|v| v, |v| v % 10));
+    }
+
+    #[test]
+    fn test_worry_widening() {
+        // Without reducing the worry levels, those of part 2 overflow a usize in round 13 and a u128 in round 31.
+        let unreduced = SimulationConfig { reduce: false, widen: true };
+        let mut simulation = Simulation::create(parse_input(TEST_INPUT).unwrap(), SimulationVersion::Puzzle2).with_config(unreduced);
+        simulation.play_rounds(12).unwrap();
+        assert_eq!(None, simulation.widened_in_round);
+        simulation.play_rounds(18).unwrap();
+        assert_eq!(Some(13), simulation.widened_in_round);
+        assert_eq!(Err("Monkey 0 in round 31: worry level overflows computing new = old * 19 with old = 234271887539542109550433007260887848071".to_string()),
+                   simulation.play_round());
+
+        let narrow = SimulationConfig { reduce: false, widen: false };
+        let mut simulation = Simulation::create(parse_input(TEST_INPUT).unwrap(), SimulationVersion::Puzzle2).with_config(narrow);
+        let error = simulation.play_rounds(20).unwrap_err();
+        assert!(error.contains("in round 13: worry level") && error.ends_with("does not fit a usize, and widening is disabled"), "{}", error);
+
+        // With the relief of part 1 the levels stay small, and reducing them changes nothing.
+        let mut simulation = Simulation::create(parse_input(TEST_INPUT).unwrap(), SimulationVersion::Puzzle1).with_config(narrow);
+        assert_eq!(Ok(10605), simulation.play_puzzle(20));
+        assert_eq!(None, simulation.widened_in_round);
+    }
+
+    const TEST_INPUT: &str = include_str!("../../examples/day11/input.txt");
 }
\ No newline at end of file
@@ -1,98 +1,190 @@
 use std::cmp::Ordering;
-use std::collections::BinaryHeap;
+use std::collections::{BinaryHeap, VecDeque};
+use std::env;
 use std::str::FromStr;
-use crate::days::Day;
-use crate::util::geometry::{Directions, Grid, Point};
+use crate::days::{Day, Explanation, Solver, solve_part1, solve_part2, parse_only, ParseSummary};
+use crate::util::error::AocError;
+use crate::util::geometry::{Bounds, Directions, Grid, Point};
+use crate::util::search::Predecessors;
 
 pub const DAY12: Day = Day {
-    puzzle1,
-    puzzle2
+    puzzle1: solve_part1::<Day12>,
+    puzzle2: solve_part2::<Day12>,
+    parse: parse_only::<Day12>,
+    describe: "Hill Climbing Algorithm: find the shortest climb to the best signal location",
+    explain: Some(explain),
+    selftest: None,
+    algorithms: &[]
 };
 
-fn puzzle1(input: &String) {
-    let map: HeightMap = input.parse().unwrap();
-    let steps = map.find_shortest_route().unwrap();
-
-    println!("It takes {} steps to the top!", steps);
+fn explain() -> String {
+    Explanation {
+        approach: "Dijkstra over the height map from the start (part 1), and from all lowest squares at once (part 2).",
+        complexity: "O(n log n) for n squares.",
+        alternatives: &[]
+    }.to_string()
 }
 
-fn puzzle2(input: &String) {
-    let map: HeightMap = input.parse().unwrap();
-    let steps = map.find_scenic_route().unwrap();
+#[derive(Default)]
+struct Day12;
+
+impl Solver for Day12 {
+    type Output = usize;
+
+    fn part1(&self, input: &str) -> Result<Self::Output, AocError> {
+        let map = HeightMap::parse(input, true)?;
+        let route = map.find_shortest_route().ok_or("There is no route to the top")?;
+
+        if map.starts.len() > 1 {
+            println!("The shortest route starts at {}, out of {} start markers", route.start, map.starts.len());
+        }
+        if env::var("AOC_VERBOSE").is_ok() {
+            println!("{}", map.render_route(&route));
+        }
+        Ok(route.steps)
+    }
+
+    fn part2(&self, input: &str) -> Result<Self::Output, AocError> {
+        let map = HeightMap::parse(input, true)?;
+        let steps = map.find_scenic_route().ok_or("There is no scenic route to the top")?;
+
+        if env::var("AOC_VERBOSE").is_ok() {
+            let lowest = map.lowest_square_distances();
+            println!("{} of the {} lowest squares can reach the top", lowest.count(|d| d.is_some()), map.area.count(|h| *h == 0));
+        }
+        Ok(steps)
+    }
 
-    println!("Shortest scenic route to the top is {} steps!", steps);
+    fn parse(&self, input: &str) -> Result<ParseSummary, AocError> {
+        let map = HeightMap::parse(input, true)?;
+        Ok(ParseSummary::new()
+            .count(map.area.bounds.width * map.area.bounds.height, "squares")
+            .count(map.starts.len(), "starts"))
+    }
 }
 
 struct HeightMap {
     area: Grid<usize>,
-    start: Point,
+    /// All points marked 'S', in reading order.
+    starts: Vec<Point>,
     end: Point
 }
 
+/// The shortest route to the top, from the start it was found for.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Route {
+    start: Point,
+    steps: usize,
+    /// Every square on the route, from the start up to and including the end.
+    path: Vec<Point>,
+}
+
 impl FromStr for HeightMap {
-    type Err = String;
+    type Err = AocError;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
-        let mut area = Grid::default();
-        let mut start = None;
+        Self::parse(input, false)
+    }
+}
+
+impl HeightMap {
+    /// Creates a height map from an existing (dense) grid of heights in 0..=25, with every start inside it.
+    fn from_grid(area: Grid<usize>, starts: Vec<Point>, end: Point) -> Result<Self, String> {
+        if let Some(point) = area.points().into_iter().find(|p| !area.has(p)) {
+            return Err(format!("Missing height at {} within bounds {:?}", point, area.bounds));
+        }
+        if let Some(point) = area.points().into_iter().find(|p| area.get(p).is_some_and(|h| h > 25)) {
+            return Err(format!("Height {} at {} is out of range 0..=25", area.get(&point).unwrap(), point));
+        }
+        if starts.is_empty() {
+            return Err("There should be at least one start point".to_string());
+        }
+        for (name, point) in starts.iter().map(|s| ("Start", *s)).chain([("End", end)]) {
+            if !area.has(&point) {
+                return Err(format!("{} point {} is outside of the map {:?}", name, point, area.bounds));
+            }
+        }
+
+        Ok(HeightMap { area, starts, end })
+    }
+
+    /// Parses the map. There can be several start points, all of them are considered by `find_shortest_route`.
+    /// In strict mode, ragged lines and more than one end point are rejected as well.
+    fn parse(input: &str, strict: bool) -> Result<Self, AocError> {
+        let mut starts = vec![];
         let mut end = None;
 
         let lines: Vec<_> = input.lines().collect();
-        for y in 0..lines.len() {
-            let line: Vec<_> = lines[y].chars().collect();
-            for x in 0..line.len() {
+        let width = lines.first().map(|l| l.chars().count()).unwrap_or(0);
+        let mut area = Grid::dense(Bounds::from_size(width, lines.len()));
+        for (y, line) in lines.iter().enumerate() {
+            let line: Vec<_> = line.chars().collect();
+            if strict && line.len() != width {
+                return Err(AocError::parse(format!("Found {} columns, but expected {}", line.len(), width)).in_line(y + 1));
+            }
+
+            for (x, c) in line.iter().enumerate() {
                 let point = (x, y).try_into()?;
-                match line[x] {
-                    'S' => {
-                        start = Some(point);
-                        area.set(point, 0);
-                    },
-                    'E' => {
-                        end = Some(point);
-                        area.set(point, 25);
-                    },
-                    'a'..='z' => {
-                        area.set(point, (line[x] as usize) - ('a' as usize))
-                    },
-                    _ => return Err(format!("Invalid height entry '{}'", line[x]))
+                match c {
+                    'S' => starts.push(point),
+                    'E' if strict && end.is_some() => return Err(AocError::parse_at(y + 1, x + 1, "Duplicate 'E'")),
+                    'E' => end = Some(point),
+                    _ => {}
+                }
+
+                match c {
+                    'S' => area.set(point, 0),
+                    'E' => area.set(point, 25),
+                    'a'..='z' => area.set(point, (*c as usize) - ('a' as usize)),
+                    _ => return Err(AocError::parse_at(y + 1, x + 1, format!("Invalid height entry '{}'", c)))
                 }
             }
         }
 
-        match (start, end) {
-            (Some(start), Some(end)) => Ok(HeightMap { area, start, end }),
-            _ => Err(format!("Could not find start or end point inside input"))
+        match (starts.first(), end) {
+            (Some(_), Some(end)) if strict => Self::from_grid(area, starts, end).map_err(AocError::parse),
+            (Some(_), Some(end)) => Ok(HeightMap { area, starts, end }),
+            _ => Err(AocError::parse("Could not find start or end point inside input"))
         }
     }
 }
 
+/// A point in the queue, `origin` is the point the search started from.
 #[derive(Debug, Eq, PartialEq)]
-struct PrioPoint { point: Point, distance: usize, height: usize }
+struct PrioPoint { point: Point, distance: usize, height: usize, origin: Point }
 impl Ord for PrioPoint {
     fn cmp(&self, other: &Self) -> Ordering {
         other.distance.cmp(&self.distance)
             .then_with(|| self.point.cmp(&other.point))
+            .then_with(|| other.origin.cmp(&self.origin))
     }
 }
 impl PartialOrd for PrioPoint {fn partial_cmp(&self, other: &Self) -> Option<Ordering> {Some(self.cmp(other))}}
 
 impl HeightMap {
-    fn find_shortest_route(&self) -> Option<usize> {
+    /// The shortest route from any of the starts to the end.
+    fn find_shortest_route(&self) -> Option<Route> {
         // Hey look. Time for Dijkstra again!
         // We need:
         // - A priority queue to keep tracking the current shortest option
         let mut queue: BinaryHeap<PrioPoint> = BinaryHeap::new();
         // - A map of shortest-path values to a given point
-        let mut values: Grid<usize> = Grid::default();
-        // - The start added to both
-        values.set(self.start, 0);
-        queue.push(PrioPoint { point: self.start, distance: 0, height: 0 });
+        let mut values: Grid<usize> = Grid::dense(self.area.bounds);
+        // - The square every square was reached from, to trace the route back
+        let mut predecessors = Predecessors::new();
+        // - The starts added to all, every route remembers where it started
+        for start in &self.starts {
+            values.set(*start, 0);
+            predecessors.add_root(*start);
+            queue.push(PrioPoint { point: *start, distance: 0, height: self.area.get(start)?, origin: *start });
+        }
 
         // Now we just keep handling the point with the shortest current distance
         while let Some(current) = queue.pop() {
             // Have we reached the destination?
             if current.point == self.end {
-                return Some(current.distance);
+                let path = predecessors.path_to(&self.end)?;
+                return Some(Route { start: current.origin, steps: current.distance, path });
             }
 
             // Has someone else already reached our point with a shorter distance?
@@ -103,40 +195,59 @@ impl HeightMap {
             }
 
             // Otherwise, look for options and push them with new values onto the queue
-            for neighbor in self.area.get_adjacent_points(&current.point, Directions::NonDiagonal) {
+            for (neighbor, val) in self.area.get_adjacent_with_points(&current.point, Directions::NonDiagonal) {
                 // We can step to neighbors that are at most one higher than our current point
-                if let Some(val) = self.area.get(&neighbor) {
-                    if val > current.height + 1 {
-                        continue;
-                    }
+                if *val > current.height + 1 {
+                    continue;
+                }
 
-                    // Check if we haven't already visited said point:
-                    if let Some(dist) = values.get(&neighbor) {
-                        if dist <= current.distance + 1 {
-                            continue;
-                        }
+                // Check if we haven't already visited said point:
+                if let Some(dist) = values.get(&neighbor) {
+                    if dist <= current.distance + 1 {
+                        continue;
                     }
-
-                    // We can add this one to the queue!
-                    values.set(neighbor, current.distance + 1);
-                    queue.push(PrioPoint { point: neighbor, distance: current.distance + 1, height: val });
                 }
+
+                // We can add this one to the queue!
+                values.set(neighbor, current.distance + 1);
+                predecessors.link(neighbor, current.point);
+                queue.push(PrioPoint { point: neighbor, distance: current.distance + 1, height: *val, origin: current.origin });
             }
         }
 
         None
     }
 
+    /// Draws the route like the puzzle does: every square on it shows the direction of the next step, the end is
+    /// an 'E' and all other squares are '.'.
+    fn render_route(&self, route: &Route) -> String {
+        let mut squares: Grid<char> = Grid::default();
+        for step in route.path.windows(2) {
+            let arrow = match (step[1].x - step[0].x, step[1].y - step[0].y) {
+                (1, _) => '>',
+                (-1, _) => '<',
+                (_, 1) => 'v',
+                _ => '^',
+            };
+            squares.set(step[0], arrow);
+        }
+        squares.set(self.end, 'E');
+
+        self.area.bounds.y().map(|y| {
+            self.area.bounds.x().map(|x| squares.get(&Point { x, y }).unwrap_or('.')).collect::<String>()
+        }).collect::<Vec<_>>().join("\n")
+    }
+
     fn find_scenic_route(&self) -> Option<usize> {
         // Hey look. Time for Dijkstra again!
         // We need:
         // - A priority queue to keep tracking the current shortest option
         let mut queue: BinaryHeap<PrioPoint> = BinaryHeap::new();
         // - A map of shortest-path values to a given point
-        let mut values: Grid<usize> = Grid::default();
+        let mut values: Grid<usize> = Grid::dense(self.area.bounds);
         // - The end added to both
         values.set(self.end, 0);
-        queue.push(PrioPoint { point: self.end, distance: 0, height: 25 });
+        queue.push(PrioPoint { point: self.end, distance: 0, height: self.area.get(&self.end)?, origin: self.end });
 
         // Now we just keep handling the point with the shortest current distance
         while let Some(current) = queue.pop() {
@@ -153,35 +264,61 @@ impl HeightMap {
             }
 
             // Otherwise, look for options and push them with new values onto the queue
-            for neighbor in self.area.get_adjacent_points(&current.point, Directions::NonDiagonal) {
+            for (neighbor, val) in self.area.get_adjacent_with_points(&current.point, Directions::NonDiagonal) {
                 // We should only consider neighbors from which we could've reached this point. That is, 1 below or anything above.
-                if let Some(val) = self.area.get(&neighbor) {
-                    if current.height > val + 1 {
+                if current.height > *val + 1 {
+                    continue;
+                }
+
+                // Check if we haven't already visited said point:
+                if let Some(dist) = values.get(&neighbor) {
+                    if dist <= current.distance + 1 {
                         continue;
                     }
+                }
 
-                    // Check if we haven't already visited said point:
-                    if let Some(dist) = values.get(&neighbor) {
-                        if dist <= current.distance + 1 {
-                            continue;
-                        }
-                    }
+                // We can add this one to the queue!
+                values.set(neighbor, current.distance + 1);
+                queue.push(PrioPoint { point: neighbor, distance: current.distance + 1, height: *val, origin: current.origin });
+            }
+        }
+
+        None
+    }
 
-                    // We can add this one to the queue!
-                    values.set(neighbor, current.distance + 1);
-                    queue.push(PrioPoint { point: neighbor, distance: current.distance + 1, height: val });
+    /// The number of steps from every square to the end. Squares that cannot reach the end have no value.
+    fn distances_to_end(&self) -> Grid<usize> {
+        // Breadth-first from the end, stepping to the squares from which we could have climbed to the current one.
+        let mut distances = Grid::dense(self.area.bounds);
+        let mut queue = VecDeque::new();
+        distances.set(self.end, 0);
+        queue.push_back(self.end);
+
+        while let Some(point) = queue.pop_front() {
+            let (Some(height), Some(distance)) = (self.area.get(&point), distances.get(&point)) else { continue };
+            for (neighbor, val) in self.area.get_adjacent_with_points(&point, Directions::NonDiagonal) {
+                if height <= *val + 1 && !distances.has(&neighbor) {
+                    distances.set(neighbor, distance + 1);
+                    queue.push_back(neighbor);
                 }
             }
         }
 
-        None
+        distances
+    }
+
+    /// The distance to the end of the squares that can reach it: Some for the lowest squares, None for the others.
+    fn lowest_square_distances(&self) -> Grid<Option<usize>> {
+        self.area.zip(&self.distances_to_end(), |_, height, distance| (*height == 0).then_some(*distance))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::days::day12::{HeightMap};
-    use crate::util::geometry::Point;
+    use std::collections::HashMap;
+    use crate::days::day12::HeightMap;
+    use crate::util::error::AocError;
+    use crate::util::geometry::{Grid, Point};
 
     #[test]
     fn test_parse_input() {
@@ -189,10 +326,10 @@ mod tests {
         assert!(result.is_ok());
 
         let map = result.unwrap();
-        assert_eq!(Point { x: 0, y: 0 }, map.start);
+        assert_eq!(vec![Point { x: 0, y: 0 }], map.starts);
         assert_eq!(Point { x: 5, y: 2 }, map.end);
 
-        assert_eq!(Some(0), map.area.get(&map.start));
+        assert_eq!(Some(0), map.area.get(&map.starts[0]));
         assert_eq!(Some(25), map.area.get(&map.end));
         assert_eq!(Some(0), map.area.get(&(0,1).into()));
         assert_eq!(Some(1), map.area.get(&(1,1).into()));
@@ -201,12 +338,85 @@ mod tests {
         assert_eq!(Some(23), map.area.get(&(5,1).into()));
     }
 
+    #[test]
+    fn test_parse_strict() {
+        assert!(HeightMap::parse(TEST_INPUT, true).is_ok());
+
+        let ragged = TEST_INPUT.replacen("acctuvwj", "acctuvw", 1);
+        assert!(ragged.parse::<HeightMap>().is_ok());
+        assert_eq!(Err("Line 4: Found 7 columns, but expected 8".to_string()), HeightMap::parse(&ragged, true).map(|_| ()).map_err(|e| e.to_string()));
+
+        let invalid = TEST_INPUT.replacen("abcryxxl", "abcrYxxl", 1);
+        assert_eq!(Err(AocError::parse_at(2, 5, "Invalid height entry 'Y'")), HeightMap::parse(&invalid, false).map(|_| ()));
+
+        let duplicate = TEST_INPUT.replacen("abdefghi", "abdefghE", 1);
+        assert!(HeightMap::parse(&duplicate, false).is_ok());
+        assert_eq!(Err(AocError::parse_at(5, 8, "Duplicate 'E'")), HeightMap::parse(&duplicate, true).map(|_| ()));
+    }
+
+    #[test]
+    fn test_from_grid() {
+        let heights: HashMap<Point, usize> = (0..4).map(|x| ((x, 0).into(), x as usize)).collect();
+        let map = HeightMap::from_grid(Grid::new(heights.clone()), vec![(0, 0).into()], (3, 0).into()).unwrap();
+        assert_eq!(Some(((0, 0).into(), 3)), map.find_shortest_route().map(|r| (r.start, r.steps)));
+        assert_eq!(Some(3), map.find_scenic_route());
+
+        assert!(HeightMap::from_grid(Grid::new(heights.clone()), vec![(0, 0).into()], (4, 0).into()).is_err());
+        // Every start is checked, not just the first.
+        assert_eq!(Err("Start point (0,1) is outside of the map Bounds { top: 0, left: 0, width: 4, height: 1 }".to_string()),
+                   HeightMap::from_grid(Grid::new(heights.clone()), vec![(0, 0).into(), (0, 1).into()], (3, 0).into()).map(|_| ()));
+        assert!(HeightMap::from_grid(Grid::new(heights.clone()), vec![], (3, 0).into()).is_err());
+
+        let mut sparse = heights.clone();
+        sparse.insert((1, 1).into(), 1);
+        assert_eq!(Err("Missing height at (0,1) within bounds Bounds { top: 0, left: 0, width: 4, height: 2 }".to_string()),
+                   HeightMap::from_grid(Grid::new(sparse), vec![(0, 0).into()], (3, 0).into()).map(|_| ()));
+
+        let mut too_high = heights.clone();
+        too_high.insert((2, 0).into(), 26);
+        assert!(HeightMap::from_grid(Grid::new(too_high), vec![(0, 0).into()], (3, 0).into()).is_err());
+    }
+
     #[test]
     fn test_get_shortest_route() {
         let map: HeightMap = TEST_INPUT.parse().unwrap();
-        let steps = map.find_shortest_route();
+        let route = map.find_shortest_route().unwrap();
+
+        assert_eq!((Point::from((0, 0)), 31), (route.start, route.steps));
+    }
+
+    #[test]
+    fn test_route_path() {
+        let map: HeightMap = TEST_INPUT.parse().unwrap();
+        let route = map.find_shortest_route().unwrap();
+
+        assert_eq!(route.steps + 1, route.path.len());
+        assert_eq!(Some(&route.start), route.path.first());
+        assert_eq!(Some(&map.end), route.path.last());
+        for step in route.path.windows(2) {
+            assert_eq!(1, (step[1].x - step[0].x).abs() + (step[1].y - step[0].y).abs());
+            assert!(map.area.get(&step[1]).unwrap() <= map.area.get(&step[0]).unwrap() + 1);
+        }
+
+        let rendered = map.render_route(&route);
+        assert_eq!(5, rendered.lines().count());
+        assert_eq!(Some('v'), rendered.chars().next());
+        assert_eq!(route.steps, rendered.chars().filter(|c| "<>^v".contains(*c)).count());
+        assert_eq!(Some('E'), rendered.lines().nth(2).and_then(|l| l.chars().nth(5)));
+    }
 
-        assert_eq!(Some(31), steps);
+    #[test]
+    fn test_multiple_starts() {
+        // A second start at the bottom left, where the scenic route of part 2 starts.
+        let input = TEST_INPUT.replacen("abdefghi", "Sbdefghi", 1);
+        let map = HeightMap::parse(&input, true).unwrap();
+        assert_eq!(vec![Point::from((0, 0)), (0, 4).into()], map.starts);
+        assert_eq!(Some(((0, 4).into(), 29)), map.find_shortest_route().map(|r| (r.start, r.steps)));
+
+        // A start right below the original one saves a step.
+        let input = TEST_INPUT.replacen("abcryxxl", "Sbcryxxl", 1);
+        let map = HeightMap::parse(&input, true).unwrap();
+        assert_eq!(Some(((0, 1).into(), 30)), map.find_shortest_route().map(|r| (r.start, r.steps)));
     }
 
     #[test]
@@ -217,11 +427,19 @@ mod tests {
         assert_eq!(Some(29), steps);
     }
 
-    const TEST_INPUT: &str = "\
-        Sabqponm\n\
-        abcryxxl\n\
-        accszExk\n\
-        acctuvwj\n\
-        abdefghi\n\
-    ";
+    #[test]
+    fn test_lowest_square_distances() {
+        let map: HeightMap = TEST_INPUT.parse().unwrap();
+        let distances = map.distances_to_end();
+        assert_eq!(map.area.bounds, distances.bounds);
+        assert_eq!(Some(0), distances.get(&map.end));
+        assert_eq!(Some(31), distances.get(&map.starts[0]));
+
+        let lowest = map.lowest_square_distances();
+        assert_eq!(map.area.bounds, lowest.bounds);
+        assert_eq!(map.find_scenic_route(), lowest.values().into_iter().flatten().min());
+        assert_eq!(map.area.count(|h| *h == 0), lowest.count(|d| d.is_some()));
+    }
+
+    const TEST_INPUT: &str = include_str!("../../examples/day12/input.txt");
 }
\ No newline at end of file
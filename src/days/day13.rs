@@ -1,44 +1,86 @@
 use std::cmp::{max, Ordering};
-use std::fmt;
+use std::{env, fmt};
 use std::str::FromStr;
-use crate::days::Day;
+use crate::days::{Day, Explanation, Solver, solve_part1, solve_part2, parse_only, ParseSummary};
+use crate::util::error::AocError;
+use crate::util::collection::CollectionExtension;
 
 pub const DAY13: Day = Day {
-    puzzle1,
-    puzzle2
+    puzzle1: solve_part1::<Day13>,
+    puzzle2: solve_part2::<Day13>,
+    parse: parse_only::<Day13>,
+    describe: "Distress Signal: compare nested packet lists and find the decoder key",
+    explain: Some(explain),
+    selftest: None,
+    algorithms: &[]
 };
 
-fn puzzle1(input: &String) {
-    let pairs = parse_input(input).unwrap();
+fn explain() -> String {
+    Explanation {
+        approach: "Parse packets into a recursive structure with an Ord implementation following the comparison rules, then compare pairs and sort with the divider packets.",
+        complexity: "O(n log n) comparisons for the sort, each linear in the packet size.",
+        alternatives: &[]
+    }.to_string()
+}
 
-    let correct_indices = get_right_ordered_indices(&pairs);
-    let answer: usize = correct_indices.iter().sum();
+#[derive(Default)]
+struct Day13;
 
-    println!("Sum of correctly ordered packet indices: {}", answer);
-}
+impl Solver for Day13 {
+    type Output = usize;
+
+    fn part1(&self, input: &str) -> Result<Self::Output, AocError> {
+        let packets = parse_input(input)?;
+        let pairs = pair_packets(&packets)?;
 
-fn puzzle2(input: &String) {
-    let pairs = parse_input(input).unwrap();
+        if env::var("AOC_VERBOSE").is_ok() {
+            for (i, (lhs, rhs)) in pairs.iter().enumerate() {
+                println!("== Pair {} ==", i + 1);
+                println!("{}", lhs.cmp_with_trace(rhs).1.join("\n"));
+                println!();
+            }
+        }
 
-    let answer: usize = get_distress_decoder_key(&pairs);
+        let correct_indices = get_right_ordered_indices(&packets)?;
+        let answer: usize = correct_indices.iter().sum();
 
-    println!("Distress decoder key: {}", answer);
+        Ok(answer)
+    }
+
+    fn part2(&self, input: &str) -> Result<Self::Output, AocError> {
+        let packets = parse_input(input)?;
+
+        Ok(get_distress_decoder_key(&packets))
+    }
+
+    fn parse(&self, input: &str) -> Result<ParseSummary, AocError> {
+        let packets = parse_input(input)?;
+        Ok(ParseSummary::new().count(packets.len(), "packets"))
+    }
 }
 
-fn get_right_ordered_indices(pairs: &Vec<(Packet, Packet)>) -> Vec<usize> {
-    pairs.iter().enumerate().filter_map(|(i, (lhs, rhs))| match lhs.cmp(rhs) {
+/// Pairs up the packets in the order they were received: the first and second packet form the first pair, etc.
+fn pair_packets(packets: &[Packet]) -> Result<Vec<(&Packet, &Packet)>, String> {
+    if !packets.len().is_multiple_of(2) {
+        return Err(format!("Expected pairs of packets, but got an odd number of packets ({})", packets.len()));
+    }
+    Ok(packets.chunks(2).map(|pair| (&pair[0], &pair[1])).collect())
+}
+
+fn get_right_ordered_indices(packets: &[Packet]) -> Result<Vec<usize>, String> {
+    Ok(pair_packets(packets)?.iter().enumerate().filter_map(|(i, (lhs, rhs))| match lhs.cmp(rhs) {
         Ordering::Less => Some(i + 1), // Puzzle expects first index to be 1
         _ => None
-    }).collect()
+    }).collect())
 }
 
-fn get_distress_decoder_key(pairs: &Vec<(Packet, Packet)>) -> usize {
-    let ordered = order_packets_for_distress_signal(pairs);
+fn get_distress_decoder_key(packets: &[Packet]) -> usize {
+    let ordered = order_packets_for_distress_signal(packets);
     ordered.iter().enumerate().filter_map(|(i, p)| if Packet::decoder_key_a().eq(p) || Packet::decoder_key_b().eq(p) { Some(i+1) } else { None }).fold(1, |acc, v| acc * v)
 }
 
-fn order_packets_for_distress_signal(pairs: &Vec<(Packet, Packet)>) -> Vec<Packet> {
-    let mut packets: Vec<_> = pairs.iter().flat_map(|(l, r)| vec![l, r]).cloned().collect();
+fn order_packets_for_distress_signal(packets: &[Packet]) -> Vec<Packet> {
+    let mut packets = packets.to_vec();
     // Add the two distress divider packets
     packets.push(Packet::decoder_key_a());
     packets.push(Packet::decoder_key_b());
@@ -63,18 +105,47 @@ impl Packet {
     }
 }
 
-impl Ord for Packet {
-    fn cmp(&self, other: &Self) -> Ordering {
+impl Packet {
+    /// Compares the packets like `cmp`, but also returns the explanation of the comparison in the
+    /// same format as the puzzle description.
+    fn cmp_with_trace(&self, other: &Self) -> (Ordering, Vec<String>) {
+        let mut trace = vec![];
+        let result = self.cmp_traced(other, 0, &mut Some(&mut trace));
+        (result, trace)
+    }
+
+    fn cmp_traced(&self, other: &Self, depth: usize, trace: &mut Option<&mut Vec<String>>) -> Ordering {
+        fn log(trace: &mut Option<&mut Vec<String>>, depth: usize, message: String) {
+            if let Some(lines) = trace {
+                lines.push(format!("{}- {}", "  ".repeat(depth), message));
+            }
+        }
+
+        log(trace, depth, format!("Compare {} vs {}", self, other));
         match (self, other) {
-            (Packet::Value(lhs), Packet::Value(rhs)) => lhs.cmp(rhs),
+            (Packet::Value(lhs), Packet::Value(rhs)) => {
+                let result = lhs.cmp(rhs);
+                match result {
+                    Ordering::Less => log(trace, depth + 1, "Left side is smaller, so inputs are in the right order".to_string()),
+                    Ordering::Greater => log(trace, depth + 1, "Right side is smaller, so inputs are not in the right order".to_string()),
+                    Ordering::Equal => ()
+                }
+                result
+            },
             (Packet::List(lhs), Packet::List(rhs)) => {
                 let max_len = max(lhs.len(), rhs.len());
                 for i in 0..max_len {
                     match (lhs.get(i), rhs.get(i)) {
                         (None, None) => panic!("This is not supposed to happen!"),
-                        (None, _) => return Ordering::Less,
-                        (_, None) => return Ordering::Greater,
-                        (Some(lhp), Some(rhp)) => match lhp.cmp(rhp) {
+                        (None, _) => {
+                            log(trace, depth + 1, "Left side ran out of items, so inputs are in the right order".to_string());
+                            return Ordering::Less
+                        },
+                        (_, None) => {
+                            log(trace, depth + 1, "Right side ran out of items, so inputs are not in the right order".to_string());
+                            return Ordering::Greater
+                        },
+                        (Some(lhp), Some(rhp)) => match lhp.cmp_traced(rhp, depth + 1, trace) {
                             Ordering::Equal => (),
                             res @ _ => return res
                         }
@@ -82,25 +153,40 @@ impl Ord for Packet {
                 }
                 Ordering::Equal
             },
-            (lhs @ Packet::List(_), rhs @ Packet::Value(_)) => lhs.cmp(&Packet::List(vec![rhs.clone()])),
-            (lhs @ Packet::Value(_), rhs @ Packet::List(_)) => Packet::List(vec![lhs.clone()]).cmp(rhs)
+            (lhs @ Packet::List(_), rhs @ Packet::Value(_)) => {
+                let converted = Packet::List(vec![rhs.clone()]);
+                log(trace, depth + 1, format!("Mixed types; convert right to {} and retry comparison", converted));
+                lhs.cmp_traced(&converted, depth + 1, trace)
+            },
+            (lhs @ Packet::Value(_), rhs @ Packet::List(_)) => {
+                let converted = Packet::List(vec![lhs.clone()]);
+                log(trace, depth + 1, format!("Mixed types; convert left to {} and retry comparison", converted));
+                converted.cmp_traced(rhs, depth + 1, trace)
+            }
         }
     }
 }
+
+impl Ord for Packet {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cmp_traced(other, 0, &mut None)
+    }
+}
 impl PartialOrd for Packet { fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }}
 
 impl FromStr for Packet {
-    type Err = String;
+    type Err = AocError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let chars: Vec<_> = s.trim().chars().collect();
+        let indent = s.chars().take_while(|c| c.is_whitespace()).count();
 
         let mut packet = None;
         let mut stack = vec![];
         for i in 0..chars.len() {
             let char = chars[i];
             let parse_error = |details: String| -> Self::Err {
-                format!("{} at '{}':{}", details, s, i)
+                AocError::parse_at(1, indent + i + 1, format!("{} in '{}'", details, s))
             };
 
             match char {
@@ -143,8 +229,8 @@ impl FromStr for Packet {
 
         match packet {
             Some(p @ Packet::List(_)) => Ok(p),
-            Some(_) => Err(format!("Unexpected end of packet, missing ']'? '{}':EOL", s)),
-            None => Err(format!("Unexpected end of packet, no packet parsed? '{}':EOL", s))
+            Some(_) => Err(AocError::parse(format!("Unexpected end of packet, missing ']'? '{}'", s))),
+            None => Err(AocError::parse(format!("Unexpected end of packet, no packet parsed? '{}'", s)))
         }
     }
 }
@@ -158,46 +244,33 @@ impl fmt::Display for Packet {
     }
 }
 
-fn parse_input(input: &str) -> Result<Vec<(Packet, Packet)>, String> {
-    let lines: Vec<_> = input.lines().collect();
-
-    let mut left = None;
-    let mut right = None;
-    let mut result = vec![];
-
-    for i in 0..=lines.len() {
-        // Make sure we iterate one more time with an empty string to avoid duplicating the result handling.
-        let line = lines.get(i).unwrap_or(&"");
-
-        if line.trim().is_empty() {
-            if let Some(lv) = left {
-                if let Some(rv) = right {
-                    result.push((lv, rv));
-                    left = None;
-                    right = None;
-                } else {
-                    return Err(format!("Missing second packet!"));
-                }
+/// Parses all packets in the order they were received. Pairs are normally separated by a blank line, but a
+/// list of packets without separators works as well; pairing happens afterwards (see `pair_packets`).
+fn parse_input(input: &str) -> Result<Vec<Packet>, AocError> {
+    // Every line with its (1-based) line number, to locate errors.
+    let lines: Vec<_> = input.lines().enumerate().map(|(index, line)| (index + 1, line)).collect();
+    let groups = lines.split_when(|(_, line)| line.trim().is_empty());
+
+    // When there are separators, they should separate pairs.
+    if groups.len() > 1 {
+        for group in &groups {
+            match group[..] {
+                [_, _] => {}
+                [(line, _)] => return Err(AocError::parse("Missing second packet!").in_line(line)),
+                _ => return Err(AocError::parse(format!("Extraneous line '{}' after reading two packets!", group[2].1)).in_line(group[2].0))
             }
-            continue;
-        }
-
-        if left.is_none() {
-            left = Some(line.parse()?)
-        } else if right.is_none() {
-            right = Some(line.parse()?)
-        } else {
-            return Err(format!("Extraneous line '{}' after reading two packets!", line))
         }
     }
 
-    Ok(result)
+    groups.concat().iter().map(|(number, line)| line.parse::<Packet>().map_err(|e| e.in_line(*number))).collect()
 }
 
 #[cfg(test)]
 mod tests {
     use std::cmp::Ordering;
-    use crate::days::day13::{get_distress_decoder_key, get_right_ordered_indices, order_packets_for_distress_signal, Packet, parse_input};
+    use crate::days::day13::{get_distress_decoder_key, get_right_ordered_indices, order_packets_for_distress_signal, Packet, pair_packets, parse_input};
+    use crate::util::collection::VecToString;
+    use crate::util::error::AocError;
 
     impl Packet {
         fn values(vals: Vec<usize>) -> Packet {
@@ -219,10 +292,27 @@ mod tests {
     #[test]
     fn test_parse_input() {
         let result = parse_input(TEST_INPUT);
-        assert!(result.is_ok(), "{}", result.err().unwrap_or("unexpected".to_string()));
+        assert!(result.is_ok(), "{}", result.err().map(|e| e.to_string()).unwrap_or("unexpected".to_string()));
+
+        let packets = result.unwrap();
+        assert_eq!(16, packets.len());
+        assert_eq!(Ok(8), pair_packets(&packets).map(|p| p.len()));
+
+        assert_eq!(Err(AocError::parse("Missing second packet!").in_line(4)), parse_input("[1]\n[2]\n\n[3]\n"));
+        assert_eq!(Err(AocError::parse("Extraneous line '[3]' after reading two packets!").in_line(3)), parse_input("[1]\n[2]\n[3]\n\n[4]\n[5]"));
+        assert_eq!(Err(AocError::parse_at(4, 3, "Invalid char: 'x' in '[1x]'")), parse_input("[1]\n[2]\n\n[1x]\n[3]"));
+    }
 
-        let pairs = result.unwrap();
-        assert_eq!(8, pairs.len());
+    #[test]
+    fn test_parse_input_without_separators() {
+        let unseparated: String = TEST_INPUT.lines().filter(|l| !l.trim().is_empty()).map(|l| format!("{}\n", l)).collect();
+        let packets = parse_input(&unseparated).unwrap();
+        assert_eq!(parse_input(TEST_INPUT), Ok(packets.clone()));
+        assert_eq!(Ok(vec![1, 2, 4, 6]), get_right_ordered_indices(&packets));
+        assert_eq!(140, get_distress_decoder_key(&packets));
+
+        let odd = parse_input("[1]\n[2]\n[3]").unwrap();
+        assert_eq!(Err("Expected pairs of packets, but got an odd number of packets (3)".to_string()), get_right_ordered_indices(&odd));
     }
 
     #[test]
@@ -234,7 +324,8 @@ mod tests {
         assert_eq!(Ordering::Greater, Packet::values(vec![1,2,3,4]).cmp(&Packet::values(vec![1,2,3])));
         assert_eq!(Ordering::Equal, Packet::values(vec![1,2,3,4]).cmp(&Packet::values(vec![1,2,3,4])));
 
-        let pairs = parse_input(TEST_INPUT).unwrap();
+        let packets = parse_input(TEST_INPUT).unwrap();
+        let pairs = pair_packets(&packets).unwrap();
         assert_eq!(Ordering::Less, pairs[0].0.cmp(&pairs[0].1));
         assert_eq!(Ordering::Less, pairs[1].0.cmp(&pairs[1].1));
         assert_eq!(Ordering::Greater, pairs[2].0.cmp(&pairs[2].1));
@@ -245,18 +336,65 @@ mod tests {
         assert_eq!(Ordering::Greater, pairs[7].0.cmp(&pairs[7].1));
     }
 
+    #[test]
+    fn test_cmp_with_trace() {
+        let packets = parse_input(TEST_INPUT).unwrap();
+        let pairs = pair_packets(&packets).unwrap();
+
+        assert_eq!((Ordering::Less, vec![
+            "- Compare [1,1,3,1,1] vs [1,1,5,1,1]",
+            "  - Compare 1 vs 1",
+            "  - Compare 1 vs 1",
+            "  - Compare 3 vs 5",
+            "    - Left side is smaller, so inputs are in the right order",
+        ].to_string()), pairs[0].0.cmp_with_trace(pairs[0].1));
+
+        assert_eq!((Ordering::Less, vec![
+            "- Compare [[1],[2,3,4]] vs [[1],4]",
+            "  - Compare [1] vs [1]",
+            "    - Compare 1 vs 1",
+            "  - Compare [2,3,4] vs 4",
+            "    - Mixed types; convert right to [4] and retry comparison",
+            "    - Compare [2,3,4] vs [4]",
+            "      - Compare 2 vs 4",
+            "        - Left side is smaller, so inputs are in the right order",
+        ].to_string()), pairs[1].0.cmp_with_trace(pairs[1].1));
+
+        assert_eq!((Ordering::Greater, vec![
+            "- Compare [9] vs [[8,7,6]]",
+            "  - Compare 9 vs [8,7,6]",
+            "    - Mixed types; convert left to [9] and retry comparison",
+            "    - Compare [9] vs [8,7,6]",
+            "      - Compare 9 vs 8",
+            "        - Right side is smaller, so inputs are not in the right order",
+        ].to_string()), pairs[2].0.cmp_with_trace(pairs[2].1));
+
+        assert_eq!((Ordering::Greater, vec![
+            "- Compare [7,7,7,7] vs [7,7,7]",
+            "  - Compare 7 vs 7",
+            "  - Compare 7 vs 7",
+            "  - Compare 7 vs 7",
+            "  - Right side ran out of items, so inputs are not in the right order",
+        ].to_string()), pairs[4].0.cmp_with_trace(pairs[4].1));
+
+        assert_eq!((Ordering::Less, vec![
+            "- Compare [] vs [3]",
+            "  - Left side ran out of items, so inputs are in the right order",
+        ].to_string()), pairs[5].0.cmp_with_trace(pairs[5].1));
+    }
+
     #[test]
     fn test_get_right_ordered_indices() {
-        let pairs = parse_input(TEST_INPUT).unwrap();
-        let indices = get_right_ordered_indices(&pairs);
+        let packets = parse_input(TEST_INPUT).unwrap();
+        let indices = get_right_ordered_indices(&packets);
 
-        assert_eq!(vec![1, 2, 4, 6], indices);
+        assert_eq!(Ok(vec![1, 2, 4, 6]), indices);
     }
 
     #[test]
     fn test_order_packets_for_distress_signal() {
-        let pairs = parse_input(TEST_INPUT).unwrap();
-        let result = order_packets_for_distress_signal(&pairs);
+        let packets = parse_input(TEST_INPUT).unwrap();
+        let result = order_packets_for_distress_signal(&packets);
 
         assert_eq!("[]", format!("{}", result[0]));
         assert_eq!("[[]]", format!("{}", result[1]));
@@ -280,33 +418,9 @@ mod tests {
 
     #[test]
     fn test_get_distress_decoder_key() {
-        let pairs = parse_input(TEST_INPUT).unwrap();
-        assert_eq!(140, get_distress_decoder_key(&pairs));
+        let packets = parse_input(TEST_INPUT).unwrap();
+        assert_eq!(140, get_distress_decoder_key(&packets));
     }
 
-    const TEST_INPUT: &str = "\
-        [1,1,3,1,1]\n\
-        [1,1,5,1,1]\n\
-        \n\
-        [[1],[2,3,4]]\n\
-        [[1],4]\n\
-        \n\
-        [9]\n\
-        [[8,7,6]]\n\
-        \n\
-        [[4,4],4,4]\n\
-        [[4,4],4,4,4]\n\
-        \n\
-        [7,7,7,7]\n\
-        [7,7,7]\n\
-        \n\
-        []\n\
-        [3]\n\
-        \n\
-        [[[]]]\n\
-        [[]]\n\
-        \n\
-        [1,[2,[3,[4,[5,6,7]]]],8,9]\n\
-        [1,[2,[3,[4,[5,6,0]]]],8,9]\n\
-    ";
+    const TEST_INPUT: &str = include_str!("../../examples/day13/input.txt");
 }
\ No newline at end of file
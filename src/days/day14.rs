@@ -1,25 +1,77 @@
-use std::fmt;
-use crate::days::Day;
+use std::{env, fmt};
+use crate::days::{Day, Explanation, Solver, solve_part1, solve_part2, parse_only, ParseSummary};
+use crate::util::error::AocError;
 use crate::util::geometry::{Grid, Line, Point};
-use crate::util::number::parse_isize;
+use crate::util::parser::Parser;
+use crate::util::vis;
+use crate::util::vis::{Animation, Frame};
 
 pub const DAY14: Day = Day {
-    puzzle1,
-    puzzle2
+    puzzle1: solve_part1::<Day14>,
+    puzzle2: solve_part2::<Day14>,
+    parse: parse_only::<Day14>,
+    describe: "Regolith Reservoir: simulate falling sand in a cave of rock paths",
+    explain: Some(explain),
+    selftest: None,
+    algorithms: &[]
 };
 
-fn puzzle1(input: &String) {
-    let cave = create_cave(input).unwrap();
+fn explain() -> String {
+    Explanation {
+        approach: "Drop sand grains one at a time from the sources until they fall out of the cave or cover their source; the floor is a virtual row below the lowest rock.",
+        complexity: "O(grains * cave height).",
+        alternatives: &[]
+    }.to_string()
+}
+
+#[derive(Default)]
+struct Day14;
+
+impl Solver for Day14 {
+    type Output = usize;
+
+    fn part1(&self, input: &str) -> Result<Self::Output, AocError> {
+        let cave = create_cave(input)?;
+
+        let held_sand = cave.get_max_held_sand(None);
+        print_per_source(&cave.get_held_sand_per_source(None));
+
+        if env::var("AOC_VERBOSE").is_ok() {
+            let frames = cave.get_sand_frames(None, 100);
+            for (i, frame) in frames.windows(2).enumerate() {
+                println!("== After {} sand blocks ==\n{}\n", ((i + 1) * 100).min(held_sand), frame[0].render_diff(&frame[1]));
+            }
+        }
+        if vis::enabled() {
+            cave.animate(None, held_sand).save()?;
+        }
+        Ok(held_sand)
+    }
+
+    fn part2(&self, input: &str) -> Result<Self::Output, AocError> {
+        let cave = create_cave(input)?;
+        let flooring = cave.determine_flooring();
 
-    let held_sand = cave.get_max_held_sand(None);
-    println!("The cave holds at most {} sand blocks", held_sand);
+        let held_sand = cave.get_max_held_sand(flooring);
+        print_per_source(&cave.get_held_sand_per_source(flooring));
+        Ok(held_sand)
+    }
+
+    fn parse(&self, input: &str) -> Result<ParseSummary, AocError> {
+        let cave = create_cave(input)?;
+        let tiles = cave.values();
+        Ok(ParseSummary::new()
+            .count(tiles.iter().filter(|t| **t == Tile::Rock).count(), "rocks")
+            .count(tiles.iter().filter(|t| **t == Tile::Extruder).count(), "extruders"))
+    }
 }
-fn puzzle2(input: &String) {
-    let cave = create_cave(input).unwrap();
-    let flooring = cave.determine_flooring();
 
-    let held_sand = cave.get_max_held_sand(flooring);
-    println!("With a floor, the cave holds at most {} sand blocks", held_sand);
+fn print_per_source(counts: &[(Point, usize)]) {
+    if counts.len() > 1 {
+        for (source, count) in counts {
+            println!("  source {} dropped {} sand blocks", source, count);
+        }
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Default)]
@@ -44,11 +96,23 @@ impl fmt::Display for Tile {
     }
 }
 
-fn create_cave(input: &str) -> Result<Cave, String> {
+fn create_cave(input: &str) -> Result<Cave, AocError> {
     let mut cave = Grid::default();
+    let mut extruders = vec![];
+
+    for (index, r_line) in input.lines().enumerate() {
+        let error = |e: AocError| e.in_line(index + 1);
+        // Besides rock lines, the input can list extra sand sources as 'extruder x,y'.
+        let mut parser = Parser::new(r_line);
+        if parser.try_parse(|parser| parser.literal("extruder ")).is_ok() {
+            match parse_rock_line(&mut parser).map_err(error)?[..] {
+                [point] => extruders.push((index + 1, point)),
+                _ => return Err(error(AocError::parse(format!("Expected a single coordinate for the extruder: '{}'", r_line))))
+            }
+            continue;
+        }
 
-    for r_line in input.lines() {
-        let points = parse_rock_line(r_line)?;
+        let points = parse_rock_line(&mut parser).map_err(error)?;
 
         for i in 0..points.len()-1 {
             let line = Line { start: points[i], end: points[i+1] };
@@ -58,19 +122,27 @@ fn create_cave(input: &str) -> Result<Cave, String> {
 
     // The cave always has a source of falling sand at 500,0:
     cave.set((500, 0).into(), Tile::Extruder);
+    for (line, extruder) in extruders {
+        if cave.get(&extruder) == Some(Tile::Rock) {
+            return Err(AocError::parse(format!("Extruder {} is placed inside rock", extruder)).in_line(line));
+        }
+        cave.set(extruder, Tile::Extruder);
+    }
 
     Ok(cave)
 }
 
-fn parse_rock_line(line: &str) -> Result<Vec<Point>, String> {
-    let mut points = vec![];
-
-    for part in line.split(" -> ") {
-        let coords: Vec<_> = part.split(",").map(|s| s.trim()).collect();
-        if coords.len() != 2 { return Err(format!("Invalid coordinate '{}'", part)) }
-        let x = parse_isize(coords[0])?;
-        let y = parse_isize(coords[1])?;
-        points.push((x,y).into());
+/// The points of a rock line like "498,4 -> 498,6", up to the end of the line.
+fn parse_rock_line(parser: &mut Parser) -> Result<Vec<Point>, AocError> {
+    let points = parser.separated_list("->", |parser| {
+        let x = parser.isize()?;
+        parser.literal(",")?;
+        let y = parser.isize()?;
+        Ok((x, y).into())
+    })?;
+    let rest = parser.rest();
+    if !rest.is_empty() {
+        return Err(AocError::parse(format!("Unexpected '{}' after the rock line '{}'", rest, parser.input())));
     }
 
     Ok(points)
@@ -78,33 +150,96 @@ fn parse_rock_line(line: &str) -> Result<Vec<Point>, String> {
 
 impl Cave {
     fn determine_flooring(&self) -> Option<isize> {
-        let max_y = self.entries().iter().filter(|(_, t)| Tile::Rock.eq(t)).map(|(p, _)| p.y).max();
+        let max_y = self.find_all(|t| Tile::Rock.eq(t)).iter().map(|p| p.y).max();
         return max_y.map(|v| v + 2);
     }
 
     fn get_max_held_sand(&self, flooring: Option<isize>) -> usize {
+        self.get_held_sand_per_source(flooring).iter().map(|(_, count)| count).sum()
+    }
+
+    /// The sand sources of the cave, in reading order.
+    fn get_sources(&self) -> Vec<Point> {
+        self.find_all(|t| Tile::Extruder.eq(t))
+    }
+
+    /// Fills the cave with the sources taking turns dropping sand, and counts the blocks of sand held per source.
+    /// A source stops once its sand falls out of the cave, or once it is covered with sand.
+    fn get_held_sand_per_source(&self, flooring: Option<isize>) -> Vec<(Point, usize)> {
         // We could just make this param mut, but that's less fun.
         let mut sim = self.clone();
+        let mut counts: Vec<_> = sim.get_sources().into_iter().map(|p| (p, 0)).collect();
+        let mut active: Vec<_> = (0..counts.len()).collect();
+
+        while !active.is_empty() {
+            active.retain(|i| {
+                let dropped = sim.drop_sand_from(counts[*i].0, flooring);
+                if dropped {
+                    counts[*i].1 += 1;
+                }
+                dropped
+            });
+        }
+        counts
+    }
+
+    /// Snapshots of the cave while filling it with sand: the initial cave, one after every `every` blocks of sand,
+    /// and the final cave (when it is not already the last snapshot).
+    fn get_sand_frames(&self, flooring: Option<isize>, every: usize) -> Vec<Cave> {
+        let mut sim = self.clone();
+        let mut frames = vec![sim.clone()];
         let mut sands = 0;
         while sim.drop_sand(flooring) {
             sands += 1;
+            if sands % every == 0 {
+                frames.push(sim.clone());
+            }
+        }
+        if sands % every != 0 {
+            frames.push(sim);
+        }
+        frames
+    }
+
+    /// An animation of filling the cave holding `held_sand` blocks of sand, in at most 200 frames.
+    fn animate(&self, flooring: Option<isize>, held_sand: usize) -> Animation {
+        const PALETTE: [[u8; 3]; 4] = [[20, 20, 30], [110, 100, 90], [230, 190, 80], [220, 50, 50]];
+        let frames = self.get_sand_frames(flooring, held_sand.div_ceil(200).max(1));
+        // The sand can only grow the cave, so the last frame covers all others.
+        let bounds = frames.last().unwrap().bounds;
+
+        let mut animation = Animation::new(&PALETTE);
+        animation.scale = 4;
+        for frame in &frames {
+            animation.push(Frame::from_grid(frame, bounds, |tile| match tile {
+                Tile::Air => 0,
+                Tile::Rock => 1,
+                Tile::Sand => 2,
+                Tile::Extruder => 3,
+            }));
         }
-        sands
+        animation
     }
 
     fn drop_sand(&mut self, flooring: Option<isize>) -> bool {
-        let known_cells = self.entries();
-        let extruder = match known_cells.iter().find(|(_, t)| Tile::Extruder.eq(t)).map(|(p, _)| p) {
-            Some(point) => point,
-            None => return false
-        };
+        match self.get_sources().first() {
+            Some(extruder) => self.drop_sand_from(*extruder, flooring),
+            None => false
+        }
+    }
+
+    fn drop_sand_from(&mut self, extruder: Point, flooring: Option<isize>) -> bool {
+        // A source that got covered with sand cannot drop any more.
+        if self.get(&extruder) != Some(Tile::Extruder) {
+            return false;
+        }
 
         // Sand falls straight down (y+1) until it hits rock or sand.
         // Once it finds rock or sand:
         // 1. if the spot to the left (x-1) is free, continue there
         // 2. if the spot to the right is free, continue there
         // 3. if either spot is taken, this sand stacks on top of the found tile.
-        let mut current_point = extruder.clone();
+        let mut current_point = extruder;
 
         'main: loop {
             let next_point: Point = current_point + (0, 1);
@@ -157,7 +292,10 @@ impl Cave {
 
 #[cfg(test)]
 mod tests {
-    use crate::days::day14::{create_cave, Tile};
+    use crate::days::day14::{create_cave, parse_rock_line, Tile};
+    use crate::util::error::AocError;
+    use crate::util::geometry::Point;
+    use crate::util::parser::Parser;
 
     #[test]
     fn test_create_cave() {
@@ -253,6 +391,19 @@ mod tests {
         assert_eq!(24, cave.get_max_held_sand(None));
     }
 
+    #[test]
+    fn test_get_sand_frames() {
+        let cave = create_cave(TEST_INPUT).unwrap();
+        let frames = cave.get_sand_frames(None, 10);
+
+        assert_eq!(4, frames.len());
+        assert_eq!(cave, frames[0]);
+        let added: Vec<_> = frames.windows(2).map(|f| f[0].diff(&f[1]).len()).collect();
+        assert_eq!(vec![10, 10, 4], added);
+        assert!(frames[0].diff(&frames[1]).iter().all(|(_, before, after)| before.is_none() && *after == Some(Tile::Sand)));
+        assert_eq!(vec![((500, 8).into(), None, Some(Tile::Sand))], frames[0].diff(&cave.get_sand_frames(None, 1)[1]));
+    }
+
     #[test]
     fn test_determine_flooring() {
         let cave = create_cave(TEST_INPUT).unwrap();
@@ -324,8 +475,39 @@ mod tests {
         assert_eq!(93, cave.get_max_held_sand(Some(11)));
     }
 
-    const TEST_INPUT: &str = "\
-        498,4 -> 498,6 -> 496,6\n\
-        503,4 -> 502,4 -> 502,9 -> 494,9\n\
-    ";
+    #[test]
+    fn test_extra_extruders() {
+        let cave = create_cave(&format!("{}extruder 496,0\n", TEST_INPUT)).unwrap();
+        assert_eq!(vec![Point::from((496, 0)), Point::from((500, 0))], cave.get_sources());
+
+        // The extra source's first block comes to rest on the left rock ledge, after which its sand slides off into
+        // the abyss. That block takes the place of one of the original source's blocks.
+        let counts = cave.get_held_sand_per_source(None);
+        assert_eq!(vec![(Point::from((496, 0)), 1), (Point::from((500, 0)), 23)], counts);
+        assert_eq!(24, cave.get_max_held_sand(None));
+
+        // With a floor, both sources build their own pile until these merge and cover the extruders.
+        let counts = cave.get_held_sand_per_source(Some(11));
+        assert_eq!(vec![(Point::from((496, 0)), 63), (Point::from((500, 0)), 70)], counts);
+
+        assert_eq!(Err("Line 2: Extruder (498,5) is placed inside rock".to_string()), create_cave("498,4 -> 498,6\nextruder 498,5\n").map(|_| ()).map_err(|e| e.to_string()));
+        assert!(create_cave("extruder 498,5 -> 498,6\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_rock_line() {
+        let mut parser = Parser::new("498,4 -> 498,6 -> 496,6");
+        assert_eq!(Ok(vec![(498, 4).into(), (498, 6).into(), (496, 6).into()]), parse_rock_line(&mut parser));
+        assert_eq!(Err(AocError::parse_at(2, 13, "Expected '' to match ','\n  503,4 -> 502\n              ^").to_string()),
+                   create_cave("498,4 -> 498,6\n503,4 -> 502").map(|_| ()).map_err(|e| e.to_string()));
+    }
+
+    #[test]
+    fn test_single_source_counts() {
+        let cave = create_cave(TEST_INPUT).unwrap();
+        assert_eq!(vec![(Point::from((500, 0)), 24)], cave.get_held_sand_per_source(None));
+        assert_eq!(vec![(Point::from((500, 0)), 93)], cave.get_held_sand_per_source(Some(11)));
+    }
+
+    const TEST_INPUT: &str = include_str!("../../examples/day14/input.txt");
 }
\ No newline at end of file
@@ -1,27 +1,99 @@
+use std::fmt;
 use std::ops::{RangeInclusive};
 use std::str::FromStr;
-use crate::days::Day;
+use crate::days::{Day, Explanation, Solver, solve_part1, solve_part2, parse_only, ParseSummary};
+use crate::util::error::AocError;
+use crate::util::config::day15::{MAX_COORDINATE, ROW};
 use crate::util::geometry::Point;
+use crate::util::input::parse_lines_strict;
 use crate::util::parser::Parser;
+use crate::util::range::intersection;
 
 pub const DAY15: Day = Day {
-    puzzle1,
-    puzzle2
+    puzzle1: solve_part1::<Day15>,
+    puzzle2: solve_part2::<Day15>,
+    parse: parse_only::<Day15>,
+    describe: "Beacon Exclusion Zone: find where beacons cannot be and locate the distress beacon",
+    explain: Some(explain),
+    selftest: None,
+    algorithms: &[]
 };
 
-fn puzzle1(input: &String) {
-    let sensors = parse_input(input).unwrap();
+fn explain() -> String {
+    Explanation {
+        approach: "Row coverage is the union of the sensor ranges on that row via inclusion-exclusion. The beacon must sit just outside a sensor's range, so only those border points are checked.",
+        complexity: "O(s²) ranges for part 1; O(s² * r) for s sensors with range r for part 2.",
+        alternatives: &["Intersecting the border lines of pairs of sensors would avoid walking the borders."]
+    }.to_string()
+}
+
+#[derive(Default)]
+struct Day15;
+
+impl Solver for Day15 {
+    type Output = i128;
+
+    fn part1(&self, input: &str) -> Result<Self::Output, AocError> {
+        let sensors = parse_input(input)?;
+
+        let parameters = get_parameters(&sensors)?;
+        println!("Using {}", parameters);
+        println!("Counting the spots on line {}", parameters.row);
+        Ok(get_coverage_on_line(&sensors, parameters.row) as i128)
+    }
+
+    fn part2(&self, input: &str) -> Result<Self::Output, AocError> {
+        let sensors = parse_input(input)?;
+
+        // Crossing fingers
+        let parameters = get_parameters(&sensors)?;
+        println!("Using {}", parameters);
+        let point = find_sensor_location(&sensors, 0..=parameters.max_coordinate).ok_or("There is no spot left for the beacon")?;
+        println!("Found where the beacon has to be: {}", point);
+        Ok(tuning_frequency(point))
+    }
+
+    fn parse(&self, input: &str) -> Result<ParseSummary, AocError> {
+        let sensors = parse_input(input)?;
+        Ok(ParseSummary::new().count(sensors.len(), "sensors"))
+    }
+}
+
+/// The tuning frequency is x * TUNING_MULTIPLIER + y, for the example as well.
+const TUNING_MULTIPLIER: i128 = 4_000_000;
+/// Row and maximum coordinate used by the example.
+const EXAMPLE_PARAMETERS: (isize, isize) = (10, 20);
+/// The example's coordinates stay below this, while the real input's are in the millions.
+const EXAMPLE_SCALE_LIMIT: isize = 1_000;
+
+/// The tuning frequency of the beacon at the point, in i128 as it does not fit an isize on 32-bit targets.
+fn tuning_frequency(point: Point) -> i128 {
+    point.x as i128 * TUNING_MULTIPLIER + point.y as i128
+}
 
-    let coverage = get_coverage_on_line(&sensors, 2_000_000);
-    println!("There are {} spots on line 2.000.000 that cannot have a beacon", coverage);
+fn is_example_scale(sensors: &[Sensor]) -> bool {
+    sensors.iter().flat_map(|s| [s.location, s.beacon]).all(|p| p.x.abs() < EXAMPLE_SCALE_LIMIT && p.y.abs() < EXAMPLE_SCALE_LIMIT)
 }
 
-fn puzzle2(input: &String) {
-    let sensors = parse_input(input).unwrap();
+/// The row to check for part 1 and the maximum coordinate for part 2, see `get_parameters`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Parameters {
+    row: isize,
+    max_coordinate: isize,
+    example_scale: bool
+}
+
+impl fmt::Display for Parameters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "row {} and maximum coordinate {} ({} scale input)", self.row, self.max_coordinate, if self.example_scale { "example" } else { "real" })
+    }
+}
 
-    // Crossing fingers
-    let point = find_sensor_location(&sensors, 0..=4_000_000).unwrap();
-    println!("Found where the beacon has to be: {}, result = {}", point, point.x * 4_000_000 + point.y);
+/// The example's or the real parameters depending on the scale of the input, unless set explicitly.
+fn get_parameters(sensors: &[Sensor]) -> Result<Parameters, String> {
+    let example_scale = is_example_scale(sensors);
+    let (row, max) = if example_scale { EXAMPLE_PARAMETERS } else { (ROW.default, MAX_COORDINATE.default) };
+    Ok(Parameters { row: ROW.get_or(row)?, max_coordinate: MAX_COORDINATE.get_or(max)?, example_scale })
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -36,22 +108,11 @@ impl Sensor {
 }
 
 impl FromStr for Sensor {
-    type Err = String;
+    type Err = AocError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        // Sensor at x=#, y=##: closest beacon is at x=##, y=##
-        let mut parser = Parser::new(s.to_string());
-        parser.literal("Sensor at x=")?;
-        let sx = parser.isize()?;
-        parser.literal(",")?;
-        parser.literal("y=")?;
-        let sy = parser.isize()?;
-        parser.literal(":")?;
-        parser.literal("closest beacon is at x=")?;
-        let bx = parser.isize()?;
-        parser.literal(",")?;
-        parser.literal("y=")?;
-        let by = parser.isize()?;
+        let values = Parser::new(s).pattern("Sensor at x={i}, y={i}: closest beacon is at x={i}, y={i}")?;
+        let (sx, sy, bx, by) = (values[0].as_isize()?, values[1].as_isize()?, values[2].as_isize()?, values[3].as_isize()?);
 
         let location = (sx, sy).into();
         let beacon = (bx, by).into();
@@ -84,19 +145,25 @@ impl ManhattanArea {
         let mut points = vec![];
         // We can start above this area (y - length - 1) and walk around it?
         let distance = self.length + 1;
+        // Every side starts at a corner and walks diagonally towards the next corner.
+        let sides = [
+            ((0, -1), (1, 1)),   // top (x,y-distance) -> right (x+distance,y)
+            ((1, 0), (-1, 1)),   // right (x+distance,y) -> bottom (x,y+distance)
+            ((0, 1), (-1, -1)),  // bottom (x,y+distance) -> left (x-distance,y)
+            ((-1, 0), (1, -1)),  // left (x-distance,y) -> top (x,y-distance)
+        ];
         for i in 0..distance {
-            points.push(self.center + (i, -(distance - i)));  // top (x,y-distance) -> right (x+distance,y)
-            points.push(self.center + ((distance - i), i));   // right (x+distance,y) -> bottom (x,y+distance)
-            points.push(self.center + (-i, distance - i));    // bottom (x,y+distance) -> left (x-distance,y)
-            points.push(self.center + (-(distance - i), -i)); // left (x-distance,y) -> top (x,y-distance)
+            for (corner, step) in sides {
+                points.push(self.center + Point::from(corner) * distance + Point::from(step) * i);
+            }
         }
 
         points
     }
 }
 
-fn parse_input(input: &str) -> Result<Vec<Sensor>, String> {
-    input.lines().map(|l| l.parse()).collect()
+fn parse_input(input: &str) -> Result<Vec<Sensor>, AocError> {
+    parse_lines_strict(input, |l| l.parse::<Sensor>())
 }
 
 fn find_sensor_location(sensors: &Vec<Sensor>, cap: RangeInclusive<isize>) -> Option<Point> {
@@ -127,20 +194,10 @@ fn get_coverage_on_line(sensors: &Vec<Sensor>, line: isize) -> usize {
 
     let mut coverages: Vec<Coverage> = vec![];
 
-    fn overlap_range(range: &RangeInclusive<isize>, other: &RangeInclusive<isize>) -> RangeInclusive<isize> {
-        let range_start = range.start().clone();
-        let range_end = range.end().clone();
-        let other_start = other.start().clone();
-        let other_end = other.end().clone();
-
-        other_start.max(range_start)..=other_end.min(range_end)
-    }
-
     for sensor in sensors {
         if let Some(xs) = sensor.area.get_cols_for_line(line) {
             for coverage in coverages.clone() {
-                let overlap = overlap_range(&xs, &coverage.range);
-                if !overlap.is_empty() {
+                if let Some(overlap) = intersection(&xs, &coverage.range) {
                     coverages.push(Coverage { range: overlap, is_overlap: !coverage.is_overlap });
                 }
             }
@@ -166,7 +223,7 @@ fn get_coverage_on_line(sensors: &Vec<Sensor>, line: isize) -> usize {
 
 #[cfg(test)]
 mod tests {
-    use crate::days::day15::{find_sensor_location, get_coverage_on_line, ManhattanArea, parse_input, Sensor};
+    use crate::days::day15::{find_sensor_location, get_coverage_on_line, get_parameters, is_example_scale, ManhattanArea, Parameters, parse_input, Sensor, tuning_frequency};
     use crate::util::geometry::Point;
 
     #[test]
@@ -223,20 +280,27 @@ mod tests {
         assert_eq!(Some(Point { x: 14, y: 11 }), find_sensor_location(&sensors, 0..=20));
     }
 
-    const TEST_INPUT: &str = "\
-        Sensor at x=2, y=18: closest beacon is at x=-2, y=15\n\
-        Sensor at x=9, y=16: closest beacon is at x=10, y=16\n\
-        Sensor at x=13, y=2: closest beacon is at x=15, y=3\n\
-        Sensor at x=12, y=14: closest beacon is at x=10, y=16\n\
-        Sensor at x=10, y=20: closest beacon is at x=10, y=16\n\
-        Sensor at x=14, y=17: closest beacon is at x=10, y=16\n\
-        Sensor at x=8, y=7: closest beacon is at x=2, y=10\n\
-        Sensor at x=2, y=0: closest beacon is at x=2, y=10\n\
-        Sensor at x=0, y=11: closest beacon is at x=2, y=10\n\
-        Sensor at x=20, y=14: closest beacon is at x=25, y=17\n\
-        Sensor at x=17, y=20: closest beacon is at x=21, y=22\n\
-        Sensor at x=16, y=7: closest beacon is at x=15, y=3\n\
-        Sensor at x=14, y=3: closest beacon is at x=15, y=3\n\
-        Sensor at x=20, y=1: closest beacon is at x=15, y=3\n\
-    ";
+    #[test]
+    fn test_tuning_frequency() {
+        assert_eq!(56_000_011, tuning_frequency(Point { x: 14, y: 11 }));
+        assert_eq!(16_000_000_000_000, tuning_frequency(Point { x: 4_000_000, y: 0 }));
+        // Even the extremes of isize fit.
+        assert_eq!(isize::MAX as i128 * 4_000_001, tuning_frequency(Point { x: isize::MAX, y: isize::MAX }));
+        assert_eq!(isize::MIN as i128 * 4_000_001, tuning_frequency(Point { x: isize::MIN, y: isize::MIN }));
+    }
+
+    #[test]
+    fn test_get_parameters() {
+        let sensors = parse_input(TEST_INPUT).unwrap();
+        assert!(is_example_scale(&sensors));
+        assert_eq!(Ok(Parameters { row: 10, max_coordinate: 20, example_scale: true }), get_parameters(&sensors));
+
+        let sensors = parse_input("Sensor at x=2302110, y=2237242: closest beacon is at x=2348729, y=1239977").unwrap();
+        assert!(!is_example_scale(&sensors));
+        let parameters = get_parameters(&sensors).unwrap();
+        assert_eq!(Parameters { row: 2_000_000, max_coordinate: 4_000_000, example_scale: false }, parameters);
+        assert_eq!("row 2000000 and maximum coordinate 4000000 (real scale input)", parameters.to_string());
+    }
+
+    const TEST_INPUT: &str = include_str!("../../examples/day15/input.txt");
 }
\ No newline at end of file
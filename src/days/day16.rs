@@ -1,26 +1,113 @@
-use std::collections::{HashMap, VecDeque};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::{env, fmt};
 use std::hash::Hash;
 use std::str::FromStr;
-use crate::days::Day;
+use crate::days::{Day, Explanation, Solver, solve_part1, solve_part2, parse_only, ParseSummary};
+use crate::util::config::day16::ALGORITHM;
+use crate::util::error::AocError;
+use crate::util::input::parse_lines_strict;
+use crate::util::number::parse_usize;
 use crate::util::parser::Parser;
+use crate::util::results;
+use crate::util::search::WorkQueue;
+use crate::util::stats::Stats;
 
 pub const DAY16: Day = Day {
-    puzzle1,
-    puzzle2
+    puzzle1: solve_part1::<Day16>,
+    puzzle2: solve_part2::<Day16>,
+    parse: parse_only::<Day16>,
+    describe: "Proboscidea Volcanium: open valves to release the most pressure, with and without an elephant",
+    explain: Some(explain),
+    selftest: None,
+    algorithms: ALGORITHM.options
 };
 
-fn puzzle1(input: &String) {
-    let valves = parse_input(input).unwrap();
+fn explain() -> String {
+    Explanation {
+        approach: "Contract zero-flow corridors, compute the distances between interesting valves, and explore all orders of opening valves, keeping the best flow per set of opened valves. With the elephant, combine the best two disjoint sets.",
+        complexity: "Exponential in the number of valves with flow, O(sets²) for pairing.",
+        alternatives: &["Compute the distances over all valves instead of the contracted graph, set AOC_DAY16_ALGORITHM=full."]
+    }.to_string()
+}
+
+#[derive(Default)]
+struct Day16;
+
+impl Solver for Day16 {
+    type Output = usize;
+
+    fn part1(&self, input: &str) -> Result<Self::Output, AocError> {
+        let mut stats = Stats::new();
+        let valves = {
+            let _parse = stats.phase("parse");
+            parse_input(input)?
+        };
+
+        let algorithm = ALGORITHM.get()?;
+        let flow_map = load_or_build_flow_map(input, &valves, false, algorithm == "contracted", &mut stats);
+        let (highest_rate, itinerary) = choose_itinerary(&flow_map, false, &mut stats).ok_or("No itinerary found")?;
+        print_itinerary(&valves, &itinerary);
+        if env::var("AOC_VERBOSE").is_ok() {
+            stats.print(16, 1, algorithm);
+        }
+        Ok(highest_rate)
+    }
+
+    fn part2(&self, input: &str) -> Result<Self::Output, AocError> {
+        let mut stats = Stats::new();
+        let valves = {
+            let _parse = stats.phase("parse");
+            parse_input(input)?
+        };
+
+        let algorithm = ALGORITHM.get()?;
+        let flow_map = load_or_build_flow_map(input, &valves, true, algorithm == "contracted", &mut stats);
+        let (highest_rate, itinerary) = choose_itinerary(&flow_map, true, &mut stats).ok_or("No itinerary found")?;
+        print_itinerary(&valves, &itinerary);
+        if env::var("AOC_VERBOSE").is_ok() {
+            stats.print(16, 2, algorithm);
+        }
+        Ok(highest_rate)
+    }
 
-    let highest_rate = find_highest_flow(&valves, false).unwrap();
-    println!("The highest flow rate is: {}", highest_rate);
+    fn parse(&self, input: &str) -> Result<ParseSummary, AocError> {
+        let valves = parse_input(input)?;
+        Ok(ParseSummary::new()
+            .count(valves.len(), "valves")
+            .count(valves.iter().filter(|v| v.flow_rate > 0).count(), "with flow")
+            .count(valves.iter().map(|v| v.tunnels.len()).sum(), "tunnels"))
+    }
 }
 
-fn puzzle2(input: &String) {
-    let valves = parse_input(input).unwrap();
+fn print_itinerary(valves: &[Valve], itinerary: &[Visit]) {
+    if env::var("AOC_VERBOSE").is_ok() {
+        // Both agents start at AA, and walk from one valve they open to the next.
+        let mut positions = HashMap::from([(Agent::You, "AA"), (Agent::Elephant, "AA")]);
+        for visit in itinerary {
+            if let Some(route) = tunnel_route(valves, positions[&visit.agent], &visit.valve) {
+                println!("   walking {}", route.join(" -> "));
+            }
+            positions.insert(visit.agent, &visit.valve);
+            println!("{}", visit);
+        }
+    }
+}
 
-    let highest_rate = find_highest_flow(&valves, true).unwrap();
-    println!("The highest flow rate, with an elephant helping, is: {}", highest_rate);
+/// The valves on a shortest walk through the tunnels, from `from` up to and including `to`.
+fn tunnel_route(valves: &[Valve], from: &str, to: &str) -> Option<Vec<String>> {
+    let by_name: HashMap<_, _> = valves.iter().map(|v| (v.name.as_str(), v)).collect();
+    let mut todo = WorkQueue::fifo().tracking_paths();
+    todo.push(from);
+    while let Some(current) = todo.pop() {
+        if current == to {
+            break;
+        }
+        for tunnel in by_name.get(current).map(|v| v.tunnels.as_slice()).unwrap_or_default() {
+            todo.push_from(&current, tunnel.as_str());
+        }
+    }
+    todo.path_to(&to).map(|path| path.into_iter().map(|v| v.to_string()).collect())
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -31,29 +118,26 @@ struct Valve {
 }
 
 impl FromStr for Valve {
-    type Err = String;
+    type Err = AocError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut parser = Parser::new(s);
-        parser.literal("Valve ")?;
-        let name = parser.str(2)?;
-        parser.literal("has flow rate=")?;
-        let flow_rate = parser.usize()?;
-        parser.literal(";")?;
-        parser.literal("tunnel leads to valve ")
-            .or_else(|_| parser.literal("tunnels lead to valves "))?;
-        let mut tunnels = vec![parser.str(2)?];
-        while !parser.is_exhausted() {
-            parser.literal(",")?;
-            tunnels.push(parser.str(2)?);
+        let values = parser.pattern("Valve {w} has flow rate={u};")?;
+        let name = values[0].as_str()?.to_string();
+        let flow_rate = values[1].as_usize()?;
+        parser.one_of(vec!["tunnel leads to valve ", "tunnels lead to valves "])?;
+        let tunnels = parser.separated_list(",", |parser| parser.str(2))?;
+        let rest = parser.rest();
+        if !rest.is_empty() {
+            return Err(AocError::parse(format!("Unexpected '{}' after the tunnels of valve {}", rest, name)));
         }
 
         Ok(Valve { name, flow_rate, tunnels })
     }
 }
 
-fn parse_input(input: &str) -> Result<Vec<Valve>, String> {
-    input.lines().map(|l| l.parse()).collect()
+fn parse_input(input: &str) -> Result<Vec<Valve>, AocError> {
+    parse_lines_strict(input, |l| l.parse::<Valve>())
 }
 
 type DistanceMap = HashMap<String, HashMap<String, usize>>;
@@ -85,37 +169,229 @@ fn build_distance_map(valves: &Vec<Valve>) -> DistanceMap {
     result
 }
 
+/// The valves worth visiting (the start valve and all valves with a flow rate), connected by weighted edges that
+/// replace the corridors of zero-flow valves between them.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct ContractedGraph {
+    nodes: Vec<String>,
+    // For every node, the nodes directly reachable (without passing another node) and the number of steps to get there.
+    edges: HashMap<String, Vec<(String, usize)>>
+}
+
+impl ContractedGraph {
+    fn create(valves: &[Valve], start: &str) -> Self {
+        let by_name: HashMap<_, _> = valves.iter().map(|v| (v.name.as_str(), v)).collect();
+        let nodes: Vec<_> = valves.iter().filter(|v| v.flow_rate > 0 || v.name == start).map(|v| v.name.clone()).collect();
+
+        let mut edges = HashMap::new();
+        for node in &nodes {
+            // Walk through the zero-flow valves from this node, stopping at every other node we reach.
+            let mut node_edges = vec![];
+            let mut steps_to: HashMap<&str, usize> = HashMap::from([(node.as_str(), 0)]);
+            let mut todo = WorkQueue::fifo();
+            todo.push(node.as_str());
+            while let Some(current) = todo.pop() {
+                let steps = steps_to[current];
+                for tunnel in by_name.get(current).map(|v| v.tunnels.as_slice()).unwrap_or_default() {
+                    if todo.is_seen(&tunnel.as_str()) {
+                        continue;
+                    }
+                    steps_to.insert(tunnel, steps + 1);
+                    if nodes.contains(tunnel) {
+                        todo.mark_seen(tunnel);
+                        node_edges.push((tunnel.clone(), steps + 1));
+                    } else {
+                        todo.push(tunnel);
+                    }
+                }
+            }
+            edges.insert(node.clone(), node_edges);
+        }
+
+        ContractedGraph { nodes, edges }
+    }
+
+    fn edge_count(&self) -> usize {
+        self.edges.values().map(|e| e.len()).sum()
+    }
+
+    /// Like `build_distance_map`, but only between the nodes of the contracted graph.
+    fn distance_map(&self) -> DistanceMap {
+        let mut result = HashMap::new();
+
+        for node in &self.nodes {
+            // Distances include the minute it takes to open the valve, like in `build_distance_map`.
+            let mut distances: HashMap<String, usize> = HashMap::new();
+            let mut queue = BinaryHeap::from([(Reverse(1), node.clone())]);
+            while let Some((Reverse(distance), current)) = queue.pop() {
+                if distances.contains_key(&current) {
+                    continue;
+                }
+                for (next, steps) in &self.edges[&current] {
+                    if !distances.contains_key(next) {
+                        queue.push((Reverse(distance + steps), next.clone()));
+                    }
+                }
+                distances.insert(current, distance);
+            }
+            result.insert(node.clone(), distances);
+        }
+
+        result
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+enum Agent {
+    You,
+    Elephant
+}
+
+/// A valve being opened: `minute` is the minute during which it is opened, `released` the pressure it releases
+/// from then on until the time runs out.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Visit {
+    agent: Agent,
+    minute: usize,
+    valve: String,
+    released: usize,
+}
+
+impl fmt::Display for Visit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let agent = match self.agent {
+            Agent::You => "You open",
+            Agent::Elephant => "The elephant opens"
+        };
+        write!(f, "== Minute {} == {} valve {}, releasing {} pressure", self.minute, agent, self.valve, self.released)
+    }
+}
+
+#[allow(unused)]
 fn find_highest_flow(valves: &Vec<Valve>, include_elephant: bool) -> Option<usize> {
-    let distance_map = build_distance_map(valves);
+    find_best_itinerary(valves, include_elephant).map(|(flow, _)| flow)
+}
 
-    // We will build up a map of <open valves> => max_flow by visiting everything like we initially did.
-    // This map can then be used to find pairs of entries with no overlapping valves to find a solution
-    // for part 2 without taking way too long.
-    #[derive(Debug, Eq, PartialEq, Hash)]
-    struct FlowKey {
-        open_valves: Vec<String>
-    }
-    impl FlowKey {
-        fn create(valves: &Vec<String>) -> Self {
-            let mut open_valves = valves.clone();
-            open_valves.sort();
-            FlowKey { open_valves }
+/// Finds the highest possible flow, together with the valves opened (ordered by minute) to get there.
+fn find_best_itinerary(valves: &Vec<Valve>, include_elephant: bool) -> Option<(usize, Vec<Visit>)> {
+    find_best_itinerary_with(valves, include_elephant, true, &mut Stats::new())
+}
+
+/// The open valves of an entry in the flow map, sorted.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+struct FlowKey {
+    open_valves: Vec<String>
+}
+
+impl FlowKey {
+    fn create(valves: &[String]) -> Self {
+        let mut open_valves = valves.to_vec();
+        open_valves.sort();
+        FlowKey { open_valves }
+    }
+}
+
+/// For every set of valves that can be opened in time, the highest flow opening them gives and the visits to get it.
+type FlowMap = HashMap<FlowKey, (usize, Vec<Visit>)>;
+
+/// Bump when the flow map changes for the same input, to invalidate the cached flow maps.
+const FLOW_MAP_CACHE_VERSION: &str = "1";
+
+fn total_time(include_elephant: bool) -> usize {
+    if include_elephant { 26 } else { 30 }
+}
+
+/// Same as `find_best_itinerary`; with `contract` the distances are computed over the `ContractedGraph` instead
+/// of over all valves.
+fn find_best_itinerary_with(valves: &Vec<Valve>, include_elephant: bool, contract: bool, stats: &mut Stats) -> Option<(usize, Vec<Visit>)> {
+    let flow_map = build_flow_map(valves, total_time(include_elephant), contract, stats);
+    choose_itinerary(&flow_map, include_elephant, stats)
+}
+
+/// Builds the flow map, or with `AOC_CACHE` set, reads it from the cache when it was built for this input before.
+/// Building the map is the slow part, so caching it speeds up tuning the pairing a lot.
+fn load_or_build_flow_map(input: &str, valves: &Vec<Valve>, include_elephant: bool, contract: bool, stats: &mut Stats) -> FlowMap {
+    let total_time = total_time(include_elephant);
+    if env::var("AOC_CACHE").is_err() {
+        return build_flow_map(valves, total_time, contract, stats);
+    }
+
+    let name = format!("day16-flow-map-{}", total_time);
+    let key = results::cache_key(&[input, FLOW_MAP_CACHE_VERSION]);
+    if let Some(cached) = results::load_cached(&name, &key) {
+        match parse_flow_map(&cached) {
+            Ok(flow_map) => {
+                stats.set("flow_map_cached", 1);
+                stats.set("flow_map_entries", flow_map.len());
+                return flow_map;
+            }
+            Err(e) => eprintln!("Ignoring the cached flow map: {}", e),
         }
     }
 
+    let flow_map = build_flow_map(valves, total_time, contract, stats);
+    if let Err(e) = results::store_cached(&name, &key, &format_flow_map(&flow_map)) {
+        eprintln!("Could not cache the flow map: {}", e);
+    }
+    flow_map
+}
+
+/// One line per entry: the flow, followed by the visits as valve:minute:released.
+fn format_flow_map(flow_map: &FlowMap) -> String {
+    let mut lines: Vec<_> = flow_map.values().map(|(flow, visits)| {
+        let visits: Vec<_> = visits.iter().map(|v| format!("{}:{}:{}", v.valve, v.minute, v.released)).collect();
+        format!("{} {}", flow, visits.join(" "))
+    }).collect();
+    lines.sort();
+    lines.join("\n")
+}
+
+fn parse_flow_map(input: &str) -> Result<FlowMap, AocError> {
+    let entries = parse_lines_strict(input, |line| {
+        let mut parts = line.split(' ');
+        let flow = parse_usize(parts.next().unwrap_or_default())?;
+        let visits = parts.map(|part| match part.split(':').collect::<Vec<_>>()[..] {
+            [valve, minute, released] => Ok(Visit { agent: Agent::You, minute: parse_usize(minute)?, valve: valve.to_string(), released: parse_usize(released)? }),
+            _ => Err(AocError::parse(format!("Invalid visit '{}' in flow map", part)))
+        }).collect::<Result<Vec<_>, _>>()?;
+        let open: Vec<_> = visits.iter().map(|v| v.valve.clone()).collect();
+        Ok::<_, AocError>((FlowKey::create(&open), (flow, visits)))
+    })?;
+    Ok(entries.into_iter().collect())
+}
+
+fn build_flow_map(valves: &Vec<Valve>, total_time: usize, contract: bool, stats: &mut Stats) -> FlowMap {
+    stats.set("valves", valves.len());
+    stats.set("tunnels", valves.iter().map(|v| v.tunnels.len()).sum());
+    let mut distance_phase = stats.phase("build_distance_map");
+    let distance_map = if contract {
+        let graph = ContractedGraph::create(valves, "AA");
+        distance_phase.set("contracted_nodes", graph.nodes.len());
+        distance_phase.set("contracted_edges", graph.edge_count());
+        graph.distance_map()
+    } else {
+        build_distance_map(valves)
+    };
+    drop(distance_phase);
+
+    // We will build up a map of <open valves> => max_flow by visiting everything like we initially did.
+    // This map can then be used to find pairs of entries with no overlapping valves to find a solution
+    // for part 2 without taking way too long.
     struct ExploreEntry {
         pos: String,
         time_left: usize,
         open: Vec<String>,
         flow: usize,
+        visits: Vec<Visit>,
     }
 
     let interesting_valves: Vec<_> = valves.iter().filter(|v| v.flow_rate > 0).cloned().collect();
 
     let mut queue: VecDeque<ExploreEntry> = VecDeque::new();
-    queue.push_back(ExploreEntry { pos: "AA".to_string(), time_left: if include_elephant { 26 } else { 30 }, open: vec![], flow: 0 });
+    queue.push_back(ExploreEntry { pos: "AA".to_string(), time_left: total_time, open: vec![], flow: 0, visits: vec![] });
 
-    let mut flow_map: HashMap<FlowKey, usize> = HashMap::new();
+    let mut flow_map: FlowMap = HashMap::new();
+    let search_phase = stats.phase("search");
 
     while let Some(entry) = queue.pop_front() {
         // For every non-zero valve we haven't opened here yet, but still can in the time left:
@@ -136,45 +412,64 @@ fn find_highest_flow(valves: &Vec<Valve>, include_elephant: bool) -> Option<usiz
             let extra_flow = time_left * v.flow_rate;
             let flow = entry.flow + extra_flow;
             let open: Vec<_> = entry.open.iter().chain(vec![v.name.clone()].iter()).cloned().collect();
+            let mut visits = entry.visits.clone();
+            visits.push(Visit { agent: Agent::You, minute: total_time - time_left, valve: v.name.clone(), released: extra_flow });
             let key = FlowKey::create(&open);
             match flow_map.get(&key) {
-                None => { flow_map.insert(key, flow); },
-                Some(v) if flow.gt(v) => { flow_map.insert(key, flow); },
-                _ => {}
+                Some((v, _)) if flow.le(v) => {},
+                _ => { flow_map.insert(key, (flow, visits.clone())); }
             };
-            queue.push_back(ExploreEntry { pos: v.name.clone(), time_left, open, flow });
+            queue.push_back(ExploreEntry { pos: v.name.clone(), time_left, open, flow, visits });
         });
     }
 
+    drop(search_phase);
+    stats.set("flow_map_entries", flow_map.len());
+    flow_map
+}
+
+/// The best entry of the flow map, or with the elephant, the best pair of entries without common valves.
+fn choose_itinerary(flow_map: &FlowMap, include_elephant: bool, stats: &mut Stats) -> Option<(usize, Vec<Visit>)> {
     // If no elephant, return the highest value in the map:
     if !include_elephant {
-        return flow_map.values().max().cloned()
+        return flow_map.values().max_by_key(|(flow, _)| *flow).cloned()
     }
 
     // Otherwise, find entries that go together (have no common open valves), and sum their rates:
+    let _pairing_phase = stats.phase("pairing");
     let mut max_flow = 0;
+    let mut best_pair: Option<(&FlowKey, &FlowKey)> = None;
 
-    for (first_key, first_size) in &flow_map {
-        for (second_key, second_size) in &flow_map {
-            if first_size + second_size < max_flow { continue; }
+    for (first_key, (first_size, _)) in flow_map {
+        for (second_key, (second_size, _)) in flow_map {
+            if first_size + second_size <= max_flow { continue; }
             if second_key.open_valves.iter().any(|v| first_key.open_valves.contains(v)) { continue; }
             max_flow = first_size + second_size;
+            // Keep the assignment stable: you take the route with the alphabetically first valves.
+            best_pair = Some(if first_key.open_valves <= second_key.open_valves { (first_key, second_key) } else { (second_key, first_key) });
         }
     }
 
-    Some(max_flow)
-}
+    let (yours, elephants) = best_pair?;
+    let mut itinerary: Vec<_> = flow_map[yours].1.iter().cloned()
+        .chain(flow_map[elephants].1.iter().map(|v| Visit { agent: Agent::Elephant, ..v.clone() }))
+        .collect();
+    itinerary.sort_by_key(|v| v.minute);
 
+    Some((max_flow, itinerary))
+}
 
 #[cfg(test)]
 mod tests {
-    use crate::days::day16::{build_distance_map, find_highest_flow, parse_input, Valve};
+    use crate::days::day16::{Agent, build_distance_map, build_flow_map, ContractedGraph, find_best_itinerary, find_best_itinerary_with, find_highest_flow, format_flow_map, parse_flow_map, parse_input, tunnel_route, Valve, Visit};
     use crate::util::collection::VecToString;
+    use crate::util::error::AocError;
+    use crate::util::stats::Stats;
 
     #[test]
     fn test_parse_input() {
         let result = parse_input(TEST_INPUT);
-        assert!(result.is_ok(), "Expected success, but was {}", result.err().unwrap_or_default());
+        assert!(result.is_ok(), "Expected success, but was {}", result.err().map(|e| e.to_string()).unwrap_or_default());
 
         let valves = result.unwrap();
         assert_eq!(10, valves.len());
@@ -197,16 +492,102 @@ mod tests {
         assert_eq!(Some(1707), find_highest_flow(&valves, true));
     }
 
-    const TEST_INPUT: &str = "\
-        Valve AA has flow rate=0; tunnels lead to valves DD, II, BB\n\
-        Valve BB has flow rate=13; tunnels lead to valves CC, AA\n\
-        Valve CC has flow rate=2; tunnels lead to valves DD, BB\n\
-        Valve DD has flow rate=20; tunnels lead to valves CC, AA, EE\n\
-        Valve EE has flow rate=3; tunnels lead to valves FF, DD\n\
-        Valve FF has flow rate=0; tunnels lead to valves EE, GG\n\
-        Valve GG has flow rate=0; tunnels lead to valves FF, HH\n\
-        Valve HH has flow rate=22; tunnel leads to valve GG\n\
-        Valve II has flow rate=0; tunnels lead to valves AA, JJ\n\
-        Valve JJ has flow rate=21; tunnel leads to valve II\n\
-    ";
+    #[test]
+    fn test_contracted_graph() {
+        let valves = parse_input(TEST_INPUT).unwrap();
+        let graph = ContractedGraph::create(&valves, "AA");
+
+        assert_eq!(vec!["AA", "BB", "CC", "DD", "EE", "HH", "JJ"].to_string(), graph.nodes);
+        // The corridors FF-GG (between EE and HH) and II (between AA and JJ) are contracted.
+        assert!(graph.edges["EE"].contains(&("HH".to_string(), 3)));
+        let mut from_start = graph.edges["AA"].clone();
+        from_start.sort();
+        assert_eq!(vec![("BB".to_string(), 1), ("DD".to_string(), 1), ("JJ".to_string(), 2)], from_start);
+
+        // Distances between the remaining nodes are the same as over the full graph.
+        let full = build_distance_map(&valves);
+        let contracted = graph.distance_map();
+        for from in &graph.nodes {
+            for to in &graph.nodes {
+                assert_eq!(full[from][to], contracted[from][to], "Distance {} -> {}", from, to);
+            }
+        }
+    }
+
+    #[test]
+    fn test_tunnel_route() {
+        let valves = parse_input(TEST_INPUT).unwrap();
+        assert_eq!(Some(vec!["AA".to_string(), "II".to_string(), "JJ".to_string()]), tunnel_route(&valves, "AA", "JJ"));
+        assert_eq!(Some(vec!["AA".to_string()]), tunnel_route(&valves, "AA", "AA"));
+        assert_eq!(None, tunnel_route(&valves, "AA", "ZZ"));
+
+        // Every route is as long as the distance map says, which includes the minute to open the valve.
+        let distances = build_distance_map(&valves);
+        for (from, to) in [("AA", "HH"), ("JJ", "CC"), ("HH", "EE")] {
+            let route = tunnel_route(&valves, from, to).unwrap();
+            assert_eq!(distances[from][to], route.len());
+        }
+    }
+
+    #[test]
+    fn test_contraction_is_equivalent() {
+        let valves = parse_input(TEST_INPUT).unwrap();
+
+        for elephant in [false, true] {
+            let mut stats = Stats::new();
+            let contracted = find_best_itinerary_with(&valves, elephant, true, &mut stats);
+            assert_eq!(find_best_itinerary_with(&valves, elephant, false, &mut Stats::new()), contracted);
+
+            assert_eq!(Some(10), stats.get("valves"));
+            assert_eq!(Some(7), stats.get("contracted_nodes"));
+            assert!(stats.get("contracted_edges").unwrap() < stats.get("tunnels").unwrap());
+        }
+    }
+
+    fn visit(agent: Agent, minute: usize, valve: &str, released: usize) -> Visit {
+        Visit { agent, minute, valve: valve.to_string(), released }
+    }
+
+    #[test]
+    fn test_find_best_itinerary() {
+        let valves = parse_input(TEST_INPUT).unwrap();
+
+        assert_eq!(Some((1651, vec![
+            visit(Agent::You, 2, "DD", 560),
+            visit(Agent::You, 5, "BB", 325),
+            visit(Agent::You, 9, "JJ", 441),
+            visit(Agent::You, 17, "HH", 286),
+            visit(Agent::You, 21, "EE", 27),
+            visit(Agent::You, 24, "CC", 12),
+        ])), find_best_itinerary(&valves, false));
+
+        let (flow, itinerary) = find_best_itinerary(&valves, true).unwrap();
+        assert_eq!(1707, flow);
+        assert_eq!(flow, itinerary.iter().map(|v| v.released).sum::<usize>());
+        assert_eq!(vec![
+            visit(Agent::Elephant, 2, "DD", 480),
+            visit(Agent::You, 3, "JJ", 483),
+            visit(Agent::You, 7, "BB", 247),
+            visit(Agent::Elephant, 7, "HH", 418),
+            visit(Agent::You, 9, "CC", 34),
+            visit(Agent::Elephant, 11, "EE", 45),
+        ], itinerary);
+        assert_eq!("== Minute 2 == The elephant opens valve DD, releasing 480 pressure", itinerary[0].to_string());
+    }
+
+    #[test]
+    fn test_flow_map_serde() {
+        let valves = parse_input(TEST_INPUT).unwrap();
+        let flow_map = build_flow_map(&valves, 26, true, &mut Stats::new());
+
+        let formatted = format_flow_map(&flow_map);
+        assert_eq!(flow_map.len(), formatted.lines().count());
+        assert!(formatted.lines().any(|l| l == "480 DD:2:480"), "{}", formatted);
+        assert_eq!(Ok(flow_map), parse_flow_map(&formatted));
+
+        assert_eq!(Err(AocError::parse("Invalid visit 'DD:2' in flow map").in_line(1)), parse_flow_map("480 DD:2"));
+        assert!(parse_flow_map("many DD:2:480").is_err());
+    }
+
+    const TEST_INPUT: &str = include_str!("../../examples/day16/input.txt");
 }
@@ -1,26 +1,72 @@
 use std::collections::HashMap;
 use std::fmt;
 use std::ops::RangeInclusive;
-use crate::days::Day;
+use crate::days::{Day, Explanation, Solver, solve_part1, solve_part2, parse_only, ParseSummary};
+use crate::util::error::AocError;
+use crate::util::config::day17::{DROPS_PUZZLE1, DROPS_PUZZLE2};
 use crate::util::geometry::{Grid, Point};
+use crate::util::vis;
+use crate::util::vis::{Animation, Frame};
 
 pub const DAY17: Day = Day {
-    puzzle1,
-    puzzle2
+    puzzle1: solve_part1::<Day17>,
+    puzzle2: solve_part2::<Day17>,
+    parse: parse_only::<Day17>,
+    describe: "Pyroclastic Flow: simulate falling rocks and predict the tower height",
+    explain: Some(explain),
+    selftest: Some(selftest),
+    algorithms: &[]
 };
 
-fn puzzle1(input: &String) {
-    let tape = parse_input(input).unwrap();
+fn explain() -> String {
+    Explanation {
+        approach: "Simulate the falling rocks, and detect a repetition of (shape, jet, top of the tower) to extrapolate the height for large drop counts.",
+        complexity: "O(drops until repetition * tower rows checked).",
+        alternatives: &[]
+    }.to_string()
+}
+
+/// The drops for `selftest`: enough to go through the repetition many times, while dropping them all stays quick.
+const SELFTEST_DROPS: usize = 100_000;
 
-    let height = Tetris::get_height_after(2022, tape);
-    println!("The tetris tower reaches {} height after 2022 drops", height);
+fn selftest(input: &str) -> Result<String, AocError> {
+    selftest_with(input, SELFTEST_DROPS)
 }
 
-fn puzzle2(input: &String) {
-    let tape = parse_input(input).unwrap();
+fn selftest_with(input: &str, drops: usize) -> Result<String, AocError> {
+    let height = Tetris::create(parse_input(input)?).verify_extrapolation(drops)?;
+    Ok(format!("Extrapolated and dropped heights agree after {} drops: {}", drops, height))
+}
+
+#[derive(Default)]
+struct Day17;
+
+impl Solver for Day17 {
+    type Output = usize;
+
+    fn part1(&self, input: &str) -> Result<Self::Output, AocError> {
+        let tape = parse_input(input)?;
+
+        let drops = DROPS_PUZZLE1.get()?;
+        let tetris = Tetris::create(tape);
+        let height = tetris.simulate_height_after(drops);
+        if vis::enabled() {
+            tetris.animate(drops.min(200)).save()?;
+        }
+        Ok(height)
+    }
+
+    fn part2(&self, input: &str) -> Result<Self::Output, AocError> {
+        let tape = parse_input(input)?;
 
-    let height = Tetris::get_height_after(1_000_000_000_000, tape);
-    println!("The tetris tower will be {} block high after 1.000.000.000.000 drops", height);
+        let drops = DROPS_PUZZLE2.get()?;
+        Ok(Tetris::get_height_after(drops, tape))
+    }
+
+    fn parse(&self, input: &str) -> Result<ParseSummary, AocError> {
+        let tape = parse_input(input)?;
+        Ok(ParseSummary::new().count(tape.len(), "jets"))
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -52,38 +98,53 @@ impl TryFrom<char> for Movement {
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, Default, Hash)]
-enum Shape {
-    // ####
-    #[default]
-    HorBlock,
-    //  #
-    // ###
-    //  #
-    Plus,
-    //   #
-    //   #
-    // ###
-    WeirdL,
-    // #
-    // #
-    // #
-    // #
-    VerBlock,
-    // ##
-    // ##
-    Square
+/// A rock shape, defined by its points relative to the bottom-left of the shape.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+struct Shape {
+    points: Vec<Point>
 }
 
 impl Shape {
-    fn get_points(&self, bottom_left: Point) -> Vec<Point> {
-        match self {
-            Shape::HorBlock => vec![(0,0).into(), (1,0).into(), (2,0).into(), (3,0).into()] + bottom_left,
-            Shape::Plus => vec![(1,0).into(), (0,1).into(), (1,1).into(), (2,1).into(), (1,2).into()] + bottom_left,
-            Shape::WeirdL => vec![(0,0).into(), (1,0).into(), (2,0).into(), (2,1).into(), (2,2).into()] + bottom_left,
-            Shape::VerBlock => vec![(0,0).into(), (0,1).into(), (0,2).into(), (0,3).into()] + bottom_left,
-            Shape::Square => vec![(0,0).into(), (1,0).into(), (0,1).into(), (1,1).into()] + bottom_left
+    fn new(points: Vec<(isize, isize)>) -> Result<Self, String> {
+        if points.is_empty() {
+            return Err("A shape needs at least one point".to_string());
         }
+        if points.iter().any(|(x, y)| *x < 0 || *y < 0) {
+            return Err(format!("Shape points should be relative to the bottom-left, but got {:?}", points));
+        }
+        Ok(Shape { points: points.into_iter().map(|p| p.into()).collect() })
+    }
+
+    /// The five shapes from the puzzle, in the order they fall.
+    fn default_shapes() -> Vec<Shape> {
+        vec![
+            // ####
+            vec![(0,0), (1,0), (2,0), (3,0)],
+            //  #
+            // ###
+            //  #
+            vec![(1,0), (0,1), (1,1), (2,1), (1,2)],
+            //   #
+            //   #
+            // ###
+            vec![(0,0), (1,0), (2,0), (2,1), (2,2)],
+            // #
+            // #
+            // #
+            // #
+            vec![(0,0), (0,1), (0,2), (0,3)],
+            // ##
+            // ##
+            vec![(0,0), (1,0), (0,1), (1,1)],
+        ].into_iter().map(|points| Shape::new(points).unwrap()).collect()
+    }
+
+    fn width(&self) -> isize {
+        self.points.iter().map(|p| p.x).max().unwrap_or(0) + 1
+    }
+
+    fn get_points(&self, bottom_left: Point) -> Vec<Point> {
+        self.points.clone() + bottom_left
     }
 }
 
@@ -92,29 +153,45 @@ struct Tetris {
     formation: Grid<String>,
     move_tape: Vec<Movement>,
     move_loc: usize,
+    shapes: Vec<Shape>,
     cave_width: RangeInclusive<isize> // coult be usize, but isize calculates nicer with Point
 }
 
 impl Tetris {
     fn create(tape: Vec<Movement>) -> Self {
-        Tetris {
+        Self::create_with(tape, 7, Shape::default_shapes()).unwrap()
+    }
+
+    /// Creates a cave of the given width, where the given shapes fall in turn.
+    /// Every shape should fit when dropped at its start location, 2 from the left wall.
+    fn create_with(tape: Vec<Movement>, width: usize, shapes: Vec<Shape>) -> Result<Self, String> {
+        if tape.is_empty() {
+            return Err("The movement tape should not be empty".to_string());
+        }
+        if shapes.is_empty() {
+            return Err("There should be at least one shape to drop".to_string());
+        }
+        if let Some(shape) = shapes.iter().find(|s| s.width() + 2 > width as isize) {
+            return Err(format!("Shape {:?} does not fit in a cave of width {}", shape.points, width));
+        }
+
+        Ok(Tetris {
             blocks: 0,
             formation: Grid::default(),
             move_tape: tape,
             move_loc: 0,
-            cave_width: 0..=6
-        }
+            shapes,
+            cave_width: 0..=(width as isize - 1)
+        })
     }
 
-    fn get_drop_shape(&self) -> Shape {
-        match self.blocks % 5 {
-            0 => Shape::HorBlock,
-            1 => Shape::Plus,
-            2 => Shape::WeirdL,
-            3 => Shape::VerBlock,
-            4 => Shape::Square,
-            oops => panic!("{} should not be a result of {} % 5!", oops, self.blocks)
-        }
+    /// A new cave with the same tape, width and shapes as this one.
+    fn fresh(&self) -> Self {
+        Self::create_with(self.move_tape.clone(), self.cave_width.clone().count(), self.shapes.clone()).unwrap()
+    }
+
+    fn get_drop_shape_index(&self) -> usize {
+        self.blocks % self.shapes.len()
     }
 
     fn get_drop_loc(&self) -> Point {
@@ -133,11 +210,8 @@ impl Tetris {
         let floor = height - deepest_point;
         let mut points = vec![];
         for y in 1..=deepest_point {
-            for x in self.cave_width.clone() {
-                let point: Point = (x,height-y).into();
-                if self.formation.get(&point).is_some() {
-                    points.push(point + (0, -1 * floor));
-                }
+            for (point, _) in self.formation.iter_row(height - y).filter(|(p, _)| self.cave_width.contains(&p.x)) {
+                points.push(point + (0, -floor));
             }
         }
         points
@@ -147,13 +221,7 @@ impl Tetris {
         // Calculate for ever row, how far down the top block is:
         let height = self.formation.bounds.height as isize;
         self.cave_width.clone().map(|x| {
-            let mut y = height - 1;
-            while y > 0 {
-                if self.formation.get(&(x,y).into()).is_some() {
-                    break;
-                }
-                y -= 1;
-            }
+            let y = self.formation.iter_col(x).rev().map(|(p, _)| p.y).find(|y| (1..height).contains(y)).unwrap_or(0);
             height - y
         }).collect()
     }
@@ -164,15 +232,22 @@ impl Tetris {
     // too easy...)
     // We need to somehow see when we're in a state that we recognize.
     // A state would need to entail (I think): the dropped shape (= dropped block % 5), the tape loc, and the drop position (relative to entry)
+    // The shape index cycles through the (configurable) shape list, so the cache key does not depend on there being five.
+    // It does rely on every column getting covered now and then; a cave where that never happens keeps
+    // growing its keys and will (slowly) simulate every drop.
     fn get_height_after(drops: usize, tape: Vec<Movement>) -> usize {
+        Self::create(tape).simulate_height_after(drops)
+    }
+
+    fn simulate_height_after(&self, drops: usize) -> usize {
         #[derive(Eq, PartialEq, Hash)]
         struct CacheKey {
-            drop_shape: Shape,
+            drop_shape: usize,
             tape_pos: usize,
             points: Vec<Point>
         }
 
-        let mut tetris = Self::create(tape.clone());
+        let mut tetris = self.fresh();
         let mut cache: HashMap<CacheKey, (usize, usize)> = HashMap::new();
 
         let repetition_start;
@@ -181,7 +256,7 @@ impl Tetris {
 
         // We need to loop until we reach a state that we recognize.
         loop {
-            let drop_shape = tetris.get_drop_shape();
+            let drop_shape = tetris.get_drop_shape_index();
             let tape_pos = tetris.move_loc;
             let blocks = tetris.blocks;
             let height = tetris.formation.bounds.height;
@@ -196,7 +271,7 @@ impl Tetris {
                 repetition_amount = blocks - old_blocks;
                 repetition_height = height - old_height;
 
-                println!("Found a repetition {} -> {}, with {} blocks and {} height, next shape = {:?}", repetition_start, blocks, repetition_amount, repetition_height, key.drop_shape);
+                println!("Found a repetition {} -> {}, with {} blocks and {} height, next shape = {}", repetition_start, blocks, repetition_amount, repetition_height, key.drop_shape);
                 break;
             } else {
                 cache.insert(key, (blocks, height));
@@ -212,17 +287,84 @@ impl Tetris {
 
         // Rest should be relatively small so that we can actually just drop those blocks for simplicity
         // (We will calculate the initial repeat height again with this, but that is fine.)
-        let rest_height = Tetris::get_height_after(repetition_start + rest, tape.clone());
+        let rest_height = self.simulate_height_after(repetition_start + rest);
 
         rep_height + rest_height
     }
 
+    /// Draws the top `n_rows` rows of the tower like the puzzle does, the floor being the row below the lowest rock.
+    fn render_top(&self, n_rows: usize) -> String {
+        let mut result = String::new();
+        for y in self.formation.bounds.y().rev().take(n_rows) {
+            result.push('|');
+            for x in self.cave_width.clone() {
+                result.push(if self.formation.get(&(x, y).into()).is_some() { '#' } else { '.' });
+            }
+            result.push_str("|\n");
+        }
+        if n_rows > self.formation.bounds.height {
+            result.push('+');
+            self.cave_width.clone().for_each(|_| result.push('-'));
+            result.push_str("+\n");
+        }
+        result
+    }
+
+    /// The height after dropping every one of the blocks, without looking for a repetition.
+    fn drop_height_after(&self, drops: usize) -> usize {
+        let mut tetris = self.fresh();
+        for _ in 0..drops {
+            tetris.drop_block();
+        }
+        tetris.formation.bounds.height
+    }
+
+    /// Checks the height extrapolated from a repetition against dropping every block, returning the height when
+    /// both agree. Dropping every block is slow, so keep the drops moderate.
+    fn verify_extrapolation(&self, drops: usize) -> Result<usize, String> {
+        let extrapolated = self.simulate_height_after(drops);
+        let dropped = self.drop_height_after(drops);
+        if extrapolated != dropped {
+            return Err(format!("After {} drops, the extrapolated height is {}, but dropping every block gives {}", drops, extrapolated, dropped));
+        }
+        Ok(dropped)
+    }
+
+    /// An animation of dropping the given number of blocks, a frame per block.
+    fn animate(&self, drops: usize) -> Animation {
+        const PALETTE: [[u8; 3]; 7] = [[20, 20, 30], [90, 90, 100], [230, 90, 60], [240, 200, 70], [90, 200, 110], [80, 150, 230], [190, 100, 220]];
+        let mut tetris = self.fresh();
+        let mut formations = vec![tetris.formation.clone()];
+        for _ in 0..drops {
+            tetris.drop_block();
+            formations.push(tetris.formation.clone());
+        }
+
+        // The tower grows upwards, so draw it upside down in the (final) height of the tower and a floor.
+        let width = tetris.cave_width.clone().count();
+        let height = tetris.formation.bounds.height.max(1);
+        let mut animation = Animation::new(&PALETTE);
+        animation.scale = 4;
+        animation.delay = 10;
+        for formation in formations {
+            let mut frame = Frame::new(width, height + 1);
+            (0..width).for_each(|x| frame.set(x, height, 1));
+            for (point, shape) in formation.iter() {
+                // Shapes are stored by their (1-based) number, the palette has 5 colors for them.
+                let color = 2 + (shape.parse::<usize>().unwrap_or(1) - 1) % 5;
+                frame.set(point.x as usize, height - 1 - point.y as usize, color as u8);
+            }
+            animation.push(frame);
+        }
+        animation
+    }
 
     fn drop_block(&mut self) -> Point {
         // Dropping a block starts at `get_drop_loc`, and will:
         // Move left/right according to the tape & location if possible.
         // Move the shape down if possible, otherwise it's placed and we're done dropping this block.
-        let shape = self.get_drop_shape();
+        let shape_index = self.get_drop_shape_index();
+        let shape = &self.shapes[shape_index];
         let mut drop_loc = self.get_drop_loc();
 
         loop {
@@ -244,13 +386,7 @@ impl Tetris {
             } else {
                 // We hit something, mark all current points in the cave and return:
                 self.blocks += 1;
-                shape.get_points(drop_loc).iter().for_each(|p| self.formation.set(p.clone(), match shape {
-                    Shape::HorBlock => "1",
-                    Shape::Plus => "2",
-                    Shape::WeirdL => "3",
-                    Shape::VerBlock => "4",
-                    Shape::Square => "5"
-                }.to_string()));
+                shape.get_points(drop_loc).iter().for_each(|p| self.formation.set(*p, (shape_index + 1).to_string()));
                 return drop_loc;
             }
         }
@@ -259,37 +395,23 @@ impl Tetris {
 
 impl fmt::Display for Tetris {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for y in self.formation.bounds.y().rev() {
-            write!(f, "|")?;
-            for x in self.cave_width.clone() {
-                if let Some(_) = self.formation.get(&(x,y).into()) {
-                    write!(f, "#")?;
-                } else {
-                    write!(f, ".")?;
-                }
-            }
-            write!(f, "|\n")?;
-        }
-        write!(f, "+")?;
-        for _ in self.cave_width.clone() {
-            write!(f, "-")?;
-        }
-        write!(f, "+\n")
+        write!(f, "{}", self.render_top(self.formation.bounds.height + 1))
     }
 }
 
-fn parse_input(input: &str) -> Result<Vec<Movement>, String> {
-    input.chars().map(|c| c.try_into()).collect()
+fn parse_input(input: &str) -> Result<Vec<Movement>, AocError> {
+    input.chars().enumerate().map(|(index, c)| Movement::try_from(c).map_err(|e| AocError::parse_at(1, index + 1, e))).collect()
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::days::day17::{Movement, parse_input, Tetris};
+    use crate::days::day17::{Movement, parse_input, selftest_with, Shape, Tetris};
+    use crate::util::snaptest::assert_snapshot;
 
     #[test]
     fn test_parse_input() {
         let result = parse_input(TEST_INPUT);
-        assert!(result.is_ok(), "Expected OK result, but got {}", result.err().unwrap_or("unexpected".to_string()));
+        assert!(result.is_ok(), "Expected OK result, but got {:?}", result.err());
 
         let tape = result.unwrap();
         assert_eq!(40, tape.len());
@@ -308,59 +430,47 @@ mod tests {
         tetris.drop_block();
 
         assert_eq!(4, tetris.move_loc);
-        assert_eq!("\
-        |..####.|\n\
-        +-------+\n\
-        ", format!("{}", tetris));
+        assert_snapshot("day17_drop_1", &format!("{}", tetris));
         assert_eq!(4, tetris.get_points_from_floor().len());
 
         tetris.drop_block();
 
-        assert_eq!("\
-        |...#...|\n\
-        |..###..|\n\
-        |...#...|\n\
-        |..####.|\n\
-        +-------+\n\
-        ", format!("{}", tetris));
+        assert_snapshot("day17_drop_2", &format!("{}", tetris));
         assert_eq!(9, tetris.get_points_from_floor().len());
 
         tetris.drop_block();
 
-        assert_eq!("\
-        |..#....|\n\
-        |..#....|\n\
-        |####...|\n\
-        |..###..|\n\
-        |...#...|\n\
-        |..####.|\n\
-        +-------+\n\
-        ", format!("{}", tetris));
+        assert_snapshot("day17_drop_3", &format!("{}", tetris));
 
         for _ in 0..7 {
             tetris.drop_block();
         }
 
-        assert_eq!("\
-        |....#..|\n\
-        |....#..|\n\
-        |....##.|\n\
-        |##..##.|\n\
-        |######.|\n\
-        |.###...|\n\
-        |..#....|\n\
-        |.####..|\n\
-        |....##.|\n\
-        |....##.|\n\
-        |....#..|\n\
-        |..#.#..|\n\
-        |..#.#..|\n\
-        |#####..|\n\
-        |..###..|\n\
-        |...#...|\n\
-        |..####.|\n\
-        +-------+\n\
-        ", format!("{}", tetris));
+        assert_snapshot("day17_drop_10", &format!("{}", tetris));
+    }
+
+    #[test]
+    fn test_render_top() {
+        // The newest rows after every rock, as drawn in the puzzle description.
+        let expected_tops = [
+            "|..####.|\n+-------+\n",
+            "|...#...|\n|..###..|\n|...#...|\n|..####.|\n+-------+\n",
+            "|..#....|\n|..#....|\n|####...|\n",
+            "|....#..|\n|..#.#..|\n|..#.#..|\n|#####..|\n",
+            "|....##.|\n|....##.|\n",
+            "|.####..|\n",
+            "|..#....|\n|.###...|\n|..#....|\n",
+            "|.....#.|\n|.....#.|\n|..####.|\n",
+            "|....#..|\n|....#..|\n|....##.|\n|....##.|\n",
+            "|....#..|\n|....#..|\n|....##.|\n|##..##.|\n|######.|\n",
+        ];
+        let mut tetris = Tetris::create(parse_input(TEST_INPUT).unwrap());
+        for (rock, expected) in expected_tops.iter().enumerate() {
+            tetris.drop_block();
+            assert_eq!(*expected, tetris.render_top(expected.lines().count()), "After rock {}", rock + 1);
+        }
+        assert_eq!("", tetris.render_top(0));
+        assert_eq!(format!("{}", tetris), tetris.render_top(100));
     }
 
     #[test]
@@ -370,5 +480,46 @@ mod tests {
         assert_eq!(1_514_285_714_288, Tetris::get_height_after(1_000_000_000_000, tape.clone()));
     }
 
-    const TEST_INPUT: &str = ">>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>";
+    #[test]
+    fn test_create_with() {
+        let tape = parse_input(TEST_INPUT).unwrap();
+
+        assert!(Tetris::create_with(tape.clone(), 5, Shape::default_shapes()).is_err(), "The horizontal block does not fit in 5");
+        assert!(Tetris::create_with(tape.clone(), 7, vec![]).is_err());
+        assert!(Tetris::create_with(vec![], 7, Shape::default_shapes()).is_err());
+        assert!(Shape::new(vec![]).is_err());
+        assert!(Shape::new(vec![(0, -1)]).is_err());
+
+        let mut tetris = Tetris::create_with(tape, 4, vec![Shape::new(vec![(0, 0), (1, 0)]).unwrap()]).unwrap();
+        tetris.drop_block();
+        assert_snapshot("day17_width_4", &format!("{}", tetris));
+    }
+
+    #[test]
+    fn test_get_height_after_variants() {
+        let tape = parse_input(TEST_INPUT).unwrap();
+        let mut reversed = Shape::default_shapes();
+        reversed.reverse();
+        let small_shapes = Shape::default_shapes().into_iter().filter(|s| s.width() <= 3).collect::<Vec<_>>();
+
+        let variants = vec![
+            Tetris::create_with(tape.clone(), 7, reversed).unwrap(),
+            Tetris::create_with(tape.clone(), 5, small_shapes.clone()).unwrap(),
+            Tetris::create_with(tape.clone(), 6, Shape::default_shapes()).unwrap(),
+        ];
+        for tetris in variants {
+            let expected = tetris.drop_height_after(2022);
+            assert_eq!(expected, tetris.simulate_height_after(2022), "Mismatch for width {:?} and {} shapes", tetris.cave_width, tetris.shapes.len());
+        }
+    }
+
+    #[test]
+    fn test_verify_extrapolation() {
+        let tetris = Tetris::create(parse_input(TEST_INPUT).unwrap());
+        assert_eq!(Ok(3068), tetris.verify_extrapolation(2022));
+        assert_eq!(Ok(0), tetris.verify_extrapolation(0));
+        assert_eq!(Ok("Extrapolated and dropped heights agree after 5000 drops: 7577".to_string()), selftest_with(TEST_INPUT, 5000));
+    }
+
+    const TEST_INPUT: &str = include_str!("../../examples/day17/input.txt");
 }
\ No newline at end of file
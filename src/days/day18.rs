@@ -1,24 +1,63 @@
-use std::collections::HashSet;
-use crate::days::Day;
-use crate::util::geometry::{Point3D};
+use crate::days::{Day, Explanation, Solver, solve_part1, solve_part2, parse_only, ParseSummary};
+use crate::util::config::day18::{Axis, SLICES};
+use crate::util::error::AocError;
+use crate::util::geometry::{Grid3, Point3D};
+use crate::util::input::parse_lines;
+use crate::util::search::WorkQueue;
 
 pub const DAY18: Day = Day {
-    puzzle1,
-    puzzle2
+    puzzle1: solve_part1::<Day18>,
+    puzzle2: solve_part2::<Day18>,
+    parse: parse_only::<Day18>,
+    describe: "Boiling Boulders: measure the (outer) surface area of a lava droplet",
+    explain: Some(explain),
+    selftest: None,
+    algorithms: &[]
 };
 
-fn puzzle1(input: &String) {
-    let drops = parse_input(input).unwrap();
-
-    let area = get_surface_area(&drops);
-    println!("Total surface area of droplets: {}", area);
+fn explain() -> String {
+    Explanation {
+        approach: "Count the faces not touching another cube. For the outer surface, flood fill the air from outside a box around the droplet; air pockets are the remaining components.",
+        complexity: "O(n) for part 1 with a set lookup, O(volume of the bounding box) for part 2.",
+        alternatives: &[]
+    }.to_string()
 }
 
-fn puzzle2(input: &String) {
-    let drops = parse_input(input).unwrap();
+#[derive(Default)]
+struct Day18;
+
+impl Solver for Day18 {
+    type Output = usize;
+
+    fn part1(&self, input: &str) -> Result<Self::Output, AocError> {
+        let drops = parse_input(input)?;
+
+        Ok(get_surface_area(&drops))
+    }
+
+    fn part2(&self, input: &str) -> Result<Self::Output, AocError> {
+        let drops = parse_input(input)?;
 
-    let area = get_outer_surface_area(&drops);
-    println!("Total outer surface area of droplets: {}", area);
+        let area = get_outer_surface_area(&drops);
+
+        let pockets = interior_pockets(&drops);
+        println!("The droplet has {} interior air pockets with a total volume of {} and a surface area of {}",
+                 pockets.len(), pockets.iter().map(|p| p.volume).sum::<usize>(), pockets.iter().map(|p| p.surface_area).sum::<usize>());
+
+        if let Some(axis) = SLICES.get()? {
+            let cells = classify(&drops);
+            let (min, max) = axis.range(&cells);
+            for index in (min + 1)..max {
+                println!("{} = {}:\n{}\n", axis, index, render_slice(&cells, axis, index));
+            }
+        }
+        Ok(area)
+    }
+
+    fn parse(&self, input: &str) -> Result<ParseSummary, AocError> {
+        let drops = parse_input(input)?;
+        Ok(ParseSummary::new().count(drops.len(), "cubes"))
+    }
 }
 
 fn get_surface_area(drops: &Vec<Point3D>) -> usize {
@@ -61,25 +100,23 @@ fn get_outer_surface_area(drops: &Vec<Point3D>) -> usize {
     let max_y = drops.iter().map(|p| p.y).max().unwrap() + 1;
     let max_z = drops.iter().map(|p| p.z).max().unwrap() + 1;
 
-    let mut done: HashSet<Point3D> = HashSet::new();
-    let mut queue: Vec<Point3D> = vec![Point3D::from((min_x, min_y, min_z))];
+    let mut queue = WorkQueue::lifo();
+    queue.push(Point3D::from((min_x, min_y, min_z)));
 
     let mut sides = 0;
 
     while let Some(point) = queue.pop() {
-        // For every neighbouring point that is not already done and in bounds:
+        // For every neighbouring point that is in bounds:
         // - If it is a cube, add a side
-        // - If it is not a cube, add to queue
+        // - If it is not a cube, add to queue (if not already seen)
         // - cross fingers
-        if !done.insert(point) { continue; }
-
         point.get_points_around().iter()
             .filter(|p| min_x <= p.x && p.x <= max_x && min_y <= p.y && p.y <= max_y && min_z <= p.z && p.z <= max_z)
             .filter(|p| p.manhattan(&point) == 1) // Filter out diagonal points
             .for_each(|p| {
                 if drops.contains(p) {
                     sides += 1;
-                } else if !done.contains(p) {
+                } else {
                     queue.push(*p);
                 }
             });
@@ -88,13 +125,111 @@ fn get_outer_surface_area(drops: &Vec<Point3D>) -> usize {
     sides
 }
 
-fn parse_input(input: &str) -> Result<Vec<Point3D>, String> {
-    input.lines().map(|l| l.parse()).collect()
+/// An air pocket fully enclosed by the droplet, `surface_area` counts the droplet sides facing into the pocket.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct PocketInfo {
+    cells: Vec<Point3D>,
+    volume: usize,
+    surface_area: usize,
+}
+
+fn interior_pockets(drops: &[Point3D]) -> Vec<PocketInfo> {
+    // Classify all air around and inside the droplet into connected components. With one cell of padding,
+    // the outside air is a single component containing the min corner; every other component is a pocket.
+    let mut grid: Grid3<()> = Grid3::around(drops, 1);
+    for drop in drops {
+        grid.set(*drop, ()).unwrap();
+    }
+
+    let (labels, count) = grid.label_components(|c| c.is_none());
+    let outside = *labels.get(&grid.min).unwrap();
+
+    let mut pockets: Vec<PocketInfo> = (0..count).map(|_| PocketInfo { cells: vec![], volume: 0, surface_area: 0 }).collect();
+    for point in grid.points() {
+        if let Some(label) = labels.get(&point) {
+            let pocket = &mut pockets[*label];
+            pocket.cells.push(point);
+            pocket.volume += 1;
+            pocket.surface_area += point.get_face_neighbours().iter().filter(|n| grid.has(n)).count();
+        }
+    }
+
+    pockets.into_iter().enumerate().filter(|(label, _)| *label != outside).map(|(_, pocket)| pocket).collect()
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Cell {
+    Lava,
+    Pocket,
+    Steam,
+}
+
+/// Classifies every cell of the box around the droplet (with one cell of padding): lava, air trapped in a
+/// pocket, or steam (air connected to the outside).
+fn classify(drops: &[Point3D]) -> Grid3<Cell> {
+    let mut grid: Grid3<()> = Grid3::around(drops, 1);
+    for drop in drops {
+        grid.set(*drop, ()).unwrap();
+    }
+    let (labels, _) = grid.label_components(|c| c.is_none());
+    let outside = *labels.get(&grid.min).unwrap();
+
+    let mut cells = Grid3::new(grid.min, grid.max);
+    for point in grid.points() {
+        let cell = match labels.get(&point) {
+            None => Cell::Lava,
+            Some(label) if *label == outside => Cell::Steam,
+            Some(_) => Cell::Pocket,
+        };
+        cells.set(point, cell).unwrap();
+    }
+    cells
+}
+
+impl Axis {
+    /// The minimum and maximum coordinate along this axis.
+    fn range<T: Clone>(&self, grid: &Grid3<T>) -> (isize, isize) {
+        match self {
+            Axis::X => (grid.min.x, grid.max.x),
+            Axis::Y => (grid.min.y, grid.max.y),
+            Axis::Z => (grid.min.z, grid.max.z),
+        }
+    }
+}
+
+/// Renders the cross-section at `index` along the axis, without the padding around the droplet: lava as '#', a
+/// trapped pocket as 'o' and steam as '.'. Rows and columns are the other two axes in order, e.g. y and z for a
+/// slice across x.
+fn render_slice(cells: &Grid3<Cell>, axis: Axis, index: isize) -> String {
+    let (min, max) = (cells.min, cells.max);
+    let (rows, columns) = match axis {
+        Axis::X => ((min.z + 1)..max.z, (min.y + 1)..max.y),
+        Axis::Y => ((min.z + 1)..max.z, (min.x + 1)..max.x),
+        Axis::Z => ((min.y + 1)..max.y, (min.x + 1)..max.x),
+    };
+
+    rows.map(|row| columns.clone().map(|column| {
+        let point: Point3D = match axis {
+            Axis::X => (index, column, row),
+            Axis::Y => (column, index, row),
+            Axis::Z => (column, row, index),
+        }.into();
+        match cells.get(&point) {
+            Some(Cell::Lava) => '#',
+            Some(Cell::Pocket) => 'o',
+            Some(Cell::Steam) | None => '.',
+        }
+    }).collect::<String>()).collect::<Vec<_>>().join("\n")
+}
+
+fn parse_input(input: &str) -> Result<Vec<Point3D>, AocError> {
+    parse_lines(input, |l| l.parse())
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::days::day18::{get_outer_surface_area, get_surface_area, parse_input};
+    use crate::days::day18::{classify, get_outer_surface_area, get_surface_area, interior_pockets, parse_input, PocketInfo, render_slice};
+    use crate::util::config::day18::Axis;
 
     #[test]
     fn test_parse_input() {
@@ -115,19 +250,41 @@ mod tests {
         assert_eq!(58, get_outer_surface_area(&drops));
     }
 
-    const TEST_INPUT: &str = "\
-        2,2,2\n\
-        1,2,2\n\
-        3,2,2\n\
-        2,1,2\n\
-        2,3,2\n\
-        2,2,1\n\
-        2,2,3\n\
-        2,2,4\n\
-        2,2,6\n\
-        1,2,5\n\
-        3,2,5\n\
-        2,1,5\n\
-        2,3,5\n\
-    ";
+    #[test]
+    fn test_interior_pockets() {
+        let drops = parse_input(TEST_INPUT).unwrap();
+
+        assert_eq!(vec![PocketInfo { cells: vec![(2, 2, 5).into()], volume: 1, surface_area: 6 }], interior_pockets(&drops));
+
+        // A hollow 4x4x4 cube has a single 2x2x2 pocket
+        let mut cube = vec![];
+        for x in 0..4 {
+            for y in 0..4 {
+                for z in 0..4 {
+                    if !(1..=2).contains(&x) || !(1..=2).contains(&y) || !(1..=2).contains(&z) {
+                        cube.push((x, y, z).into());
+                    }
+                }
+            }
+        }
+        let pockets = interior_pockets(&cube);
+        assert_eq!(1, pockets.len());
+        assert_eq!(8, pockets[0].volume);
+        assert_eq!(24, pockets[0].surface_area);
+        assert_eq!(get_surface_area(&cube) - get_outer_surface_area(&cube), pockets[0].surface_area);
+    }
+
+    #[test]
+    fn test_render_slice() {
+        let cells = classify(&parse_input(TEST_INPUT).unwrap());
+        // The example's pocket at (2, 2, 5), as seen from all sides:
+        assert_eq!(".#.\n#o#\n.#.", render_slice(&cells, Axis::Z, 5));
+        assert_eq!("...\n.#.\n...", render_slice(&cells, Axis::Z, 4));
+        // Across x and y, rows are z: the pocket sits between the lava at z = 4 and z = 6.
+        assert_eq!(".#.\n###\n.#.\n.#.\n#o#\n.#.", render_slice(&cells, Axis::X, 2));
+        assert_eq!(render_slice(&cells, Axis::X, 2), render_slice(&cells, Axis::Y, 2));
+        assert_eq!(Err("Unknown axis 'w', expected 'x', 'y' or 'z'".to_string()), "w".parse::<Axis>());
+    }
+
+    const TEST_INPUT: &str = include_str!("../../examples/day18/input.txt");
 }
\ No newline at end of file
@@ -1,28 +1,105 @@
 use std::cmp::{Ordering};
-use std::collections::{BinaryHeap};
+use std::collections::BinaryHeap;
+use std::collections::hash_map::{Entry, HashMap};
+use std::env;
+use std::fmt;
 use std::str::FromStr;
-use crate::days::Day;
+use rayon::prelude::*;
+use crate::days::{Day, Explanation, Solver, solve_part1, solve_part2, parse_only, ParseSummary};
+use crate::util::error::AocError;
+use crate::util::number::triangular;
 use crate::util::parser::Parser;
+use crate::util::simd::U16x8;
+use crate::util::stats::Stats;
 
 pub const DAY19: Day = Day {
-    puzzle1,
-    puzzle2
+    puzzle1: solve_part1::<Day19>,
+    puzzle2: solve_part2::<Day19>,
+    parse: parse_only::<Day19>,
+    describe: "Not Enough Minerals: find the best robot building order for each blueprint",
+    explain: Some(explain),
+    selftest: None,
+    algorithms: &[]
 };
 
-fn puzzle1(input: &String) {
-    let blueprints = parse_input(input).unwrap();
+fn explain() -> String {
+    Explanation {
+        approach: "Search over build orders, jumping ahead to the moment the next bot can be built. States are packed into SIMD-like lanes, normalized by capping unspendable resources, deduplicated, and pruned with an optimistic geode limit. Blueprints are searched in parallel; states have a total order and ties between equally good schedules are broken the same way every run, so the reported build orders are reproducible.",
+        complexity: "Exponential in the time, but capping and deduplication keep it to tens of thousands of states per blueprint.",
+        alternatives: &[]
+    }.to_string()
+}
+
+#[derive(Default)]
+struct Day19;
+
+impl Solver for Day19 {
+    type Output = usize;
+
+    fn part1(&self, input: &str) -> Result<Self::Output, AocError> {
+        let mut stats = Stats::new();
+        let blueprints = {
+            let _parse = stats.phase("parse");
+            parse_input(input)?
+        };
+
+        let schedules = best_schedules(&blueprints, 24, &mut stats.phase("search"));
+        let summed_quality: usize = blueprints.iter().zip(&schedules)
+            .map(|(bp, schedule)| schedule.geodes as usize * bp.id)
+            .sum();
+        if env::var("AOC_VERBOSE").is_ok() {
+            print_schedules(&blueprints, &schedules);
+            stats.print(19, 1, "capped");
+        }
+        Ok(summed_quality)
+    }
+
+    fn part2(&self, input: &str) -> Result<Self::Output, AocError> {
+        let mut stats = Stats::new();
+        let blueprints = {
+            let _parse = stats.phase("parse");
+            parse_input(input)?
+        };
+
+        let blueprints = &blueprints[..blueprints.len().min(3)];
+        let schedules = best_schedules(blueprints, 32, &mut stats.phase("search"));
+        let result: usize = schedules.iter()
+            .map(|schedule| schedule.geodes as usize)
+            .reduce(|a,s| a*s).ok_or("No blueprints in the input")?;
+        if env::var("AOC_VERBOSE").is_ok() {
+            print_schedules(blueprints, &schedules);
+            stats.print(19, 2, "capped");
+        }
+        Ok(result)
+    }
 
-    let summed_quality: usize = blueprints.iter().map(|bp| Simulation::get_max_geodes(bp, 24).unwrap() * bp.id).sum();
-    println!("The sum of all quality levels: {}", summed_quality);
+    fn parse(&self, input: &str) -> Result<ParseSummary, AocError> {
+        let blueprints = parse_input(input)?;
+        Ok(ParseSummary::new().count(blueprints.len(), "blueprints"))
+    }
 }
 
-fn puzzle2(input: &String) {
-    let blueprints = parse_input(input).unwrap();
+/// Searches the best schedule of every blueprint in parallel, in the order of the blueprints. The stats of the
+/// searches are added to the given stats in that same order.
+fn best_schedules(blueprints: &[Blueprint], time_allotted: u16, stats: &mut Stats) -> Vec<Schedule> {
+    let results: Vec<_> = blueprints.par_iter()
+        .map(|bp| {
+            let mut search_stats = Stats::new();
+            let schedule = PackedState::search(&bp.into(), time_allotted, true, &mut search_stats).unwrap();
+            (schedule, search_stats)
+        })
+        .collect();
+
+    results.into_iter().map(|(schedule, search_stats)| {
+        stats.merge(&search_stats);
+        schedule
+    }).collect()
+}
 
-    let result: usize = blueprints.iter().take(3)
-        .map(|bp| Simulation::get_max_geodes(bp, 32).unwrap())
-        .reduce(|a,s| a*s).unwrap();
-    println!("The multiplied max geodes of the first three blueprints: {}", result);
+fn print_schedules(blueprints: &[Blueprint], schedules: &[Schedule]) {
+    for (bp, schedule) in blueprints.iter().zip(schedules) {
+        println!("Blueprint {}: {}", bp.id, schedule);
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -33,37 +110,44 @@ struct BOM {
 }
 
 impl BOM {
-    fn from_parser(parser: &mut Parser) -> Result<Self, String> {
+    fn from_parser(parser: &mut Parser) -> Result<Self, AocError> {
         let mut bom = BOM { ore: 0, clay: 0, obsidian: 0 };
-        while let Ok(cost) = parser.usize() {
-            // find out what cost:
-            match parser.one_of(vec!["ore", "clay", "obsidian"])? {
+        // Costs like "3 ore and 14 clay", an 'and' means another cost follows.
+        let cost = |parser: &mut Parser| -> Result<(usize, &str), AocError> { Ok((parser.usize()?, parser.one_of(vec!["ore", "clay", "obsidian"])?)) };
+        let mut costs = vec![cost(parser)?];
+        while parser.opt(|parser| parser.literal("and")).is_some() {
+            costs.push(cost(parser)?);
+        }
+        for (cost, resource) in costs {
+            match resource {
                 "ore" => {
                     if bom.ore != 0 {
-                        return Err(format!("Got two values for ore?!"))
+                        return Err(AocError::parse("Got two values for ore?!"))
                     }
                     bom.ore = cost;
                 },
                 "clay" => {
                     if bom.clay != 0 {
-                        return Err(format!("Got two values for clay?!"))
+                        return Err(AocError::parse("Got two values for clay?!"))
                     }
                     bom.clay = cost;
                 },
                 "obsidian" => {
                     if bom.obsidian != 0 {
-                        return Err(format!("Got two values for obsidian?!"))
+                        return Err(AocError::parse("Got two values for obsidian?!"))
                     }
                     bom.obsidian = cost;
                 },
-                oops => return Err(format!("Unexpected literal '{}'", oops))
+                oops => return Err(AocError::parse(format!("Unexpected literal '{}'", oops)))
             }
-            // Ignore if failed.
-            let _ = parser.literal("and");
         }
 
         Ok(bom)
     }
+
+    fn packed(&self) -> U16x8 {
+        U16x8([self.ore as u16, self.clay as u16, self.obsidian as u16, 0, 0, 0, 0, 0])
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -76,7 +160,7 @@ struct Blueprint {
 }
 
 impl FromStr for Blueprint {
-    type Err = String;
+    type Err = AocError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut parser = Parser::new(s);
@@ -85,7 +169,7 @@ impl FromStr for Blueprint {
 }
 
 impl Blueprint {
-    fn from_parser(mut parser: &mut Parser) -> Result<Self, String> {
+    fn from_parser(mut parser: &mut Parser) -> Result<Self, AocError> {
         parser.literal("Blueprint")?;
         let id = parser.usize()?;
         parser.literal(":")?;
@@ -124,19 +208,254 @@ impl Blueprint {
     }
 }
 
+const ORE: usize = 0;
+const CLAY: usize = 1;
+const OBSIDIAN: usize = 2;
+const GEODE: usize = 3;
+/// Offset of the bot counts in the packed state, the bot for resource `r` is stored in lane `BOTS + r`.
+const BOTS: usize = 4;
+const RESOURCE_NAMES: [&str; 4] = ["ore", "clay", "obsidian", "geode"];
+
+/// The blueprint costs in packed form, indexed by the resource of the bot being built.
+struct PackedBlueprint {
+    costs: [U16x8; 4],
+    max_bots: [u16; 3],
+}
+
+impl From<&Blueprint> for PackedBlueprint {
+    fn from(blueprint: &Blueprint) -> Self {
+        PackedBlueprint {
+            costs: [blueprint.ore_robot.packed(), blueprint.clay_robot.packed(), blueprint.obsidian_robot.packed(), blueprint.geode_robot.packed()],
+            max_bots: [blueprint.max_ore() as u16, blueprint.max_clay() as u16, blueprint.max_obsidian() as u16],
+        }
+    }
+}
+
+/// Lanes in the order they break ties between states that spent the same time: the geodes, then the bots from
+/// geode down to ore, then the other resources from obsidian down to ore.
+const LANE_ORDER: [usize; 8] = [GEODE, BOTS + GEODE, BOTS + OBSIDIAN, BOTS + CLAY, BOTS + ORE, OBSIDIAN, CLAY, ORE];
+
+/// Search state with (ore, clay, obsidian, geode, ore_bots, clay_bots, obsidian_bots, geode_bots) packed in one vector.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+struct PackedState {
+    time_spend: u16,
+    lanes: U16x8,
+}
+
+/// A total order, consistent with `Eq`: by time spend, then by the lanes in `LANE_ORDER`. The search queue pops the
+/// greatest state first, so which state is explored next never depends on the order the states were queued in.
+impl Ord for PackedState {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.time_spend.cmp(&other.time_spend)
+            .then_with(|| LANE_ORDER.iter().map(|&l| self.lanes.lane(l)).cmp(LANE_ORDER.iter().map(|&l| other.lanes.lane(l))))
+    }
+}
+impl PartialOrd for PackedState {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PackedState {
+    fn new() -> Self {
+        PackedState { time_spend: 0, lanes: U16x8::unit(BOTS + ORE, 1) }
+    }
+
+    fn get_max_geodes(blueprint: &PackedBlueprint, time_allotted: u16) -> Option<u16> {
+        Self::search(blueprint, time_allotted, true, &mut Stats::new()).map(|schedule| schedule.geodes)
+    }
+
+    /// Finds the schedule opening the most geodes, see `Schedule` for which one is picked when several open as many.
+    /// Generated states are deduplicated; with `cap_resources` they are normalized using `cap_resources` first,
+    /// which lets more of them collapse into one.
+    fn search(blueprint: &PackedBlueprint, time_allotted: u16, cap_resources: bool, stats: &mut Stats) -> Option<Schedule> {
+        // Simulate the allotted minutes and find the most geodes we can get.
+        // Instead of simulating every minute, each state jumps ahead to the moment the next bot can be built.
+        let mut queue: BinaryHeap<PackedState> = BinaryHeap::new();
+        // Every state seen, with the state it was first generated from, to trace back the build order.
+        let mut parents: HashMap<PackedState, PackedState> = HashMap::new();
+        queue.push(Self::new());
+
+        let mut best: Option<Schedule> = None;
+        // The geodes of a greedy schedule are a lower bound on the best, to prune with before any schedule is found.
+        let greedy_geodes = Self::greedy_geodes(blueprint, time_allotted);
+        stats.add("greedy_geodes", greedy_geodes as usize);
+        let mut enqueue = |queue: &mut BinaryHeap<PackedState>, parents: &mut HashMap<PackedState, PackedState>, parent: PackedState, state: PackedState| {
+            let state = if cap_resources { state.cap_resources(blueprint, time_allotted) } else { state };
+            stats.add("generated_states", 1);
+            match parents.entry(state) {
+                Entry::Vacant(entry) => {
+                    entry.insert(parent);
+                    queue.push(state);
+                }
+                Entry::Occupied(_) => stats.add("duplicate_states", 1),
+            }
+        };
+
+        while let Some(state) = queue.pop() {
+            // By the ord implementation, this queue should act as DFS, so we should get a best schedule allowing to prune
+            // states that even most favorable won't make it. Ties are not pruned, they might give the preferred schedule.
+            if state.silly_upper_geode_limit(time_allotted) < best.as_ref().map_or(greedy_geodes, |schedule| schedule.geodes) {
+                continue;
+            }
+
+            if state.time_spend == time_allotted {
+                // Only trace the schedules that can compete with the best one so far.
+                if best.as_ref().is_none_or(|schedule| state.lanes.lane(GEODE) >= schedule.geodes) {
+                    best = best.max(Some(Schedule::trace(&parents, state)));
+                }
+                continue;
+            }
+
+            for resource in [GEODE, OBSIDIAN, CLAY, ORE] {
+                if let Some(next) = state.jump_build(blueprint, resource, time_allotted) {
+                    enqueue(&mut queue, &mut parents, state, next);
+                }
+            }
+            // Also queue what would happen when this state does nothing but generate:
+            enqueue(&mut queue, &mut parents, state, state.time_jump(time_allotted - state.time_spend));
+        }
+
+        if let Some(schedule) = &best {
+            stats.add("best_geodes", schedule.geodes as usize);
+        }
+        best
+    }
+
+    /// The geodes opened by building the best bot that can be afforded every minute (geode, obsidian, clay, then ore).
+    fn greedy_geodes(blueprint: &PackedBlueprint, time_allotted: u16) -> u16 {
+        let mut state = Self::new();
+        while state.time_spend < time_allotted {
+            state = [GEODE, OBSIDIAN, CLAY, ORE].into_iter()
+                .filter(|resource| state.time_to_allow_building(&blueprint.costs[*resource]) == Some(0))
+                .find_map(|resource| state.jump_build(blueprint, resource, time_allotted))
+                .unwrap_or_else(|| state.time_jump(1));
+        }
+        state.lanes.lane(GEODE)
+    }
+
+    /// Caps the ore, clay, and obsidian at what can still be spent in the time left. At most one bot is built per
+    /// minute, so no more than the highest cost of a resource can be spent per minute; anything above
+    /// `max cost × minutes left` will never be used. States that only differ in such surplus have the same outcome.
+    fn cap_resources(&self, blueprint: &PackedBlueprint, time_limit: u16) -> Self {
+        let time_left = time_limit - self.time_spend;
+        let mut lanes = self.lanes;
+        for resource in [ORE, CLAY, OBSIDIAN] {
+            let cap = blueprint.max_bots[resource] * time_left;
+            lanes = lanes.with_lane(resource, lanes.lane(resource).min(cap));
+        }
+        PackedState { time_spend: self.time_spend, lanes }
+    }
+
+    fn bots(&self, resource: usize) -> u16 {
+        self.lanes.lane(BOTS + resource)
+    }
+
+    fn time_jump(&self, time: u16) -> Self {
+        PackedState {
+            time_spend: self.time_spend + time,
+            lanes: self.lanes + self.lanes.shift_down(BOTS) * time,
+        }
+    }
+
+    fn time_to_allow_building(&self, cost: &U16x8) -> Option<u16> {
+        if self.lanes.all_ge(cost) {
+            return Some(0);
+        }
+
+        let missing = cost.saturating_sub(&self.lanes);
+        let mut time = 0;
+        for resource in [ORE, CLAY, OBSIDIAN] {
+            let needed = missing.lane(resource);
+            if needed > 0 {
+                let bots = self.bots(resource);
+                if bots == 0 { return None; }
+                time = time.max(needed.div_ceil(bots));
+            }
+        }
+
+        Some(time)
+    }
+
+    fn jump_build(&self, blueprint: &PackedBlueprint, resource: usize, time_limit: u16) -> Option<Self> {
+        if resource != GEODE && self.bots(resource) >= blueprint.max_bots[resource] { return None; } // no need to build bot.
+
+        // Calculate time needed to get necessary materials:
+        let cost = &blueprint.costs[resource];
+        let time = self.time_to_allow_building(cost)?;
+        if self.time_spend + time + 1 >= time_limit { return None; }
+
+        let mut result = self.time_jump(time + 1); // +1 for building the robot
+        result.lanes = result.lanes - *cost + U16x8::unit(BOTS + resource, 1);
+        Some(result)
+    }
+
+    fn silly_upper_geode_limit(&self, time_limit: u16) -> u16 {
+        let time_left = time_limit - self.time_spend;
+        let mut geodes_produced = self.bots(GEODE) * time_left;
+
+        // Assume every minute left, we add another geode bot for this silly limit
+        geodes_produced += triangular(time_left.saturating_sub(1));
+
+        self.lanes.lane(GEODE) + geodes_produced
+    }
+}
+
+/// The bots built to open the most geodes, as (minute the bot is ready, resource) pairs in build order.
+/// Schedules are ordered by the geodes they open, and among equally many geodes the schedule with the smallest
+/// build order (comparing the pairs in turn) is the greatest. Taking the greatest schedule thus gives the same
+/// answer every run, in whatever order the candidates were found.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Schedule {
+    geodes: u16,
+    builds: Vec<(u16, usize)>,
+}
+
+impl Ord for Schedule {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.geodes.cmp(&other.geodes).then_with(|| other.builds.cmp(&self.builds))
+    }
+}
+impl PartialOrd for Schedule {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Schedule {
+    /// Follows the parents from the final state back to the start, noting the bot built at every step.
+    fn trace(parents: &HashMap<PackedState, PackedState>, end: PackedState) -> Self {
+        let mut builds = vec![];
+        let mut state = end;
+        while let Some(parent) = parents.get(&state) {
+            if let Some(resource) = (ORE..=GEODE).find(|&r| state.bots(r) > parent.bots(r)) {
+                builds.push((state.time_spend, resource));
+            }
+            state = *parent;
+        }
+        builds.reverse();
+        Schedule { geodes: end.lanes.lane(GEODE), builds }
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let builds: Vec<_> = self.builds.iter().map(|(minute, resource)| format!("{}@{}", RESOURCE_NAMES[*resource], minute)).collect();
+        write!(f, "{} geodes, building {}", self.geodes, builds.join(" "))
+    }
+}
+
+/// Readable version of the search state, converted to and from the packed state.
 #[derive(Clone, Debug, Eq, PartialEq)]
 struct Simulation<'a> {
     blueprint: &'a Blueprint,
     time_spend: usize,
     ore: usize,
     ore_bots: usize,
-    build_ore: bool,
     clay: usize,
     clay_bots: usize,
-    build_clay: bool,
     obsidian: usize,
     obsidian_bots: usize,
-    build_obsidian: bool,
     geode: usize,
     geode_bots: usize,
     history: Vec<String>,
@@ -144,9 +463,7 @@ struct Simulation<'a> {
 
 impl<'a> Ord for Simulation<'a> {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.time_spend.cmp(&other.time_spend)
-            .then_with(|| self.geode.cmp(&other.geode))
-            .then_with(|| self.geode_bots.cmp(&other.geode_bots))
+        self.pack().cmp(&other.pack())
     }
 }
 impl<'a> PartialOrd for Simulation<'a> {
@@ -155,121 +472,38 @@ impl<'a> PartialOrd for Simulation<'a> {
     }
 }
 
+#[allow(unused)]
 impl<'a> Simulation<'a> {
     fn new(blueprint: &'a Blueprint) -> Self {
-        Simulation {
-            blueprint, time_spend: 0,
-            ore: 0, clay: 0, obsidian: 0, geode: 0,
-            ore_bots: 1, clay_bots: 0, obsidian_bots: 0, geode_bots: 0,
-            build_ore: true, build_clay: true, build_obsidian: true,
-            history: vec![]
-        }
+        Self::unpack(blueprint, &PackedState::new(), vec![])
     }
 
     fn get_max_geodes(blueprint: &'a Blueprint, time_allotted: usize) -> Option<usize> {
-        // Simulate 24 minutes and find the most kinds of geode we can get.
-        // Every minute each bot collects 1 of their ores.
-        // If not busy, the factory can start building a robot, which will take two minutes
-        // Each cycle:
-        // Generate a case for every option (nothing, build ore, build clay, etc) on the queue with the new state
-        // Find the highest geode output
-
-        let mut queue: BinaryHeap<Simulation> = BinaryHeap::new();
-
-        queue.push(Self::new(blueprint));
-
-        let mut max_sim: Option<Simulation> = None;
+        PackedState::get_max_geodes(&PackedBlueprint::from(blueprint), time_allotted as u16).map(|g| g as usize)
+    }
 
-        while let Some(sim) = queue.pop() {
-            // By the ord implementation, this queue should act as DFS, so we should get max_sim populated allowing to prune
-            // some sims that even most favorable won't make it.
-            // Check if there is a cache from the previous time or current with already more geodes, meaning we can never win.
-            if sim.silly_upper_geode_limit(time_allotted) < max_sim.as_ref().map(|s| s.geode).unwrap_or(0) {
-                continue;
-            }
-            
-            if sim.time_spend == time_allotted {
-                if sim.geode >= max_sim.as_ref().map(|s| s.geode).unwrap_or(0) {
-                    max_sim = Some(sim);
-                }
-                continue;
-            }
-            
-            // println!("Sim: {} ({}[{}{}], {}[{}{}], {}[{}{}], {}[{}])",
-            //          sim.time_spend,
-            //          sim.ore, sim.ore_bots, if sim.build_ore { "+" } else { "-" },
-            //          sim.clay, sim.clay_bots, if sim.build_clay { "+" } else { "-" },
-            //          sim.obsidian, sim.obsidian_bots, if sim.build_obsidian { "+" } else { "-" },
-            //          sim.geode, sim.geode_bots
-            // );
-
-            // Let's try jump-building
-            if let Some(state) = sim.jump_build_geode_bot(time_allotted) {
-                queue.push(state);
-            }
-            if let Some(state) = sim.jump_build_obsidian_bot(time_allotted) {
-                queue.push(state);
-            }
-            if let Some(state) = sim.jump_build_clay_bot(time_allotted) {
-                queue.push(state);
-            }
-            if let Some(state) = sim.jump_build_ore_bot(time_allotted) {
-                queue.push(state);
-            }
-            // Also queue what would happen when this state does nothing but generate:
-            queue.push(sim.time_jump(time_allotted - sim.time_spend));
+    fn pack(&self) -> PackedState {
+        PackedState {
+            time_spend: self.time_spend as u16,
+            lanes: U16x8([
+                self.ore as u16, self.clay as u16, self.obsidian as u16, self.geode as u16,
+                self.ore_bots as u16, self.clay_bots as u16, self.obsidian_bots as u16, self.geode_bots as u16,
+            ]),
         }
-        
-        // println!("Max: {}, path:\n\t{}", 
-        //          max_sim.as_ref().map(|s| s.geode).unwrap_or(0),
-        //          max_sim.as_ref().map(|s| s.history.clone()).unwrap_or(vec![]).join("\n\t-> ")
-        // );
-
-        max_sim.map(|s| s.geode)
     }
 
-    fn has_materials_for(&self, bom: &BOM) -> bool {
-        self.ore >= bom.ore && self.clay >= bom.clay && self.obsidian >= bom.obsidian
-    }
-    
-    fn time_to_allow_building(&self, bom: &BOM) -> Option<usize> {
-        if self.has_materials_for(bom) {
-            return Some(0)
-        }
-        
-        fn time_to(num: usize, per_tick: usize) -> usize {
-            let floor = num / per_tick;
-            // If there is a remainder, we want to ceil the outcome.
-            if num % per_tick == 0 { floor } else { floor + 1 }
-        }
-        
-        let mut ore_time = 0;
-        let mut clay_time = 0;
-        let mut obsidian_time = 0;
-        if bom.ore > self.ore {
-            if self.ore_bots == 0 { return None; }
-            ore_time = time_to(bom.ore - self.ore, self.ore_bots);
-        }
-        if bom.clay > self.clay {
-            if self.clay_bots == 0 { return None; }
-            clay_time = time_to(bom.clay - self.clay, self.clay_bots);
-        }
-        if bom.obsidian > self.obsidian {
-            if self.obsidian_bots == 0 { return None; }
-            obsidian_time = time_to(bom.obsidian - self.obsidian, self.obsidian_bots);
+    fn unpack(blueprint: &'a Blueprint, state: &PackedState, history: Vec<String>) -> Self {
+        let lane = |l: usize| state.lanes.lane(l) as usize;
+        Simulation {
+            blueprint, time_spend: state.time_spend as usize,
+            ore: lane(ORE), clay: lane(CLAY), obsidian: lane(OBSIDIAN), geode: lane(GEODE),
+            ore_bots: lane(BOTS + ORE), clay_bots: lane(BOTS + CLAY), obsidian_bots: lane(BOTS + OBSIDIAN), geode_bots: lane(BOTS + GEODE),
+            history
         }
-        
-        Some(ore_time.max(clay_time).max(obsidian_time))
     }
-    
+
     fn time_jump(&self, time: usize) -> Self {
-        let mut result = self.clone();
-        result.time_spend += time;
-        result.ore += self.ore_bots * time;
-        result.clay += self.clay_bots * time;
-        result.obsidian += self.obsidian_bots * time;
-        result.geode += self.geode_bots * time;
-        
+        let mut result = Self::unpack(self.blueprint, &self.pack().time_jump(time as u16), self.history.clone());
         result.history.push(format!(
             "Jumped {} time ({}) +{} ore ({}), +{} clay ({}) +{} obsidian ({}), +{} geode ({})",
             time, result.time_spend,
@@ -278,92 +512,34 @@ impl<'a> Simulation<'a> {
             self.obsidian_bots * time, result.obsidian,
             self.geode_bots * time, result.geode
         ));
-        
         result
     }
 
+    fn jump_build(&self, resource: usize, time_limit: usize) -> Option<Self> {
+        let state = self.pack().jump_build(&PackedBlueprint::from(self.blueprint), resource, time_limit as u16)?;
+        let mut result = Self::unpack(self.blueprint, &state, self.history.clone());
+        result.history.push(format!("Created {} bot @ {}", RESOURCE_NAMES[resource], result.time_spend));
+        Some(result)
+    }
+
     fn jump_build_ore_bot(&self, time_limit: usize) -> Option<Self> {
-        if self.ore_bots >= self.blueprint.max_ore() { return None; } // no need to build bot.
-        
-        // Calculate time needed to get necessary materials:
-        if let Some(time) = self.time_to_allow_building(&self.blueprint.ore_robot) {
-            if self.time_spend + time + 1 >= time_limit { return None; }
-            let mut res = self.time_jump(time + 1); // +1 for building the robot
-            res.ore -= self.blueprint.ore_robot.ore;
-            res.clay -= self.blueprint.ore_robot.clay;
-            res.obsidian -= self.blueprint.ore_robot.obsidian;
-            res.ore_bots += 1;
-            res.history.push(format!("Created ore bot @ {}", res.time_spend));
-            Some(res)
-        } else {
-            None
-        }
+        self.jump_build(ORE, time_limit)
     }
 
     fn jump_build_clay_bot(&self, time_limit: usize) -> Option<Self> {
-        if self.clay_bots >= self.blueprint.max_clay() { return None; } // no need to build bot.
-        
-        // Calculate time needed to get necessary materials:
-        if let Some(time) = self.time_to_allow_building(&self.blueprint.clay_robot) {
-            if self.time_spend + time + 1 >= time_limit { return None; }
-            let mut res = self.time_jump(time + 1); // +1 for building the robot
-            res.ore -= self.blueprint.clay_robot.ore;
-            res.clay -= self.blueprint.clay_robot.clay;
-            res.obsidian -= self.blueprint.clay_robot.obsidian;
-            res.clay_bots += 1;
-            res.history.push(format!("Created clay bot @ {}", res.time_spend));
-            Some(res)
-        } else {
-            None
-        }
+        self.jump_build(CLAY, time_limit)
     }
 
     fn jump_build_obsidian_bot(&self, time_limit: usize) -> Option<Self> {
-        if self.obsidian_bots >= self.blueprint.max_obsidian() { return None; } // no need to build bot.
-        
-        // Calculate time needed to get necessary materials:
-        if let Some(time) = self.time_to_allow_building(&self.blueprint.obsidian_robot) {
-            if self.time_spend + time + 1 >= time_limit { return None; }
-            let mut res = self.time_jump(time + 1); // +1 for building the robot
-            res.ore -= self.blueprint.obsidian_robot.ore;
-            res.clay -= self.blueprint.obsidian_robot.clay;
-            res.obsidian -= self.blueprint.obsidian_robot.obsidian;
-            res.obsidian_bots += 1;
-            res.history.push(format!("Created obsidian bot @ {}", res.time_spend));
-            Some(res)
-        } else {
-            None
-        }
+        self.jump_build(OBSIDIAN, time_limit)
     }
 
     fn jump_build_geode_bot(&self, time_limit: usize) -> Option<Self> {
-        // Calculate time needed to get necessary materials:
-        if let Some(time) = self.time_to_allow_building(&self.blueprint.geode_robot) {
-            if self.time_spend + time + 1 >= time_limit { return None; }
-            let mut res = self.time_jump(time + 1); // +1 for building the robot
-            res.ore -= self.blueprint.geode_robot.ore;
-            res.clay -= self.blueprint.geode_robot.clay;
-            res.obsidian -= self.blueprint.geode_robot.obsidian;
-            res.geode_bots += 1;
-            res.history.push(format!("Created geode bot @ {}", res.time_spend));
-            Some(res)
-        } else {
-            None
-        }
-    }
-    
-    fn silly_upper_geode_limit(&self, time_limit: usize) -> usize {
-        let time_left = time_limit - self.time_spend;
-        let mut geodes_produced = self.geode_bots * time_left;
-        
-        // Assume every minute left, we add another geode bot for this silly limit
-        geodes_produced += if time_left > 1 { ((time_left - 1) * time_left) / 2 } else { 0 };
-        
-        self.geode + geodes_produced
+        self.jump_build(GEODE, time_limit)
     }
 }
 
-fn parse_input(input: &str) -> Result<Vec<Blueprint>, String> {
+fn parse_input(input: &str) -> Result<Vec<Blueprint>, AocError> {
     let mut parser = Parser::new(input);
 
     let mut blueprints = vec![];
@@ -377,13 +553,16 @@ fn parse_input(input: &str) -> Result<Vec<Blueprint>, String> {
 #[cfg(test)]
 mod tests {
     use std::collections::BinaryHeap;
-    use crate::days::day19::{Blueprint, BOM, parse_input, Simulation};
+    use crate::days::day19::{best_schedules, Blueprint, BOM, GEODE, PackedBlueprint, PackedState, parse_input, Schedule, Simulation};
+    use crate::util::error::AocError;
+    use crate::util::simd::U16x8;
+    use crate::util::stats::Stats;
 
     #[test]
     fn test_parse_input() {
         let result = parse_input(TEST_INPUT);
 
-        assert!(result.is_ok(), "Result error: {}", result.err().unwrap_or_default());
+        assert!(result.is_ok(), "Result error: {}", result.err().map(|e| e.to_string()).unwrap_or_default());
 
         let blueprints = result.unwrap();
         assert_eq!(2, blueprints.len());
@@ -403,6 +582,13 @@ mod tests {
         }, blueprints[1]);
     }
 
+    #[test]
+    fn test_parse_invalid_cost() {
+        // The error points at the cost after the 'and', rather than at the '.' expected after the first cost.
+        let input = "Blueprint 1: Each ore robot costs 4 ore and x clay.";
+        assert_eq!(Err(AocError::parse_at(1, 45, format!("Expected to find a number\n  {}\n  {}^", input, " ".repeat(44)))), input.parse::<Blueprint>().map(|_| ()));
+    }
+
     #[test]
     fn test_jump_building_ex1() {
         let blueprint = &parse_input(TEST_INPUT).unwrap()[0];
@@ -494,6 +680,30 @@ mod tests {
         assert_eq!(Some(sim2), stack.pop());
     }
     
+    #[test]
+    fn test_pack_unpack() {
+        let blueprints = parse_input(TEST_INPUT).unwrap();
+        let sim = Simulation { time_spend: 7, ore: 1, clay: 6, obsidian: 0, geode: 2, clay_bots: 3, geode_bots: 1, ..Simulation::new(&blueprints[0]) };
+
+        let packed = sim.pack();
+        assert_eq!(7, packed.time_spend);
+        assert_eq!(U16x8([1, 6, 0, 2, 1, 3, 0, 1]), packed.lanes);
+        assert_eq!(sim, Simulation::unpack(&blueprints[0], &packed, vec![]));
+
+        let packed_blueprint = PackedBlueprint::from(&blueprints[0]);
+        assert_eq!(U16x8([3, 14, 0, 0, 0, 0, 0, 0]), packed_blueprint.costs[2]);
+        assert_eq!([4, 14, 7], packed_blueprint.max_bots);
+    }
+
+    #[test]
+    fn test_packed_time_jump() {
+        let state = PackedState { time_spend: 3, lanes: U16x8([1, 0, 0, 0, 2, 1, 0, 0]) };
+        let result = state.time_jump(4);
+
+        assert_eq!(7, result.time_spend);
+        assert_eq!(U16x8([9, 4, 0, 0, 2, 1, 0, 0]), result.lanes);
+    }
+
     #[test]
     fn test_get_max_geodes() {
         let blueprints = parse_input(TEST_INPUT).unwrap();
@@ -505,17 +715,90 @@ mod tests {
         assert_eq!(Some(62), Simulation::get_max_geodes(&blueprints[1], 32));
     }
 
-    const TEST_INPUT: &str = "\
-        Blueprint 1:
-            Each ore robot costs 4 ore.
-            Each clay robot costs 2 ore.
-            Each obsidian robot costs 3 ore and 14 clay.
-            Each geode robot costs 2 ore and 7 obsidian.
+    #[test]
+    fn test_cap_resources() {
+        let blueprint = PackedBlueprint::from(&parse_input(TEST_INPUT).unwrap()[0]);
+        // Max costs are 4 ore, 14 clay, and 7 obsidian; with 2 minutes left that is 8, 28, and 14.
+        let state = PackedState { time_spend: 22, lanes: U16x8([9, 28, 30, 5, 1, 2, 3, 4]) };
+        assert_eq!(U16x8([8, 28, 14, 5, 1, 2, 3, 4]), state.cap_resources(&blueprint, 24).lanes);
+        assert_eq!(22, state.cap_resources(&blueprint, 24).time_spend);
+        // Nothing can be spent anymore once the time is up.
+        assert_eq!(U16x8([0, 0, 0, 5, 1, 2, 3, 4]), state.cap_resources(&blueprint, 22).lanes);
+        // Early on, nothing is capped.
+        let early = PackedState { time_spend: 2, lanes: U16x8([9, 28, 30, 5, 1, 2, 3, 4]) };
+        assert_eq!(early, early.cap_resources(&blueprint, 24));
+    }
+
+    #[test]
+    fn test_search_with_and_without_capping() {
+        for blueprint in parse_input(TEST_INPUT).unwrap() {
+            let packed = PackedBlueprint::from(&blueprint);
+            let mut capped = Stats::new();
+            let mut uncapped = Stats::new();
+
+            let geodes = |cap_resources, stats| PackedState::search(&packed, 24, cap_resources, stats).map(|schedule| schedule.geodes);
+            assert_eq!(geodes(false, &mut uncapped), geodes(true, &mut capped));
+            // Capping makes many more states collapse, and so fewer states are explored (and generated).
+            assert!(capped.get("duplicate_states").unwrap() > 5 * uncapped.get("duplicate_states").unwrap());
+            assert!(capped.get("generated_states").unwrap() < uncapped.get("generated_states").unwrap());
+        }
+    }
+
+    #[test]
+    fn test_greedy_geodes() {
+        let blueprints: Vec<_> = parse_input(TEST_INPUT).unwrap().iter().map(PackedBlueprint::from).collect();
+        assert_eq!(vec![0, 10], blueprints.iter().map(|bp| PackedState::greedy_geodes(bp, 24)).collect::<Vec<_>>());
+        assert_eq!(vec![0, 55], blueprints.iter().map(|bp| PackedState::greedy_geodes(bp, 32)).collect::<Vec<_>>());
+
+        // The greedy result is in the stats of the search, next to the best one.
+        let mut stats = Stats::new();
+        assert_eq!(Some(62), PackedState::search(&blueprints[1], 32, true, &mut stats).map(|schedule| schedule.geodes));
+        assert_eq!((Some(55), Some(62)), (stats.get("greedy_geodes"), stats.get("best_geodes")));
+    }
+
+    #[test]
+    fn test_packed_state_order_is_total() {
+        let states: Vec<_> = [[1, 0, 0, 0, 2, 1, 0, 0], [0, 1, 0, 0, 2, 1, 0, 0], [0, 0, 0, 0, 1, 2, 0, 0], [0, 0, 0, 0, 2, 1, 0, 0], [0, 0, 0, 1, 1, 1, 0, 0]]
+            .into_iter().map(|lanes| PackedState { time_spend: 5, lanes: U16x8(lanes) }).collect();
+        // Only equal states compare as equal, so the queue hands them out in the same order, whatever the push order.
+        for (a, b) in states.iter().zip(&states[1..]) {
+            assert_ne!(std::cmp::Ordering::Equal, a.cmp(b));
+        }
+        let forward: BinaryHeap<_> = states.iter().copied().collect();
+        let backward: BinaryHeap<_> = states.iter().rev().copied().collect();
+        assert_eq!(forward.into_sorted_vec(), backward.into_sorted_vec());
+    }
+
+    #[test]
+    fn test_schedule_order() {
+        let early = Schedule { geodes: 9, builds: vec![(3, 1), (5, 1)] };
+        let late = Schedule { geodes: 9, builds: vec![(3, 1), (6, 0)] };
+        let more = Schedule { geodes: 10, builds: vec![(4, 0)] };
+        assert_eq!(Some(&early), [&late, &early].into_iter().max());
+        assert_eq!(Some(&more), [&early, &more, &late].into_iter().max());
+    }
+
+    #[test]
+    fn test_reproducible_schedules() {
+        let blueprints = parse_input(TEST_INPUT).unwrap();
+        let first = best_schedules(&blueprints, 24, &mut Stats::new());
+        assert_eq!(vec![9, 12], first.iter().map(|s| s.geodes).collect::<Vec<_>>());
+        for _ in 0..5 {
+            assert_eq!(first, best_schedules(&blueprints, 24, &mut Stats::new()));
+        }
+
+        // The reported build order replays to the reported geodes.
+        for (blueprint, schedule) in blueprints.iter().zip(&first) {
+            let mut simulation = Simulation::new(blueprint);
+            for (minute, resource) in &schedule.builds {
+                simulation = simulation.jump_build(*resource, 24).unwrap();
+                assert_eq!(*minute as usize, simulation.time_spend, "{}", schedule);
+            }
+            simulation = simulation.time_jump(24 - simulation.time_spend);
+            assert_eq!(schedule.geodes as usize, simulation.geode, "{}", schedule);
+        }
+        assert!(first[0].builds.iter().any(|(_, resource)| *resource == GEODE));
+    }
 
-        Blueprint 2:
-            Each ore robot costs 2 ore.
-            Each clay robot costs 3 ore.
-            Each obsidian robot costs 3 ore and 8 clay.
-            Each geode robot costs 3 ore and 12 obsidian.
-    ";
+    const TEST_INPUT: &str = include_str!("../../examples/day19/input.txt");
 }
\ No newline at end of file
@@ -1,74 +1,256 @@
-use std::collections::VecDeque;
-use crate::days::Day;
+use std::collections::{HashMap, VecDeque};
+use crate::days::{Day, Explanation, Solver, solve_part1, solve_part2, parse_only, ParseSummary};
+use crate::util::error::AocError;
+use crate::util::hash::fnv1a;
+use crate::util::input::parse_lines;
 use crate::util::number::parse_isize;
 
 pub const DAY20: Day = Day {
-    puzzle1,
-    puzzle2
+    puzzle1: solve_part1::<Day20>,
+    puzzle2: solve_part2::<Day20>,
+    parse: parse_only::<Day20>,
+    describe: "Grove Positioning System: mix an encrypted number list and find the grove coordinates",
+    explain: Some(explain),
+    selftest: None,
+    algorithms: &[]
 };
 
-fn puzzle1(input: &String) {
-    let numbers: Vec<isize> = input.lines().map(|l| parse_isize(l).unwrap()).collect();
+fn explain() -> String {
+    Explanation {
+        approach: "Mix the numbers in a deque of (original index, value), rotating each number to its new position.",
+        complexity: "O(n²) per mixing round.",
+        alternatives: &["An order-statistic tree or skip list would make every move O(log n)."]
+    }.to_string()
+}
 
-    let coords = get_coordinates(&numbers, 1, 1);
-    let result = coords[0] + coords[1] + coords[2];
-    
-    println!("Sum of coordinates ({}, {}, {}): {}", coords[0], coords[1], coords[2], result);
+#[derive(Default)]
+struct Day20;
+
+impl Solver for Day20 {
+    type Output = isize;
+
+    fn part1(&self, input: &str) -> Result<Self::Output, AocError> {
+        let numbers = parse_input(input)?;
+
+        let coords = get_coordinates(&numbers, 1, 1)?;
+        println!("Coordinates: ({}, {}, {})", coords[0], coords[1], coords[2]);
+        Ok(coords[0] + coords[1] + coords[2])
+    }
+
+    fn part2(&self, input: &str) -> Result<Self::Output, AocError> {
+        let numbers = parse_input(input)?;
+        
+        let coords = get_coordinates(&numbers, 811589153, 10)?;
+        println!("Coordinates: ({}, {}, {})", coords[0], coords[1], coords[2]);
+        Ok(coords[0] + coords[1] + coords[2])
+    }
+
+    fn parse(&self, input: &str) -> Result<ParseSummary, AocError> {
+        let numbers = parse_input(input)?;
+        Ok(ParseSummary::new().count(numbers.len(), "numbers"))
+    }
 }
-fn puzzle2(input: &String) {
-    let numbers: Vec<isize> = input.lines().map(|l| parse_isize(l).unwrap()).collect();
-    
-    let coords = get_coordinates(&numbers, 811589153, 10);
-    let result = coords[0] + coords[1] + coords[2];
 
-    println!("Sum of coordinates ({}, {}, {}): {}", coords[0], coords[1], coords[2], result);
+fn parse_input(input: &str) -> Result<Vec<isize>, AocError> {
+    parse_lines(input, parse_isize)
 }
 
-fn get_coordinates(input: &Vec<isize>, key: isize, rounds: usize) -> [isize;3] {
-    // Handle numbers from input left -> right.
-    // Each number moves as much as their value (e.g. 1 moves 1 to the right, -2 moves 2 to the left)
-    // Index wraps around the list
-    let mut values: VecDeque<(usize, isize)> = input.iter()
-        .map(|v| *v * key)
-        .enumerate()
-        .collect();
+/// The list being mixed, every value is kept together with its original index.
+struct Mixer {
+    values: VecDeque<(usize, isize)>
+}
+
+impl Mixer {
+    fn new(input: &[isize], key: isize) -> Self {
+        Mixer { values: input.iter().map(|v| *v * key).enumerate().collect() }
+    }
 
+    /// Moves the number that was originally at `move_idx` as much as its value (wrapping around the list).
+    fn move_number(&mut self, move_idx: usize) {
+        let index = self.values.iter().position(|(i, _)| move_idx == *i).unwrap();
+        // Move what we need to move to the front of this list
+        self.values.rotate_left(index);
+        let (og_idx, val) = self.values.pop_front().unwrap();
+        let dest_index = val.rem_euclid(self.values.len() as isize) as usize;
+        // Move the list again to where we need to insert the value
+        self.values.rotate_left(dest_index);
+        self.values.push_front((og_idx, val));
+    }
+
+    fn mix_round(&mut self) {
+        for move_idx in 0..self.values.len() {
+            self.move_number(move_idx);
+        }
+    }
+
+    /// The mixed list rotated to start at 0. Since the list is circular, this is the same for any rotation of it.
+    /// Without a 0 there is no such start, which is an error.
+    fn canonical(&self) -> Result<Vec<isize>, String> {
+        let mut result: Vec<_> = self.values.iter().map(|(_, v)| *v).collect();
+        let start_idx = result.iter().position(|v| 0.eq(v)).ok_or("The file has no 0 to start the grove coordinates from")?;
+        result.rotate_left(start_idx);
+        Ok(result)
+    }
+
+    /// A (FNV-1a) hash of the canonical arrangement, to compare mixing results regardless of rotation.
+    #[allow(unused)]
+    fn checksum(&self) -> Result<u64, String> {
+        Ok(fnv1a(self.canonical()?.iter().flat_map(|v| v.to_le_bytes())))
+    }
+}
+
+/// Mixes the (keyed) numbers, returning them as (original index, value) so duplicate values can be told apart.
+fn mix_indexed(input: &[isize], key: isize, rounds: usize) -> Vec<(usize, isize)> {
+    let mut mixer = Mixer::new(input, key);
     for _ in 0..rounds {
-        for move_idx in 0..input.len() {
-            let index = values.iter().position(|(i, _)| move_idx == *i).unwrap();
-            // Move what we need to move to the front of this list
-            values.rotate_left(index);
-            let (og_idx, val) = values.pop_front().unwrap();
-            let dest_index = val.rem_euclid(values.len() as isize) as usize;
-            // Move the list again to where we need to insert the value
-            values.rotate_left(dest_index);
-            values.push_front((og_idx, val));
+        mixer.mix_round();
+    }
+    mixer.values.into()
+}
+
+/// Checks that `mixed` is a permutation of the (keyed) input: every original index exactly once, with its
+/// original value, and so the same multiset of values. Returns all mismatches found otherwise.
+fn verify_mix(input: &[isize], key: isize, mixed: &[(usize, isize)]) -> Result<(), String> {
+    let mut errors = vec![];
+    if mixed.len() != input.len() {
+        errors.push(format!("expected {} numbers, but got {}", input.len(), mixed.len()));
+    }
+
+    let mut seen = vec![0; input.len()];
+    for (idx, value) in mixed {
+        match input.get(*idx) {
+            None => errors.push(format!("unknown index {} (value {})", idx, value)),
+            Some(original) => {
+                seen[*idx] += 1;
+                if original * key != *value {
+                    errors.push(format!("index {} has value {}, but should have {}", idx, value, original * key));
+                }
+            }
+        }
+    }
+    for (idx, count) in seen.iter().enumerate() {
+        match count {
+            0 => errors.push(format!("index {} (value {}) is missing", idx, input[idx] * key)),
+            1 => {},
+            n => errors.push(format!("index {} occurs {} times", idx, n)),
         }
     }
 
-    let result: Vec<_> = values.iter().map(|(_, v)| *v).collect();
+    let mut counts: HashMap<isize, (usize, usize)> = HashMap::new();
+    input.iter().for_each(|v| counts.entry(v * key).or_default().0 += 1);
+    mixed.iter().for_each(|(_, v)| counts.entry(*v).or_default().1 += 1);
+    let mut differences: Vec<_> = counts.into_iter().filter(|(_, (expected, actual))| expected != actual).collect();
+    differences.sort();
+    for (value, (expected, actual)) in differences {
+        errors.push(format!("value {} occurs {} times, but {} times in the input", value, actual, expected));
+    }
+
+    if errors.is_empty() { Ok(()) } else { Err(format!("Invalid mix: {}", errors.join("; "))) }
+}
+
+fn get_coordinates(input: &[isize], key: isize, rounds: usize) -> Result<[isize;3], String> {
+    // Handle numbers from input left -> right.
+    // Each number moves as much as their value (e.g. 1 moves 1 to the right, -2 moves 2 to the left)
+    // Index wraps around the list
+    let mixed = mix_indexed(input, key, rounds);
+    debug_assert_eq!(Ok(()), verify_mix(input, key, &mixed));
+
     // The first coordinate is the 1000th number (with wrapping) from 0. The second is at 2000, and the third at 3000.
-    let start_idx = result.iter().position(|v| 0.eq(v)).unwrap();
-    
-    let first_idx = (start_idx + 1000) % result.len();
-    let second_idx = (start_idx + 2000) % result.len();
-    let third_idx = (start_idx + 3000) % result.len();
-    
-    [result[first_idx], result[second_idx], result[third_idx]]
+    let result = Mixer { values: mixed.into() }.canonical()?;
+    Ok([result[1000 % result.len()], result[2000 % result.len()], result[3000 % result.len()]])
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::days::day20::{get_coordinates};
+    use crate::days::day20::{get_coordinates, mix_indexed, Mixer, verify_mix};
 
     #[test]
     fn test_get_coordinates() {
-        let result = TEST_INPUT.into();
-        assert_eq!([4, -3, 2], get_coordinates(&result, 1, 1));
-        assert_eq!(3, get_coordinates(&result, 1, 1).iter().sum::<isize>());
+        let result: Vec<isize> = TEST_INPUT.into();
+        assert_eq!(Ok([4, -3, 2]), get_coordinates(&result, 1, 1));
+        assert_eq!([811589153, 2434767459, -1623178306], get_coordinates(&result, 811589153, 10).unwrap());
 
-        assert_eq!([811589153, 2434767459, -1623178306], get_coordinates(&result, 811589153, 10));
+        assert_eq!(Err("The file has no 0 to start the grove coordinates from".to_string()), get_coordinates(&[1, 2, -3], 1, 1));
     }
     
+    /// Rotates an arrangement as written in the puzzle to start at 0.
+    fn canonical(arrangement: &[isize]) -> Vec<isize> {
+        let mut result = arrangement.to_vec();
+        let start_idx = result.iter().position(|v| *v == 0).expect("The arrangement has no 0");
+        result.rotate_left(start_idx);
+        result
+    }
+
+    #[test]
+    fn test_mix_steps() {
+        let mut mixer = Mixer::new(&TEST_INPUT, 1);
+        assert_eq!(canonical(&TEST_INPUT), mixer.canonical().unwrap());
+
+        let expected_states: [[isize; 7]; 7] = [
+            [2, 1, -3, 3, -2, 0, 4],
+            [1, -3, 2, 3, -2, 0, 4],
+            [1, 2, 3, -2, -3, 0, 4],
+            [1, 2, -2, -3, 0, 3, 4],
+            [1, 2, -3, 0, 3, 4, -2],
+            [1, 2, -3, 0, 3, 4, -2],
+            [1, 2, -3, 4, 0, 3, -2],
+        ];
+        for (move_idx, expected) in expected_states.iter().enumerate() {
+            mixer.move_number(move_idx);
+            assert_eq!(canonical(expected), mixer.canonical().unwrap(), "After moving {}", TEST_INPUT[move_idx]);
+        }
+    }
+
+    #[test]
+    fn test_mix_rounds_with_key() {
+        let mut mixer = Mixer::new(&TEST_INPUT, 811589153);
+        mixer.mix_round();
+        assert_eq!(vec![0, -2434767459, 3246356612, -1623178306, 2434767459, 1623178306, 811589153], mixer.canonical().unwrap());
+
+        for _ in 1..10 {
+            mixer.mix_round();
+        }
+        assert_eq!(vec![0, -2434767459, 1623178306, 3246356612, -1623178306, 2434767459, 811589153], mixer.canonical().unwrap());
+    }
+
+    #[test]
+    fn test_checksum() {
+        let mut mixer = Mixer::new(&TEST_INPUT, 1);
+        let initial = mixer.checksum().unwrap();
+
+        // Rotating the underlying list does not change the arrangement, nor the checksum:
+        mixer.values.rotate_left(3);
+        assert_eq!(initial, mixer.checksum().unwrap());
+
+        mixer.mix_round();
+        assert_ne!(initial, mixer.checksum().unwrap());
+        assert_eq!(Mixer { values: [1, 2, -3, 4, 0, 3, -2].into_iter().enumerate().collect() }.checksum().unwrap(), mixer.checksum().unwrap());
+    }
+
+    #[test]
+    fn test_mix_indexed() {
+        let input = [1, 0, 1, -1, 1];
+        let mixed = mix_indexed(&input, 1, 1);
+        assert_eq!(Ok(()), verify_mix(&input, 1, &mixed));
+        // The duplicate 1s can be told apart by their original index:
+        let mut indices: Vec<_> = mixed.iter().filter(|(_, v)| *v == 1).map(|(i, _)| *i).collect();
+        indices.sort();
+        assert_eq!(vec![0, 2, 4], indices);
+
+        assert_eq!(Ok(()), verify_mix(&TEST_INPUT, 811589153, &mix_indexed(&TEST_INPUT, 811589153, 2)));
+    }
+
+    #[test]
+    fn test_verify_mix() {
+        let input = [1, 0, 1];
+        assert_eq!(Ok(()), verify_mix(&input, 1, &[(2, 1), (0, 1), (1, 0)]));
+        assert_eq!(Err("Invalid mix: index 0 occurs 2 times; index 2 (value 1) is missing".to_string()),
+                   verify_mix(&input, 1, &[(0, 1), (0, 1), (1, 0)]));
+        assert_eq!(Err("Invalid mix: index 2 has value 5, but should have 1; value 1 occurs 1 times, but 2 times in the input; value 5 occurs 1 times, but 0 times in the input".to_string()),
+                   verify_mix(&input, 1, &[(0, 1), (2, 5), (1, 0)]));
+        assert_eq!(Err("Invalid mix: expected 3 numbers, but got 2; index 1 (value 0) is missing; value 0 occurs 0 times, but 1 times in the input".to_string()),
+                   verify_mix(&input, 1, &[(0, 1), (2, 1)]));
+    }
+
     static TEST_INPUT: [isize;7] = [1,2,-3,3,-2,0,4];
 }
\ No newline at end of file
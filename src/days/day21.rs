@@ -1,32 +1,66 @@
+use std::collections::HashMap;
+use std::env;
 use std::str::FromStr;
-use crate::days::Day;
+use crate::days::{Day, Explanation, Solver, solve_part1, solve_part2, parse_only, ParseSummary};
+use crate::util::error::AocError;
+use crate::util::input::parse_lines_strict;
 use crate::util::parser::Parser;
 
 pub const DAY21: Day = Day {
-    puzzle1,
-    puzzle2
+    puzzle1: solve_part1::<Day21>,
+    puzzle2: solve_part2::<Day21>,
+    parse: parse_only::<Day21>,
+    describe: "Monkey Math: evaluate the monkeys' expression tree and solve for the human value",
+    explain: Some(explain),
+    selftest: None,
+    algorithms: &[]
 };
 
-fn puzzle1(input: &String) {
-    let monkeys = parse_input(input).unwrap();
-    let root = get_monkey_number(&monkeys, &"root").unwrap();
-    
-    println!("The root monkey yells: {}", root);
+fn explain() -> String {
+    Explanation {
+        approach: "Evaluate the expression tree recursively. For part 2, walk down from root towards humn, inverting each operation against the known value of the other side.",
+        complexity: "O(n) per evaluation, O(n * depth) for solving.",
+        alternatives: &[]
+    }.to_string()
 }
 
-fn puzzle2(input: &String) {
-    let mut monkeys = parse_input(input).unwrap();
-    let root = get_monkey(&"root", &monkeys).unwrap();
-    
-    let human_number = find_humn_number(&monkeys);
-    
-    let (left, right) = root.operation.get_sides();
-    // Validation
-    monkeys.iter_mut().find(|m| m.name == "humn").unwrap().operation = Operation::Yell(human_number);
-    
-    println!("After yelling {}: {} vs {}", human_number, get_monkey_number(&monkeys, &left).unwrap(), get_monkey_number(&monkeys, &right).unwrap());
-    
-    println!("The human needs to yell: {}", human_number);
+#[derive(Default)]
+struct Day21;
+
+impl Solver for Day21 {
+    type Output = isize;
+
+    fn part1(&self, input: &str) -> Result<Self::Output, AocError> {
+        let monkeys = parse_input(input)?;
+
+        Ok(get_monkey_number(&monkeys, "root")?)
+    }
+
+    fn part2(&self, input: &str) -> Result<Self::Output, AocError> {
+        // Everything not depending on humn is evaluated once up front, rather than over and over while solving.
+        let (mut monkeys, fold_stats) = fold_constants(&parse_input(input)?, "root")?;
+        let root = get_monkey("root", &monkeys).ok_or("There is no root monkey")?;
+        
+        let human_number = find_humn_number(&monkeys);
+        
+        let (left, right) = root.operation.get_sides();
+        // Validation
+        monkeys.iter_mut().find(|m| m.name == "humn").unwrap().operation = Operation::Yell(human_number);
+        
+        println!("After yelling {}: {} vs {}", human_number, get_monkey_number(&monkeys, &left)?, get_monkey_number(&monkeys, &right)?);
+        if env::var("AOC_VERBOSE").is_ok() {
+            println!("Constant folding: {} monkeys before, {} after ({} operations folded)", fold_stats.before, fold_stats.after, fold_stats.folded);
+            println!("{}", render_infix(&monkeys, "root")?);
+            println!("{}", render_tree(&monkeys, "root")?);
+        }
+        
+        Ok(human_number)
+    }
+
+    fn parse(&self, input: &str) -> Result<ParseSummary, AocError> {
+        let monkeys = parse_input(input)?;
+        Ok(ParseSummary::new().count(monkeys.len(), "monkeys"))
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -39,8 +73,8 @@ enum Operation {
 }
 
 impl Operation {
-    fn parse(parser: &mut Parser) -> Result<Self, String> {
-        if let Ok(val) = parser.isize() {
+    fn parse(parser: &mut Parser) -> Result<Self, AocError> {
+        if let Ok(val) = parser.try_parse(Parser::isize) {
             return Ok(Operation::Yell(val))
         }
         
@@ -54,10 +88,20 @@ impl Operation {
             "-" => Ok(Operation::Subtract(name1, name2)),
             "*" => Ok(Operation::Multiply(name1, name2)),
             "/" => Ok(Operation::Divide(name1, name2)),
-            _ => Err(format!("Invalid operator '{}'", op))
+            _ => Err(AocError::parse(format!("Invalid operator '{}'", op)))
         }
     }
     
+    fn symbol(&self) -> &str {
+        match self {
+            Operation::Yell(_) => "",
+            Operation::Add(_, _) => "+",
+            Operation::Subtract(_, _) => "-",
+            Operation::Multiply(_, _) => "*",
+            Operation::Divide(_, _) => "/",
+        }
+    }
+
     fn get_sides(&self) -> (String, String) {
         match self {
             Operation::Yell(_) => panic!("Yell has no sides!"),
@@ -76,7 +120,7 @@ struct Monkey {
 }
 
 impl FromStr for Monkey {
-    type Err = String;
+    type Err = AocError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut parser = Parser::new(s);
@@ -87,8 +131,8 @@ impl FromStr for Monkey {
     }
 }
 
-fn parse_input(input: &str) -> Result<Vec<Monkey>, String> {
-    input.lines().map(|l| l.parse()).collect()
+fn parse_input(input: &str) -> Result<Vec<Monkey>, AocError> {
+    parse_lines_strict(input, |l| l.parse())
 }
 
 fn get_monkey_number(monkeys: &Vec<Monkey>, target: &str) -> Result<isize, String> {
@@ -105,6 +149,70 @@ fn get_monkey_number(monkeys: &Vec<Monkey>, target: &str) -> Result<isize, Strin
     }
 }
 
+/// Counts of the monkeys before and after `fold_constants`, and the number of operations replaced by a constant.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct FoldStats {
+    before: usize,
+    after: usize,
+    folded: usize
+}
+
+/// Replaces every monkey that does not depend on humn by a monkey yelling its value, and drops the monkeys no longer
+/// needed to compute the target. The monkeys keep their order.
+fn fold_constants(monkeys: &[Monkey], target: &str) -> Result<(Vec<Monkey>, FoldStats), String> {
+    // The value of every monkey visited, None for the monkeys depending on humn.
+    fn fold(monkeys: &HashMap<&str, &Monkey>, name: &str, values: &mut HashMap<String, Option<isize>>) -> Result<Option<isize>, String> {
+        if let Some(value) = values.get(name) {
+            return Ok(*value);
+        }
+        let monkey = monkeys.get(name).ok_or(format!("No monkey named '{}'", name))?;
+        let value = match &monkey.operation {
+            _ if name == "humn" => None,
+            Operation::Yell(value) => Some(*value),
+            operation => {
+                let (left, right) = operation.get_sides();
+                match (fold(monkeys, &left, values)?, fold(monkeys, &right, values)?) {
+                    (Some(left), Some(right)) => Some(match operation {
+                        Operation::Add(_, _) => left + right,
+                        Operation::Subtract(_, _) => left - right,
+                        Operation::Multiply(_, _) => left * right,
+                        _ => left / right,
+                    }),
+                    _ => None
+                }
+            }
+        };
+        values.insert(name.to_string(), value);
+        Ok(value)
+    }
+
+    let by_name: HashMap<&str, &Monkey> = monkeys.iter().map(|m| (m.name.as_str(), m)).collect();
+    let mut values = HashMap::new();
+    fold(&by_name, target, &mut values)?;
+
+    let mut folded = 0;
+    let mut result = vec![];
+    for monkey in monkeys.iter().filter(|m| values.contains_key(&m.name)) {
+        match (values[&monkey.name], &monkey.operation) {
+            (Some(value), Operation::Yell(_)) => result.push(Monkey { name: monkey.name.clone(), operation: Operation::Yell(value) }),
+            (Some(value), _) => {
+                folded += 1;
+                result.push(Monkey { name: monkey.name.clone(), operation: Operation::Yell(value) });
+            }
+            (None, _) => result.push(monkey.clone()),
+        }
+    }
+    // Monkeys below a folded monkey were visited, but are no longer needed.
+    let needed = |monkey: &Monkey| monkey.name == target || result.iter().any(|m| match &m.operation {
+        Operation::Yell(_) => false,
+        operation => { let (left, right) = operation.get_sides(); left == monkey.name || right == monkey.name }
+    });
+    let result: Vec<_> = result.iter().filter(|m| needed(m)).cloned().collect();
+
+    let stats = FoldStats { before: monkeys.len(), after: result.len(), folded };
+    Ok((result, stats))
+}
+
 fn get_monkey<'a>(target: &str, monkeys: &'a Vec<Monkey>) -> Option<&'a Monkey> {
     monkeys.iter().find(|m| m.name == target)
 }
@@ -188,14 +296,62 @@ fn get_human_input_to_equal(monkey: &str, target: isize, monkeys: &Vec<Monkey>)
     }
 }
 
+/// Renders the equation of the target monkey as a single infix expression. Only the path towards humn is
+/// expanded, everything that does not depend on humn is shown as its value. Root is shown as an equality.
+fn render_infix(monkeys: &Vec<Monkey>, target: &str) -> Result<String, String> {
+    let monkey = get_monkey(target, monkeys).ok_or(format!("No monkey named '{}'", target))?;
+    if monkey.name == "humn" {
+        return Ok(format!("humn[{}]", get_monkey_number(monkeys, target)?));
+    }
+    if matches!(monkey.operation, Operation::Yell(_)) || !depends_on_humn(target, monkeys) {
+        return Ok(get_monkey_number(monkeys, target)?.to_string());
+    }
+
+    let (left, right) = monkey.operation.get_sides();
+    let (left, right) = (render_infix(monkeys, &left)?, render_infix(monkeys, &right)?);
+    if target == "root" {
+        Ok(format!("{} = {}", left, right))
+    } else {
+        Ok(format!("({} {} {})", left, monkey.operation.symbol(), right))
+    }
+}
+
+/// Renders the equation of the target monkey as a tree with the value of every monkey, one monkey per line.
+/// Like `render_infix`, subtrees that do not depend on humn are collapsed to their value.
+fn render_tree(monkeys: &Vec<Monkey>, target: &str) -> Result<String, String> {
+    fn render(monkeys: &Vec<Monkey>, target: &str, depth: usize, lines: &mut Vec<String>) -> Result<(), String> {
+        let monkey = get_monkey(target, monkeys).ok_or(format!("No monkey named '{}'", target))?;
+        let indent = "  ".repeat(depth);
+        if monkey.name != "humn" && !matches!(monkey.operation, Operation::Yell(_)) && depends_on_humn(target, monkeys) {
+            let (left, right) = monkey.operation.get_sides();
+            let (left_value, right_value) = (get_monkey_number(monkeys, &left)?, get_monkey_number(monkeys, &right)?);
+            let result = if target == "root" {
+                format!("{} = {} => {}", left_value, right_value, if left_value == right_value { "equal" } else { "NOT equal" })
+            } else {
+                format!("{} {} {} = {}", left_value, monkey.operation.symbol(), right_value, get_monkey_number(monkeys, target)?)
+            };
+            lines.push(format!("{}{}: {} {} {} => {}", indent, target, left, if target == "root" { "=" } else { monkey.operation.symbol() }, right, result));
+            render(monkeys, &left, depth + 1, lines)?;
+            render(monkeys, &right, depth + 1, lines)?;
+        } else {
+            lines.push(format!("{}{}: {}", indent, target, get_monkey_number(monkeys, target)?));
+        }
+        Ok(())
+    }
+
+    let mut lines = vec![];
+    render(monkeys, target, 0, &mut lines)?;
+    Ok(lines.join("\n"))
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::days::day21::{find_humn_number, get_monkey_number, Monkey, Operation, parse_input};
+    use crate::days::day21::{find_humn_number, fold_constants, FoldStats, get_monkey_number, Monkey, Operation, parse_input, render_infix, render_tree};
 
     #[test]
     fn test_parse_input() {
         let result = parse_input(TEST_INPUT);
-        assert!(result.is_ok(), "Expected OK but got: '{}'", result.err().unwrap_or_default());
+        assert!(result.is_ok(), "Expected OK but got: '{}'", result.err().map(|e| e.to_string()).unwrap_or_default());
         
         let monkeys = result.unwrap();
         assert_eq!(Monkey { name: "root".to_string(), operation: Operation::Add("pppw".to_string(), "sjmn".to_string()) }, monkeys[0]);
@@ -213,21 +369,43 @@ mod tests {
         assert_eq!(301, find_humn_number(&monkeys));
     }
     
-    const TEST_INPUT: &str = "\
-        root: pppw + sjmn\n\
-        dbpl: 5\n\
-        cczh: sllz + lgvd\n\
-        zczc: 2\n\
-        ptdq: humn - dvpt\n\
-        dvpt: 3\n\
-        lfqf: 4\n\
-        humn: 5\n\
-        ljgn: 2\n\
-        sjmn: drzm * dbpl\n\
-        sllz: 4\n\
-        pppw: cczh / lfqf\n\
-        lgvd: ljgn * ptdq\n\
-        drzm: hmdt - zczc\n\
-        hmdt: 32\n\
-    ";
+    #[test]
+    fn test_fold_constants() {
+        let monkeys = parse_input(TEST_INPUT).unwrap();
+        let (folded, stats) = fold_constants(&monkeys, "root").unwrap();
+
+        // Only the path from root to humn remains, with the constants next to it.
+        assert_eq!(FoldStats { before: 15, after: 11, folded: 2 }, stats);
+        assert_eq!(Some(&Operation::Yell(150)), folded.iter().find(|m| m.name == "sjmn").map(|m| &m.operation));
+        assert!(folded.iter().all(|m| m.name != "drzm"));
+        assert_eq!(Ok("((4 + (2 * (humn[5] - 3))) / 4) = 150".to_string()), render_infix(&folded, "root"));
+
+        assert_eq!(find_humn_number(&monkeys), find_humn_number(&folded));
+        assert_eq!(get_monkey_number(&monkeys, "root"), get_monkey_number(&folded, "root"));
+        assert_eq!(Err("No monkey named 'nope'".to_string()), fold_constants(&monkeys, "nope").map(|(_, stats)| stats));
+    }
+
+    #[test]
+    fn test_render_solved_equation() {
+        let mut monkeys = parse_input(TEST_INPUT).unwrap();
+        monkeys.iter_mut().find(|m| m.name == "humn").unwrap().operation = Operation::Yell(301);
+
+        assert_eq!(Ok("((4 + (2 * (humn[301] - 3))) / 4) = 150".to_string()), render_infix(&monkeys, "root"));
+        assert_eq!(Ok("\
+root: pppw = sjmn => 150 = 150 => equal
+  pppw: cczh / lfqf => 600 / 4 = 150
+    cczh: sllz + lgvd => 4 + 596 = 600
+      sllz: 4
+      lgvd: ljgn * ptdq => 2 * 298 = 596
+        ljgn: 2
+        ptdq: humn - dvpt => 301 - 3 = 298
+          humn: 301
+          dvpt: 3
+    lfqf: 4
+  sjmn: 150".to_string()), render_tree(&monkeys, "root"));
+
+        assert!(render_tree(&monkeys, "nope").is_err());
+    }
+
+    const TEST_INPUT: &str = include_str!("../../examples/day21/input.txt");
 }
\ No newline at end of file
@@ -1,25 +1,70 @@
-use std::fmt;
+use std::{env, fmt};
 use std::str::FromStr;
-use crate::days::Day;
+use crate::days::{Day, Explanation, Solver, solve_part1, solve_part2, parse_only, ParseSummary};
+use crate::util::error::AocError;
 use crate::util::geometry::{Directions, Grid, Point};
-use crate::util::parser::Parser;
+use crate::util::moves;
+use crate::util::moves::MoveToken;
+use crate::util::number::isqrt;
 
 pub const DAY22: Day = Day {
-    puzzle1,
-    puzzle2
+    puzzle1: solve_part1::<Day22>,
+    puzzle2: solve_part2::<Day22>,
+    parse: parse_only::<Day22>,
+    describe: "Monkey Map: follow a path over a flat map and over a folded cube",
+    explain: Some(explain),
+    selftest: None,
+    algorithms: &[]
 };
 
-fn puzzle1(input: &String) {
-    let puzzle: Puzzle = input.parse().unwrap();
-    let password = puzzle.get_password(false);
-    
-    println!("Our password: {}", password);
+fn explain() -> String {
+    Explanation {
+        approach: "Walk the path over the map, wrapping around on the flat map. For the cube, the net is validated and a hardcoded transition table for the supported layout folds the edges. With AOC_VERBOSE, the faces and the edges they connect through on the folded cube are listed, to check against a paper cube.",
+        complexity: "O(path length * steps per instruction).",
+        alternatives: &["Folding the net generically from the validated face normals."]
+    }.to_string()
 }
-fn puzzle2(input: &String) {
-    let puzzle: Puzzle = input.parse().unwrap();
 
-    let password = puzzle.get_password(true);
-    println!("Our password on a cube: {}", password);
+#[derive(Default)]
+struct Day22;
+
+impl Solver for Day22 {
+    type Output = isize;
+
+    fn part1(&self, input: &str) -> Result<Self::Output, AocError> {
+        let puzzle: Puzzle = input.parse()?;
+        let (password, pos, direction) = puzzle.get_password(false);
+
+        if env::var("AOC_VERBOSE").is_ok() {
+            println!("Ended at {} facing {:?}", pos, direction);
+        }
+        Ok(password)
+    }
+
+    fn part2(&self, input: &str) -> Result<Self::Output, AocError> {
+        let puzzle: Puzzle = input.parse()?;
+        let net = validate_net(&puzzle.map)?;
+        if env::var("AOC_VERBOSE").is_ok() {
+            println!("Cube faces of {0}x{0}, laid out as:\n{1}", net.face_size, net);
+            print!("{}", net.report(Point::from((puzzle.map.bounds.left, puzzle.map.bounds.top)))?);
+        }
+        if net.faces != SUPPORTED_LAYOUT || net.face_size != 50 {
+            return Err(format!("The cube folding in this solution only supports the 50x50 layout:\n{}", NetInfo { face_size: 50, faces: SUPPORTED_LAYOUT.to_vec() }).into());
+        }
+
+        let (password, pos, direction) = puzzle.get_password(true);
+        if env::var("AOC_VERBOSE").is_ok() {
+            println!("Ended at {} facing {:?}", pos, direction);
+        }
+        Ok(password)
+    }
+
+    fn parse(&self, input: &str) -> Result<ParseSummary, AocError> {
+        let puzzle: Puzzle = input.parse()?;
+        Ok(ParseSummary::new()
+            .count(puzzle.map.entries().len(), "tiles")
+            .count(puzzle.moves.len(), "moves"))
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -44,6 +89,32 @@ enum Move {
     Left
 }
 
+/// The offset of a single step in the given direction.
+fn step(direction: &Directions) -> Point {
+    match direction {
+        Directions::Top => (0, -1).into(),
+        Directions::Right => (1, 0).into(),
+        Directions::Bottom => (0, 1).into(),
+        Directions::Left => (-1, 0).into(),
+        _ => panic!("Invalid direction")
+    }
+}
+
+/// The value of a facing in the password, which is also the number of quarter turns clockwise from facing right.
+fn facing_value(direction: Directions) -> usize {
+    match direction {
+        Directions::Right => 0,
+        Directions::Bottom => 1,
+        Directions::Left => 2,
+        Directions::Top => 3,
+        _ => panic!("Invalid direction!?")
+    }
+}
+
+fn get_password_for(pos: Point, direction: Directions) -> isize {
+    pos.y * 1000 + pos.x * 4 + facing_value(direction) as isize
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 struct Puzzle {
     map: Grid<Tile>,
@@ -51,12 +122,24 @@ struct Puzzle {
 }
 
 impl Puzzle {
-    fn get_password(&self, solve_on_cube: bool) -> isize {
-        let start_y = 1;
-        let start_x = self.map.bounds.x().find(|x| self.map.has(&(*x, start_y).into())).unwrap();
-        let mut pos: Point = (start_x, start_y).into();
-        let mut direction = Directions::Right;
-        
+    /// The password after following the moves from the start, with the position and facing we end up at.
+    fn get_password(&self, solve_on_cube: bool) -> (isize, Point, Directions) {
+        // We start at the leftmost tile of the top row, facing right.
+        let start = self.map.bounds.iter_row_major().find(|p| self.map.has(p)).unwrap();
+        self.get_password_from(start, Directions::Right, solve_on_cube)
+    }
+
+    /// The password after following the moves from the given position and facing, rather than the start.
+    fn get_password_from(&self, start: Point, facing: Directions, solve_on_cube: bool) -> (isize, Point, Directions) {
+        let (pos, direction) = self.walk(start, facing, solve_on_cube);
+        (get_password_for(pos, direction), pos, direction)
+    }
+
+    /// Follows all moves from the given position and facing, returning where we end up and which way we face.
+    fn walk(&self, start: Point, facing: Directions, solve_on_cube: bool) -> (Point, Directions) {
+        let mut pos = start;
+        let mut direction = facing;
+
         for mov in &self.moves {
             match mov {
                 Move::Forward(amount) => {
@@ -93,27 +176,12 @@ impl Puzzle {
                 }
             }
         }
-        
-        println!("Ended at {} facing {:?}", pos, direction);
-        let facing_value = match direction {
-            Directions::Right => 0,
-            Directions::Bottom => 1,
-            Directions::Left => 2,
-            Directions::Top => 3,
-            _ => panic!("Invalid direction!?")
-        };
-        
-        pos.y * 1000 + pos.x * 4 + facing_value
+
+        (pos, direction)
     }
     
     fn get_next_in_direction(&self, direction: &Directions, from: &Point) -> Point {
-        let next_point: Point = match direction {
-            Directions::Top => *from + (0, -1),
-            Directions::Right => *from + (1, 0),
-            Directions::Bottom => *from + (0, 1),
-            Directions::Left => *from + (-1, 0),
-            _ => panic!("Invalid direction")
-        };
+        let next_point = *from + step(direction);
         if self.map.has(&next_point) { return next_point; }
         // If the map does not have the point, we will need to wrap around
         match direction {
@@ -126,13 +194,7 @@ impl Puzzle {
     }
     
     fn get_next_on_cube(&self, direction: &Directions, from: &Point) -> (Point, Directions) {
-        let next_point: Point = match direction {
-            Directions::Top => *from + (0, -1),
-            Directions::Right => *from + (1, 0),
-            Directions::Bottom => *from + (0, 1),
-            Directions::Left => *from + (-1, 0),
-            _ => panic!("Invalid direction")
-        };
+        let next_point = *from + step(direction);
         if self.map.has(&next_point) { return (next_point, *direction); }
         
         println!("Map has not {} (from {}, dir {:?})", next_point, from, direction);
@@ -225,8 +287,201 @@ impl Puzzle {
     }
 }
 
+/// The face layout (in face coordinates) that `get_next_on_cube` was written for.
+const SUPPORTED_LAYOUT: [(usize, usize); 6] = [(1, 0), (2, 0), (1, 1), (0, 2), (1, 2), (0, 3)];
+
+/// The faces of a cube net, as (column, row) in units of faces, in reading order.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct NetInfo {
+    face_size: usize,
+    faces: Vec<(usize, usize)>
+}
+
+impl fmt::Display for NetInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let columns = self.faces.iter().map(|(c, _)| c + 1).max().unwrap_or(0);
+        let rows = self.faces.iter().map(|(_, r)| r + 1).max().unwrap_or(0);
+        for row in 0..rows {
+            for column in 0..columns {
+                match self.faces.iter().position(|face| *face == (column, row)) {
+                    Some(index) => write!(f, "{}", index + 1)?,
+                    None => write!(f, ".")?
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// The direction a face points to on the cube (its normal), and the directions of its right and bottom edges.
+type Vector = (isize, isize, isize);
+type Orientation = (Vector, Vector, Vector);
+
+fn neg((x, y, z): Vector) -> Vector { (-x, -y, -z) }
+
+/// Folds the net: walks over the connected faces, starting with the first face on top, tracking the orientation of
+/// every face. Faces not connected to the first remain None.
+fn fold_faces(faces: &[(usize, usize)]) -> Vec<Option<Orientation>> {
+    let mut folded: Vec<Option<Orientation>> = vec![None; faces.len()];
+    if faces.is_empty() {
+        return folded;
+    }
+    folded[0] = Some(((0, 0, -1), (1, 0, 0), (0, 1, 0)));
+    let mut queue = vec![0];
+    while let Some(index) = queue.pop() {
+        let (column, row) = faces[index];
+        let (normal, right, down) = folded[index].unwrap();
+        let neighbours = [
+            ((column + 1, row), (right, neg(normal), down)),
+            ((column.wrapping_sub(1), row), (neg(right), normal, down)),
+            ((column, row + 1), (down, right, neg(normal))),
+            ((column, row.wrapping_sub(1)), (neg(down), right, normal)),
+        ];
+        for (face, orientation) in neighbours {
+            if let Some(next) = faces.iter().position(|f| *f == face) {
+                if folded[next].is_none() {
+                    folded[next] = Some(orientation);
+                    queue.push(next);
+                }
+            }
+        }
+    }
+    folded
+}
+
+/// Where walking off an edge of a face leads to on the folded cube. Faces are indices into `NetInfo::faces`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct EdgeTransition {
+    face: usize,
+    edge: Directions,
+    neighbour: usize,
+    /// The facing after crossing the edge; we enter the neighbour through its opposite edge.
+    facing: Directions,
+}
+
+impl EdgeTransition {
+    /// The number of quarter turns clockwise the facing changes by when crossing the edge.
+    fn rotation(&self) -> usize {
+        (facing_value(self.facing) + 4 - facing_value(self.edge)) % 4
+    }
+}
+
+impl NetInfo {
+    /// The transitions over all four edges of every face, in the order of the faces.
+    fn transitions(&self) -> Result<Vec<EdgeTransition>, String> {
+        let folded = fold_faces(&self.faces).into_iter().collect::<Option<Vec<_>>>()
+            .ok_or("Not all faces are connected edge-to-edge".to_string())?;
+        let find = |normal: Vector| folded.iter().position(|(n, _, _)| *n == normal)
+            .ok_or(format!("No face points to {:?} on the folded cube", normal));
+
+        let mut transitions = vec![];
+        for (face, (normal, right, down)) in folded.iter().enumerate() {
+            for (edge, direction) in [(Directions::Top, neg(*down)), (Directions::Right, *right),
+                                      (Directions::Bottom, *down), (Directions::Left, neg(*right))] {
+                // Crossing the edge, we continue down the side of the cube: opposite to the normal of this face.
+                let neighbour = find(direction)?;
+                let (_, next_right, next_down) = folded[neighbour];
+                let heading = neg(*normal);
+                let facing = if heading == next_right { Directions::Right }
+                    else if heading == neg(next_right) { Directions::Left }
+                    else if heading == next_down { Directions::Bottom }
+                    else { Directions::Top };
+                transitions.push(EdgeTransition { face, edge, neighbour, facing });
+            }
+        }
+        Ok(transitions)
+    }
+
+    /// Lists every face with its tiles (in map coordinates, with `origin` the top-left of the map) and where its
+    /// edges lead to on the folded cube.
+    fn report(&self, origin: Point) -> Result<String, String> {
+        fn name(direction: Directions) -> &'static str {
+            match direction {
+                Directions::Top => "top",
+                Directions::Right => "right",
+                Directions::Bottom => "bottom",
+                _ => "left",
+            }
+        }
+        let opposite = |direction: Directions| match direction {
+            Directions::Top => Directions::Bottom,
+            Directions::Right => Directions::Left,
+            Directions::Bottom => Directions::Top,
+            _ => Directions::Right,
+        };
+
+        let transitions = self.transitions()?;
+        let size = self.face_size as isize;
+        let mut report = String::new();
+        for (index, (column, row)) in self.faces.iter().enumerate() {
+            let left = origin.x + *column as isize * size;
+            let top = origin.y + *row as isize * size;
+            report += &format!("Face {}: x {}..={}, y {}..={}\n", index + 1, left, left + size - 1, top, top + size - 1);
+            for transition in transitions.iter().filter(|t| t.face == index) {
+                report += &format!("  {:<6} -> face {} through its {} edge, facing {} ({} quarter turns clockwise)\n",
+                                   name(transition.edge), transition.neighbour + 1, name(opposite(transition.facing)),
+                                   name(transition.facing), transition.rotation());
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// Checks that the map consists of exactly six equally sized square faces that fold into a cube.
+fn validate_net(map: &Grid<Tile>) -> Result<NetInfo, String> {
+    let tiles = map.points().iter().filter(|p| map.has(p)).count();
+    let face_size = isqrt(tiles / 6);
+    if face_size == 0 || 6 * face_size * face_size != tiles {
+        return Err(format!("A cube net has 6 * size * size tiles, but the map has {} tiles", tiles));
+    }
+
+    // Classify every face-sized block of the map as full, empty, or (invalid) partially filled:
+    let columns = map.bounds.width.div_ceil(face_size);
+    let rows = map.bounds.height.div_ceil(face_size);
+    let mut faces = vec![];
+    let mut diagram = String::new();
+    let mut partial = false;
+    for row in 0..rows {
+        for column in 0..columns {
+            let filled = (0..face_size * face_size).filter(|i| {
+                let x = map.bounds.left + (column * face_size + i % face_size) as isize;
+                let y = map.bounds.top + (row * face_size + i / face_size) as isize;
+                map.has(&(x, y).into())
+            }).count();
+            diagram.push(match filled {
+                0 => '.',
+                n if n == face_size * face_size => { faces.push((column, row)); '#' },
+                _ => { partial = true; '?' }
+            });
+        }
+        diagram.push('\n');
+    }
+
+    let describe = |problem: &str| format!("{} (faces of {}x{}, '#' = face, '?' = partially filled):\n{}", problem, face_size, face_size, diagram);
+    if partial {
+        return Err(describe("The map has partially filled faces"));
+    }
+    if faces.len() != 6 {
+        return Err(describe(&format!("Expected 6 faces, but found {}", faces.len())));
+    }
+
+    let folded = fold_faces(&faces);
+    if folded.iter().any(|f| f.is_none()) {
+        return Err(describe("Not all faces are connected edge-to-edge"));
+    }
+    let mut normals: Vec<_> = folded.iter().map(|f| f.unwrap().0).collect();
+    normals.sort();
+    normals.dedup();
+    if normals.len() != 6 {
+        return Err(describe("The faces overlap when folded, so this is not a cube net"));
+    }
+
+    Ok(NetInfo { face_size, faces })
+}
+
 impl FromStr for Puzzle {
-    type Err = String;
+    type Err = AocError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut grid: Grid<Tile> = Grid::empty();
@@ -241,25 +496,19 @@ impl FromStr for Puzzle {
                     '.' => { grid.set((x+1,y+1).try_into()?, Tile::Empty); }
                     '#' => { grid.set((x+1,y+1).try_into()?, Tile::Wall); }
                     c if c.is_whitespace() => { /* ignore whitespace */ },
-                    c => return Err(format!("Invalid map char '{}'", c))
+                    c => return Err(AocError::parse_at(y + 1, x + 1, format!("Invalid map char '{}'", c)))
                 }
             }
         }
         
-        let mut moves = vec![];
         // Parse moves.
-        let mut parser = Parser::new(moves_str);
-        while !parser.is_exhausted() {
-            if let Ok(steps) = parser.usize() {
-                moves.push(Move::Forward(steps));
-            } else if let Ok(()) = parser.literal("R") {
-                moves.push(Move::Right);
-            } else if let Ok(()) = parser.literal("L") {
-                moves.push(Move::Left);
-            } else {
-                return Err(format!("Could not match a number, L, or R."))
-            }
-        }
+        let moves_line = lines.len() + 1;
+        let moves = moves::tokenize(moves_str).map_err(|e| e.in_line(moves_line))?.into_iter().map(|token| match token {
+            MoveToken::Number(steps) => Ok(Move::Forward(steps)),
+            MoveToken::Letter('R') => Ok(Move::Right),
+            MoveToken::Letter('L') => Ok(Move::Left),
+            MoveToken::Letter(c) => Err(AocError::parse(format!("Expected a number, L, or R in the moves, got '{}'", c)).in_line(moves_line)),
+        }).collect::<Result<Vec<_>, _>>()?;
         
         Ok(Puzzle {
             map: grid, moves
@@ -269,13 +518,13 @@ impl FromStr for Puzzle {
 
 #[cfg(test)]
 mod tests {
-    use crate::days::day22::Puzzle;
+    use crate::days::day22::{EdgeTransition, get_password_for, NetInfo, Puzzle, step, SUPPORTED_LAYOUT, validate_net};
     use crate::util::geometry::{Directions, Point};
 
     #[test]
     fn test_parse() {
         let puzzle_result: Result<Puzzle, _> = TEST_INPUT.parse();
-        assert!(puzzle_result.is_ok(), "Expected OK but got: {}", puzzle_result.err().unwrap_or_default());
+        assert!(puzzle_result.is_ok(), "Expected OK but got: {}", puzzle_result.err().map(|e| e.to_string()).unwrap_or_default());
         
         let puzzle = puzzle_result.unwrap();
         assert_eq!(12, puzzle.map.bounds.height);
@@ -294,23 +543,126 @@ mod tests {
     #[test]
     fn test_get_password() {
         let puzzle: Puzzle = TEST_INPUT.parse().unwrap();
-        assert_eq!(6032, puzzle.get_password(false));
+        assert_eq!((6032, Point::from((8, 6)), Directions::Right), puzzle.get_password(false));
     }
     
-    const TEST_INPUT: &str = "\
-        \x20       ...#\n\
-        \x20       .#..\n\
-        \x20       #...\n\
-        \x20       ....\n\
-        ...#.......#\n\
-        ........#...\n\
-        ..#....#....\n\
-        ..........#.\n\
-        \x20       ...#....\n\
-        \x20       .....#..\n\
-        \x20       .#......\n\
-        \x20       ......#.\n\
-        \n\
-        10R5L5R10L4R5L5\n\
-    ";
+    #[test]
+    fn test_get_password_from() {
+        let puzzle: Puzzle = TEST_INPUT.parse().unwrap();
+        let start = Point::from((9, 1));
+        assert_eq!(puzzle.get_password(false), puzzle.get_password_from(start, Directions::Right, false));
+
+        // Walking right from the right edge of the map wraps around to the left, where a wall stops us.
+        let puzzle: Puzzle = format!("{}\n\n2", TEST_INPUT.split("\n\n").next().unwrap()).parse().unwrap();
+        assert_eq!(11_000 + 9 * 4, puzzle.get_password_from((16, 11).into(), Directions::Right, false).0);
+    }
+
+    #[test]
+    fn test_cube_edge_transitions() {
+        // An open map with the supported layout: stepping off any edge and walking back should end up where
+        // we started, facing the other way.
+        let rows: Vec<String> = (0..200).map(|y| match y / 50 {
+            0 => format!("{}{}", " ".repeat(50), ".".repeat(100)),
+            1 => format!("{}{}", " ".repeat(50), ".".repeat(50)),
+            2 => ".".repeat(100),
+            _ => ".".repeat(50),
+        }).collect();
+        let puzzle: Puzzle = format!("{}\n\n1LL1", rows.join("\n")).parse().unwrap();
+
+        let mut transitions = 0;
+        for pos in puzzle.map.bounds.iter_row_major().filter(|p| puzzle.map.has(p)) {
+            for (facing, opposite) in [(Directions::Top, Directions::Bottom), (Directions::Right, Directions::Left),
+                                       (Directions::Bottom, Directions::Top), (Directions::Left, Directions::Right)] {
+                if !puzzle.map.has(&(pos + step(&facing))) {
+                    transitions += 1;
+                    assert_eq!(get_password_for(pos, opposite), puzzle.get_password_from(pos, facing, true).0,
+                               "Walking off the cube at {} facing {:?} does not lead back", pos, facing);
+                }
+            }
+        }
+        // 14 outer edges of 50 tiles each.
+        assert_eq!(14 * 50, transitions);
+    }
+
+    #[test]
+    fn test_transitions_match_supported_layout() {
+        let net = NetInfo { face_size: 50, faces: SUPPORTED_LAYOUT.to_vec() };
+        let transitions = net.transitions().unwrap();
+        assert_eq!(24, transitions.len());
+
+        // Walking off the middle of every outer edge should end up where the computed transition says. The map
+        // itself does not matter, as long as it does not have the tile across the edge.
+        let puzzle: Puzzle = ".\n\n1".parse().unwrap();
+        let face_at = |p: Point| SUPPORTED_LAYOUT.iter()
+            .position(|(c, r)| p.x > 0 && p.y > 0 && (*c as isize, *r as isize) == ((p.x - 1) / 50, (p.y - 1) / 50));
+        let mut outer = 0;
+        for transition in &transitions {
+            let (column, row) = SUPPORTED_LAYOUT[transition.face];
+            let (left, top) = (1 + column as isize * 50, 1 + row as isize * 50);
+            let from: Point = match transition.edge {
+                Directions::Top => (left + 20, top),
+                Directions::Right => (left + 49, top + 20),
+                Directions::Bottom => (left + 20, top + 49),
+                _ => (left, top + 20),
+            }.into();
+            if let Some(next) = face_at(from + step(&transition.edge)) {
+                // The faces are next to each other on the map as well.
+                assert_eq!((transition.neighbour, transition.facing), (next, transition.edge));
+                continue;
+            }
+            outer += 1;
+            let (to, facing) = puzzle.get_next_on_cube(&transition.edge, &from);
+            assert_eq!((Some(transition.neighbour), transition.facing), (face_at(to), facing), "Walking off face {} at {}", transition.face + 1, from);
+        }
+        assert_eq!(14, outer);
+    }
+
+    #[test]
+    fn test_report() {
+        let puzzle: Puzzle = TEST_INPUT.parse().unwrap();
+        let net = validate_net(&puzzle.map).unwrap();
+        let transitions = net.transitions().unwrap();
+        // Up from face 1 onto the top of face 2, facing down.
+        assert_eq!(EdgeTransition { face: 0, edge: Directions::Top, neighbour: 1, facing: Directions::Bottom }, transitions[0]);
+        assert_eq!(2, transitions[0].rotation());
+
+        let report = net.report(Point::from((1, 1))).unwrap();
+        assert_eq!("\
+Face 1: x 9..=12, y 1..=4
+  top    -> face 2 through its top edge, facing bottom (2 quarter turns clockwise)
+  right  -> face 6 through its right edge, facing left (2 quarter turns clockwise)
+  bottom -> face 4 through its top edge, facing bottom (0 quarter turns clockwise)
+  left   -> face 3 through its top edge, facing bottom (3 quarter turns clockwise)
+", report.lines().take(5).map(|l| format!("{}\n", l)).collect::<String>());
+        assert_eq!(6 * 5, report.lines().count());
+    }
+
+    fn validate_map(map: &str) -> Result<NetInfo, String> {
+        validate_net(&format!("{}\n\n1", map).parse::<Puzzle>().unwrap().map)
+    }
+
+    #[test]
+    fn test_validate_net() {
+        let puzzle: Puzzle = TEST_INPUT.parse().unwrap();
+        let net = validate_net(&puzzle.map).unwrap();
+        assert_eq!(NetInfo { face_size: 4, faces: vec![(2, 0), (0, 1), (1, 1), (2, 1), (2, 2), (3, 2)] }, net);
+        assert_eq!("..1.\n234.\n..56\n", net.to_string());
+
+        let supported = NetInfo { face_size: 1, faces: SUPPORTED_LAYOUT.to_vec() };
+        assert_eq!(Ok(supported.clone()), validate_map(" ##\n #\n##\n#"));
+        assert_eq!(".12\n.3.\n45.\n6..\n", supported.to_string());
+    }
+
+    #[test]
+    fn test_validate_malformed_net() {
+        assert_eq!(Err("A cube net has 6 * size * size tiles, but the map has 5 tiles".to_string()), validate_map("#####"));
+        assert_eq!(Err("The faces overlap when folded, so this is not a cube net (faces of 1x1, '#' = face, '?' = partially filled):\n###\n###\n".to_string()),
+                   validate_map("###\n###"));
+        assert_eq!(Err("Not all faces are connected edge-to-edge (faces of 1x1, '#' = face, '?' = partially filled):\n.#..\n####\n....\n#...\n".to_string()),
+                   validate_map(" #\n####\n\n#"));
+        assert_eq!(Err("The map has partially filled faces (faces of 2x2, '#' = face, '?' = partially filled):\n.?.\n###\n.#?\n.#.\n".to_string()),
+                   validate_map("  ##\n  # \n######\n######\n  ##\n  ###\n  ##\n  ##"));
+    }
+
+    const TEST_INPUT: &str = include_str!("../../examples/day22/input.txt");
 }
\ No newline at end of file
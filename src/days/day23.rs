@@ -1,31 +1,127 @@
 use std::collections::{HashMap, VecDeque};
-use std::fmt;
+use std::collections::hash_map::Entry;
+use std::{env, fmt};
 use std::str::FromStr;
-use crate::days::Day;
+use crate::days::{Day, Explanation, Solver, solve_part1, solve_part2, parse_only, ParseSummary};
+use crate::util::config::day23::MAX_ROUNDS;
+use crate::util::error::AocError;
 use crate::util::geometry::{Bounds, Directions, Grid, Point};
+use crate::util::stats::Stats;
 
 pub const DAY23: Day = Day {
-    puzzle1,
-    puzzle2
+    puzzle1: solve_part1::<Day23>,
+    puzzle2: solve_part2::<Day23>,
+    parse: parse_only::<Day23>,
+    describe: "Unstable Diffusion: spread out elves and find when they stop moving",
+    explain: Some(explain),
+    selftest: None,
+    algorithms: &[]
 };
 
-fn puzzle1(input: &String) {
-    let mut game: GameOfElves = input.parse().unwrap();
-    
-    for _ in 0..10 {
-        game.play_round();
+fn explain() -> String {
+    Explanation {
+        approach: "Simulate the elves on a chunked field with cheap neighbour lookups, proposing moves in rotating direction order and cancelling conflicts.",
+        complexity: "O(rounds * elves).",
+        alternatives: &["A plain Grid representation (Representation::Grid) for comparison."]
+    }.to_string()
+}
+
+#[derive(Default)]
+struct Day23;
+
+impl Solver for Day23 {
+    type Output = usize;
+
+    fn part1(&self, input: &str) -> Result<Self::Output, AocError> {
+        let empty_ground = get_empty_ground_after(input, 10, Representation::Chunked)?;
+
+        if env::var("AOC_VERBOSE").is_ok() {
+            for report in get_round_reports(input, 10)? {
+                println!("{}\n", report);
+            }
+        }
+        Ok(empty_ground)
+    }
+
+    fn part2(&self, input: &str) -> Result<Self::Output, AocError> {
+        if env::var("AOC_VERBOSE").is_ok() {
+            // The analysis plays the same rounds, so it finds the answer as well.
+            let mut game: GameOfElves<ChunkedField> = input.parse()?;
+            let mut stats = Stats::new();
+            let stabilize_round = game.analyze_stabilization(MAX_ROUNDS.get()?, &mut stats);
+            stats.print(23, 2, "analysis");
+            return Ok(stabilize_round?);
+        }
+        get_stabilize_round(input, Representation::Chunked, MAX_ROUNDS.get()?)
+    }
+
+    fn parse(&self, input: &str) -> Result<ParseSummary, AocError> {
+        let game: GameOfElves = input.parse()?;
+        Ok(ParseSummary::new().count(game.tiles.elves().len(), "elves"))
     }
-    
-    let empty_ground = game.get_empty_ground();
-    println!("There are {} empty tiles after 10 rounds between the elves.", empty_ground);
 }
 
-fn puzzle2(input: &String) {
-    let mut game: GameOfElves = input.parse().unwrap();
-    
-    let stabilize_round = game.get_stabilize_round();
-    
-    println!("Game stabilizes after {} rounds.", stabilize_round);
+fn get_empty_ground_after(input: &str, rounds: usize, representation: Representation) -> Result<usize, String> {
+    fn play<F: ElfField>(input: &str, rounds: usize) -> Result<usize, String> {
+        let mut game: GameOfElves<F> = input.parse()?;
+        for _ in 0..rounds {
+            game.play_round();
+        }
+        Ok(game.get_empty_ground())
+    }
+
+    match representation {
+        Representation::Grid => play::<Grid<Tile>>(input, rounds),
+        Representation::Chunked => play::<ChunkedField>(input, rounds)
+    }
+}
+
+/// Plays the given rounds, reporting the elves after each round with the tiles that changed highlighted.
+fn get_round_reports(input: &str, rounds: usize) -> Result<Vec<String>, String> {
+    let mut game: GameOfElves<ChunkedField> = input.parse()?;
+    let mut reports = vec![];
+    let mut previous = game.to_grid();
+    for round in 1..=rounds {
+        let moves = game.play_round();
+        let current = game.to_grid();
+        reports.push(format!("== End of Round {} ({} elves moved) ==\n{}", round, moves, previous.render_diff(&current)));
+        previous = current;
+    }
+    Ok(reports)
+}
+
+fn get_stabilize_round(input: &str, representation: Representation, max_rounds: usize) -> Result<usize, AocError> {
+    fn play<F: ElfField>(input: &str, max_rounds: usize) -> Result<usize, AocError> {
+        let mut game: GameOfElves<F> = input.parse()?;
+        Ok(game.get_stabilize_round(max_rounds)?)
+    }
+
+    match representation {
+        Representation::Grid => play::<Grid<Tile>>(input, max_rounds),
+        Representation::Chunked => play::<ChunkedField>(input, max_rounds)
+    }
+}
+
+/// The state of the elves when they are still moving after the maximum number of rounds.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct NotStabilized {
+    rounds: usize,
+    /// The number of elves that moved in the last round.
+    moves: usize,
+    /// The elves after the last round.
+    snapshot: String,
+}
+
+impl fmt::Display for NotStabilized {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The elves did not stop moving within {} rounds, {} elves moved in the last one:\n{}", self.rounds, self.moves, self.snapshot)
+    }
+}
+
+impl From<NotStabilized> for AocError {
+    fn from(error: NotStabilized) -> Self {
+        AocError::Solve(error.to_string())
+    }
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
@@ -44,7 +140,7 @@ impl fmt::Display for Tile {
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
 enum Direction {
     North,
     East,
@@ -62,46 +158,171 @@ impl Direction {
         }
     }
     
-    fn can_move(&self, point: &Point, tiles: &Grid<Tile>) -> bool {
+    fn can_move(&self, neighbours: u8) -> bool {
         let directions = match self {
             Direction::North => Directions::TopAll,
             Direction::East => Directions::RightAll,
             Direction::South => Directions::BottomAll,
             Direction::West => Directions::LeftAll
         };
-        tiles.get_adjacent(point, directions).iter().all(|t| *t != Tile::Elf)
+        neighbours & directions as u8 == 0
+    }
+}
+
+const SINGLE_DIRECTIONS: [Directions; 8] = [
+    Directions::Top, Directions::Right, Directions::Bottom, Directions::Left,
+    Directions::TopLeft, Directions::TopRight, Directions::BottomLeft, Directions::BottomRight
+];
+
+/// Storage of elf positions the game can be played on.
+trait ElfField: Default {
+    fn contains(&self, p: &Point) -> bool;
+    fn add_elf(&mut self, p: Point);
+    fn remove_elf(&mut self, p: &Point);
+    fn elves(&self) -> Vec<Point>;
+
+    /// Returns the occupied neighbours of the given point as a mask of `Directions` flags.
+    fn neighbours(&self, p: &Point) -> u8 {
+        SINGLE_DIRECTIONS.iter()
+            .filter(|d| p.get_points_around(**d).iter().any(|n| self.contains(n)))
+            .fold(0, |mask, d| mask | *d as u8)
     }
 }
 
-struct GameOfElves {
-    tiles: Grid<Tile>,
+impl ElfField for Grid<Tile> {
+    fn contains(&self, p: &Point) -> bool {
+        self.get(p) == Some(Tile::Elf)
+    }
+
+    fn add_elf(&mut self, p: Point) {
+        self.set(p, Tile::Elf);
+    }
+
+    fn remove_elf(&mut self, p: &Point) {
+        self.set(*p, Tile::Nothing);
+    }
+
+    fn elves(&self) -> Vec<Point> {
+        self.find_all(|t| *t == Tile::Elf)
+    }
+
+    fn neighbours(&self, p: &Point) -> u8 {
+        SINGLE_DIRECTIONS.iter()
+            .filter(|d| self.get_adjacent_or_none(p, **d).iter().any(|(_, t)| *t == Some(&Tile::Elf)))
+            .fold(0, |mask, d| mask | *d as u8)
+    }
+}
+
+const CHUNK_WIDTH: isize = 64;
+
+/// Stores elves as bits in chunks of 64x1 tiles, keyed by (x / 64, y).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct ChunkedField {
+    chunks: HashMap<Point, u64>
+}
+
+impl ChunkedField {
+    fn locate(p: &Point) -> (Point, u32) {
+        (Point { x: p.x.div_euclid(CHUNK_WIDTH), y: p.y }, p.x.rem_euclid(CHUNK_WIDTH) as u32)
+    }
+
+    // Returns the occupation of (x-1, y), (x, y), and (x+1, y) as bits 1, 2, and 4.
+    fn row_mask(&self, x: isize, y: isize) -> u8 {
+        let (chunk, bit) = Self::locate(&(x, y).into());
+        if bit > 0 && bit < (CHUNK_WIDTH - 1) as u32 {
+            // All three positions are within the same chunk:
+            self.chunks.get(&chunk).map_or(0, |bits| ((bits >> (bit - 1)) & 0b111) as u8)
+        } else {
+            // We're on a chunk border, check the positions separately:
+            (-1..=1).filter(|dx| self.contains(&(x + dx, y).into())).fold(0, |mask, dx| mask | 1 << (dx + 1))
+        }
+    }
+}
+
+impl ElfField for ChunkedField {
+    fn contains(&self, p: &Point) -> bool {
+        let (chunk, bit) = Self::locate(p);
+        self.chunks.get(&chunk).is_some_and(|bits| bits & (1 << bit) != 0)
+    }
+
+    fn add_elf(&mut self, p: Point) {
+        let (chunk, bit) = Self::locate(&p);
+        *self.chunks.entry(chunk).or_insert(0) |= 1 << bit;
+    }
+
+    fn remove_elf(&mut self, p: &Point) {
+        let (chunk, bit) = Self::locate(p);
+        if let Some(bits) = self.chunks.get_mut(&chunk) {
+            *bits &= !(1 << bit);
+            if *bits == 0 {
+                self.chunks.remove(&chunk);
+            }
+        }
+    }
+
+    fn elves(&self) -> Vec<Point> {
+        let mut elves = vec![];
+        for (chunk, bits) in &self.chunks {
+            for bit in 0..CHUNK_WIDTH {
+                if bits & (1 << bit) != 0 {
+                    elves.push(Point { x: chunk.x * CHUNK_WIDTH + bit, y: chunk.y });
+                }
+            }
+        }
+        elves
+    }
+
+    fn neighbours(&self, p: &Point) -> u8 {
+        let above = self.row_mask(p.x, p.y - 1);
+        let current = self.row_mask(p.x, p.y);
+        let below = self.row_mask(p.x, p.y + 1);
+
+        let flag = |mask: u8, bit: u8, direction: Directions| if mask & bit != 0 { direction as u8 } else { 0 };
+        flag(above, 1, Directions::TopLeft) | flag(above, 2, Directions::Top) | flag(above, 4, Directions::TopRight) |
+            flag(current, 1, Directions::Left) | flag(current, 4, Directions::Right) |
+            flag(below, 1, Directions::BottomLeft) | flag(below, 2, Directions::Bottom) | flag(below, 4, Directions::BottomRight)
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+enum Representation {
+    #[default]
+    Grid,
+    Chunked
+}
+
+struct GameOfElves<F: ElfField = Grid<Tile>> {
+    tiles: F,
     directions: VecDeque<Direction>
 }
 
-impl GameOfElves {
+impl<F: ElfField> GameOfElves<F> {
     fn new() -> Self {
         let directions = VecDeque::from([Direction::North, Direction::South, Direction::West, Direction::East]);
-        Self { tiles: Grid::empty(), directions }
+        Self { tiles: F::default(), directions }
     }
     
     fn play_round(&mut self) -> usize {
-        // I am so sure this will not be good enough for part 2... but let's start simple anyway
-        
+        self.play_round_with_moves().len()
+    }
+
+    /// Plays a round, returning the moves made as (from, to).
+    fn play_round_with_moves(&mut self) -> Vec<(Point, Point)> {
         // Elves without any adjacent elves don't move, so we can skip them in the round
-        let cells = self.tiles.entries();
-        let elves_to_move: Vec<_> = cells.iter()
-            .filter(|(p, t)| *t == Tile::Elf && self.tiles.get_adjacent(p, Directions::All).iter().any(|v| *v == Tile::Elf))
+        let elves_to_move: Vec<_> = self.tiles.elves().into_iter()
+            .map(|p| (p, self.tiles.neighbours(&p)))
+            .filter(|(_, neighbours)| *neighbours != 0)
             .collect();
         // Map of destination => source(s)
         let mut move_map: HashMap<Point, Vec<Point>> = HashMap::new();
-        'move_loop: for (elf, _) in elves_to_move {
+        'move_loop: for (elf, neighbours) in elves_to_move {
             for direction in &self.directions {
-                if direction.can_move(elf, &self.tiles) {
-                    let move_to = direction.apply(elf);
+                if direction.can_move(neighbours) {
+                    let move_to = direction.apply(&elf);
                     if let Some(list) = move_map.get_mut(&move_to) {
-                        list.push(*elf);
+                        list.push(elf);
                     } else {
-                        move_map.insert(move_to, vec![*elf]);
+                        move_map.insert(move_to, vec![elf]);
                     }
                     continue 'move_loop;
                 }
@@ -111,60 +332,148 @@ impl GameOfElves {
         // Move the initial preferred direction
         self.directions.rotate_left(1);
         
-        let mut moves = 0;
+        let mut moves = vec![];
         
         // Move all elves that had a unique target point:
         for (dest, sources) in move_map {
             if sources.len() == 1 {
-                self.tiles.set(sources[0], Tile::Nothing);
-                self.tiles.set(dest, Tile::Elf);
-                moves += 1;
+                self.tiles.remove_elf(&sources[0]);
+                self.tiles.add_elf(dest);
+                moves.push((sources[0], dest));
             }
         }
         
         moves
     }
 
+    fn to_grid(&self) -> Grid<Tile> {
+        Grid::new(self.tiles.elves().into_iter().map(|p| (p, Tile::Elf)).collect())
+    }
+
     fn get_elf_bounds(&self) -> Bounds {
-        let entries = self.tiles.entries();
-        let elves: Vec<_> = entries.iter().filter(|(_, v)| *v == Tile::Elf).map(|(p, _)| p).collect();
-        
-        let top = elves.iter().map(|p| p.y).min().unwrap();
-        let left = elves.iter().map(|p| p.x).min().unwrap();
-        let bottom = elves.iter().map(|p| p.y).max().unwrap();
-        let right = elves.iter().map(|p| p.x).max().unwrap();
-        
-        Bounds::from_tlbr(top, left, bottom, right)
+        Bounds::from_points(self.tiles.elves()).unwrap()
     }
     
     fn get_empty_ground(&self) -> usize {
         let bounds = self.get_elf_bounds();
-        bounds.points().iter().filter(|p| self.tiles.get(p) != Some(Tile::Elf)).count()
+        bounds.iter_row_major().filter(|p| !self.tiles.contains(p)).count()
     }
     
-    fn get_stabilize_round(&mut self) -> usize {
-        let mut rounds = 1; // assuming the first round is not actually stable already 
-        while self.play_round() > 0 {
-            rounds += 1;
+    /// The first round in which no elf moves, giving up after `max_rounds` rounds.
+    fn get_stabilize_round(&mut self, max_rounds: usize) -> Result<usize, NotStabilized> {
+        let mut moves = 0;
+        for round in 1..=max_rounds {
+            moves = self.play_round();
+            if moves == 0 {
+                return Ok(round);
+            }
+        }
+        Err(NotStabilized { rounds: max_rounds, moves, snapshot: self.to_grid().to_string() })
+    }
+
+    /// Plays until the elves stop moving like `get_stabilize_round`, but keeps track of how the movement
+    /// develops, recording the evidence in the stats:
+    /// - moves per round (`total_moves`, `peak_moves`, `last_moves`) and where they happen (`active_area`, the
+    ///   bounding box of the last round's moves, against `bounds_area` of all elves);
+    /// - `frozen_elves`: elves that did not move during the last full cycle of directions;
+    /// - `extrapolated_round`: when the moves would reach zero, following the trend of the last rounds.
+    ///
+    /// Stops early with an error when a configuration repeats (up to translation, with the same direction
+    /// order), as the elves will then never stop moving, or when `max_rounds` pass without stabilizing.
+    fn analyze_stabilization(&mut self, max_rounds: usize, stats: &mut Stats) -> Result<usize, String> {
+        const TREND_ROUNDS: usize = 50;
+
+        let mut history = ConfigurationHistory::default();
+        history.record(&self.tiles.elves(), self.phase(), 0);
+        let mut last_moved: HashMap<Point, usize> = HashMap::new();
+        let mut moves_per_round = vec![];
+
+        for round in 1..=max_rounds {
+            let moves = self.play_round_with_moves();
+            stats.set("rounds", round);
+            if moves.is_empty() {
+                return Ok(round);
+            }
+
+            stats.add("total_moves", moves.len());
+            stats.peak("peak_moves", moves.len());
+            stats.set("last_moves", moves.len());
+            moves_per_round.push(moves.len());
+            for (from, to) in &moves {
+                last_moved.remove(from);
+                last_moved.insert(*to, round);
+            }
+
+            let elves = self.tiles.elves();
+            let area = |b: Bounds| b.width * b.height;
+            stats.set("bounds_area", Bounds::from_points(&elves).map_or(0, area));
+            stats.set("active_area", Bounds::from_points(moves.iter().map(|(_, to)| to)).map_or(0, area));
+            stats.set("frozen_elves", elves.iter().filter(|e| last_moved.get(e).is_none_or(|r| r + 4 <= round)).count());
+
+            if moves_per_round.len() >= TREND_ROUNDS {
+                // Compare the average moves of the older and newer half of the last rounds.
+                let recent = &moves_per_round[moves_per_round.len() - TREND_ROUNDS..];
+                let half = TREND_ROUNDS / 2;
+                let older = recent[..half].iter().sum::<usize>() as f64 / half as f64;
+                let newer = recent[half..].iter().sum::<usize>() as f64 / half as f64;
+                let decline_per_round = (older - newer) / half as f64;
+                if decline_per_round > 0.0 {
+                    stats.set("extrapolated_round", round + (newer / decline_per_round).round() as usize);
+                }
+            }
+
+            if let Some(seen) = history.record(&elves, self.phase(), round) {
+                return Err(format!("The elves in round {} repeat round {}, they will never stop moving", round, seen));
+            }
+        }
+
+        Err(format!("The elves did not stop moving within {} rounds", max_rounds))
+    }
+
+    /// Which direction is considered first, the only other state besides the elves.
+    fn phase(&self) -> Direction {
+        self.directions[0]
+    }
+}
+
+/// The configurations seen, normalized to the top left of their bounds so shapes moving as a whole are detected
+/// as well.
+#[derive(Default)]
+struct ConfigurationHistory {
+    seen: HashMap<(Direction, Vec<Point>), usize>,
+}
+
+impl ConfigurationHistory {
+    /// Records the configuration, returning the round it was seen before (if any).
+    fn record(&mut self, elves: &[Point], phase: Direction, round: usize) -> Option<usize> {
+        let bounds = Bounds::from_points(elves)?;
+        let mut normalized: Vec<_> = elves.iter().map(|e| *e - (bounds.left, bounds.top)).collect();
+        normalized.sort();
+
+        match self.seen.entry((phase, normalized)) {
+            Entry::Occupied(seen) => Some(*seen.get()),
+            Entry::Vacant(entry) => {
+                entry.insert(round);
+                None
+            }
         }
-        rounds
     }
 }
 
-impl FromStr for GameOfElves {
-    type Err = String;
+impl<F: ElfField> FromStr for GameOfElves<F> {
+    type Err = AocError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut game = GameOfElves::new();
+        let mut game = GameOfElves::<F>::new();
         
         let lines: Vec<_> = s.lines().collect();
         for y in 0..lines.len() {
             let chars: Vec<_> = lines[y].chars().collect();
             for x in 0..chars.len() {
                 match chars[x] {
-                    '.' => game.tiles.set((x,y).try_into()?, Tile::Nothing),
-                    '#' => game.tiles.set((x,y).try_into()?, Tile::Elf),
-                    _ => return Err(format!("Invalid game char: '{}'", chars[x]))
+                    '.' => game.tiles.remove_elf(&(x,y).try_into()?),
+                    '#' => game.tiles.add_elf((x,y).try_into()?),
+                    _ => return Err(AocError::parse_at(y + 1, x + 1, format!("Invalid game char: '{}'", chars[x])))
                 }
             }
         }
@@ -175,12 +484,17 @@ impl FromStr for GameOfElves {
 
 #[cfg(test)]
 mod tests {
-    use crate::days::day23::GameOfElves;
+    use std::time::Instant;
+    use crate::days::day23::{ChunkedField, ConfigurationHistory, Direction, ElfField, GameOfElves, get_empty_ground_after, get_round_reports, get_stabilize_round, Representation};
+    use crate::util::error::AocError;
+    use crate::util::geometry::{Directions, Point};
+    use crate::util::snaptest::SeededRng;
+    use crate::util::stats::Stats;
 
     #[test]
     fn test_parse() {
         let parse_result: Result<GameOfElves, _> = TEST_INPUT.parse();
-        assert!(parse_result.is_ok(), "Expected Ok but was '{}'", parse_result.err().unwrap_or_default());
+        assert!(parse_result.is_ok(), "Expected Ok but was '{}'", parse_result.err().map(|e| e.to_string()).unwrap_or_default());
         
         let game = parse_result.unwrap();
         assert_eq!(7, game.tiles.bounds.width);
@@ -232,19 +546,107 @@ mod tests {
         assert_eq!(110, game.get_empty_ground());
     }
     
+    #[test]
+    fn test_get_round_reports() {
+        let input = ".....\n..##.\n..#..\n.....\n..##.\n.....";
+        let hl = |c: &str| format!("\x1b[7m{}\x1b[27m", c);
+
+        let reports = get_round_reports(input, 1).unwrap();
+        assert_eq!(vec![format!("== End of Round 1 (3 elves moved) ==\n{}\n{}\n# \n {}\n#{}",
+                                hl("#") + &hl("#"), hl(" ") + &hl(" "), hl("#"), hl(" "))], reports);
+    }
+
     #[test]
     fn test_get_stabilize_round() {
         let mut game: GameOfElves = TEST_INPUT.parse().unwrap();
-        assert_eq!(20, game.get_stabilize_round());
+        assert_eq!(Ok(20), game.get_stabilize_round(100));
+
+        let mut game: GameOfElves = TEST_INPUT.parse().unwrap();
+        let error = game.get_stabilize_round(19).unwrap_err();
+        assert_eq!((19, 2), (error.rounds, error.moves));
+        assert_eq!(game.to_grid().to_string(), error.snapshot);
+        assert!(AocError::from(error).to_string().starts_with("The elves did not stop moving within 19 rounds, 2 elves moved in the last one:\n"));
     }
     
-    const TEST_INPUT: &str = "\
-        ....#..\n\
-        ..###.#\n\
-        #...#.#\n\
-        .#...##\n\
-        #.###..\n\
-        ##.#.##\n\
-        .#..#..\n\
-    ";
+    #[test]
+    fn test_analyze_stabilization() {
+        let mut game: GameOfElves<ChunkedField> = TEST_INPUT.parse().unwrap();
+        let mut stats = Stats::new();
+        assert_eq!(Ok(20), game.analyze_stabilization(100, &mut stats));
+        assert_eq!(Some(20), stats.get("rounds"));
+        assert!(stats.get("peak_moves").unwrap() <= 22);
+        assert!(stats.get("frozen_elves").is_some());
+        assert!(stats.get("active_area").unwrap() <= stats.get("bounds_area").unwrap());
+
+        let mut game: GameOfElves<ChunkedField> = TEST_INPUT.parse().unwrap();
+        assert_eq!(Err("The elves did not stop moving within 10 rounds".to_string()), game.analyze_stabilization(10, &mut Stats::new()));
+    }
+
+    #[test]
+    fn test_configuration_history() {
+        let mut history = ConfigurationHistory::default();
+        let elves = [Point::from((0, 0)), Point::from((1, 0))];
+        assert_eq!(None, history.record(&elves, Direction::North, 0));
+        assert_eq!(None, history.record(&elves, Direction::South, 1));
+        // The same shape elsewhere, in the same phase, repeats.
+        assert_eq!(Some(0), history.record(&[Point::from((6, 5)), Point::from((5, 5))], Direction::North, 2));
+        assert_eq!(None, history.record(&[Point::from((0, 0)), Point::from((2, 0))], Direction::North, 3));
+    }
+
+    #[test]
+    fn test_chunked_field() {
+        let mut field = ChunkedField::default();
+        for p in [(63, 0), (64, 0), (-1, 1), (0, 1), (10, -1)] {
+            field.add_elf(p.into());
+        }
+        assert!(field.contains(&(63, 0).into()));
+        assert!(field.contains(&(-1, 1).into()));
+        assert!(!field.contains(&(62, 0).into()));
+
+        // Neighbours across chunk borders:
+        assert_eq!(Directions::Right as u8, field.neighbours(&(63, 0).into()));
+        assert_eq!(Directions::Left as u8, field.neighbours(&(64, 0).into()));
+        assert_eq!(Directions::BottomLeft as u8 | Directions::Bottom as u8, field.neighbours(&(0, 0).into()));
+        assert_eq!(0, field.neighbours(&(10, 1).into()));
+        assert_eq!(Directions::Top as u8, field.neighbours(&(10, 0).into()));
+
+        field.remove_elf(&(64, 0).into());
+        assert!(!field.contains(&(64, 0).into()));
+        assert_eq!(0, field.neighbours(&(63, 0).into()));
+
+        let mut elves = field.elves();
+        elves.sort();
+        assert_eq!(vec![Point::from((10, -1)), Point::from((63, 0)), Point::from((-1, 1)), Point::from((0, 1))], elves);
+    }
+
+    #[test]
+    fn test_representations_match() {
+        for representation in [Representation::Grid, Representation::Chunked] {
+            assert_eq!(Ok(110), get_empty_ground_after(TEST_INPUT, 10, representation));
+            assert_eq!(Ok(20), get_stabilize_round(TEST_INPUT, representation, 100));
+        }
+    }
+
+    // Compares the representations on a large generated field. Run with
+    // `cargo test --release day23::tests::bench_representations -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_representations() {
+        let mut rng = SeededRng::new(2022);
+        let input: String = (0..400).map(|_| {
+            (0..400).map(|_| {
+                if rng.below(3) == 0 { '#' } else { '.' }
+            }).collect::<String>() + "\n"
+        }).collect();
+
+        let mut results = vec![];
+        for representation in [Representation::Grid, Representation::Chunked] {
+            let start = Instant::now();
+            results.push(get_empty_ground_after(&input, 10, representation));
+            println!("{:?}: {:?}", representation, start.elapsed());
+        }
+        assert_eq!(results[0], results[1]);
+    }
+
+    const TEST_INPUT: &str = include_str!("../../examples/day23/input.txt");
 }
\ No newline at end of file
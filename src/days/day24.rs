@@ -1,28 +1,103 @@
 use std::cmp::{Ordering};
-use std::collections::{BinaryHeap, HashMap};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::collections::hash_map::Entry;
+use std::{env, fmt};
 use std::str::FromStr;
-use crate::days::Day;
+use crate::days::{Day, Explanation, Solver, solve_part1, solve_part2, parse_only, ParseSummary};
+use crate::util::config::day24::{ALGORITHM, ROUTE};
+use crate::util::error::AocError;
 use crate::util::geometry::{Bounds, Point};
-use crate::util::number::lcm;
+use crate::util::number::NumberExtensions;
+use crate::util::search::{iddfs_with_table, WorkQueue};
+use crate::util::stats::Stats;
+use crate::util::vis;
+use crate::util::vis::{Animation, Frame};
 
 pub const DAY24: Day = Day {
-    puzzle1,
-    puzzle2
+    puzzle1: solve_part1::<Day24>,
+    puzzle2: solve_part2::<Day24>,
+    parse: parse_only::<Day24>,
+    describe: "Blizzard Basin: cross a valley of moving blizzards and make the return trips",
+    explain: Some(explain),
+    selftest: Some(selftest),
+    algorithms: ALGORITHM.options
 };
 
-fn puzzle1(input: &String) {
-    let valley: Valley = input.parse().unwrap();
-    
-    let minutes = valley.shortest_steps_to_exit(0).unwrap();
-    println!("The fastest route to the exit takes {} minutes", minutes);
+fn explain() -> String {
+    Explanation {
+        approach: "Best-first search over (position, time) with blizzard positions computed from their start and the time; states repeat once every lcm(width, height) minutes.",
+        complexity: "O(positions * lcm(width, height)) states at most.",
+        alternatives: &[
+            "Iterative deepening DFS with a transposition table, set AOC_DAY24_ALGORITHM=iddfs.",
+            "Meet-in-the-middle, breadth-first from both the start and the end, set AOC_DAY24_ALGORITHM=bidirectional."
+        ]
+    }.to_string()
 }
-fn puzzle2(input: &String) {
-    let valley: Valley = input.parse().unwrap();
 
-    let first = valley.shortest_steps_to_exit(0).unwrap();
-    let back = valley.shortest_steps_to_entrance(first).unwrap();
-    let again = valley.shortest_steps_to_exit(back).unwrap();
-    println!("The fastest route to the exit, back, and again takes {} minutes", again);
+/// Replays the moves of the fastest route to the exit, or of the route in `AOC_DAY24_ROUTE`, against the blizzards.
+/// The fastest route should take as long as the search says.
+fn selftest(input: &str) -> Result<String, AocError> {
+    let valley: Valley = input.parse()?;
+    if let Some(route) = ROUTE.get()? {
+        let minutes = valley.verify_route(&route).map_err(|e| AocError::Solve(format!("Invalid route: {}", e)))?;
+        return Ok(format!("The route reaches the exit in {} minutes", minutes));
+    }
+
+    let route = describe_moves(&valley.expedition_route(0, valley.entrance, valley.exit).ok_or("There is no route to the exit")?);
+    let minutes = valley.verify_route(&route).map_err(|e| AocError::Solve(format!("Invalid fastest route {}: {}", route, e)))?;
+    let searched = valley.shortest_steps_to_exit(0).ok_or("The search found no route to the exit")?;
+    if minutes != searched {
+        return Err(AocError::Solve(format!("The fastest route {} takes {} minutes, but the search says {}", route, minutes, searched)));
+    }
+    Ok(format!("The fastest route reaches the exit in {} minutes: {}", minutes, route))
+}
+
+#[derive(Default)]
+struct Day24;
+
+impl Solver for Day24 {
+    type Output = usize;
+
+    fn part1(&self, input: &str) -> Result<Self::Output, AocError> {
+        let valley: Valley = input.parse()?;
+        let options = SearchOptions::from_env()?;
+
+        let mut stats = Stats::new();
+        let minutes = valley.search(0, valley.entrance, valley.exit, &options, &mut stats).ok_or("There is no route to the exit")?;
+        if env::var("AOC_VERBOSE").is_ok() {
+            stats.print(24, 1, options.algorithm.name());
+            valley.print_route(0, valley.entrance, valley.exit);
+        }
+        if vis::enabled() {
+            valley.animate(minutes).save()?;
+        }
+        Ok(minutes)
+    }
+
+    fn part2(&self, input: &str) -> Result<Self::Output, AocError> {
+        let valley: Valley = input.parse()?;
+        let options = SearchOptions::from_env()?;
+
+        let mut stats = Stats::new();
+        let no_route = || "There is no route to the exit, back, and again".to_string();
+        let first = valley.search(0, valley.entrance, valley.exit, &options, &mut stats).ok_or_else(no_route)?;
+        let back = valley.search(first, valley.exit, valley.entrance, &options, &mut stats).ok_or_else(no_route)?;
+        let again = valley.search(back, valley.entrance, valley.exit, &options, &mut stats).ok_or_else(no_route)?;
+        if env::var("AOC_VERBOSE").is_ok() {
+            stats.print(24, 2, options.algorithm.name());
+            valley.print_route(0, valley.entrance, valley.exit);
+            valley.print_route(first, valley.exit, valley.entrance);
+            valley.print_route(back, valley.entrance, valley.exit);
+        }
+        Ok(again)
+    }
+
+    fn parse(&self, input: &str) -> Result<ParseSummary, AocError> {
+        let valley: Valley = input.parse()?;
+        Ok(ParseSummary::new()
+            .count(valley.bounds.width * valley.bounds.height, "squares")
+            .count(valley.blizzards.len(), "blizzards"))
+    }
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
@@ -71,6 +146,47 @@ impl Blizzard {
     }
 }
 
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+enum Algorithm {
+    #[default]
+    BestFirst,
+    // Iterative deepening DFS with a transposition table; uses less memory, but is a lot slower.
+    IterativeDeepening,
+    // Meet-in-the-middle: breadth-first from the start, and backwards from the end at every minute of the blizzard cycle.
+    Bidirectional
+}
+
+impl Algorithm {
+    fn name(&self) -> &'static str {
+        match self {
+            Algorithm::BestFirst => "best-first",
+            Algorithm::IterativeDeepening => "iddfs",
+            Algorithm::Bidirectional => "bidirectional"
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+struct SearchOptions {
+    algorithm: Algorithm,
+    // When set, the seen-states map is trimmed to the current time frontier when it grows beyond this many
+    // entries, at most once a minute. This trades memory for re-exploring states, but never affects the result.
+    // Only used by the best-first search.
+    max_states: Option<usize>
+}
+
+impl SearchOptions {
+    /// Picks the search algorithm based on `AOC_DAY24_ALGORITHM`, defaulting to the best-first search.
+    fn from_env() -> Result<Self, String> {
+        let algorithm = match ALGORITHM.get()? {
+            "iddfs" => Algorithm::IterativeDeepening,
+            "bidirectional" => Algorithm::Bidirectional,
+            _ => Algorithm::BestFirst
+        };
+        Ok(SearchOptions { algorithm, ..SearchOptions::default() })
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Debug)]
 struct Valley {
     bounds: Bounds,
@@ -84,11 +200,245 @@ impl Valley {
         self.shortest_steps_between(start_time, self.entrance, self.exit)
     }
 
+    #[cfg(test)]
     fn shortest_steps_to_entrance(&self, start_time: usize) -> Option<usize> {
         self.shortest_steps_between(start_time, self.exit, self.entrance)
     }
     
     fn shortest_steps_between(&self, start_time: usize, start: Point, end: Point) -> Option<usize> {
+        self.search(start_time, start, end, &SearchOptions::default(), &mut Stats::new())
+    }
+
+    fn search(&self, start_time: usize, start: Point, end: Point, options: &SearchOptions, stats: &mut Stats) -> Option<usize> {
+        match options.algorithm {
+            Algorithm::BestFirst => self.best_first_search(start_time, start, end, options, stats),
+            Algorithm::IterativeDeepening => self.iterative_deepening_search(start_time, start, end, stats),
+            Algorithm::Bidirectional => self.bidirectional_search(start_time, start, end, stats)
+        }
+    }
+
+    /// The positions of the expedition on a fastest route, one for every minute from `start_time` until it
+    /// arrives. Breadth-first over (position, minute), so it does not depend on the selected algorithm.
+    fn expedition_route(&self, start_time: usize, start: Point, end: Point) -> Option<Vec<Point>> {
+        let blizzard_time = [self.bounds.width, self.bounds.height].lcm();
+        let max_time = start_time + blizzard_time * (self.bounds.width * self.bounds.height + 2);
+        let mut blizzards_at: HashMap<usize, HashSet<Point>> = HashMap::new();
+
+        let mut queue = WorkQueue::fifo().tracking_paths();
+        queue.push((start, start_time));
+        while let Some((pos, time)) = queue.pop() {
+            if pos == end {
+                let path = queue.path_to(&(pos, time))?;
+                return Some(path.into_iter().map(|(pos, _)| pos).collect());
+            }
+            if time >= max_time {
+                break;
+            }
+
+            let blizzards = blizzards_at.entry((time + 1) % blizzard_time)
+                .or_insert_with(|| self.blizzards.iter().map(|b| b.location_at(time + 1, self.bounds)).collect());
+            for next in self.next_positions(pos, end, |p| blizzards.contains(p)) {
+                queue.push_from(&(pos, time), (next, time + 1));
+            }
+        }
+        None
+    }
+
+    /// Prints the moves of the expedition on a fastest route as a move string, see `describe_moves`.
+    fn print_route(&self, start_time: usize, start: Point, end: Point) {
+        match self.expedition_route(start_time, start, end) {
+            Some(route) => {
+                let moves = describe_moves(&route);
+                debug_assert_eq!(Ok(route.len() - 1), self.replay_route(start_time, start, end, &moves), "Route {}", moves);
+                println!("Route from {} to {} at minute {} ({} minutes): {}", start, end, start_time, route.len() - 1, moves)
+            }
+            None => println!("No route from {} to {} at minute {}", start, end, start_time),
+        }
+    }
+
+    /// Checks a move string (see `describe_moves`) from the entrance at minute 0, returning the minutes it takes to
+    /// reach the exit.
+    fn verify_route(&self, route: &str) -> Result<usize, RouteError> {
+        self.replay_route(0, self.entrance, self.exit, route)
+    }
+
+    /// Replays the moves against the blizzards from `start` at `start_time`, failing on the first move into a wall or
+    /// a blizzard, or when the moves do not end at `end`. Returns the number of minutes the moves take.
+    fn replay_route(&self, start_time: usize, start: Point, end: Point, route: &str) -> Result<usize, RouteError> {
+        let mut pos = start;
+        for (index, c) in route.trim().chars().enumerate() {
+            let minute = start_time + index + 1;
+            let next = match c {
+                '^' => pos + (0, -1),
+                'v' => pos + (0, 1),
+                '<' => pos + (-1, 0),
+                '>' => pos + (1, 0),
+                'w' => pos,
+                _ => return Err(RouteError::InvalidMove { index, c }),
+            };
+            // Besides moving within the valley, the expedition can step out to the end, or back to where it started.
+            if next != pos && next != start && next != end && !self.bounds.contains(&next) {
+                return Err(RouteError::HitsWall { minute, position: next });
+            }
+            if self.blizzards.iter().any(|b| b.location_at(minute, self.bounds) == next) {
+                return Err(RouteError::HitsBlizzard { minute, position: next });
+            }
+            pos = next;
+        }
+
+        if pos != end {
+            return Err(RouteError::DoesNotArrive { position: pos });
+        }
+        Ok(route.trim().chars().count())
+    }
+
+    /// An animation of the first `minutes` minutes: the blizzards, and every position the expedition could be at.
+    fn animate(&self, minutes: usize) -> Animation {
+        const PALETTE: [[u8; 3]; 4] = [[20, 20, 30], [110, 100, 90], [170, 200, 230], [240, 120, 40]];
+        let mut reachable = HashSet::from([self.entrance]);
+        let mut animation = Animation::new(&PALETTE);
+        animation.scale = 4;
+        animation.delay = 10;
+        for time in 0..=minutes {
+            let blizzards: HashSet<_> = self.blizzards.iter().map(|b| b.location_at(time, self.bounds)).collect();
+            if time > 0 {
+                reachable = reachable.iter().flat_map(|pos| self.next_positions(*pos, self.exit, |p| blizzards.contains(p))).collect();
+            }
+
+            // The valley is drawn with its walls, so everything is offset by one.
+            let mut frame = Frame::new(self.bounds.width + 2, self.bounds.height + 2);
+            for x in 0..frame.width {
+                frame.set(x, 0, 1);
+                frame.set(x, frame.height - 1, 1);
+            }
+            for y in 0..frame.height {
+                frame.set(0, y, 1);
+                frame.set(frame.width - 1, y, 1);
+            }
+            for (point, color) in [(self.entrance, 0), (self.exit, 0)].into_iter()
+                .chain(blizzards.iter().map(|p| (*p, 2)))
+                .chain(reachable.iter().map(|p| (*p, 3))) {
+                frame.set((point.x + 1) as usize, (point.y + 1) as usize, color);
+            }
+            animation.push(frame);
+        }
+        animation
+    }
+
+    /// Moves possible from `pos` given the blizzard locations of the next minute; waiting is included.
+    fn next_positions(&self, pos: Point, end: Point, is_blizzard: impl Fn(&Point) -> bool) -> Vec<Point> {
+        let mut candidates = vec![];
+        // Can we wait?
+        if !is_blizzard(&pos) {
+            candidates.push(pos);
+        }
+
+        let up = pos + (0, -1);
+        let down = pos + (0, 1);
+        let left = pos + (-1, 0);
+        let right = pos + (1, 0);
+
+        // Can we go up/down/left/right?
+        for next in [up, down, left, right] {
+            if (end == next || self.bounds.contains(&next)) && !is_blizzard(&next) {
+                candidates.push(next);
+            }
+        }
+        candidates
+    }
+
+    fn iterative_deepening_search(&self, start_time: usize, start: Point, end: Point, stats: &mut Stats) -> Option<usize> {
+        // States repeat once the blizzards are back in their starting positions, so there can be no more
+        // distinct states than every position at every minute of that cycle.
+        let blizzard_time = [self.bounds.width, self.bounds.height].lcm();
+        let max_depth = blizzard_time * (self.bounds.width * self.bounds.height + 2);
+
+        let mut blizzards_at: HashMap<usize, HashSet<Point>> = HashMap::new();
+        let mut expanded = 0;
+        let expand = |&(pos, time): &(Point, usize)| {
+            expanded += 1;
+            let blizzards = blizzards_at.entry((time + 1) % blizzard_time)
+                .or_insert_with(|| self.blizzards.iter().map(|b| b.location_at(time + 1, self.bounds)).collect());
+            let mut next = self.next_positions(pos, end, |p| blizzards.contains(p));
+            // Try the moves getting us closest to the end first, to find the route early in the last iteration.
+            next.sort_by_key(|p| p.manhattan_distance(&end));
+            next.into_iter().map(move |p| (p, time + 1))
+        };
+
+        let path = iddfs_with_table((start, start_time), expand, |(pos, _)| *pos == end, max_depth,
+                                    |(pos, time)| (*pos, time % blizzard_time));
+        stats.add("expanded_states", expanded);
+        path.and_then(|p| p.last().map(|(_, time)| *time))
+    }
+
+    fn bidirectional_search(&self, start_time: usize, start: Point, end: Point, stats: &mut Stats) -> Option<usize> {
+        // Breadth-first from the start and backwards from the end at the same time, a layer (minute) at a time on the
+        // side with the smaller frontier. States are (position, minute in the blizzard cycle). The arrival time is not
+        // known up front, so the backward search starts from the end at every minute of the cycle. A state seen from
+        // both sides is a route taking the minutes of both; once the depths of both sides together reach the best of
+        // those, no shorter route can be found anymore.
+        let blizzard_time = [self.bounds.width, self.bounds.height].lcm();
+
+        // Every minute of the blizzard cycle is visited many times, so compute them all up front.
+        let blizzards_at: Vec<HashSet<Point>> = (0..blizzard_time)
+            .map(|time| self.blizzards.iter().map(|b| b.location_at(time, self.bounds)).collect())
+            .collect();
+        let occupied = |time: usize| &blizzards_at[time];
+
+        // The minutes from the start, or to the end, of every state seen; and the states of the last layer.
+        let mut forward_seen: HashMap<(Point, usize), usize> = HashMap::from([((start, start_time % blizzard_time), 0)]);
+        let mut backward_seen: HashMap<(Point, usize), usize> = (0..blizzard_time).map(|time| ((end, time), 0)).collect();
+        let mut forward: Vec<_> = forward_seen.keys().copied().collect();
+        let mut backward: Vec<_> = backward_seen.keys().copied().collect();
+        let (mut forward_depth, mut backward_depth) = (0, 0);
+        let mut best = backward_seen.get(&(start, start_time % blizzard_time)).copied();
+
+        while best.is_none_or(|b| forward_depth + backward_depth < b) && !forward.is_empty() && !backward.is_empty() {
+            let mut next = vec![];
+            if forward.len() <= backward.len() {
+                forward_depth += 1;
+                for (pos, time) in forward {
+                    let time = (time + 1) % blizzard_time;
+                    for p in self.next_positions(pos, end, |b| occupied(time).contains(b)) {
+                        if let Entry::Vacant(seen) = forward_seen.entry((p, time)) {
+                            seen.insert(forward_depth);
+                            next.push((p, time));
+                            if let Some(steps) = backward_seen.get(&(p, time)) {
+                                best = Some(best.map_or(forward_depth + steps, |b| b.min(forward_depth + steps)));
+                            }
+                        }
+                    }
+                }
+                stats.add("forward_states", next.len());
+                forward = next;
+            } else {
+                backward_depth += 1;
+                for (pos, time) in backward {
+                    // The states a minute earlier from which `pos` can be reached.
+                    let previous_time = (time + blizzard_time - 1) % blizzard_time;
+                    for p in [pos, pos + (0, -1), pos + (0, 1), pos + (-1, 0), pos + (1, 0)] {
+                        let is_open = (p == start || p == end || self.bounds.contains(&p)) && !occupied(previous_time).contains(&p);
+                        if !is_open || !self.next_positions(p, end, |b| occupied(time).contains(b)).contains(&pos) {
+                            continue;
+                        }
+                        if let Entry::Vacant(seen) = backward_seen.entry((p, previous_time)) {
+                            seen.insert(backward_depth);
+                            next.push((p, previous_time));
+                            if let Some(steps) = forward_seen.get(&(p, previous_time)) {
+                                best = Some(best.map_or(backward_depth + steps, |b| b.min(backward_depth + steps)));
+                            }
+                        }
+                    }
+                }
+                stats.add("backward_states", next.len());
+                backward = next;
+            }
+        }
+
+        best.map(|steps| start_time + steps)
+    }
+
+    fn best_first_search(&self, start_time: usize, start: Point, end: Point, options: &SearchOptions, stats: &mut Stats) -> Option<usize> {
         // Every turn, move the blizzards first. This should give a set of options:
         // - Wait, if our current tile is still empty.
         // - Move (non-diagonally) to an empty tile next to us.
@@ -118,14 +468,20 @@ impl Valley {
             }
         }
 
-        let blizzard_time = lcm(self.bounds.width, self.bounds.height);
+        let blizzard_time = [self.bounds.width, self.bounds.height].lcm();
         
         let mut dists: HashMap<(usize, Point), usize> = HashMap::new();
         let mut queue = BinaryHeap::new();
+        // Earliest arrival at the end we've queued so far. Any state that cannot beat it, even when
+        // walking straight to the end without waiting, can be dropped.
+        let mut best: Option<usize> = None;
+        // The minute the seen-states map was last trimmed to the time frontier, see `SearchOptions::max_states`.
+        let mut pruned_at: Option<usize> = None;
         
         queue.push(State { pos: start, time_spent: start_time });
         
         while let Some(state) = queue.pop() {
+            stats.peak("peak_queue", queue.len() + 1);
             if state.pos == end {
                 // We're done!
                 return Some(state.time_spent)
@@ -137,25 +493,31 @@ impl Valley {
                     continue;
                 }
             }
+            if options.max_states.is_some_and(|cap| dists.len() >= cap) && pruned_at < Some(state.time_spent) {
+                // States are handled in order of time, so only the current and next minute are still in the
+                // queue. Forgetting older states only means we might explore some of them again. Pruning again
+                // within the same minute would not forget anything more, so it waits for the next minute.
+                let before = dists.len();
+                dists.retain(|_, time| *time + 1 >= state.time_spent);
+                stats.add("evicted_states", before - dists.len());
+                pruned_at = Some(state.time_spent);
+            }
             dists.insert((state.time_spent % blizzard_time, state.pos), state.time_spent);
+            stats.peak("peak_states", dists.len());
             
             // Check what we can actually do:
             let blizzards_at: Vec<_> = self.blizzards.iter().map(|b| b.location_at(state.time_spent + 1, self.bounds)).collect();
-            // Can we wait?
-            if !blizzards_at.contains(&state.pos) {
-                queue.push(State { pos: state.pos, time_spent: state.time_spent + 1 });
-            }
-            
-            let up = state.pos + (0, -1);
-            let down = state.pos + (0, 1);
-            let left = state.pos + (-1, 0);
-            let right = state.pos + (1, 0);
-            
-            // Can we go up/down/left/right?
-            for next in [up, down, left, right] {
-                if (end == next || self.bounds.contains(&next)) && !blizzards_at.contains(&next) {
-                    queue.push(State { pos: next, time_spent: state.time_spent + 1 });
+            let candidates = self.next_positions(state.pos, end, |p| blizzards_at.contains(p));
+
+            let time_spent = state.time_spent + 1;
+            for next in candidates {
+                if next == end {
+                    best = Some(best.map_or(time_spent, |b| b.min(time_spent)));
+                } else if best.is_some_and(|b| time_spent + next.manhattan_distance(&end) as usize >= b) {
+                    stats.add("pruned_states", 1);
+                    continue;
                 }
+                queue.push(State { pos: next, time_spent });
             }
         }
         
@@ -163,8 +525,42 @@ impl Valley {
     }
 }
 
+/// The moves between the positions of a route as a move string, one character per minute: '^', 'v', '<' and '>'
+/// for the directions, and 'w' to wait. E.g. "v>vw^".
+fn describe_moves(route: &[Point]) -> String {
+    route.windows(2).map(|step| match (step[1].x - step[0].x, step[1].y - step[0].y) {
+        (0, -1) => '^',
+        (0, 1) => 'v',
+        (-1, 0) => '<',
+        (1, 0) => '>',
+        _ => 'w',
+    }).collect()
+}
+
+/// Why a move string is not a valid route through the valley.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum RouteError {
+    /// The character at `index` (0-based) is not a move.
+    InvalidMove { index: usize, c: char },
+    HitsWall { minute: usize, position: Point },
+    HitsBlizzard { minute: usize, position: Point },
+    /// All moves are made, but the expedition is at `position` instead of its destination.
+    DoesNotArrive { position: Point },
+}
+
+impl fmt::Display for RouteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RouteError::InvalidMove { index, c } => write!(f, "'{}' at {} is not a move", c, index + 1),
+            RouteError::HitsWall { minute, position } => write!(f, "the expedition walks into the wall at {} in minute {}", position, minute),
+            RouteError::HitsBlizzard { minute, position } => write!(f, "a blizzard hits the expedition at {} in minute {}", position, minute),
+            RouteError::DoesNotArrive { position } => write!(f, "the expedition ends up at {}, not at its destination", position),
+        }
+    }
+}
+
 impl FromStr for Valley {
-    type Err = String;
+    type Err = AocError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         // The top, left, right, and bottom of the input should be walls ('#'). The top should have one
@@ -173,7 +569,7 @@ impl FromStr for Valley {
         // direction (Up '^', left '<', down 'v', or right '>').
         let lines: Vec<_> = s.lines().collect();
         if lines.len() < 3 {
-            return Err(format!("Too few lines in input."));
+            return Err(AocError::parse("Too few lines in input."));
         }
         
         let bounds = Bounds::from_size(lines[0].len() - 2, lines.len() - 2);
@@ -191,10 +587,10 @@ impl FromStr for Valley {
         for y in 0..bounds.height {
             let line = lines[y+1];
             if !(line.starts_with("#") && line.ends_with("#")) {
-                return Err(format!("Invalid line '{}', must start and end with a wall.", line));
+                return Err(AocError::parse(format!("Invalid line '{}', must start and end with a wall.", line)).in_line(y + 2));
             }
             if line.len() != bounds.width + 2 {
-                return Err(format!("Line is not the right length, expected {} but was {}: '{}'", bounds.width + 2, line.len(), line));
+                return Err(AocError::parse(format!("Line is not the right length, expected {} but was {}: '{}'", bounds.width + 2, line.len(), line)).in_line(y + 2));
             }
             
             let chars: Vec<_> = line.chars().skip(1).take(bounds.width).collect();
@@ -206,31 +602,32 @@ impl FromStr for Valley {
                     '<' => { blizzards.push(Blizzard { start_location: loc, direction: Direction::Left }); }
                     '^' => { blizzards.push(Blizzard { start_location: loc, direction: Direction::Up }); }
                     'v' => { blizzards.push(Blizzard { start_location: loc, direction: Direction::Down }); }
-                    _ => return Err(format!("Invalid valley character: '{}'", chars[x]))
+                    _ => return Err(AocError::parse_at(y + 2, x + 2, format!("Invalid valley character: '{}'", chars[x])))
                 }
             }
         }
         
         Ok(Self {
             bounds, blizzards, 
-            entrance: entrance.ok_or("Could not find an entrance".to_string())?,
-            exit: exit.ok_or("Could not find an exit".to_string())?
+            entrance: entrance.ok_or(AocError::parse("Could not find an entrance").in_line(1))?,
+            exit: exit.ok_or(AocError::parse("Could not find an exit").in_line(lines.len()))?
         })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::days::day24::{Blizzard, Direction, Valley};
+    use crate::days::day24::{Algorithm, Blizzard, describe_moves, Direction, RouteError, SearchOptions, selftest, Valley};
     use crate::util::geometry::{Bounds, Point};
+    use crate::util::stats::Stats;
 
     #[test]
     fn test_parse() {
         let small_res: Result<Valley, _> = SMALL_TEST_INPUT.parse();
         let res:Result<Valley, _> = TEST_INPUT.parse();
         
-        assert!(small_res.is_ok(), "Expected OK but was '{}'", small_res.err().unwrap_or_default());
-        assert!(res.is_ok(), "Expected OK but was '{}'", res.err().unwrap_or_default());
+        assert!(small_res.is_ok(), "Expected OK but was '{}'", small_res.err().map(|e| e.to_string()).unwrap_or_default());
+        assert!(res.is_ok(), "Expected OK but was '{}'", res.err().map(|e| e.to_string()).unwrap_or_default());
         
         let small = small_res.unwrap();
         assert_eq!(2, small.blizzards.len());
@@ -277,6 +674,114 @@ mod tests {
         assert_eq!(Some(54), valley.shortest_steps_to_exit(41));
     }
     
+    #[test]
+    fn test_expedition_route() {
+        let valley: Valley = TEST_INPUT.parse().unwrap();
+
+        for (start_time, start, end, arrival) in [(0, valley.entrance, valley.exit, 18), (18, valley.exit, valley.entrance, 41)] {
+            let route = valley.expedition_route(start_time, start, end).unwrap();
+            assert_eq!(arrival - start_time + 1, route.len());
+            assert_eq!(Some(&start), route.first());
+            assert_eq!(Some(&end), route.last());
+            for (minute, step) in route.windows(2).enumerate() {
+                let time = start_time + minute + 1;
+                assert!(step[0].manhattan_distance(&step[1]) <= 1);
+                assert!(valley.blizzards.iter().all(|b| b.location_at(time, valley.bounds) != step[1]), "Hit a blizzard at minute {}", time);
+            }
+        }
+
+        let route: Vec<Point> = vec![(0, -1).into(), (0, 0).into(), (0, 0).into(), (1, 0).into(), (1, 1).into(), (0, 1).into(), (0, 0).into()];
+        assert_eq!("vw>v<^", describe_moves(&route));
+    }
+
+    #[test]
+    fn test_verify_route() {
+        let valley: Valley = TEST_INPUT.parse().unwrap();
+
+        // The route of the puzzle description, and the one the expedition route is exported as.
+        assert_eq!(Ok(18), valley.verify_route("vvw^>>v<^>wvv>>>vv"));
+        let route = valley.expedition_route(0, valley.entrance, valley.exit).unwrap();
+        assert_eq!(Ok(18), valley.verify_route(&describe_moves(&route)));
+        let back = describe_moves(&valley.expedition_route(18, valley.exit, valley.entrance).unwrap());
+        assert_eq!(Ok(23), valley.replay_route(18, valley.exit, valley.entrance, &back));
+
+        assert_eq!(Err(RouteError::InvalidMove { index: 2, c: 'U' }), valley.verify_route("vwU"));
+        assert_eq!(Err(RouteError::HitsWall { minute: 1, position: (-1, -1).into() }), valley.verify_route("<"));
+        assert_eq!(Err(RouteError::HitsBlizzard { minute: 2, position: (1, 0).into() }), valley.verify_route("v>"));
+        assert_eq!(Err(RouteError::DoesNotArrive { position: (0, 0).into() }), valley.verify_route("v"));
+        // Stepping back to the entrance is allowed.
+        assert_eq!(Err(RouteError::DoesNotArrive { position: valley.entrance }), valley.verify_route("v^"));
+        assert_eq!("a blizzard hits the expedition at (1,0) in minute 2", valley.verify_route("v>").unwrap_err().to_string());
+    }
+
+    #[test]
+    fn test_selftest() {
+        assert_eq!(Ok("The fastest route reaches the exit in 18 minutes: wwwwwwwv>>wvv>>>vv".to_string()), selftest(TEST_INPUT).map_err(|e| e.to_string()));
+    }
+
+    #[test]
+    fn test_search_with_state_cap() {
+        let valley: Valley = TEST_INPUT.parse().unwrap();
+
+        let mut unbounded_stats = Stats::new();
+        assert_eq!(Some(18), valley.search(0, valley.entrance, valley.exit, &SearchOptions::default(), &mut unbounded_stats));
+        assert_eq!(None, unbounded_stats.get("evicted_states"));
+
+        let mut capped_stats = Stats::new();
+        let options = SearchOptions { max_states: Some(20), ..SearchOptions::default() };
+        assert_eq!(Some(18), valley.search(0, valley.entrance, valley.exit, &options, &mut capped_stats));
+        assert!(capped_stats.get("evicted_states").unwrap_or(0) > 0);
+        assert!(capped_stats.get("peak_states").unwrap() < unbounded_stats.get("peak_states").unwrap());
+
+        assert_eq!(Some(41), valley.search(18, valley.exit, valley.entrance, &options, &mut Stats::new()));
+        assert_eq!(Some(54), valley.search(41, valley.entrance, valley.exit, &options, &mut Stats::new()));
+    }
+
+    #[test]
+    fn test_iterative_deepening_search() {
+        let valley: Valley = TEST_INPUT.parse().unwrap();
+        let options = SearchOptions { algorithm: Algorithm::IterativeDeepening, ..SearchOptions::default() };
+
+        let mut stats = Stats::new();
+        assert_eq!(Some(18), valley.search(0, valley.entrance, valley.exit, &options, &mut stats));
+        assert!(stats.get("expanded_states").unwrap() > 0);
+        assert_eq!(Some(41), valley.search(18, valley.exit, valley.entrance, &options, &mut Stats::new()));
+        assert_eq!(Some(54), valley.search(41, valley.entrance, valley.exit, &options, &mut Stats::new()));
+    }
+
+    #[test]
+    fn test_bidirectional_search() {
+        let valley: Valley = TEST_INPUT.parse().unwrap();
+        let options = SearchOptions { algorithm: Algorithm::Bidirectional, ..SearchOptions::default() };
+
+        assert_eq!(Some(18), valley.search(0, valley.entrance, valley.exit, &options, &mut Stats::new()));
+        assert_eq!(Some(41), valley.search(18, valley.exit, valley.entrance, &options, &mut Stats::new()));
+        assert_eq!(Some(54), valley.search(41, valley.entrance, valley.exit, &options, &mut Stats::new()));
+
+        // Every leg from every start minute within a blizzard cycle agrees with the best-first search.
+        let blizzard_time = 12;
+        for start_time in 0..blizzard_time {
+            for (from, to) in [(valley.entrance, valley.exit), (valley.exit, valley.entrance)] {
+                assert_eq!(valley.search(start_time, from, to, &SearchOptions::default(), &mut Stats::new()),
+                           valley.search(start_time, from, to, &options, &mut Stats::new()),
+                           "Different result from {} to {} starting at {}", from, to, start_time);
+            }
+        }
+
+        // The backward search starts at every minute of the blizzard cycle, so it only gets its turn early on
+        // in a valley with a short cycle.
+        let short_cycle: Valley = "#.####\n#....#\n#.>..#\n#..v.#\n#<...#\n####.#".parse().unwrap();
+        let mut stats = Stats::new();
+        for start_time in 0..4 {
+            for (from, to) in [(short_cycle.entrance, short_cycle.exit), (short_cycle.exit, short_cycle.entrance)] {
+                assert_eq!(short_cycle.search(start_time, from, to, &SearchOptions::default(), &mut Stats::new()),
+                           short_cycle.search(start_time, from, to, &options, &mut stats),
+                           "Different result from {} to {} starting at {}", from, to, start_time);
+            }
+        }
+        assert!(stats.get("backward_states").unwrap() > 0);
+    }
+
     const SMALL_TEST_INPUT: &str = "\
         #.#####\n\
         #.....#\n\
@@ -287,12 +792,5 @@ mod tests {
         #####.#\n\
     ";
     
-    const TEST_INPUT: &str = "\
-        #.######\n\
-        #>>.<^<#\n\
-        #.<..<<#\n\
-        #>v.><>#\n\
-        #<^v^^>#\n\
-        ######.#\n\
-    ";
+    const TEST_INPUT: &str = include_str!("../../examples/day24/input.txt");
 }
\ No newline at end of file
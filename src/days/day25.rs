@@ -1,20 +1,170 @@
-use crate::days::Day;
+use std::fmt;
+use std::ops::Add;
+use std::str::FromStr;
+use crate::days::{Day, Explanation, Solver, solve_part1, solve_part2, parse_only, ParseSummary};
+use crate::util::error::AocError;
 
 pub const DAY25: Day = Day {
-    puzzle1,
-    puzzle2
+    puzzle1: solve_part1::<Day25>,
+    puzzle2: solve_part2::<Day25>,
+    parse: parse_only::<Day25>,
+    describe: "Full of Hot Air: sum fuel requirements written as SNAFU numbers",
+    explain: Some(explain),
+    selftest: None,
+    algorithms: &[]
 };
 
-fn puzzle1(input: &String) {
-    let result: isize = input.lines().map(|l| decode_snafu_number(l).unwrap()).sum();
-    let encoded = encode_snafu_number(result);
-    println!("Sum of fuel is {}, as SNAFU: {}", result, encoded);
+fn explain() -> String {
+    Explanation {
+        approach: "Convert SNAFU numbers to integers and back, or add them digit by digit with carries.",
+        complexity: "O(digits) per number.",
+        alternatives: &[]
+    }.to_string()
 }
 
-fn puzzle2(_: &String) {
-    println!("Puzzle 2 is a freebie!");
+#[derive(Default)]
+struct Day25;
+
+impl Solver for Day25 {
+    type Output = String;
+
+    fn part1(&self, input: &str) -> Result<Self::Output, AocError> {
+        let result = sum_snafu_numbers(input)?;
+        match result.to_isize() {
+            Some(value) => println!("Sum of fuel is {}", value),
+            None => println!("Sum of fuel is too large for isize"),
+        }
+        Ok(result.to_string())
+    }
+
+    fn part2(&self, _: &str) -> Result<Self::Output, AocError> {
+        Ok("Puzzle 2 is a freebie!".to_string())
+    }
+
+    fn parse(&self, input: &str) -> Result<ParseSummary, AocError> {
+        sum_snafu_numbers(input)?;
+        Ok(ParseSummary::new().count(input.lines().count(), "numbers"))
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Default)]
+struct Snafu {
+    // Digits in the range -2..=2, least significant first. No trailing (most significant) zeroes.
+    digits: Vec<i8>
+}
+
+impl Snafu {
+    fn to_isize(&self) -> Option<isize> {
+        self.digits.iter().rev().try_fold(0_isize, |acc, digit| acc.checked_mul(5)?.checked_add(*digit as isize))
+    }
+}
+
+fn parse_snafu_digit(c: char) -> Option<i8> {
+    match c {
+        '2' => Some(2),
+        '1' => Some(1),
+        '0' => Some(0),
+        '-' => Some(-1),
+        '=' => Some(-2),
+        _ => None
+    }
+}
+
+impl FromStr for Snafu {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut digits = s.chars().rev()
+            .map(|c| parse_snafu_digit(c).ok_or(format!("Invalid SNAFU number: '{}'", c)))
+            .collect::<Result<Vec<_>, _>>()?;
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        Ok(Snafu { digits })
+    }
+}
+
+impl fmt::Display for Snafu {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.digits.is_empty() {
+            return write!(f, "0");
+        }
+
+        for digit in self.digits.iter().rev() {
+            write!(f, "{}", match digit {
+                -2 => '=',
+                -1 => '-',
+                0 => '0',
+                1 => '1',
+                _ => '2'
+            })?;
+        }
+        Ok(())
+    }
+}
+
+impl Add<&Snafu> for Snafu {
+    type Output = Snafu;
+
+    fn add(self, rhs: &Snafu) -> Self::Output {
+        // Regular long addition, except every digit needs to end up in -2..=2, so a carry can be negative too.
+        let mut digits = vec![];
+        let mut carry = 0;
+        for idx in 0..self.digits.len().max(rhs.digits.len()) {
+            let sum = self.digits.get(idx).unwrap_or(&0) + rhs.digits.get(idx).unwrap_or(&0) + carry;
+            let (digit, next_carry) = match sum {
+                3..=5 => (sum - 5, 1),
+                -5..=-3 => (sum + 5, -1),
+                _ => (sum, 0)
+            };
+            digits.push(digit);
+            carry = next_carry;
+        }
+        if carry != 0 {
+            digits.push(carry);
+        }
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+
+        Snafu { digits }
+    }
 }
 
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct SnafuError {
+    line: usize,
+    column: usize,
+    character: char
+}
+
+impl fmt::Display for SnafuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid SNAFU character '{}' on line {}, column {}", self.character, self.line, self.column)
+    }
+}
+
+impl From<SnafuError> for AocError {
+    fn from(error: SnafuError) -> Self {
+        AocError::parse_at(error.line, error.column, format!("Invalid SNAFU character '{}'", error.character))
+    }
+}
+
+fn sum_snafu_numbers(input: &str) -> Result<Snafu, SnafuError> {
+    let mut total = Snafu::default();
+
+    for (line_idx, line) in input.lines().enumerate() {
+        if let Some((column, character)) = line.chars().enumerate().find(|(_, c)| parse_snafu_digit(*c).is_none()) {
+            return Err(SnafuError { line: line_idx + 1, column: column + 1, character });
+        }
+        // We just validated all characters, so parsing cannot fail here.
+        total = total + &line.parse().unwrap();
+    }
+
+    Ok(total)
+}
+
+#[allow(unused)]
 fn decode_snafu_number(input: &str) -> Result<isize, String> {
     // SNAFU numbers are interesting. Powers of 5, and with weird options.
     // 2, 1, 0, - (-1), = (-2)
@@ -31,7 +181,8 @@ fn decode_snafu_number(input: &str) -> Result<isize, String> {
     }).collect::<Result<Vec<_>, _>>()?.iter().enumerate()
         .map(|(idx, val)| (5_isize.pow(idx as u32)) as isize * val).sum())
 }
- 
+
+#[allow(unused)]
 fn encode_snafu_number(input: isize) -> String {
     // What makes sense to do?
     // Ranges:
@@ -101,7 +252,7 @@ fn encode_snafu_number(input: isize) -> String {
 
 #[cfg(test)]
 mod tests {
-    use crate::days::day25::{decode_snafu_number, encode_snafu_number};
+    use crate::days::day25::{decode_snafu_number, encode_snafu_number, Snafu, SnafuError, sum_snafu_numbers};
 
     #[test]
     fn test_decode_snafu_number() {
@@ -124,21 +275,44 @@ mod tests {
         assert_eq!("2=-1=0", encode_snafu_number(value));
     }
     
-    const TEST_INPUT: &str = "\
-        1=-0-2\n\
-        12111\n\
-        2=0=\n\
-        21\n\
-        2=01\n\
-        111\n\
-        20012\n\
-        112\n\
-        1=-1=\n\
-        1-12\n\
-        12\n\
-        1=\n\
-        122\n\
-    ";
+    #[test]
+    fn test_snafu_parse_and_format() {
+        for (expected, input) in SNAFU_TESTS {
+            let snafu: Snafu = input.parse().unwrap();
+            assert_eq!(Some(expected), snafu.to_isize());
+            assert_eq!(input, snafu.to_string());
+        }
+        assert_eq!("0", "000".parse::<Snafu>().unwrap().to_string());
+        assert!("12a".parse::<Snafu>().is_err());
+    }
+
+    #[test]
+    fn test_snafu_add() {
+        for (left, left_str) in SNAFU_TESTS {
+            for (right, right_str) in SNAFU_TESTS {
+                let sum = left_str.parse::<Snafu>().unwrap() + &right_str.parse().unwrap();
+                assert_eq!(Some(left + right), sum.to_isize(), "{} + {}", left_str, right_str);
+            }
+        }
+        let negated = "-=".parse::<Snafu>().unwrap() + &"12".parse().unwrap();
+        assert_eq!("0", negated.to_string());
+    }
+
+    #[test]
+    fn test_snafu_overflow() {
+        let huge: Snafu = "2".repeat(40).parse().unwrap();
+        assert_eq!(None, huge.to_isize());
+        // 2 * (5^40 - 1) / 2 = 5^40 - 1
+        assert_eq!("1".to_string() + &"0".repeat(39) + "-", (huge.clone() + &huge).to_string());
+    }
+
+    #[test]
+    fn test_sum_snafu_numbers() {
+        assert_eq!(Ok("2=-1=0".to_string()), sum_snafu_numbers(TEST_INPUT).map(|s| s.to_string()));
+        assert_eq!(Err(SnafuError { line: 3, column: 2, character: '3' }), sum_snafu_numbers("1=\n12\n13\n"));
+    }
+
+    const TEST_INPUT: &str = include_str!("../../examples/day25/input.txt");
     
     const SNAFU_TESTS: [(isize, &str);15] = [
         (1, "1"),
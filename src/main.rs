@@ -1,12 +1,26 @@
+// A build with only some of the days leaves the helpers used by the other days unused.
+#![cfg_attr(not(feature = "all-days"), allow(dead_code))]
+
 extern crate core;
 
+mod bench;
 mod days;
+mod poster;
 mod util;
 
+use std::env;
 use std::env::args;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::time::{Duration, Instant};
 use days::{get_day, Day};
-use util::input::{read_input};
+use util::input::{has_input, read_input};
+use util::answer::Answer;
 use util::number::{parse_i32};
+use util::panic::catch_panic;
+use serde_json::{json, Value};
+use util::results::{RunRecord, YearSummary};
 
 fn print_usage()
 {
@@ -14,40 +28,519 @@ fn print_usage()
 Usage: cargo run <command> [<command_arg>, ...]
 
 Commands:
-    day <day number> - run the puzzles for the given day.
+    day <day number> [--format text|json] [--allow-debug] [--lenient] [--visualize [--output <file>]] [--dry-run]
+                     - run the puzzles for the given day. With json, a summary of the runs is printed as the
+                       last line. Slow days (16, 19, 23 and 24) refuse to run in a debug build, unless
+                       --allow-debug is passed. With --lenient, days 1, 4, 18 and 20 skip (and report) the
+                       input lines they cannot parse, instead of failing. With --visualize, days 14, 17 and
+                       24 write an animated GIF of their first puzzle to the output file (day<NN>.gif by default).
+                       With --dry-run, the input is only parsed, printing the parse time and what was found in it.
+    --all [--allow-debug] [--lenient] [--dry-run]
+                     - run both puzzles of every implemented day with an input, and print a table of the answers
+                       and the time every day took. Slow days are skipped in a debug build, unless --allow-debug
+                       is passed. Exits with an error when any puzzle failed. With --dry-run, every input is only
+                       parsed, to check the parsers handle them. With AOC_VERBOSE, the stats of the days follow
+                       the table, every line tagged with its day and part.
+    bench <day>|--all [--runs <n>] [--allow-debug]
+                     - run both puzzles of the day (or of every day with an input) n times, 10 by default, and print
+                       the minimum, average and maximum durations next to the latest recorded run. Benchmarks are
+                       not recorded. Slow days are skipped in a debug build, unless --allow-debug is passed. With
+                       AOC_VERBOSE, the stats of the last run of every puzzle follow the table.
+    poster [--format html|text] [--output <file>] [--allow-debug]
+                     - run every day with an input and write an overview of the year to the output file
+                       (poster.html or poster.txt by default): the answers and time of every day, a thumbnail of
+                       the final frame of the days with a visualization, and a summary of the recorded runs. Slow
+                       days are skipped in a debug build, unless --allow-debug is passed.
     add <day number> - add base files and wiring for a new day.
+    --explain <day>  - print notes on the approach, complexity and alternatives of the given day.
+    inspect <day>    - print statistics about the input of the given day, to sanity-check it before solving.
+    --list [--json]  - list the implemented days, their puzzles, whether their input is present, and the algorithms
+                       to choose from with AOC_DAY<NN>_ALGORITHM.
+    stats [--json]   - summarize the recorded runs: total runtime, slowest days, speedups and missing answers.
+    selftest [<day>] - check the solutions of the given day (or of every day with an input) that support it against a
+                       slower, more direct way to solve their input, e.g. day 17's extrapolated height against
+                       dropping every block, or day 24's fastest route replayed against the blizzards. Exits with an
+                       error when any check failed.
+    parser-repl      - interactively try out parser operations on a pasted line.
+    clean [--inputs] [--results] [--vis] [--all] [--dry-run]
+                     - remove generated files: the cache next to the inputs (--inputs), the recorded results
+                       (--results), and visualizations and posters saved under their default name in the current
+                       directory (--vis). The puzzle inputs themselves are never removed. With --dry-run, only list what
+                       would be removed.
+
+Features:
+    memtrack         - count allocations and print the memory used by every puzzle (cargo run --features memtrack).
+    day01 .. day25   - the days compiled in, all of them by default. For a quicker and smaller build of some of them,
+                       e.g. cargo run --no-default-features --features day01,day02 -- --all.
+
+Environment:
+    AOC_INPUT_DIR    - directory with the puzzle inputs and recorded results, 'resources' by default.
+    AOC_VERBOSE      - when set, days that support it print additional explanations of their solution.
+    AOC_FORMAT       - output format for statistics printed in verbose mode: 'text' (default), 'csv' or 'json'.
+    AOC_DAY<NN>_ALGORITHM
+                     - the solver for days that have several (see --list): 'parallel' (default) or 'sequential'
+                       for day 11, 'contracted' (default) or 'full' for day 16, and 'best-first' (default),
+                       'iddfs' or 'bidirectional' for day 24.
+    AOC_CACHE        - when set, day 16 caches its flow maps in a 'cache' directory next to the inputs, keyed by a
+                       hash of the input, so changes to the pairing can be tried without rebuilding them.
+    AOC_DAY06_END_MARKER
+                     - extract the message of day 6 from the start-of-message marker up to these characters.
+    AOC_DAY08_PADDING
+                     - how day 8 handles lines shorter than the others: 'reject' (default), pad with trees of
+                       height 'zero', or 'none' to leave the gaps open.
+    AOC_DAY18_SLICES - print all cross-sections of day 18's droplet across the given axis: 'x', 'y' or 'z'.
+    AOC_DAY24_ROUTE  - a route to the exit as a move string (e.g. 'vw>^', w to wait) for `selftest 24` to check,
+                       instead of the fastest one.
+    AOC_DAY<NN>_<X>  - override a puzzle constant, e.g. AOC_DAY07_DISK_SIZE, AOC_DAY11_ROUNDS_PUZZLE2, AOC_DAY15_ROW,
+                       AOC_DAY15_MAX_COORDINATE, AOC_DAY17_DROPS_PUZZLE1 or AOC_DAY23_MAX_ROUNDS. Day 11 also takes
+                       AOC_DAY11_REDUCE_WORRY and AOC_DAY11_WIDEN_WORRY (true or false) to try it without reducing or
+                       widening its worry levels.
 ");
 }
 
 fn main() {
     let a: Vec<String> = args().collect();
 
-    if a.len() < 3 {
-        print_usage();
+    match (a.get(1).map(|s| s.as_str()), a.get(2)) {
+        (Some("day"), Some(day)) => {
+            match parse_day_options(a.get(3..).unwrap_or_default()) {
+                Some(options) => run_day(day, options),
+                None => print_usage(),
+            }
+        }
+        (Some("--all") | Some("all"), _) => {
+            match parse_day_options(&a[2..]).filter(|o| !o.json && !o.visualize) {
+                Some(options) => run_all(options),
+                None => print_usage(),
+            }
+        }
+        (Some("bench"), Some(_)) => {
+            match parse_bench_options(&a[2..]) {
+                Some(options) => run_bench(options),
+                None => print_usage(),
+            }
+        }
+        (Some("poster"), _) => {
+            match parse_poster_options(&a[2..]) {
+                Some(options) => make_poster(options),
+                None => print_usage(),
+            }
+        }
+        (Some("add"), Some(day)) => {
+            add_day(day)
+        }
+        (Some("--explain") | Some("explain"), Some(day)) => {
+            explain_day(day)
+        }
+        (Some("inspect"), Some(day)) => {
+            inspect_input(day)
+        }
+        (Some("--list") | Some("list"), flag) => {
+            list_days(flag.is_some_and(|f| f == "--json"))
+        }
+        (Some("stats"), flag) => {
+            print_stats(flag.is_some_and(|f| f == "--json"))
+        }
+        (Some("selftest"), day) => {
+            selftest(day.map(|d| d.as_str()))
+        }
+        (Some("parser-repl"), _) => {
+            util::parser_repl::run()
+        }
+        (Some("clean"), _) => {
+            match parse_clean_options(&a[2..]) {
+                Some(options) => clean(options),
+                None => print_usage(),
+            }
+        }
+        _ => {
+            print_usage();
+        }
+    }
+}
+
+#[derive(Default)]
+struct DayOptions {
+    json: bool,
+    allow_debug: bool,
+    lenient: bool,
+    visualize: bool,
+    output: Option<String>,
+    dry_run: bool,
+}
+
+/// Parses the options of the day command, None if they are invalid.
+fn parse_day_options(options: &[String]) -> Option<DayOptions> {
+    let mut result = DayOptions::default();
+    let mut rest = options;
+    loop {
+        rest = match rest {
+            // An output file only makes sense for a visualization, and a dry run has no runs to visualize or summarize.
+            [] => return if result.output.is_some() && !result.visualize || result.dry_run && (result.visualize || result.json) {
+                None
+            } else {
+                Some(result)
+            },
+            [flag, format, rest @ ..] if flag == "--format" && (format == "json" || format == "text") => {
+                result.json = format == "json";
+                rest
+            }
+            [flag, rest @ ..] if flag == "--allow-debug" => {
+                result.allow_debug = true;
+                rest
+            }
+            [flag, rest @ ..] if flag == "--lenient" => {
+                result.lenient = true;
+                rest
+            }
+            [flag, rest @ ..] if flag == "--visualize" => {
+                result.visualize = true;
+                rest
+            }
+            [flag, output, rest @ ..] if flag == "--output" && result.output.is_none() => {
+                result.output = Some(output.clone());
+                rest
+            }
+            [flag, rest @ ..] if flag == "--dry-run" => {
+                result.dry_run = true;
+                rest
+            }
+            _ => return None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct CleanOptions {
+    inputs: bool,
+    results: bool,
+    vis: bool,
+    dry_run: bool,
+}
+
+/// Parses the options of the clean command, None if they are invalid or do not select anything to clean.
+fn parse_clean_options(options: &[String]) -> Option<CleanOptions> {
+    let mut result = CleanOptions::default();
+    for option in options {
+        match option.as_str() {
+            "--inputs" => result.inputs = true,
+            "--results" => result.results = true,
+            "--vis" => result.vis = true,
+            "--all" => (result.inputs, result.results, result.vis) = (true, true, true),
+            "--dry-run" => result.dry_run = true,
+            _ => return None,
+        }
+    }
+    (result.inputs || result.results || result.vis).then_some(result)
+}
+
+/// The generated files (and directories) selected by the options that currently exist.
+fn generated_artifacts(options: &CleanOptions) -> Vec<PathBuf> {
+    let mut artifacts = vec![];
+    if options.inputs {
+        artifacts.push(util::results::cache_dir());
+    }
+    if options.results {
+        artifacts.push(util::results::results_path());
+    }
+    let mut artifacts: Vec<_> = artifacts.into_iter().map(PathBuf::from).filter(|p| p.exists()).collect();
+    if options.vis {
+        // Only the default names used by `--visualize`, an explicit `--output` file is the user's to keep.
+        let mut visualizations: Vec<_> = (1..=25).map(default_visualization).chain([default_poster(true), default_poster(false)])
+            .map(PathBuf::from).filter(|p| p.exists()).collect();
+        artifacts.append(&mut visualizations);
+    }
+    artifacts
+}
+
+fn clean(options: CleanOptions)
+{
+    let artifacts = generated_artifacts(&options);
+    if artifacts.is_empty() {
+        println!("Nothing to clean");
+    }
+    for artifact in artifacts {
+        if options.dry_run {
+            println!("Would remove {}", artifact.display());
+            continue;
+        }
+        let removed = if artifact.is_dir() { fs::remove_dir_all(&artifact) } else { fs::remove_file(&artifact) };
+        match removed {
+            Ok(_) => println!("Removed {}", artifact.display()),
+            Err(e) => eprintln!("Could not remove {}: {}", artifact.display(), e),
+        }
+    }
+}
+
+struct BenchOptions {
+    /// A single day, or None for all days.
+    day: Option<i32>,
+    runs: usize,
+    allow_debug: bool,
+}
+
+/// Parses the options of the bench command, None if they are invalid.
+fn parse_bench_options(options: &[String]) -> Option<BenchOptions> {
+    let (day, mut rest) = match options {
+        [all, rest @ ..] if all == "--all" => (None, rest),
+        [day, rest @ ..] => (Some(parse_i32(day).ok()?), rest),
+        [] => return None,
+    };
+    let mut result = BenchOptions { day, runs: 10, allow_debug: false };
+    loop {
+        rest = match rest {
+            [] => return Some(result),
+            [flag, runs, rest @ ..] if flag == "--runs" => {
+                result.runs = runs.parse().ok().filter(|r| *r > 0)?;
+                rest
+            }
+            [flag, rest @ ..] if flag == "--allow-debug" => {
+                result.allow_debug = true;
+                rest
+            }
+            _ => return None,
+        }
+    }
+}
+
+fn run_bench(options: BenchOptions)
+{
+    let day_nums: Vec<_> = match options.day {
+        Some(day_num) => vec![day_num],
+        None => (1..=25).filter(|d| get_day(*d).is_ok() && has_input(*d)).collect(),
+    };
+
+    let collector = util::stats::StatsCollector::new();
+    util::stats::set_collector(Some(collector.clone()));
+    let mut benchmarks = vec![];
+    for day_num in day_nums {
+        let result = get_day(day_num).and_then(|day| read_input(day_num).map(|input| (input, day)).map_err(String::from));
+        let (input, day) = match result {
+            Ok(result) => result,
+            Err(err) => {
+                eprintln!("{}", err);
+                continue;
+            }
+        };
+        if !check_build_profile(day_num, options.allow_debug) {
+            continue;
+        }
+
+        for (part, puzzle) in [(1, day.puzzle1), (2, day.puzzle2)] {
+            eprintln!("Benchmarking day {} puzzle {}...", day_num, part);
+            benchmarks.push(bench::Benchmark::run(day_num, part, puzzle, &input, options.runs));
+        }
+    }
+
+    let records = util::results::load().unwrap_or_else(|err| {
+        eprintln!("Could not read the recorded results: {}", err);
+        vec![]
+    });
+    util::stats::set_collector(None);
+    print!("{}", bench::format_table(&benchmarks, &records));
+    collector.print();
+    if benchmarks.iter().any(|b| b.answer.is_err()) {
+        process::exit(1);
+    }
+}
+
+fn default_visualization(day_num: i32) -> String {
+    format!("day{:02}.gif", day_num)
+}
+
+struct PosterOptions {
+    html: bool,
+    output: Option<String>,
+    allow_debug: bool,
+}
+
+/// Parses the options of the poster command, None if they are invalid.
+fn parse_poster_options(options: &[String]) -> Option<PosterOptions> {
+    let mut result = PosterOptions { html: true, output: None, allow_debug: false };
+    let mut rest = options;
+    loop {
+        rest = match rest {
+            [] => return Some(result),
+            [flag, format, rest @ ..] if flag == "--format" && (format == "html" || format == "text") => {
+                result.html = format == "html";
+                rest
+            }
+            [flag, output, rest @ ..] if flag == "--output" && result.output.is_none() => {
+                result.output = Some(output.clone());
+                rest
+            }
+            [flag, rest @ ..] if flag == "--allow-debug" => {
+                result.allow_debug = true;
+                rest
+            }
+            _ => return None,
+        }
+    }
+}
+
+fn default_poster(html: bool) -> String {
+    (if html { "poster.html" } else { "poster.txt" }).to_string()
+}
+
+fn make_poster(options: PosterOptions)
+{
+    let mut days = vec![];
+    for day_num in 1..=25 {
+        let Ok(day) = get_day(day_num) else { continue };
+        let title = poster::PosterDay::title_from(day.describe);
+        let skipped = |note| poster::PosterDay { day: day_num, title: title.clone(), answers: vec![], elapsed: None, note: Some(note), visualization: None };
+        if cfg!(debug_assertions) && days::SLOW_DAYS.contains(&day_num) && !options.allow_debug {
+            days.push(skipped("skipped in a debug build"));
+            continue;
+        }
+        let Ok(input) = read_input(day_num) else {
+            days.push(skipped("input missing"));
+            continue;
+        };
+
+        println!("== Day {} ==", day_num);
+        util::vis::set_capture(true);
+        let runs = run_puzzles(day_num, &day, &input);
+        days.push(poster::PosterDay {
+            day: day_num,
+            title,
+            answers: runs.iter().map(|(_, answer)| answer.as_ref().map(|a| a.to_string())).collect(),
+            elapsed: Some(runs.iter().map(|(run, _)| run.duration).sum()),
+            note: None,
+            visualization: util::vis::take_captured(),
+        });
+    }
+    util::vis::set_capture(false);
+
+    let records = util::results::load().unwrap_or_else(|err| {
+        eprintln!("Could not read the recorded results: {}", err);
+        vec![]
+    });
+    let day_nums: Vec<_> = (1..=25).filter(|d| get_day(*d).is_ok()).collect();
+    let poster = poster::Poster { days, summary: YearSummary::create(&records, &day_nums) };
+    let output = options.output.unwrap_or(default_poster(options.html));
+    let contents = if options.html { poster.render_html() } else { Ok(poster.render_text()) };
+    let written = contents.and_then(|contents| fs::write(&output, contents).map_err(|e| format!("Could not write {}: {}", output, e)));
+    match written {
+        Ok(_) => println!("Saved the poster to {}", output),
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    }
+    if poster.days.iter().any(|d| d.answers.iter().any(|a| a.is_none())) {
+        process::exit(1);
+    }
+}
+
+/// Warns about running a slow day in a debug build, as it would seem to hang. Returns whether to run it anyway.
+fn check_build_profile(day_num: i32, allow_debug: bool) -> bool {
+    if !cfg!(debug_assertions) || !days::SLOW_DAYS.contains(&day_num) {
+        return true;
+    }
+
+    eprintln!("!!! Day {} is slow in a debug build, use `cargo run --release -- day {}` instead !!!", day_num, day_num);
+    if allow_debug {
+        eprintln!("Running it anyway, as --allow-debug was passed.");
+    } else {
+        eprintln!("Pass --allow-debug to run it in the debug build anyway.");
+    }
+    allow_debug
+}
+
+fn list_days(as_json: bool)
+{
+    let days: Vec<_> = (1..=25).filter_map(|d| get_day(d).ok().map(|day| (d, day))).collect();
+    if as_json {
+        let days: Vec<_> = days.iter().map(|(day_num, day)| json!({
+            "day": day_num,
+            "describe": day.describe,
+            "input": has_input(*day_num),
+            "algorithms": day.algorithms,
+        })).collect();
+        println!("{}", Value::Array(days));
         return;
     }
 
-    match a[1].as_str() {
-        "day" => {
-            run_day(&a[2])
+    for (day_num, day) in days {
+        let input = if has_input(day_num) { "input present" } else { "input missing" };
+        println!("Day {:>2} [{}] {}", day_num, input, day.describe);
+        if !day.algorithms.is_empty() {
+            println!("       algorithms: {}", day.algorithms.join(", "));
         }
-        "add" => {
-            add_day(&a[2])
+    }
+}
+
+fn explain_day(day_num: &str)
+{
+    match parse_i32(day_num).map_err(String::from).and_then(get_day) {
+        Ok(day) => match day.explain {
+            Some(explain) => {
+                println!("{}\n", day.describe);
+                print!("{}", explain());
+            }
+            None => println!("{}\n\nNo notes for this day yet.", day.describe),
         }
-        _ => {
-            print_usage();
+        Err(err) => {
+            eprintln!("{}", err);
+        }
+    }
+}
+
+fn inspect_input(day_num: &str)
+{
+    match parse_i32(day_num).and_then(read_input) {
+        Ok(input) => print!("{}", util::inspect::InputStats::create(&input)),
+        Err(err) => eprintln!("{}", err),
+    }
+}
+
+fn print_stats(as_json: bool)
+{
+    match util::results::load() {
+        Ok(records) => {
+            let days: Vec<_> = (1..=25).filter(|d| get_day(*d).is_ok()).collect();
+            let summary = YearSummary::create(&records, &days);
+            if as_json {
+                println!("{}", summary.to_json());
+            } else {
+                print!("{}", summary);
+            }
+        }
+        Err(err) => {
+            eprintln!("Could not read the recorded results: {}", err);
         }
     }
 }
 
-fn run_day(day_num: &str)
+fn run_day(day_num: &str, options: DayOptions)
 {
-    let result: Result<(String, Day), String> = parse_i32(day_num)
-        .and_then(|d| get_day(d).and_then(|day| read_input(d).and_then(|input| Ok((input, day)))));
+    let result: Result<(i32, String, Day), String> = parse_i32(day_num).map_err(String::from)
+        .and_then(|d| get_day(d).and_then(|day| read_input(d).map(|input| (d, input, day)).map_err(String::from)));
     match result {
-        Ok((input, day)) => {
-            (day.puzzle1)(&input);
-            (day.puzzle2)(&input);
+        Ok((day_num, input, day)) => {
+            util::input::set_lenient(options.lenient);
+            // Parsing is quick, even for the slow days in a debug build.
+            if options.dry_run {
+                if !dry_run(day_num, &day, &input) {
+                    process::exit(1);
+                }
+                return;
+            }
+            if !check_build_profile(day_num, options.allow_debug) {
+                return;
+            }
+            if options.visualize {
+                util::vis::set_output(Some(options.output.clone().unwrap_or(default_visualization(day_num))));
+            }
+
+            let runs = run_puzzles(day_num, &day, &input);
+            if options.visualize && !util::vis::was_saved() {
+                eprintln!("Day {} has no visualization", day_num);
+            }
+            if options.json {
+                println!("{}", util::results::runs_to_json(day_num, &runs));
+            }
         }
         Err(err) => {
             eprintln!("{}", err);
@@ -55,6 +548,184 @@ fn run_day(day_num: &str)
     }
 }
 
+/// Runs both puzzles of the day, printing and recording the results.
+fn run_puzzles(day_num: i32, day: &Day, input: &str) -> Vec<(RunRecord, Option<Answer>)> {
+    let mut runs = vec![];
+    // A panic in one puzzle should not prevent the other one from running.
+    for (part, puzzle) in [(1, day.puzzle1), (2, day.puzzle2)] {
+        let memory = util::memtrack::track();
+        let start = Instant::now();
+        let result = catch_panic(|| puzzle(input));
+        let run = RunRecord::now(day_num, part, start.elapsed(), matches!(result, Ok(Ok(_))));
+        let answer = match result {
+            Ok(Ok(answer)) => {
+                print_answer(part, &answer);
+                Some(answer)
+            }
+            Ok(Err(e)) => {
+                eprintln!("Puzzle {} failed: {}", part, e);
+                None
+            }
+            Err(e) => {
+                eprintln!("Puzzle {} failed: {}", part, e);
+                if env::var("AOC_VERBOSE").is_ok() {
+                    eprintln!("{}", e.backtrace);
+                }
+                None
+            }
+        };
+        if let Some(memory) = memory {
+            println!("Puzzle {} memory: {}", part, memory.finish());
+        }
+        if let Err(e) = util::results::record(&run) {
+            eprintln!("Could not record the result of puzzle {}: {}", part, e);
+        }
+        runs.push((run, answer));
+    }
+    runs
+}
+
+/// A row of the table printed by `run_all`: the answers of a day, or why it did not run.
+struct DayResult {
+    day: i32,
+    answers: Vec<String>,
+    elapsed: Option<Duration>,
+    note: Option<&'static str>,
+}
+
+fn run_all(options: DayOptions)
+{
+    util::input::set_lenient(options.lenient);
+    if options.dry_run {
+        return dry_run_all();
+    }
+
+    // Stats of the days (with AOC_VERBOSE) are printed together after the table, tagged with their day and part.
+    let collector = util::stats::StatsCollector::new();
+    util::stats::set_collector(Some(collector.clone()));
+    let mut results = vec![];
+    for day_num in 1..=25 {
+        let Ok(day) = get_day(day_num) else { continue };
+        let skipped = |note| DayResult { day: day_num, answers: vec!["-".to_string(); 2], elapsed: None, note: Some(note) };
+        if cfg!(debug_assertions) && days::SLOW_DAYS.contains(&day_num) && !options.allow_debug {
+            results.push(skipped("skipped in a debug build"));
+            continue;
+        }
+        let input = match read_input(day_num) {
+            Ok(input) => input,
+            Err(_) => {
+                results.push(skipped("input missing"));
+                continue;
+            }
+        };
+
+        println!("== Day {} ==", day_num);
+        let start = Instant::now();
+        let runs = run_puzzles(day_num, &day, &input);
+        let answers = runs.iter().map(|(_, answer)| match answer.as_ref().map(|a| a.to_string()) {
+            Some(text) if text.contains('\n') => format!("({} lines)", text.lines().count()),
+            Some(text) => text,
+            None => "FAILED".to_string(),
+        }).collect();
+        results.push(DayResult { day: day_num, answers, elapsed: Some(start.elapsed()), note: None });
+    }
+
+    util::stats::set_collector(None);
+
+    println!();
+    print!("{}", format_results(&results));
+    collector.print();
+    if results.iter().any(|r| r.answers.iter().any(|a| a == "FAILED")) {
+        process::exit(1);
+    }
+}
+
+fn format_results(results: &[DayResult]) -> String {
+    let width = |part: usize| results.iter().map(|r| r.answers[part].len()).chain([8]).max().unwrap_or(0);
+    let (width1, width2) = (width(0), width(1));
+
+    let mut table = format!("{:>5} | {:<width1$} | {:<width2$} | {:>10}\n", "Day", "Puzzle 1", "Puzzle 2", "Time (ms)");
+    for result in results {
+        let time = match (result.elapsed, result.note) {
+            (Some(elapsed), _) => format!("{:>10.3}", elapsed.as_secs_f64() * 1000.0),
+            (None, Some(note)) => note.to_string(),
+            (None, None) => "-".to_string(),
+        };
+        table += &format!("{:>5} | {:<width1$} | {:<width2$} | {}\n", result.day, result.answers[0], result.answers[1], time);
+    }
+    let total: Duration = results.iter().filter_map(|r| r.elapsed).sum();
+    table += &format!("Total: {:.3}ms\n", total.as_secs_f64() * 1000.0);
+    table
+}
+
+/// Only parses the input of the day, printing what the parser found or why it failed. Returns whether it parsed.
+fn dry_run(day_num: i32, day: &Day, input: &str) -> bool {
+    let start = Instant::now();
+    let result = catch_panic(|| (day.parse)(input)).map_err(|e| e.to_string()).and_then(|r| r.map_err(String::from));
+    let millis = start.elapsed().as_secs_f64() * 1000.0;
+    match &result {
+        Ok(summary) => println!("Day {}: parsed in {:.3}ms, {}", day_num, millis, summary),
+        Err(e) => println!("Day {}: failed to parse after {:.3}ms: {}", day_num, millis, e),
+    }
+    result.is_ok()
+}
+
+/// Runs the self-checks of the day, or of every day that has one and an input.
+fn selftest(day_num: Option<&str>)
+{
+    let days: Vec<i32> = match day_num.map(parse_i32) {
+        Some(Ok(day)) => vec![day],
+        Some(Err(err)) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+        None => (1..=25).filter(|d| has_input(*d) && get_day(*d).is_ok_and(|day| day.selftest.is_some())).collect(),
+    };
+
+    let mut failed = false;
+    for day_num in days {
+        let result = get_day(day_num)
+            .and_then(|day| day.selftest.ok_or(format!("Day {} has no self-check", day_num)))
+            .and_then(|selftest| read_input(day_num).map(|input| (selftest, input)).map_err(String::from))
+            .and_then(|(selftest, input)| catch_panic(|| selftest(&input)).map_err(|e| e.to_string()).and_then(|r| r.map_err(String::from)));
+        match result {
+            Ok(checked) => println!("Day {}: OK, {}", day_num, checked),
+            Err(e) => {
+                println!("Day {}: FAILED, {}", day_num, e);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
+
+fn dry_run_all()
+{
+    let mut failed = false;
+    for day_num in 1..=25 {
+        let Ok(day) = get_day(day_num) else { continue };
+        match read_input(day_num) {
+            Ok(input) => failed |= !dry_run(day_num, &day, &input),
+            Err(_) => println!("Day {}: input missing", day_num),
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
+
+/// Prints the answer of a puzzle, on lines of its own when it is rendered text (like day 10's screen).
+fn print_answer(part: usize, answer: &Answer) {
+    let answer = answer.to_string();
+    if answer.contains('\n') {
+        println!("Puzzle {}:\n{}", part, answer);
+    } else {
+        println!("Puzzle {}: {}", part, answer);
+    }
+}
+
 fn add_day(input: &str)
 {
     // This is going to be fun. Write code to modify the running code! Woohoo!
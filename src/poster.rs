@@ -0,0 +1,198 @@
+use std::time::Duration;
+use crate::util::results::YearSummary;
+use crate::util::vis::Animation;
+
+/// The largest thumbnail of a visualization in pixels, a pixel is two characters wide on the text poster.
+const TEXT_THUMBNAIL: (usize, usize) = (32, 24);
+const HTML_THUMBNAIL: (usize, usize) = (160, 160);
+
+/// A day on the poster: the answers and time of a fresh run, and the final frame of its visualization if it has one.
+pub struct PosterDay {
+    pub day: i32,
+    /// The name of the puzzle, the part of the description of the day before the ':'.
+    pub title: String,
+    /// The answers of both puzzles, None for the puzzles that failed. Empty when the day did not run.
+    pub answers: Vec<Option<String>>,
+    pub elapsed: Option<Duration>,
+    /// Why the day did not run.
+    pub note: Option<&'static str>,
+    pub visualization: Option<Animation>,
+}
+
+impl PosterDay {
+    pub fn title_from(describe: &str) -> String {
+        describe.split(':').next().unwrap_or(describe).trim().to_string()
+    }
+
+    fn heading(&self) -> String {
+        format!("Day {}: {}", self.day, self.title)
+    }
+
+    /// The answers as (part, answer) pairs, with "FAILED" for the puzzles that failed.
+    fn parts(&self) -> Vec<(usize, String)> {
+        self.answers.iter().enumerate()
+            .map(|(index, answer)| (index + 1, answer.clone().unwrap_or("FAILED".to_string())))
+            .collect()
+    }
+
+    fn time(&self) -> String {
+        match (self.elapsed, self.note) {
+            (Some(elapsed), _) => format!("{:.3}ms", elapsed.as_secs_f64() * 1000.0),
+            (None, Some(note)) => note.to_string(),
+            (None, None) => "-".to_string(),
+        }
+    }
+}
+
+/// An overview of the year: every day with its answers, time and visualization, and a summary of the recorded runs.
+pub struct Poster {
+    pub days: Vec<PosterDay>,
+    pub summary: YearSummary,
+}
+
+impl Poster {
+    pub fn render_text(&self) -> String {
+        let mut text = "Advent of Code 2022\n===================\n".to_string();
+        for day in &self.days {
+            text += &format!("\n{}\n", day.heading());
+            for (part, answer) in day.parts() {
+                // Multi-line answers (day 10's screen) start on their own line.
+                if answer.contains('\n') {
+                    text += &format!("  Puzzle {}:\n{}", part, indent(&answer, 4));
+                } else {
+                    text += &format!("  Puzzle {}: {}\n", part, answer);
+                }
+            }
+            text += &format!("  Time: {}\n", day.time());
+            if let Some(thumbnail) = day.visualization.as_ref().and_then(|v| v.thumbnail(TEXT_THUMBNAIL.0, TEXT_THUMBNAIL.1)) {
+                text += &indent(&thumbnail.render_text(), 2);
+            }
+        }
+        text += &format!("\nRecorded runs\n-------------\n{}", self.summary);
+        text
+    }
+
+    pub fn render_html(&self) -> Result<String, String> {
+        let mut html = "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Advent of Code 2022</title>\n<style>\n\
+            body { background: #0f0f23; color: #cccccc; font-family: monospace; margin: 2em; }\n\
+            h1, h2 { color: #ffff66; }\n\
+            .days { display: grid; grid-template-columns: repeat(auto-fill, minmax(22em, 1fr)); gap: 1em; }\n\
+            .day { border: 1px solid #333340; padding: 0 1em 1em; }\n\
+            .day img { display: block; max-width: 100%; image-rendering: pixelated; }\n\
+            pre { margin: 0; color: #00cc00; }\n\
+            </style>\n</head>\n<body>\n<h1>Advent of Code 2022</h1>\n<div class=\"days\">\n".to_string();
+        for day in &self.days {
+            html += &format!("<div class=\"day\">\n<h2>{}</h2>\n", escape_html(&day.heading()));
+            if let Some(mut thumbnail) = day.visualization.as_ref().and_then(|v| v.thumbnail(HTML_THUMBNAIL.0, HTML_THUMBNAIL.1)) {
+                thumbnail.scale = 2;
+                html += &format!("<img src=\"data:image/gif;base64,{}\" alt=\"Visualization of day {}\">\n", base64(&thumbnail.encode_gif()?), day.day);
+            }
+            html += "<dl>\n";
+            for (part, answer) in day.parts() {
+                html += &format!("<dt>Puzzle {}</dt><dd><pre>{}</pre></dd>\n", part, escape_html(&answer));
+            }
+            html += &format!("<dt>Time</dt><dd>{}</dd>\n</dl>\n</div>\n", escape_html(&day.time()));
+        }
+        html += &format!("</div>\n<h2>Recorded runs</h2>\n<pre>{}</pre>\n</body>\n</html>\n", escape_html(&self.summary.to_string()));
+        Ok(html)
+    }
+}
+
+fn indent(text: &str, by: usize) -> String {
+    text.lines().map(|line| format!("{}{}\n", " ".repeat(by), line)).collect()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Standard base64 with padding, to embed images in the HTML poster.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+        let value = chunk.iter().enumerate().fold(0u32, |acc, (index, byte)| acc | (*byte as u32) << (16 - 8 * index));
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(ALPHABET[(value >> (18 - 6 * index) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use crate::poster::{base64, Poster, PosterDay};
+    use crate::util::results::YearSummary;
+    use crate::util::vis::{Animation, Frame};
+
+    fn poster() -> Poster {
+        let mut frame = Frame::new(3, 2);
+        frame.set(1, 0, 1);
+        let mut animation = Animation::new(&[[0, 0, 0], [255, 255, 255]]);
+        animation.push(frame);
+
+        Poster {
+            days: vec![
+                PosterDay { day: 10, title: PosterDay::title_from("Cathode-Ray Tube: render the CRT"), answers: vec![Some("13140".to_string()), Some("#.\n.#".to_string())],
+                            elapsed: Some(Duration::from_micros(1500)), note: None, visualization: None },
+                PosterDay { day: 14, title: "Regolith Reservoir".to_string(), answers: vec![Some("24".to_string()), None],
+                            elapsed: Some(Duration::from_millis(2)), note: None, visualization: Some(animation) },
+                PosterDay { day: 16, title: "Proboscidea Volcanium".to_string(), answers: vec![], elapsed: None, note: Some("skipped in a debug build"), visualization: None },
+            ],
+            summary: YearSummary::create(&[], &[10]),
+        }
+    }
+
+    #[test]
+    fn test_render_text() {
+        let text = poster().render_text();
+        assert!(text.starts_with("Advent of Code 2022\n===================\n
+Day 10: Cathode-Ray Tube
+  Puzzle 1: 13140
+  Puzzle 2:
+    #.
+    .#
+  Time: 1.500ms
+
+Day 14: Regolith Reservoir
+  Puzzle 1: 24
+  Puzzle 2: FAILED
+  Time: 2.000ms
+    @@  \n        \n
+Day 16: Proboscidea Volcanium
+  Time: skipped in a debug build
+
+Recorded runs
+-------------
+Total runtime: 0.000ms
+"), "{}", text);
+        assert!(text.ends_with("Missing answers: 10.1, 10.2\n"), "{}", text);
+    }
+
+    #[test]
+    fn test_render_html() {
+        let html = poster().render_html().unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<h2>Day 10: Cathode-Ray Tube</h2>"));
+        assert!(html.contains("<dt>Puzzle 2</dt><dd><pre>#.\n.#</pre></dd>"));
+        assert!(html.contains("<dt>Puzzle 2</dt><dd><pre>FAILED</pre></dd>"));
+        assert!(html.contains("<dt>Time</dt><dd>skipped in a debug build</dd>"));
+        assert_eq!(1, html.matches("<img src=\"data:image/gif;base64,R0lGODlh").count());
+        assert!(html.ends_with("Missing answers: 10.1, 10.2\n</pre>\n</body>\n</html>\n"));
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!("", base64(b""));
+        assert_eq!("Zg==", base64(b"f"));
+        assert_eq!("Zm8=", base64(b"fo"));
+        assert_eq!("Zm9v", base64(b"foo"));
+        assert_eq!("Zm9vYmFy", base64(b"foobar"));
+        assert_eq!("R0lGODlh", base64(b"GIF89a"));
+    }
+}
@@ -3,4 +3,24 @@ pub mod number;
 pub mod geometry;
 pub mod create_day;
 pub mod collection;
-pub mod parser;
\ No newline at end of file
+pub mod parser;
+pub mod stats;
+pub mod panic;
+pub mod range;
+pub mod simd;
+pub mod results;
+pub mod parser_repl;
+pub mod search;
+pub mod vm;
+pub mod config;
+pub mod answer;
+pub mod memtrack;
+pub mod inspect;
+pub mod buildinfo;
+pub mod moves;
+pub mod vis;
+pub mod error;
+pub mod hash;
+
+#[cfg(test)]
+pub mod snaptest;
\ No newline at end of file
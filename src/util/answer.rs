@@ -0,0 +1,100 @@
+// Allow dead_code since this is a util file copied across years. Later in the AoC we might use everything, or not.
+#![allow(dead_code)]
+
+use std::fmt;
+
+/// The answer of a puzzle; either a number, or text like the letters rendered by day 10.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Answer {
+    Number(i128),
+    Text(String),
+}
+
+/// Unifies line endings, strips trailing whitespace from every line, and drops leading and trailing blank lines.
+fn normalize_text(text: &str) -> String {
+    let lines: Vec<_> = text.replace("\r\n", "\n").replace('\r', "\n")
+        .split('\n')
+        .map(|line| line.trim_end().to_string())
+        .collect();
+    lines.join("\n").trim_matches('\n').to_string()
+}
+
+impl Answer {
+    /// Whether this answer matches the expected answer as written down, e.g. in an examples `partN.expected` file.
+    /// Numbers match when the expected text (ignoring surrounding whitespace) parses to the same number; text
+    /// matches when both are the same after normalizing line endings and trailing whitespace.
+    pub fn matches(&self, expected: &str) -> bool {
+        match self {
+            Answer::Number(value) => expected.trim().parse::<i128>().is_ok_and(|e| e == *value),
+            Answer::Text(text) => normalize_text(text) == normalize_text(expected),
+        }
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Number(value) => write!(f, "{}", value),
+            Answer::Text(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+macro_rules! answer_from_number {
+    ($($t:ty),*) => {
+        $(impl From<$t> for Answer {
+            fn from(value: $t) -> Self {
+                Answer::Number(value as i128)
+            }
+        })*
+    };
+}
+
+answer_from_number!(i32, i64, i128, isize, u32, u64, usize);
+
+impl From<String> for Answer {
+    fn from(value: String) -> Self {
+        Answer::Text(value)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(value: &str) -> Self {
+        Answer::Text(value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::answer::Answer;
+
+    #[test]
+    fn test_number_matches() {
+        assert!(Answer::from(13140).matches("13140"));
+        assert!(Answer::from(13140usize).matches("13140\n"));
+        assert!(Answer::from(-12isize).matches("  -12\r\n"));
+        assert!(Answer::from(1_595_988_538_691u64).matches("1595988538691"));
+        assert!(Answer::from(i128::MAX).matches("170141183460469231731687303715884105727"));
+        assert!(!Answer::from(13140).matches("13141"));
+        assert!(!Answer::from(13140).matches("13140 13140"));
+        assert!(!Answer::from(0).matches(""));
+    }
+
+    #[test]
+    fn test_text_matches() {
+        let rendered = "##..##..  \n###...###.\n";
+        assert!(Answer::from(rendered).matches("##..##..\n###...###."));
+        assert!(Answer::from(rendered).matches("##..##..\r\n###...###.\r\n\r\n"));
+        assert!(Answer::from(rendered.to_string()).matches("\n##..##..\n###...###.   \n"));
+        assert!(!Answer::from(rendered).matches("##..##..\n###...####"));
+        // Leading whitespace on a line is significant for rendered text.
+        assert!(!Answer::from(rendered).matches(" ##..##..\n###...###."));
+        assert!(Answer::from("CMZ").matches("CMZ\n"));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!("42", Answer::from(42).to_string());
+        assert_eq!("MCD", Answer::from("MCD").to_string());
+    }
+}
@@ -0,0 +1,27 @@
+use serde_json::{json, Value};
+
+/// Identifies the code a binary was built from, to attach to recorded runs and structured output. The values
+/// are determined by build.rs.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// FNV-1a hash (see `hash::fnv1a`) over all files in src/, changes with every edit, committed or not.
+pub const SOURCE_HASH: &str = env!("AOC_SOURCE_HASH");
+/// Short hash of the git commit checked out during the build, or "unknown" outside a git checkout.
+pub const GIT_REVISION: &str = env!("AOC_GIT_REVISION");
+
+pub fn to_json() -> Value {
+    json!({ "version": VERSION, "source_hash": SOURCE_HASH, "git_revision": GIT_REVISION })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::buildinfo::{GIT_REVISION, SOURCE_HASH, to_json, VERSION};
+
+    #[test]
+    fn test_build_info() {
+        assert_eq!(16, SOURCE_HASH.len());
+        assert!(SOURCE_HASH.chars().all(|c| c.is_ascii_hexdigit()));
+        assert!(!GIT_REVISION.is_empty());
+        assert_eq!(VERSION, to_json()["version"]);
+        assert_eq!(SOURCE_HASH, to_json()["source_hash"]);
+    }
+}
@@ -2,6 +2,12 @@ pub trait CollectionExtension<T> {
     fn deduplicate(&self) -> Self;
     fn union(&self, other: &Self) -> Self;
     fn push_all(&mut self, other: &Self);
+    /// Groups the items by the given key, with the groups (and the items in them) in the order they were first seen.
+    #[allow(unused)]
+    fn group_by<K, F>(&self, key_fn: F) -> Vec<(K, Vec<T>)> where K: Eq, F: Fn(&T) -> K;
+    /// Splits into segments at the items matching the predicate (e.g. blank lines). The separators themselves are
+    /// dropped, as are empty segments (from leading, trailing, or consecutive separators).
+    fn split_when<F>(&self, is_separator: F) -> Vec<Self> where F: Fn(&T) -> bool, Self: Sized;
 }
 
 impl<T> CollectionExtension<T> for Vec<T> where T: Clone + Eq {
@@ -22,6 +28,25 @@ impl<T> CollectionExtension<T> for Vec<T> where T: Clone + Eq {
             self.push(value.clone());
         }
     }
+
+    fn group_by<K, F>(&self, key_fn: F) -> Vec<(K, Vec<T>)> where K: Eq, F: Fn(&T) -> K {
+        let mut groups: Vec<(K, Vec<T>)> = vec![];
+        for item in self {
+            let key = key_fn(item);
+            match groups.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, items)) => items.push(item.clone()),
+                None => groups.push((key, vec![item.clone()]))
+            }
+        }
+        groups
+    }
+
+    fn split_when<F>(&self, is_separator: F) -> Vec<Self> where F: Fn(&T) -> bool {
+        self.split(|item| is_separator(item))
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| segment.to_vec())
+            .collect()
+    }
 }
 
 pub trait VecToString {
@@ -32,4 +57,28 @@ impl<T> VecToString for Vec<T> where T : ToString {
     fn to_string(&self) -> Vec<String> {
         self.iter().map(|s| s.to_string()).collect()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::collection::CollectionExtension;
+
+    #[test]
+    fn test_group_by() {
+        let words = vec!["banana", "apple", "blueberry", "cherry", "avocado"];
+        assert_eq!(vec![
+            ('b', vec!["banana", "blueberry"]),
+            ('a', vec!["apple", "avocado"]),
+            ('c', vec!["cherry"]),
+        ], words.group_by(|w| w.chars().next().unwrap()));
+        assert!(Vec::<usize>::new().group_by(|v| v % 2).is_empty());
+    }
+
+    #[test]
+    fn test_split_when() {
+        let lines = vec!["1", "2", "", "3", "", "", "4", ""];
+        assert_eq!(vec![vec!["1", "2"], vec!["3"], vec!["4"]], lines.split_when(|l| l.is_empty()));
+        assert_eq!(vec![vec![1, 2, 3]], vec![1, 2, 3].split_when(|v| *v == 0));
+        assert!(vec![0, 0].split_when(|v| *v == 0).is_empty());
+    }
+}
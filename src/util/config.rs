@@ -0,0 +1,296 @@
+// Allow dead_code since this is a util file copied across years. Later in the AoC we might use everything, or not.
+#![allow(dead_code)]
+
+use std::env;
+use std::fmt::Display;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+fn env_var(day: u8, name: &str) -> String {
+    format!("AOC_DAY{:02}_{}", day, name.to_uppercase())
+}
+
+/// A tunable number of a day's puzzle (sizes, rounds, rows to look at, ...) with the value used by the puzzle as
+/// default. It can be overridden without editing the source by setting `AOC_DAY<NN>_<NAME>`, for example
+/// `AOC_DAY07_DISK_SIZE=100000000`. Underscores in the value are ignored, so `100_000_000` works as well.
+pub struct Setting<T: 'static> {
+    pub day: u8,
+    pub name: &'static str,
+    pub default: T,
+}
+
+impl<T> Setting<T> where T: FromStr + Display + Copy {
+    pub const fn new(day: u8, name: &'static str, default: T) -> Self {
+        Setting { day, name, default }
+    }
+
+    pub fn env_var(&self) -> String {
+        env_var(self.day, self.name)
+    }
+
+    /// The value of this setting, taking an override from the environment into account.
+    pub fn get(&self) -> Result<T, String> {
+        self.get_or(self.default)
+    }
+
+    /// Like `get`, but with `fallback` instead of the default when there is no override, for puzzles that
+    /// derive the value from their input.
+    pub fn get_or(&self, fallback: T) -> Result<T, String> {
+        self.resolve(env::var(self.env_var()).ok().as_deref(), fallback)
+    }
+
+    fn resolve(&self, value: Option<&str>, fallback: T) -> Result<T, String> {
+        match value {
+            None => Ok(fallback),
+            Some(value) => value.replace('_', "").trim().parse::<T>()
+                .map_err(|_| format!("Invalid value '{}' for {} (default {})", value, self.env_var(), self.default))
+        }
+    }
+}
+
+/// A setting without a default, for optional behaviour such as extra output: None unless `AOC_DAY<NN>_<NAME>` is
+/// set. The value is used as it is, e.g. `AOC_DAY18_SLICES=z`.
+pub struct OptionalSetting<T: 'static> {
+    pub day: u8,
+    pub name: &'static str,
+    value: PhantomData<T>,
+}
+
+impl<T> OptionalSetting<T> where T: FromStr, T::Err: Display {
+    pub const fn new(day: u8, name: &'static str) -> Self {
+        OptionalSetting { day, name, value: PhantomData }
+    }
+
+    pub fn env_var(&self) -> String {
+        env_var(self.day, self.name)
+    }
+
+    pub fn get(&self) -> Result<Option<T>, String> {
+        self.resolve(env::var(self.env_var()).ok().as_deref())
+    }
+
+    fn resolve(&self, value: Option<&str>) -> Result<Option<T>, String> {
+        value.map(|value| value.parse::<T>().map_err(|e| format!("Invalid value '{}' for {}: {}", value, self.env_var(), e)))
+            .transpose()
+    }
+}
+
+/// A choice between named alternatives of a day's puzzle, e.g. its solver, overridden like a `Setting` with
+/// `AOC_DAY<NN>_<NAME>`. The first option is the default.
+pub struct Choice {
+    pub day: u8,
+    pub name: &'static str,
+    pub options: &'static [&'static str],
+}
+
+impl Choice {
+    pub const fn new(day: u8, name: &'static str, options: &'static [&'static str]) -> Self {
+        Choice { day, name, options }
+    }
+
+    pub fn env_var(&self) -> String {
+        env_var(self.day, self.name)
+    }
+
+    /// The chosen option, taking an override from the environment into account.
+    pub fn get(&self) -> Result<&'static str, String> {
+        self.resolve(env::var(self.env_var()).ok().as_deref())
+    }
+
+    fn resolve(&self, value: Option<&str>) -> Result<&'static str, String> {
+        match value {
+            None => Ok(self.options[0]),
+            Some(value) => self.options.iter().find(|o| **o == value.trim()).copied()
+                .ok_or_else(|| format!("Invalid value '{}' for {}, expected one of: {}", value, self.env_var(), self.options.join(", ")))
+        }
+    }
+}
+
+pub mod day06 {
+    use super::OptionalSetting;
+
+    // Extracts the message between the start-of-message marker and these characters.
+    pub const END_MARKER: OptionalSetting<String> = OptionalSetting::new(6, "end_marker");
+}
+
+pub mod day07 {
+    use super::Setting;
+
+    pub const DISK_SIZE: Setting<usize> = Setting::new(7, "disk_size", 70_000_000);
+    pub const FREE_SPACE_NEEDED: Setting<usize> = Setting::new(7, "free_space_needed", 30_000_000);
+}
+
+pub mod day08 {
+    use std::fmt;
+    use std::str::FromStr;
+    use super::Setting;
+
+    // How to handle lines shorter than the longest one: reject, zero or none.
+    pub const PADDING: Setting<Padding> = Setting::new(8, "padding", Padding::Reject);
+
+    /// What to do with lines shorter than the longest one (trimmed or oddly formatted inputs).
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    pub enum Padding {
+        /// Fail on ragged lines.
+        Reject,
+        /// Fill the missing cells with trees of height 0.
+        Zero,
+        /// Leave the missing cells empty, they are open sky that does not block the view.
+        None
+    }
+
+    impl FromStr for Padding {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "reject" => Ok(Padding::Reject),
+                "zero" => Ok(Padding::Zero),
+                "none" => Ok(Padding::None),
+                _ => Err(format!("Unknown padding '{}', expected 'reject', 'zero' or 'none'", s))
+            }
+        }
+    }
+
+    impl fmt::Display for Padding {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Padding::Reject => write!(f, "reject"),
+                Padding::Zero => write!(f, "zero"),
+                Padding::None => write!(f, "none")
+            }
+        }
+    }
+}
+
+pub mod day11 {
+    use super::{Choice, Setting};
+
+    pub const ALGORITHM: Choice = Choice::new(11, "algorithm", &["parallel", "sequential"]);
+
+    pub const ROUNDS_PUZZLE1: Setting<usize> = Setting::new(11, "rounds_puzzle1", 20);
+    pub const ROUNDS_PUZZLE2: Setting<usize> = Setting::new(11, "rounds_puzzle2", 10_000);
+    // Keep worry levels modulo the LCM of the divisors; without it, the levels of part 2 grow until they overflow.
+    pub const REDUCE_WORRY: Setting<bool> = Setting::new(11, "reduce_worry", true);
+    // Continue with worry levels up to u128 when they no longer fit a usize, instead of failing.
+    pub const WIDEN_WORRY: Setting<bool> = Setting::new(11, "widen_worry", true);
+}
+
+pub mod day15 {
+    use super::Setting;
+
+    // Defaults for the real input; the example uses row 10 and 20 as maximum, which day 15 detects by itself.
+    pub const ROW: Setting<isize> = Setting::new(15, "row", 2_000_000);
+    // The distress beacon is within 0..=MAX_COORDINATE on both axes.
+    pub const MAX_COORDINATE: Setting<isize> = Setting::new(15, "max_coordinate", 4_000_000);
+}
+
+pub mod day16 {
+    use super::Choice;
+
+    pub const ALGORITHM: Choice = Choice::new(16, "algorithm", &["contracted", "full"]);
+}
+
+pub mod day17 {
+    use super::Setting;
+
+    pub const DROPS_PUZZLE1: Setting<usize> = Setting::new(17, "drops_puzzle1", 2022);
+    pub const DROPS_PUZZLE2: Setting<usize> = Setting::new(17, "drops_puzzle2", 1_000_000_000_000);
+}
+
+pub mod day18 {
+    use std::fmt;
+    use std::str::FromStr;
+    use super::OptionalSetting;
+
+    // Prints all cross-sections of the droplet across this axis.
+    pub const SLICES: OptionalSetting<Axis> = OptionalSetting::new(18, "slices");
+
+    /// The axis a slice is taken across.
+    #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+    pub enum Axis {
+        X,
+        Y,
+        Z,
+    }
+
+    impl FromStr for Axis {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "x" | "X" => Ok(Axis::X),
+                "y" | "Y" => Ok(Axis::Y),
+                "z" | "Z" => Ok(Axis::Z),
+                other => Err(format!("Unknown axis '{}', expected 'x', 'y' or 'z'", other))
+            }
+        }
+    }
+
+    impl fmt::Display for Axis {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Axis::X => write!(f, "x"),
+                Axis::Y => write!(f, "y"),
+                Axis::Z => write!(f, "z"),
+            }
+        }
+    }
+}
+
+pub mod day23 {
+    use super::Setting;
+
+    // Part 2 gives up after this many rounds, rather than simulating elves that never stop moving forever.
+    pub const MAX_ROUNDS: Setting<usize> = Setting::new(23, "max_rounds", 100_000);
+}
+
+pub mod day24 {
+    use super::{Choice, OptionalSetting};
+
+    pub const ALGORITHM: Choice = Choice::new(24, "algorithm", &["best-first", "iddfs", "bidirectional"]);
+    // A route to check with `selftest 24` instead of the fastest one, as a move string (e.g. 'vw>^', w to wait).
+    pub const ROUTE: OptionalSetting<String> = OptionalSetting::new(24, "route");
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::config::{Choice, day07, day18, day24, OptionalSetting, Setting};
+
+    #[test]
+    fn test_env_var() {
+        assert_eq!("AOC_DAY07_DISK_SIZE", day07::DISK_SIZE.env_var());
+        assert_eq!("AOC_DAY15_ROW", Setting::new(15, "row", 10).env_var());
+    }
+
+    #[test]
+    fn test_resolve() {
+        let setting: Setting<isize> = Setting::new(15, "row", 2_000_000);
+        assert_eq!(Ok(2_000_000), setting.resolve(None, 2_000_000));
+        assert_eq!(Ok(10), setting.resolve(None, 10));
+        assert_eq!(Ok(10), setting.resolve(Some("10"), 2_000_000));
+        assert_eq!(Ok(-4_000_000), setting.resolve(Some("-4_000_000"), 10));
+        assert_eq!(Err("Invalid value 'ten' for AOC_DAY15_ROW (default 2000000)".to_string()), setting.resolve(Some("ten"), 2_000_000));
+    }
+
+    #[test]
+    fn test_optional_setting() {
+        let setting: OptionalSetting<String> = OptionalSetting::new(6, "end_marker");
+        assert_eq!("AOC_DAY06_END_MARKER", setting.env_var());
+        assert_eq!(Ok(None), setting.resolve(None));
+        assert_eq!(Ok(Some("a_b".to_string())), setting.resolve(Some("a_b")));
+        let setting: OptionalSetting<u8> = OptionalSetting::new(6, "size");
+        assert_eq!(Err("Invalid value 'x' for AOC_DAY06_SIZE: invalid digit found in string".to_string()), setting.resolve(Some("x")));
+        assert_eq!(Err("Invalid value 'w' for AOC_DAY18_SLICES: Unknown axis 'w', expected 'x', 'y' or 'z'".to_string()), day18::SLICES.resolve(Some("w")));
+    }
+
+    #[test]
+    fn test_choice() {
+        let choice = Choice::new(3, "algorithm", &["fast", "simple"]);
+        assert_eq!("AOC_DAY03_ALGORITHM", choice.env_var());
+        assert_eq!(Ok("fast"), choice.resolve(None));
+        assert_eq!(Ok("simple"), choice.resolve(Some("simple")));
+        assert_eq!(Err("Invalid value 'quick' for AOC_DAY03_ALGORITHM, expected one of: fast, simple".to_string()), choice.resolve(Some("quick")));
+        assert_eq!(Ok("iddfs"), day24::ALGORITHM.resolve(Some("iddfs")));
+    }
+}
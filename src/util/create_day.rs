@@ -1,5 +1,5 @@
 use crate::days::get_day;
-use std::fs::{read_to_string, write,};
+use std::fs::{create_dir_all, read_to_string, write};
 use std::path::{Path};
 use std::env::{current_dir};
 use regex::{Captures, RegexBuilder};
@@ -17,6 +17,8 @@ pub fn create_day(day: i32) -> Result<(), String> {
             let source_path = main_dir.join(Path::new(&source_file_name));
             let template_file_name = format!("resources/day{:02}.txt", day);
             let input_path = main_dir.join(Path::new(&template_file_name));
+            let example_dir_name = format!("examples/day{:02}", day);
+            let example_dir = main_dir.join(Path::new(&example_dir_name));
             let module_file_name = "src/days.rs".to_string();
             let module_path = main_dir.join(Path::new(&module_file_name));
 
@@ -26,6 +28,9 @@ pub fn create_day(day: i32) -> Result<(), String> {
             if input_path.exists() {
                 return Err(format!("Input file for day {} already exists.", day));
             }
+            if example_dir.exists() {
+                return Err(format!("Examples for day {} already exist.", day));
+            }
 
             let template = match read_to_string(main_dir.join("resources/day.rs.hbs")) {
                 Ok(v) => { v }
@@ -61,12 +66,16 @@ pub fn create_day(day: i32) -> Result<(), String> {
             });
 
             let handlebars = Handlebars::new();
-            let day_content = match handlebars.render_template(template.as_str(), &json!({ "day": day })) {
+            let day_content = match handlebars.render_template(template.as_str(), &json!({ "day": day, "padded_day": format!("{:02}", day) })) {
                 Ok(v) => { v }
                 Err(e) => { return Err(format!("{}", e)); }
             };
 
             match write(&input_path, "TODO: Add Content Here") { Err(e) => { return Err(format!("Could not write input file: {:?}\nError: {}", input_path, e)); }, _ => {} }
+            if let Err(e) = create_dir_all(&example_dir) { return Err(format!("Could not create example directory: {:?}\nError: {}", example_dir, e)); }
+            for file in ["input.txt", "part1.expected", "part2.expected"] {
+                if let Err(e) = write(example_dir.join(file), "") { return Err(format!("Could not write example file: {:?}\nError: {}", example_dir.join(file), e)); }
+            }
             match write(&source_path, day_content) { Err(e) => { return Err(format!("Could not write day file: {:?}\nError: {}", source_path, e)); }, _ => {} }
             match write(&module_path, module_content.to_string()) { Err(e) => { return Err(format!("Could not write module file: {:?}\nError: {}", module_path, e)); }, _ => {} }
 
@@ -0,0 +1,93 @@
+use std::fmt;
+use std::io;
+
+/// The errors of the puzzles and their helpers. Parse errors carry the location in the input where parsing failed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AocError {
+    /// The input could not be parsed. Line and column are 1-based, None when unknown.
+    Parse { line: Option<usize>, column: Option<usize>, message: String },
+    /// The input parsed fine, but the puzzle could not be solved for it.
+    Solve(String),
+    /// Reading or writing a file failed.
+    Io(String),
+}
+
+impl AocError {
+    pub fn parse<M: ToString>(message: M) -> Self {
+        AocError::Parse { line: None, column: None, message: message.to_string() }
+    }
+
+    pub fn parse_at<M: ToString>(line: usize, column: usize, message: M) -> Self {
+        AocError::Parse { line: Some(line), column: Some(column), message: message.to_string() }
+    }
+
+    /// The error of parsing a part of the input that starts at the given line, with its line relative to the whole
+    /// input. Any error while parsing the part is a parse error, e.g. those of the number parsers.
+    pub fn in_line(self, line: usize) -> Self {
+        match self {
+            AocError::Parse { line: inner, column, message } =>
+                AocError::Parse { line: Some(line + inner.unwrap_or(1) - 1), column, message },
+            AocError::Solve(message) | AocError::Io(message) =>
+                AocError::Parse { line: Some(line), column: None, message },
+        }
+    }
+}
+
+impl fmt::Display for AocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AocError::Parse { line: Some(line), column: Some(column), message } => write!(f, "Line {}, column {}: {}", line, column, message),
+            AocError::Parse { line: Some(line), column: None, message } => write!(f, "Line {}: {}", line, message),
+            AocError::Parse { line: None, column: Some(column), message } => write!(f, "Column {}: {}", column, message),
+            AocError::Parse { line: None, column: None, message } => f.write_str(message),
+            AocError::Solve(message) | AocError::Io(message) => f.write_str(message),
+        }
+    }
+}
+
+impl From<String> for AocError {
+    fn from(message: String) -> Self {
+        AocError::Solve(message)
+    }
+}
+
+impl From<&str> for AocError {
+    fn from(message: &str) -> Self {
+        AocError::Solve(message.to_string())
+    }
+}
+
+impl From<io::Error> for AocError {
+    fn from(error: io::Error) -> Self {
+        AocError::Io(error.to_string())
+    }
+}
+
+/// For the helpers that still report errors as plain text.
+impl From<AocError> for String {
+    fn from(error: AocError) -> Self {
+        error.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use crate::util::error::AocError;
+
+    #[test]
+    fn test_display() {
+        assert_eq!("Line 3, column 5: Expected a number", AocError::parse_at(3, 5, "Expected a number").to_string());
+        assert_eq!("Line 3: Expected a number", AocError::parse("Expected a number").in_line(3).to_string());
+        assert_eq!("Expected a number", AocError::parse("Expected a number").to_string());
+        assert_eq!("No route", AocError::Solve("No route".to_string()).to_string());
+        assert_eq!(AocError::Io("gone".to_string()), io::Error::new(io::ErrorKind::NotFound, "gone").into());
+    }
+
+    #[test]
+    fn test_in_line() {
+        // Lines of an error in part of the input count from the line that part starts at.
+        assert_eq!(AocError::parse_at(12, 4, "x"), AocError::parse_at(3, 4, "x").in_line(10));
+        assert_eq!(AocError::Parse { line: Some(10), column: None, message: "x".to_string() }, AocError::from("x").in_line(10));
+    }
+}
@@ -1,13 +1,16 @@
 // Allow dead_code since this is a util file copied across years. Later in the AoC we might use everything, or not.
 #![allow(dead_code)]
 
+use std::borrow::Borrow;
 use std::cmp::{max, Ordering};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::{cmp, fmt};
 use std::hash::Hash;
-use std::ops::{Add, RangeInclusive};
+use std::ops::{Add, AddAssign, Div, Mul, Neg, RangeInclusive, Sub, SubAssign};
 use std::str::FromStr;
+use std::iter::successors;
 use num_traits::abs;
+use crate::util::error::AocError;
 use crate::util::number;
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Default)]
@@ -59,17 +62,17 @@ impl TryFrom<(usize, usize)> for Point {
 }
 
 impl FromStr for Point {
-    type Err = String;
+    type Err = AocError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts_result: Result<Vec<isize>, String> = s.split(",").map(|p| number::parse_isize(p)).collect();
+        let parts_result: Result<Vec<isize>, AocError> = s.split(",").map(|p| number::parse_isize(p)).collect();
         let parts = match parts_result {
             Ok(v) => v,
             Err(e) => return Err(e)
         };
         match parts.len() {
             2 => Ok((parts[0], parts[1]).into()),
-            _ => Err(format!("Invalid str format for Point '{}', expected 'x,y'", s))
+            _ => Err(AocError::parse(format!("Invalid str format for Point '{}', expected 'x,y'", s)))
         }
     }
 }
@@ -108,6 +111,69 @@ impl Add<(isize, isize)> for Point {
         self + Point::from(rhs)
     }
 }
+impl AddAssign<Point> for Point {
+    fn add_assign(&mut self, rhs: Point) {
+        *self = *self + rhs;
+    }
+}
+impl AddAssign<(isize, isize)> for Point {
+    fn add_assign(&mut self, rhs: (isize, isize)) {
+        *self = *self + rhs;
+    }
+}
+impl Sub<&Point> for Point {
+    type Output = Point;
+
+    fn sub(self, rhs: &Point) -> Self::Output {
+        Point { x: self.x - rhs.x, y: self.y - rhs.y }
+    }
+}
+impl Sub<Point> for Point {
+    type Output = Point;
+
+    fn sub(self, rhs: Point) -> Self::Output {
+        self - &rhs
+    }
+}
+impl Sub<(isize, isize)> for Point {
+    type Output = Point;
+
+    fn sub(self, rhs: (isize, isize)) -> Self::Output {
+        self - Point::from(rhs)
+    }
+}
+impl SubAssign<Point> for Point {
+    fn sub_assign(&mut self, rhs: Point) {
+        *self = *self - rhs;
+    }
+}
+impl SubAssign<(isize, isize)> for Point {
+    fn sub_assign(&mut self, rhs: (isize, isize)) {
+        *self = *self - rhs;
+    }
+}
+impl Mul<isize> for Point {
+    type Output = Point;
+
+    fn mul(self, rhs: isize) -> Self::Output {
+        Point { x: self.x * rhs, y: self.y * rhs }
+    }
+}
+// Integer division, rounding towards zero like isize does.
+impl Div<isize> for Point {
+    type Output = Point;
+
+    fn div(self, rhs: isize) -> Self::Output {
+        Point { x: self.x / rhs, y: self.y / rhs }
+    }
+}
+impl Neg for Point {
+    type Output = Point;
+
+    fn neg(self) -> Self::Output {
+        Point { x: -self.x, y: -self.y }
+    }
+}
 impl Add<Point> for Vec<Point> {
     type Output = Vec<Point>;
 
@@ -137,6 +203,27 @@ mod point_tests {
     fn test_format() {
         assert_eq!(format!("{}", Point { x: 5, y: -10 }), "(5,-10)");
     }
+
+    #[test]
+    fn test_operators() {
+        let p = Point::from((3, -5));
+        let q = Point::from((1, 2));
+        assert_eq!(p + q, Point::from((4, -3)));
+        assert_eq!(p + (1, 2), Point::from((4, -3)));
+        assert_eq!(p - q, Point::from((2, -7)));
+        assert_eq!(p - (1, 2), Point::from((2, -7)));
+        assert_eq!(p * 3, Point::from((9, -15)));
+        assert_eq!(p / 2, Point::from((1, -2)));
+        assert_eq!(-p, Point::from((-3, 5)));
+
+        let mut r = p;
+        r += q;
+        r += (0, 1);
+        assert_eq!(r, Point::from((4, -2)));
+        r -= q;
+        r -= (1, 1);
+        assert_eq!(r, Point::from((2, -5)));
+    }
     
     #[test]
     fn test_ord() {
@@ -199,12 +286,12 @@ impl From<Point> for Point3D {
 }
 
 impl FromStr for Point3D {
-    type Err = String;
+    type Err = AocError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let points = s.split(",").map(|p| number::parse_isize(p)).collect::<Result<Vec<isize>, String>>()?;
+        let points = s.split(",").map(|p| number::parse_isize(p)).collect::<Result<Vec<isize>, AocError>>()?;
         if points.len() != 3 {
-            Err(format!("Expected three coordinates, but got {}", points.len()))
+            Err(AocError::parse(format!("Expected three coordinates, but got {}", points.len())))
         } else {
             Ok(Point3D { x: points[0], y: points[1], z: points[2] })
         }
@@ -263,6 +350,15 @@ impl Point3D {
 
         points
     }
+
+    /// The six points sharing a face with this one.
+    pub fn get_face_neighbours(&self) -> [Point3D; 6] {
+        [
+            (self.x - 1, self.y, self.z).into(), (self.x + 1, self.y, self.z).into(),
+            (self.x, self.y - 1, self.z).into(), (self.x, self.y + 1, self.z).into(),
+            (self.x, self.y, self.z - 1).into(), (self.x, self.y, self.z + 1).into(),
+        ]
+    }
 }
 
 #[cfg(test)]
@@ -420,6 +516,15 @@ impl Bounds {
     pub fn from_size(width: usize, height: usize) -> Self {
         Self { top: 0, left: 0, width, height }
     }
+
+    /// Returns the smallest bounds containing all given points, or None if there are no points.
+    pub fn from_points<I, P>(points: I) -> Option<Self> where I: IntoIterator<Item=P>, P: Borrow<Point> {
+        let mut bounds = GrowableBounds::new();
+        for point in points {
+            bounds.include(point.borrow());
+        }
+        bounds.bounds()
+    }
     
     pub fn grow(&mut self, by: isize) {
         self.top -= by;
@@ -449,29 +554,270 @@ impl Bounds {
     }
     
     pub fn points(&self) -> Vec<Point> {
-        let mut points = vec![];
-        
-        for y in self.y() {
-            for x in self.x() {
-                points.push((x, y).into());
+        self.iter_row_major().collect()
+    }
+
+    /// Lazily iterates all points, row by row from the top, every row from left to right.
+    pub fn iter_row_major(&self) -> impl Iterator<Item=Point> {
+        let xs = self.x();
+        self.y().flat_map(move |y| xs.clone().map(move |x| Point { x, y }))
+    }
+
+    /// Lazily iterates all points, column by column from the left, every column from top to bottom.
+    pub fn iter_column_major(&self) -> impl Iterator<Item=Point> {
+        let ys = self.y();
+        self.x().flat_map(move |x| ys.clone().map(move |y| Point { x, y }))
+    }
+
+    /// Lazily iterates all points in a clockwise spiral, starting at the top left corner and walking
+    /// the outer ring before moving inwards.
+    pub fn iter_spiral(&self) -> impl Iterator<Item=Point> {
+        let (top, left, bottom, right) = (self.top, self.left, self.bottom(), self.right());
+        let rings = number::ceil_div(self.width.min(self.height), 2) as isize;
+        (0..rings).flat_map(move |ring| {
+            let (t, l, b, r) = (top + ring, left + ring, bottom - ring, right - ring);
+            // The bottom row and left column are skipped when the ring is a single row or column, as the
+            // top row and right column already covered those points.
+            (l..=r).map(move |x| Point { x, y: t })
+                .chain((t + 1..=b).map(move |y| Point { x: r, y }))
+                .chain((l..r).rev().filter(move |_| b > t).map(move |x| Point { x, y: b }))
+                .chain((t + 1..b).rev().filter(move |_| r > l).map(move |y| Point { x: l, y }))
+        })
+    }
+
+    /// The corners in clockwise order: top left, top right, bottom right and bottom left.
+    pub fn corner_points(&self) -> [Point; 4] {
+        [
+            Point { x: self.left, y: self.top },
+            Point { x: self.right(), y: self.top },
+            Point { x: self.right(), y: self.bottom() },
+            Point { x: self.left, y: self.bottom() },
+        ]
+    }
+}
+
+/// Accumulates points, keeping track of the smallest bounds that contain all of them.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Default)]
+pub struct GrowableBounds {
+    bounds: Option<Bounds>,
+}
+
+impl GrowableBounds {
+    pub fn new() -> Self {
+        Self { bounds: None }
+    }
+
+    /// Includes the point in the bounds, returning whether the bounds had to grow for it.
+    pub fn include(&mut self, p: &Point) -> bool {
+        match self.bounds {
+            Some(bounds) if bounds.contains(p) => false,
+            Some(bounds) => {
+                let top = cmp::min(bounds.top, p.y);
+                let left = cmp::min(bounds.left, p.x);
+                let bottom = cmp::max(bounds.bottom(), p.y);
+                let right = cmp::max(bounds.right(), p.x);
+                self.bounds = Some(Bounds::from_tlbr(top, left, bottom, right));
+                true
+            }
+            None => {
+                self.bounds = Some(Bounds::from_tlbr(p.y, p.x, p.y, p.x));
+                true
             }
         }
-        
-        points
+    }
+
+    pub fn bounds(&self) -> Option<Bounds> {
+        self.bounds
+    }
+}
+
+impl From<Bounds> for GrowableBounds {
+    fn from(bounds: Bounds) -> Self {
+        Self { bounds: Some(bounds) }
+    }
+}
+
+#[cfg(test)]
+mod bounds_tests {
+    use crate::util::geometry::{Bounds, GrowableBounds, Point};
+
+    #[test]
+    fn test_from_points() {
+        assert_eq!(None, Bounds::from_points(Vec::<Point>::new()));
+        assert_eq!(Some(Bounds { top: 3, left: 2, width: 1, height: 1 }), Bounds::from_points(vec![Point { x: 2, y: 3 }]));
+
+        let points: Vec<Point> = vec![(2, 3).into(), (-1, 5).into(), (4, -2).into()];
+        assert_eq!(Some(Bounds::from_tlbr(-2, -1, 5, 4)), Bounds::from_points(&points));
+    }
+
+    #[test]
+    fn test_iter_orders() {
+        let points = |v: &[(isize, isize)]| v.iter().map(|p| Point::from(*p)).collect::<Vec<_>>();
+        let bounds = Bounds::from_tlbr(1, -1, 2, 1);
+
+        assert_eq!(points(&[(-1, 1), (0, 1), (1, 1), (-1, 2), (0, 2), (1, 2)]), bounds.iter_row_major().collect::<Vec<_>>());
+        assert_eq!(bounds.points(), bounds.iter_row_major().collect::<Vec<_>>());
+        assert_eq!(points(&[(-1, 1), (-1, 2), (0, 1), (0, 2), (1, 1), (1, 2)]), bounds.iter_column_major().collect::<Vec<_>>());
+        assert_eq!(points(&[(-1, 1), (0, 1), (1, 1), (1, 2), (0, 2), (-1, 2)]), bounds.iter_spiral().collect::<Vec<_>>());
+        assert_eq!(0, Bounds::from_size(0, 3).iter_row_major().count());
+        assert_eq!(0, Bounds::from_size(3, 0).iter_spiral().count());
+    }
+
+    #[test]
+    fn test_iter_spiral() {
+        let spiral: Vec<_> = Bounds::from_size(4, 3).iter_spiral().map(|p| (p.x, p.y)).collect();
+        assert_eq!(vec![(0, 0), (1, 0), (2, 0), (3, 0), (3, 1), (3, 2), (2, 2), (1, 2), (0, 2), (0, 1), (1, 1), (2, 1)], spiral);
+
+        let spiral: Vec<_> = Bounds::from_size(1, 3).iter_spiral().map(|p| (p.x, p.y)).collect();
+        assert_eq!(vec![(0, 0), (0, 1), (0, 2)], spiral);
+
+        let spiral: Vec<_> = Bounds::from_size(3, 1).iter_spiral().map(|p| (p.x, p.y)).collect();
+        assert_eq!(vec![(0, 0), (1, 0), (2, 0)], spiral);
+
+        for (width, height) in [(5, 5), (6, 4), (2, 7), (1, 1)] {
+            let bounds = Bounds::from_size(width, height);
+            let mut spiral: Vec<_> = bounds.iter_spiral().collect();
+            spiral.sort();
+            assert_eq!(bounds.points(), spiral, "Spiral of {}x{} does not visit every point once", width, height);
+        }
+    }
+
+    #[test]
+    fn test_corner_points() {
+        let corners = Bounds::from_tlbr(1, -1, 2, 1).corner_points();
+        assert_eq!([Point::from((-1, 1)), Point::from((1, 1)), Point::from((1, 2)), Point::from((-1, 2))], corners);
+    }
+
+    #[test]
+    fn test_growable_bounds() {
+        let mut bounds = GrowableBounds::new();
+        assert_eq!(None, bounds.bounds());
+
+        assert!(bounds.include(&(2, 3).into()));
+        assert_eq!(Some(Bounds { top: 3, left: 2, width: 1, height: 1 }), bounds.bounds());
+        assert!(!bounds.include(&(2, 3).into()));
+
+        assert!(bounds.include(&(0, 4).into()));
+        assert_eq!(Some(Bounds { top: 3, left: 0, width: 3, height: 2 }), bounds.bounds());
+        assert!(!bounds.include(&(1, 3).into()));
+
+        let mut from_existing = GrowableBounds::from(Bounds::from_size(2, 2));
+        assert!(!from_existing.include(&(1, 1).into()));
+        assert!(from_existing.include(&(-1, 1).into()));
+        assert_eq!(Some(Bounds { top: 0, left: -1, width: 3, height: 2 }), from_existing.bounds());
     }
 }
 
-#[derive(Eq, PartialEq, Clone)]
+/// A grid of values on points, with the bounds of the points that have one. Grids are sparse (a map) by default;
+/// dense grids (`Grid::dense`) keep their values in a vector, which is a lot quicker for grids that are mostly filled.
+#[derive(Clone)]
 pub struct Grid<T> where T: Clone {
     pub bounds: Bounds,
-    cells: HashMap<Point, T>,
+    cells: Storage<T>,
 }
 
 impl<T> Default for Grid<T> where T: Clone + Default {
     fn default() -> Self {
         Grid {
             bounds: Bounds::default(),
-            cells: HashMap::default()
+            cells: Storage::Sparse(HashMap::default())
+        }
+    }
+}
+
+/// Grids are equal when they have the same bounds and values, however they store them.
+impl<T> PartialEq for Grid<T> where T: Clone + PartialEq {
+    fn eq(&self, other: &Self) -> bool {
+        self.bounds == other.bounds && self.cells.len() == other.cells.len() && self.cells.iter().all(|(p, v)| other.cells.get(&p) == Some(v))
+    }
+}
+
+impl<T> Eq for Grid<T> where T: Clone + Eq {}
+
+/// Where a grid keeps its values.
+#[derive(Clone)]
+enum Storage<T> {
+    Sparse(HashMap<Point, T>),
+    /// The cells of `area` row by row, `len` of them with a value. The area grows when a value is set outside it.
+    Dense { area: Bounds, values: Vec<Option<T>>, len: usize },
+}
+
+impl<T> Storage<T> where T: Clone {
+    fn dense(area: Bounds) -> Self {
+        Storage::Dense { area, values: vec![None; area.width * area.height], len: 0 }
+    }
+
+    /// Empty storage of the same kind, covering the same area when dense.
+    fn empty_like<U: Clone>(&self) -> Storage<U> {
+        match self {
+            Storage::Sparse(_) => Storage::Sparse(HashMap::new()),
+            Storage::Dense { area, .. } => Storage::dense(*area),
+        }
+    }
+
+    fn index(area: &Bounds, p: &Point) -> Option<usize> {
+        area.contains(p).then(|| (p.y - area.top) as usize * area.width + (p.x - area.left) as usize)
+    }
+
+    fn get(&self, p: &Point) -> Option<&T> {
+        match self {
+            Storage::Sparse(cells) => cells.get(p),
+            Storage::Dense { area, values, .. } => Self::index(area, p).and_then(|i| values[i].as_ref()),
+        }
+    }
+
+    fn get_mut(&mut self, p: &Point) -> Option<&mut T> {
+        match self {
+            Storage::Sparse(cells) => cells.get_mut(p),
+            Storage::Dense { area, values, .. } => Self::index(area, p).and_then(|i| values[i].as_mut()),
+        }
+    }
+
+    fn insert(&mut self, p: Point, v: T) {
+        match self {
+            Storage::Sparse(cells) => {
+                cells.insert(p, v);
+            }
+            Storage::Dense { area, values, len } => {
+                if !area.contains(&p) {
+                    // Grow with some room to spare, so setting values along an edge does not copy everything each time.
+                    let mut grown = if area.width * area.height == 0 { GrowableBounds::new() } else { GrowableBounds::from(*area) };
+                    grown.include(&p);
+                    let mut new_area = grown.bounds().unwrap();
+                    new_area.grow((area.width.max(area.height) / 2).max(4) as isize);
+
+                    let mut new_values = vec![None; new_area.width * new_area.height];
+                    for (index, value) in values.iter_mut().enumerate().filter(|(_, v)| v.is_some()) {
+                        let point: Point = (area.left + (index % area.width) as isize, area.top + (index / area.width) as isize).into();
+                        new_values[Self::index(&new_area, &point).unwrap()] = value.take();
+                    }
+                    *area = new_area;
+                    *values = new_values;
+                }
+
+                let slot = &mut values[Self::index(area, &p).unwrap()];
+                if slot.is_none() {
+                    *len += 1;
+                }
+                *slot = Some(v);
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Storage::Sparse(cells) => cells.len(),
+            Storage::Dense { len, .. } => *len,
+        }
+    }
+
+    /// The points with a value, in no particular order.
+    fn iter(&self) -> Box<dyn Iterator<Item=(Point, &T)> + '_> {
+        match self {
+            Storage::Sparse(cells) => Box::new(cells.iter().map(|(p, v)| (*p, v))),
+            Storage::Dense { area, values, .. } => Box::new(values.iter().enumerate().filter_map(|(index, v)| {
+                v.as_ref().map(|v| ((area.left + (index % area.width) as isize, area.top + (index / area.width) as isize).into(), v))
+            })),
         }
     }
 }
@@ -504,29 +850,46 @@ impl Directions {
     }
 }
 
+/// How to measure distances on a grid: in steps to neighbouring cells (manhattan), or also allowing diagonal
+/// steps (chebyshev).
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum Metric {
+    Manhattan,
+    Chebyshev,
+}
+
+impl Metric {
+    pub fn directions(&self) -> Directions {
+        match self {
+            Metric::Manhattan => Directions::NonDiagonal,
+            Metric::Chebyshev => Directions::All,
+        }
+    }
+}
+
 #[allow(unused)]
 impl<T> Grid<T> where T: Clone {
     pub fn new(cells: HashMap<Point, T>) -> Self {
-        let points: Vec<_> = cells.keys().collect();
-        let top = points.iter().map(|p| p.y).min().unwrap_or(0);
-        let left = points.iter().map(|p| p.x).min().unwrap_or(0);
-        let bottom = points.iter().map(|p| p.y).max().unwrap_or(0);
-        let right = points.iter().map(|p| p.x).max().unwrap_or(0);
-
-        let bounds = Bounds::from_tlbr(top, left, bottom, right);
-        Self { bounds, cells }
+        let bounds = Bounds::from_points(cells.keys()).unwrap_or(Bounds::from_tlbr(0, 0, 0, 0));
+        Self { bounds, cells: Storage::Sparse(cells) }
     }
     
     pub fn empty() -> Self {
-        Self { bounds: Bounds::default(), cells: HashMap::new() }
+        Self { bounds: Bounds::default(), cells: Storage::Sparse(HashMap::new()) }
+    }
+
+    /// An empty grid that stores its values densely, with room for the values in `area`. It still grows beyond that
+    /// area when needed.
+    pub fn dense(area: Bounds) -> Self {
+        Self { bounds: Bounds::default(), cells: Storage::dense(area) }
     }
 
     pub fn get(&self, p: &Point) -> Option<T> {
-        self.cells.get(p).map(|x| x.clone())
+        self.cells.get(p).cloned()
     }
     
     pub fn has(&self, p: &Point) -> bool {
-        self.cells.contains_key(p)
+        self.cells.get(p).is_some()
     }
 
     pub fn get_mut(&mut self, p: &Point) -> Option<&mut T> {
@@ -541,14 +904,9 @@ impl<T> Grid<T> where T: Clone {
         }
 
         // If this is the first insertion, make the bounds set to that point; otherwise expand:
-        if self.cells.len() == 1 {
-            self.bounds = Bounds::from_tlbr(p.y, p.x, p.y, p.x);
-        } else {
-            let top = cmp::min(self.bounds.top, p.y);
-            let left = cmp::min(self.bounds.left, p.x);
-            let bottom = cmp::max(self.bounds.bottom(), p.y);
-            let right = cmp::max(self.bounds.right(), p.x);
-            self.bounds = Bounds::from_tlbr(top, left, bottom, right);
+        let mut bounds = if self.cells.len() == 1 { GrowableBounds::new() } else { GrowableBounds::from(self.bounds) };
+        if bounds.include(&p) {
+            self.bounds = bounds.bounds().unwrap();
         }
     }
     
@@ -560,47 +918,161 @@ impl<T> Grid<T> where T: Clone {
         p.get_points_around(directions).into_iter().filter(|p| self.bounds.contains(p)).collect()
     }
 
+    /// The adjacent cells with a value, along with their points.
+    pub fn get_adjacent_with_points(&self, p: &Point, directions: Directions) -> Vec<(Point, &T)> {
+        self.get_adjacent_points(p, directions).into_iter().filter_map(|p| self.cells.get(&p).map(|v| (p, v))).collect()
+    }
+
+    /// All points around, including those outside the bounds, with None for the points without a value.
+    pub fn get_adjacent_or_none(&self, p: &Point, directions: Directions) -> Vec<(Point, Option<&T>)> {
+        p.get_points_around(directions).into_iter().map(|p| (p, self.cells.get(&p))).collect()
+    }
+
     pub fn get_in_direction(&self, p: &Point, direction: Directions) -> Vec<T> {
-        self.get_points_in_direction(p, direction).iter().filter_map(|p| self.get(p)).collect()
+        self.iter_in_direction(p, direction).map(|(_, v)| v.clone()).collect()
     }
 
     pub fn get_points_in_direction(&self, p: &Point, direction: Directions) -> Vec<Point> {
-        match direction {
-            Directions::Top | Directions::Right | Directions::Bottom | Directions::Left => {
-                let mut points = vec![];
-                let mut current = p.clone();
-                loop {
-                    let next = self.get_adjacent_points(&current, direction.clone());
-                    if next.len() != 1 {
-                        break;
-                    }
-                    current = next[0];
-                    points.push(current);
-                }
-                points
-            },
-            _ => vec![]
-        }
+        self.points_in_direction(p, direction).collect()
+    }
+
+    /// The points from `p` (exclusive) up to the edge of the bounds, for the directions Top, Right, Bottom and Left.
+    /// There are none for other directions.
+    fn points_in_direction(&self, p: &Point, direction: Directions) -> impl Iterator<Item=Point> {
+        let step = match direction {
+            Directions::Top => Some((0, -1)),
+            Directions::Right => Some((1, 0)),
+            Directions::Bottom => Some((0, 1)),
+            Directions::Left => Some((-1, 0)),
+            _ => None
+        };
+        let (start, bounds) = (*p, self.bounds);
+        step.into_iter().flat_map(move |step| successors(Some(start + step), move |p| Some(*p + step)).take_while(move |p| bounds.contains(p)))
     }
 
-    pub fn points(&self) -> Vec<Point> {
-        let mut points = vec![];
+    /// The cells with a value from `p` (exclusive) up to the edge of the grid, see `get_points_in_direction`.
+    pub fn iter_in_direction(&self, p: &Point, direction: Directions) -> impl Iterator<Item=(Point, &T)> {
+        self.points_in_direction(p, direction).filter_map(|p| self.cells.get(&p).map(|v| (p, v)))
+    }
 
-        for y in self.bounds.y() {
-            for x in self.bounds.x() {
-                points.push((x, y).into());
-            }
-        }
+    /// The cells with a value, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item=(Point, &T)> {
+        self.cells.iter()
+    }
 
-        points
+    /// The cells with a value in row `y`, from left to right.
+    pub fn iter_row(&self, y: isize) -> impl DoubleEndedIterator<Item=(Point, &T)> {
+        self.bounds.x().filter_map(move |x| self.cells.get(&(x, y).into()).map(|v| ((x, y).into(), v)))
+    }
+
+    /// The cells with a value in column `x`, from top to bottom.
+    pub fn iter_col(&self, x: isize) -> impl DoubleEndedIterator<Item=(Point, &T)> {
+        self.bounds.y().filter_map(move |y| self.cells.get(&(x, y).into()).map(|v| ((x, y).into(), v)))
+    }
+
+    pub fn points(&self) -> Vec<Point> {
+        self.bounds.iter_row_major().collect()
     }
 
     pub fn values(&self) -> Vec<T> {
-        self.points().iter().filter_map(|p| self.get(p)).collect()
+        self.bounds.y().flat_map(|y| self.iter_row(y)).map(|(_, v)| v.clone()).collect()
     }
     
     pub fn entries(&self) -> Vec<(Point, T)> {
-        self.cells.iter().map(|(p, t)| (p.clone(), t.clone())).collect()
+        self.iter().map(|(p, t)| (p, t.clone())).collect()
+    }
+
+    /// The number of cells with a value matching the predicate.
+    pub fn count(&self, mut predicate: impl FnMut(&T) -> bool) -> usize {
+        self.cells.iter().filter(|(_, v)| predicate(v)).count()
+    }
+
+    /// The first point (in reading order) with a value matching the predicate.
+    pub fn find(&self, mut predicate: impl FnMut(&T) -> bool) -> Option<Point> {
+        self.cells.iter().filter(|(_, v)| predicate(v)).map(|(p, _)| p).min()
+    }
+
+    /// All points with a value matching the predicate, in reading order.
+    pub fn find_all(&self, mut predicate: impl FnMut(&T) -> bool) -> Vec<Point> {
+        let mut points: Vec<_> = self.cells.iter().filter(|(_, v)| predicate(v)).map(|(p, _)| p).collect();
+        points.sort();
+        points
+    }
+
+    /// The distance from every cell to the nearest of the sources, using a breadth-first search from all sources at
+    /// once. Only cells with a value can be walked through, so the result has no value for cells that cannot be
+    /// reached (or have no value themselves). Sources without a value are ignored.
+    pub fn distance_transform(&self, sources: &[Point], metric: Metric) -> Grid<usize> {
+        let mut distances = Grid { bounds: Bounds::default(), cells: self.cells.empty_like() };
+        let mut queue = VecDeque::new();
+        for source in sources {
+            if self.has(source) && !distances.has(source) {
+                distances.set(*source, 0);
+                queue.push_back(*source);
+            }
+        }
+
+        while let Some(point) = queue.pop_front() {
+            let distance = distances.cells.get(&point).copied().unwrap_or_default();
+            for next in point.get_points_around(metric.directions()) {
+                if self.has(&next) && !distances.has(&next) {
+                    distances.set(next, distance + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        distances
+    }
+
+    /// A grid with the same bounds, with every cell's value replaced by `f(point, value)`. Cells without a value
+    /// stay empty.
+    pub fn map<U: Clone>(&self, mut f: impl FnMut(Point, &T) -> U) -> Grid<U> {
+        let mut cells = self.cells.empty_like();
+        self.cells.iter().for_each(|(p, v)| cells.insert(p, f(p, v)));
+        Grid { bounds: self.bounds, cells }
+    }
+
+    /// A grid with the bounds of this one, combining the values of both grids with `f(point, value, other value)`.
+    /// Only cells that have a value in both grids get one in the result.
+    pub fn zip<U: Clone, V: Clone>(&self, other: &Grid<U>, mut f: impl FnMut(Point, &T, &U) -> V) -> Grid<V> {
+        let mut cells = self.cells.empty_like();
+        for (p, v) in self.cells.iter() {
+            if let Some(o) = other.cells.get(&p) {
+                cells.insert(p, f(p, v, o));
+            }
+        }
+        Grid { bounds: self.bounds, cells }
+    }
+}
+
+#[allow(unused)]
+impl<T> Grid<T> where T: Clone + PartialEq {
+    /// The cells that differ between this grid and `other`, as (point, value here, value in other), in reading order.
+    pub fn diff(&self, other: &Self) -> Vec<(Point, Option<T>, Option<T>)> {
+        let mut points: Vec<_> = self.cells.iter().chain(other.cells.iter()).map(|(p, _)| p).collect();
+        points.sort();
+        points.dedup();
+
+        points.into_iter()
+            .map(|p| (p, self.get(&p), other.get(&p)))
+            .filter(|(_, mine, theirs)| mine != theirs)
+            .collect()
+    }
+}
+
+#[allow(unused)]
+impl<T> Grid<T> where T: Clone + PartialEq + fmt::Display {
+    /// Renders `other` over the bounds of both grids, with the cells that differ from this grid in inverse video.
+    pub fn render_diff(&self, other: &Self) -> String {
+        let bounds = Bounds::from_points(self.cells.iter().chain(other.cells.iter()).map(|(p, _)| p)).unwrap_or_default();
+        let changed: Vec<_> = self.diff(other).into_iter().map(|(p, _, _)| p).collect();
+
+        bounds.y().map(|y| bounds.x().map(|x| {
+            let point: Point = (x, y).into();
+            let value = other.get(&point).map(|v| v.to_string()).unwrap_or(" ".to_string());
+            if changed.binary_search(&point).is_ok() { format!("\x1b[7m{}\x1b[27m", value) } else { value }
+        }).collect::<String>()).collect::<Vec<_>>().join("\n")
     }
 }
 
@@ -681,7 +1153,7 @@ impl<T> TryFrom<Vec<Vec<T>>> for Grid<T> where T: Clone + Default {
                 }
             }
 
-            Ok(Grid { bounds, cells })
+            Ok(Grid { bounds, cells: Storage::Sparse(cells) })
         } else {
             Err(format!("Not all lines in input are the same width"))
         }
@@ -690,7 +1162,7 @@ impl<T> TryFrom<Vec<Vec<T>>> for Grid<T> where T: Clone + Default {
 
 #[cfg(test)]
 mod grid_tests {
-    use crate::util::geometry::{Grid, Directions, Bounds};
+    use crate::util::geometry::{Grid, Directions, Bounds, Metric, Point};
 
     const EXAMPLE_GRID_INPUT: &str = "\
         2199943210\n\
@@ -739,6 +1211,20 @@ mod grid_tests {
         assert_eq!(EXAMPLE_GRID_INPUT.parse::<Grid<usize>>(), Ok(get_example_grid()));
     }
 
+    #[test]
+    fn test_grid_set_keeps_bounds() {
+        let mut grid = Grid::empty();
+        grid.set((2, 3).into(), 1);
+        assert_eq!(grid.bounds, Bounds::from_tlbr(3, 2, 3, 2));
+
+        // Overwriting the only cell must not shrink bounds that were widened in the meantime.
+        grid.bounds = Bounds::from_tlbr(0, 0, 5, 5);
+        grid.set((2, 3).into(), 2);
+        assert_eq!(grid.bounds, Bounds::from_tlbr(0, 0, 5, 5));
+        grid.set((7, 1).into(), 3);
+        assert_eq!(grid.bounds, Bounds::from_tlbr(0, 0, 5, 7));
+    }
+
     #[test]
     fn test_get_adjacent() {
         let grid = get_example_grid();
@@ -762,6 +1248,21 @@ mod grid_tests {
                    vec![(4, 2).into(), (5, 2).into(), (6, 2).into(), (6, 3).into(), (6, 4).into(), (5, 4).into(), (4, 4).into(), (4, 3).into()]);
     }
 
+    #[test]
+    fn test_get_adjacent_with_points() {
+        let grid = get_example_grid();
+        assert_eq!(grid.get_adjacent_with_points(&(0, 0).into(), Directions::NonDiagonal), vec![((1, 0).into(), &1), ((0, 1).into(), &3)]);
+        assert_eq!(grid.get_adjacent_with_points(&(9, 4).into(), Directions::NonDiagonal), vec![((9, 3).into(), &9), ((8, 4).into(), &7)]);
+    }
+
+    #[test]
+    fn test_get_adjacent_or_none() {
+        let grid = get_example_grid();
+        assert_eq!(grid.get_adjacent_or_none(&(0, 0).into(), Directions::NonDiagonal),
+                   vec![((0, -1).into(), None), ((1, 0).into(), Some(&1)), ((0, 1).into(), Some(&3)), ((-1, 0).into(), None)]);
+        assert_eq!(grid.get_adjacent_or_none(&(5, 3).into(), Directions::Top), vec![((5, 2).into(), Some(&8))]);
+    }
+
     #[test]
     fn test_get_points_in_direction() {
         let grid = get_example_grid();
@@ -776,6 +1277,16 @@ mod grid_tests {
         assert_eq!(grid.values(), vec![1, 2, 3, 9, 8, 7, 5, 6, 4]);
     }
 
+    #[test]
+    fn test_count_and_find() {
+        let grid: Grid<usize> = vec![vec![1, 2, 3], vec![9, 8, 7], vec![5, 6, 4]].try_into().unwrap();
+        assert_eq!(4, grid.count(|v| *v > 5));
+        assert_eq!(0, grid.count(|v| *v > 9));
+        assert_eq!(Some((0, 1).into()), grid.find(|v| *v > 5));
+        assert_eq!(None, grid.find(|v| *v == 0));
+        assert_eq!(vec![Point::from((0, 1)), (1, 1).into(), (2, 1).into(), (1, 2).into()], grid.find_all(|v| *v > 5));
+    }
+
     #[test]
     fn test_growing_grid() {
         let mut grid: Grid<usize> = Grid::default();
@@ -806,4 +1317,293 @@ mod grid_tests {
             (-2,3).into(), (-1,3).into(), (0,3).into(), (1,3).into(), (2,3).into()
         ]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_diff() {
+        let mut before: Grid<char> = Grid::default();
+        before.set((0, 0).into(), 'a');
+        before.set((1, 0).into(), 'b');
+        let mut after = before.clone();
+        after.set((1, 0).into(), 'c');
+        after.set((0, 1).into(), 'd');
+
+        assert!(before.diff(&before).is_empty());
+        assert_eq!(vec![
+            ((1, 0).into(), Some('b'), Some('c')),
+            ((0, 1).into(), None, Some('d')),
+        ], before.diff(&after));
+        assert_eq!(vec![
+            ((1, 0).into(), Some('c'), Some('b')),
+            ((0, 1).into(), Some('d'), None),
+        ], after.diff(&before));
+
+        assert_eq!("a\x1b[7mc\x1b[27m\n\x1b[7md\x1b[27m ", before.render_diff(&after));
+        assert_eq!("a\x1b[7mb\x1b[27m\n\x1b[7m \x1b[27m ", after.render_diff(&before));
+    }
+
+    #[test]
+    fn test_distance_transform() {
+        let grid = get_example_grid();
+        let distances = grid.distance_transform(&[(0, 0).into(), (9, 4).into()], Metric::Manhattan);
+        assert_eq!(grid.bounds, distances.bounds);
+        assert_eq!("\
+0123456654
+1234566543
+2345665432
+3456654321
+4566543210", distances.to_string());
+
+        let distances = grid.distance_transform(&[(4, 2).into()], Metric::Chebyshev);
+        assert_eq!(Some(0), distances.get(&(4, 2).into()));
+        assert_eq!(Some(2), distances.get(&(2, 0).into()));
+        assert_eq!(Some(5), distances.get(&(9, 4).into()));
+        assert_eq!(Some(4), distances.get(&(0, 0).into()));
+    }
+
+    #[test]
+    fn test_distance_transform_with_walls() {
+        // Only the cells with a value can be walked through:
+        let grid: Grid<char> = "...\n##.\n...".parse().unwrap();
+        let mut open = Grid::empty();
+        for (point, c) in grid.entries() {
+            if c == '.' {
+                open.set(point, c);
+            }
+        }
+
+        let distances = open.distance_transform(&[(0, 0).into(), (5, 5).into()], Metric::Manhattan);
+        assert_eq!(Some(2), distances.get(&(2, 0).into()));
+        assert_eq!(Some(6), distances.get(&(0, 2).into()));
+        assert_eq!(None, distances.get(&(0, 1).into()));
+        assert_eq!(7, distances.count(|_| true));
+
+        // Diagonal steps cut the corner:
+        let distances = open.distance_transform(&[(0, 0).into()], Metric::Chebyshev);
+        assert_eq!(Some(4), distances.get(&(0, 2).into()));
+        assert_eq!(0, open.distance_transform(&[], Metric::Chebyshev).count(|_| true));
+    }
+
+    #[test]
+    fn test_dense() {
+        let sparse: Grid<usize> = EXAMPLE_GRID_INPUT.parse().unwrap();
+        let mut dense = Grid::dense(Bounds::from_size(10, 5));
+        for (point, value) in sparse.entries() {
+            dense.set(point, value);
+        }
+        assert_eq!(sparse, dense);
+        assert_eq!(sparse.to_string(), dense.to_string());
+        assert_eq!(vec![2, 9, 9], dense.get_adjacent(&(0, 1).into(), Directions::NonDiagonal));
+        assert_eq!(sparse.find_all(|v| *v == 9), dense.find_all(|v| *v == 9));
+        assert_eq!(sparse.count(|v| *v < 5), dense.count(|v| *v < 5));
+        assert_eq!(sparse.map(|_, v| v * 2), dense.map(|_, v| v * 2));
+
+        // Setting values outside the area grows it, keeping the values.
+        dense.set((-3, 7).into(), 42);
+        dense.set((25, -1).into(), 43);
+        *dense.get_mut(&(0, 0).into()).unwrap() = 7;
+        assert_eq!(Bounds::from_tlbr(-1, -3, 7, 25), dense.bounds);
+        assert_eq!((Some(42), Some(43), Some(7), Some(8)), (dense.get(&(-3, 7).into()), dense.get(&(25, -1).into()), dense.get(&(0, 0).into()), dense.get(&(9, 4).into())));
+        assert_eq!(52, dense.entries().len());
+        assert!(!dense.has(&(-2, 7).into()));
+
+        // An empty area grows from the first value.
+        let mut grown: Grid<char> = Grid::dense(Bounds::default());
+        grown.set((5, 5).into(), 'x');
+        assert_eq!(Some('x'), grown.get(&(5, 5).into()));
+        assert_eq!(Bounds::from_tlbr(5, 5, 5, 5), grown.bounds);
+    }
+
+    #[test]
+    fn test_iterators() {
+        let grid: Grid<usize> = EXAMPLE_GRID_INPUT.parse().unwrap();
+        let values = |it: &mut dyn Iterator<Item=(Point, &usize)>| it.map(|(_, v)| *v).collect::<Vec<_>>();
+        assert_eq!(50, grid.iter().count());
+        assert_eq!(vec![9, 8, 5, 6, 7, 8, 9, 8, 9, 2], values(&mut grid.iter_row(2)));
+        assert_eq!(vec![9, 6, 5, 8, 9], values(&mut grid.iter_col(2).rev()));
+        assert_eq!(vec![6, 9, 6], values(&mut grid.iter_in_direction(&(2, 2).into(), Directions::Bottom).chain(grid.iter_in_direction(&(2, 2).into(), Directions::Right).take(1))));
+        assert_eq!(vec![(Point::from((1, 2)), &8), (Point::from((0, 2)), &9)], grid.iter_in_direction(&(2, 2).into(), Directions::Left).collect::<Vec<_>>());
+        assert_eq!(0, grid.iter_in_direction(&(2, 2).into(), Directions::TopLeft).count());
+        assert_eq!(0, grid.iter_in_direction(&(0, 0).into(), Directions::Top).count());
+        assert_eq!(vec![2, 1, 9, 9, 9, 4, 3, 2, 1, 0], grid.values()[..10].to_vec());
+    }
+
+    #[test]
+    fn test_map() {
+        let grid = get_example_grid();
+        let low = grid.map(|_, height| *height < 5);
+        assert_eq!(grid.bounds, low.bounds);
+        assert_eq!(Some(true), low.get(&(9, 0).into()));
+        assert_eq!(Some(false), low.get(&(2, 0).into()));
+        assert_eq!(grid.count(|h| *h < 5), low.count(|l| *l));
+
+        // The point is passed along, and the bounds stay the same even when the cells do not fill them:
+        let mut sparse = Grid::empty();
+        sparse.set((-2, 1).into(), 'a');
+        sparse.set((3, 4).into(), 'b');
+        let mapped = sparse.map(|p, c| format!("{}{}", c, p.x));
+        assert_eq!(sparse.bounds, mapped.bounds);
+        assert_eq!(vec![Some("a-2".to_string()), None, Some("b3".to_string())],
+                   vec![mapped.get(&(-2, 1).into()), mapped.get(&(0, 0).into()), mapped.get(&(3, 4).into())]);
+    }
+
+    #[test]
+    fn test_zip() {
+        let grid = get_example_grid();
+        let distances = grid.distance_transform(&[(0, 0).into()], Metric::Manhattan);
+        let sum = grid.zip(&distances, |_, height, distance| height + distance);
+        assert_eq!(grid.bounds, sum.bounds);
+        assert_eq!(Some(2), sum.get(&(0, 0).into()));
+        assert_eq!(Some(8 + 13), sum.get(&(9, 4).into()));
+
+        // Only cells with a value in both grids are combined, the bounds are those of the first grid:
+        let mut corner = Grid::empty();
+        corner.set((0, 0).into(), 10);
+        corner.set((20, 20).into(), 10);
+        let zipped = grid.zip(&corner, |p, height, extra| (p, height * extra));
+        assert_eq!(grid.bounds, zipped.bounds);
+        assert_eq!(vec![((0, 0).into(), 20)], zipped.values());
+        assert_eq!(corner.bounds, corner.zip(&grid, |_, a, b| a + b).bounds);
+    }
+}
+
+/// A dense 3D grid covering the cuboid between `min` and `max` (inclusive).
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct Grid3<T> where T: Clone {
+    pub min: Point3D,
+    pub max: Point3D,
+    cells: Vec<Option<T>>,
+}
+
+impl<T> Grid3<T> where T: Clone {
+    pub fn new(min: Point3D, max: Point3D) -> Self {
+        let size = ((max.x - min.x + 1).max(0) * (max.y - min.y + 1).max(0) * (max.z - min.z + 1).max(0)) as usize;
+        Grid3 { min, max, cells: vec![None; size] }
+    }
+
+    /// A grid just big enough to hold all given points, optionally with some padding around them.
+    pub fn around<'a, I>(points: I, padding: isize) -> Self where I: IntoIterator<Item=&'a Point3D> {
+        let points: Vec<_> = points.into_iter().collect();
+        let min = |f: fn(&Point3D) -> isize| points.iter().map(|p| f(p)).min().unwrap_or(0) - padding;
+        let max = |f: fn(&Point3D) -> isize| points.iter().map(|p| f(p)).max().unwrap_or(0) + padding;
+        Self::new((min(|p| p.x), min(|p| p.y), min(|p| p.z)).into(), (max(|p| p.x), max(|p| p.y), max(|p| p.z)).into())
+    }
+
+    fn index(&self, p: &Point3D) -> Option<usize> {
+        if !self.in_bounds(p) {
+            return None;
+        }
+        let width = (self.max.x - self.min.x + 1) as usize;
+        let depth = (self.max.y - self.min.y + 1) as usize;
+        let (x, y, z) = ((p.x - self.min.x) as usize, (p.y - self.min.y) as usize, (p.z - self.min.z) as usize);
+        Some(x + width * (y + depth * z))
+    }
+
+    pub fn in_bounds(&self, p: &Point3D) -> bool {
+        self.min.x <= p.x && p.x <= self.max.x &&
+            self.min.y <= p.y && p.y <= self.max.y &&
+            self.min.z <= p.z && p.z <= self.max.z
+    }
+
+    pub fn get(&self, p: &Point3D) -> Option<&T> {
+        self.index(p).and_then(|i| self.cells[i].as_ref())
+    }
+
+    pub fn has(&self, p: &Point3D) -> bool {
+        self.get(p).is_some()
+    }
+
+    /// Sets the value of the given point, returns an error when the point is out of bounds.
+    pub fn set(&mut self, p: Point3D, v: T) -> Result<(), String> {
+        let index = self.index(&p).ok_or(format!("{} is outside of the grid {} - {}", p, self.min, self.max))?;
+        self.cells[index] = Some(v);
+        Ok(())
+    }
+
+    pub fn points(&self) -> Vec<Point3D> {
+        let mut points = vec![];
+        for z in self.min.z..=self.max.z {
+            for y in self.min.y..=self.max.y {
+                for x in self.min.x..=self.max.x {
+                    points.push((x, y, z).into());
+                }
+            }
+        }
+        points
+    }
+
+    /// Labels the face-connected components of the cells matching `include` (which is also called for empty cells).
+    /// Returns a grid with a component number (starting at 0) for every included cell, and the number of components.
+    pub fn label_components<F>(&self, include: F) -> (Grid3<usize>, usize) where F: Fn(Option<&T>) -> bool {
+        let mut labels: Grid3<usize> = Grid3::new(self.min, self.max);
+        let mut count = 0;
+
+        for start in self.points() {
+            if labels.has(&start) || !include(self.get(&start)) { continue; }
+
+            let mut queue = vec![start];
+            labels.set(start, count).unwrap();
+            while let Some(point) = queue.pop() {
+                for next in point.get_face_neighbours() {
+                    if self.in_bounds(&next) && !labels.has(&next) && include(self.get(&next)) {
+                        labels.set(next, count).unwrap();
+                        queue.push(next);
+                    }
+                }
+            }
+            count += 1;
+        }
+
+        (labels, count)
+    }
+}
+
+#[cfg(test)]
+mod grid3_tests {
+    use crate::util::geometry::{Grid3, Point3D};
+
+    #[test]
+    fn test_get_set() {
+        let mut grid: Grid3<char> = Grid3::new((-1, 0, 2).into(), (1, 2, 3).into());
+        assert_eq!(18, grid.points().len());
+        assert!(grid.set((-1, 2, 3).into(), 'a').is_ok());
+        assert!(grid.set((0, 0, 2).into(), 'b').is_ok());
+        assert!(grid.set((0, 0, 4).into(), 'c').is_err());
+
+        assert_eq!(Some(&'a'), grid.get(&(-1, 2, 3).into()));
+        assert_eq!(Some(&'b'), grid.get(&(0, 0, 2).into()));
+        assert_eq!(None, grid.get(&(0, 0, 3).into()));
+        assert_eq!(None, grid.get(&(5, 5, 5).into()));
+    }
+
+    #[test]
+    fn test_around() {
+        let points: Vec<Point3D> = vec![(1, 5, -2).into(), (3, 2, 0).into()];
+        let grid: Grid3<()> = Grid3::around(&points, 1);
+        assert_eq!(Point3D::from((0, 1, -3)), grid.min);
+        assert_eq!(Point3D::from((4, 6, 1)), grid.max);
+    }
+
+    #[test]
+    fn test_label_components() {
+        // A hollow 3x3x3 cube: the empty cells form the outside and the single center cell.
+        let mut grid: Grid3<bool> = Grid3::new((-1, -1, -1).into(), (3, 3, 3).into());
+        for x in 0..3 {
+            for y in 0..3 {
+                for z in 0..3 {
+                    if (x, y, z) != (1, 1, 1) {
+                        grid.set((x, y, z).into(), true).unwrap();
+                    }
+                }
+            }
+        }
+
+        let (labels, count) = grid.label_components(|c| c.is_none());
+        assert_eq!(2, count);
+        assert_eq!(Some(&0), labels.get(&(-1, -1, -1).into()));
+        assert_eq!(Some(&1), labels.get(&(1, 1, 1).into()));
+        assert_eq!(None, labels.get(&(0, 0, 0).into()));
+
+        let (_, count) = grid.label_components(|c| c.is_some());
+        assert_eq!(1, count);
+    }
+}
@@ -0,0 +1,20 @@
+// Also included by build.rs for the source fingerprint, so this file cannot depend on the rest of the crate.
+
+/// The 64-bit FNV-1a hash of the bytes: fast and stable across builds and platforms, unlike the std hashers, so it
+/// can be stored (e.g. in cache keys) and compared later. Not meant to resist deliberate collisions.
+pub fn fnv1a(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    bytes.into_iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fnv1a;
+
+    #[test]
+    fn test_fnv1a() {
+        // Reference values of the FNV-1a specification.
+        assert_eq!(0xcbf29ce484222325, fnv1a([]));
+        assert_eq!(0xaf63dc4c8601ec8c, fnv1a(*b"a"));
+        assert_eq!(0x85944171f73967e8, fnv1a(*b"foobar"));
+    }
+}
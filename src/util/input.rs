@@ -1,5 +1,109 @@
+use std::env;
 use std::fs::read_to_string;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::util::error::AocError;
 
-pub fn read_input(day: i32) -> Result<String, String> {
-    read_to_string(format!("resources/day{:02}.txt", day)).map_err(|e| format!("{}", e))
+static LENIENT: AtomicBool = AtomicBool::new(false);
+
+/// The directory holding the puzzle inputs (and recorded results): `resources`, unless overridden with
+/// `AOC_INPUT_DIR`.
+pub fn input_dir() -> String {
+    env::var("AOC_INPUT_DIR").unwrap_or("resources".to_string())
+}
+
+fn input_path(day: i32) -> String {
+    format!("{}/day{:02}.txt", input_dir(), day)
+}
+
+pub fn read_input(day: i32) -> Result<String, AocError> {
+    read_to_string(input_path(day)).map_err(|e| AocError::Io(format!("Cannot read {}: {}", input_path(day), e)))
+}
+
+pub fn has_input(day: i32) -> bool {
+    Path::new(&input_path(day)).exists()
+}
+
+/// Makes `parse_lines` skip the lines it cannot parse instead of failing, set by the `--lenient` flag of the day
+/// command.
+pub fn set_lenient(lenient: bool) {
+    LENIENT.store(lenient, Ordering::Relaxed);
+}
+
+pub fn is_lenient() -> bool {
+    LENIENT.load(Ordering::Relaxed)
+}
+
+/// Parses every line of the input, collecting the errors (located at their line) of all lines that could not be
+/// parsed instead of stopping at the first.
+pub fn parse_lines_collect_errors<T, E: Into<AocError>>(input: &str, parse: impl Fn(&str) -> Result<T, E>) -> (Vec<T>, Vec<AocError>) {
+    let mut values = vec![];
+    let mut errors = vec![];
+    for (index, line) in input.lines().enumerate() {
+        match parse(line) {
+            Ok(value) => values.push(value),
+            Err(e) => errors.push(e.into().in_line(index + 1)),
+        }
+    }
+    (values, errors)
+}
+
+/// Parses every line of the input, failing on the first line that cannot be parsed. In lenient mode, those lines
+/// are reported and skipped instead, to solve with the lines that could be parsed.
+pub fn parse_lines<T, E: Into<AocError>>(input: &str, parse: impl Fn(&str) -> Result<T, E>) -> Result<Vec<T>, AocError> {
+    let (values, errors) = parse_lines_collect_errors(input, parse);
+    skip_errors(values, errors, is_lenient())
+}
+
+/// Parses every line of the input, failing with the first line that cannot be parsed, also in lenient mode.
+pub fn parse_lines_strict<T, E: Into<AocError>>(input: &str, parse: impl Fn(&str) -> Result<T, E>) -> Result<Vec<T>, AocError> {
+    input.lines().enumerate().map(|(index, line)| parse(line).map_err(|e| e.into().in_line(index + 1))).collect()
+}
+
+fn skip_errors<T>(values: Vec<T>, errors: Vec<AocError>, lenient: bool) -> Result<Vec<T>, AocError> {
+    match errors.first() {
+        None => Ok(values),
+        Some(e) if !lenient => Err(e.clone()),
+        Some(_) => {
+            for e in &errors {
+                eprintln!("Skipping: {}", e);
+            }
+            eprintln!("Skipped {} of {} lines", errors.len(), errors.len() + values.len());
+            Ok(values)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::error::AocError;
+    use crate::util::input::{parse_lines, parse_lines_collect_errors, parse_lines_strict, skip_errors};
+    use crate::util::number::parse_i32;
+
+    #[test]
+    fn test_parse_lines_collect_errors() {
+        let (values, errors) = parse_lines_collect_errors("1\nx\n3\n\n5", parse_i32);
+        assert_eq!(vec![1, 3, 5], values);
+        assert_eq!(vec![AocError::parse("Cannot parse 'x': invalid digit found in string").in_line(2), AocError::parse("Cannot parse '': cannot parse integer from empty string").in_line(4)], errors);
+
+        let (values, errors) = parse_lines_collect_errors("", parse_i32);
+        assert!(values.is_empty() && errors.is_empty());
+    }
+
+    #[test]
+    fn test_parse_lines() {
+        assert_eq!(Ok(vec![1, 2]), parse_lines("1\n2\n", parse_i32));
+
+        let errors = vec![AocError::parse("not a number").in_line(3), AocError::parse("not a number either").in_line(4)];
+        assert_eq!(Err("Line 3: not a number".to_string()), skip_errors(vec![1, 2], errors.clone(), false).map_err(|e| e.to_string()));
+        assert_eq!(Ok(vec![1, 2]), skip_errors(vec![1, 2], errors, true));
+        assert_eq!(Ok(vec![1]), skip_errors(vec![1], vec![], false));
+    }
+
+    #[test]
+    fn test_parse_lines_strict() {
+        assert_eq!(Ok(vec![1, 2]), parse_lines_strict("1\n2\n", parse_i32));
+        assert_eq!(Err("Line 2: Cannot parse 'x': invalid digit found in string".to_string()),
+                   parse_lines_strict("1\nx\n3", parse_i32).map_err(|e| e.to_string()));
+    }
 }
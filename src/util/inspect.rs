@@ -0,0 +1,157 @@
+use std::fmt;
+use regex::Regex;
+
+/// Statistics about a puzzle input, to sanity-check a downloaded input before solving it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InputStats {
+    pub lines: usize,
+    pub blank_lines: usize,
+    pub shortest_line: usize,
+    pub longest_line: usize,
+    /// Characters (excluding line endings) with the number of times they occur, most frequent first.
+    pub histogram: Vec<(char, usize)>,
+    pub integer_count: usize,
+    pub integer_range: Option<(i128, i128)>,
+    /// Number of lines in every block of lines separated by blank lines.
+    pub block_sizes: Vec<usize>,
+    /// Width and height, when the (first block of the) input looks like a grid: multiple lines of equal length
+    /// without whitespace.
+    pub grid: Option<(usize, usize)>,
+}
+
+impl InputStats {
+    pub fn create(input: &str) -> Self {
+        let lines: Vec<_> = input.lines().collect();
+        let lengths: Vec<_> = lines.iter().map(|l| l.chars().count()).collect();
+
+        let mut histogram: Vec<(char, usize)> = vec![];
+        for c in lines.iter().flat_map(|l| l.chars()) {
+            match histogram.iter_mut().find(|(h, _)| *h == c) {
+                Some((_, count)) => *count += 1,
+                None => histogram.push((c, 1)),
+            }
+        }
+        histogram.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+
+        // A '-' is only a sign when it does not follow a digit, so ranges like "2-4" are two positive numbers.
+        let integer_regex = Regex::new(r"(?:^|\D)(-?\d+)").unwrap();
+        // Numbers too big for an i128 are counted, but not part of the range.
+        let integers: Vec<_> = integer_regex.captures_iter(input).map(|c| c[1].parse::<i128>().ok()).collect();
+        let values: Vec<_> = integers.iter().flatten().collect();
+        let integer_range = values.iter().min().zip(values.iter().max()).map(|(min, max)| (**min, **max));
+
+        let mut block_sizes = vec![];
+        let mut current = 0;
+        for line in &lines {
+            if line.trim().is_empty() {
+                if current > 0 {
+                    block_sizes.push(current);
+                }
+                current = 0;
+            } else {
+                current += 1;
+            }
+        }
+        if current > 0 {
+            block_sizes.push(current);
+        }
+
+        let first_block: Vec<_> = lines.iter().skip_while(|l| l.trim().is_empty()).take_while(|l| !l.trim().is_empty()).collect();
+        let is_grid = first_block.len() > 1
+            && first_block.iter().all(|l| l.chars().count() == first_block[0].chars().count())
+            && first_block.iter().all(|l| !l.contains(char::is_whitespace));
+        let grid = if is_grid { Some((first_block[0].chars().count(), first_block.len())) } else { None };
+
+        InputStats {
+            lines: lines.len(),
+            blank_lines: lines.iter().filter(|l| l.trim().is_empty()).count(),
+            shortest_line: lengths.iter().copied().min().unwrap_or(0),
+            longest_line: lengths.iter().copied().max().unwrap_or(0),
+            histogram,
+            integer_count: integers.len(),
+            integer_range,
+            block_sizes,
+            grid,
+        }
+    }
+}
+
+fn describe_char(c: char) -> String {
+    match c {
+        ' ' => "' '".to_string(),
+        '\t' => "'\\t'".to_string(),
+        c => c.to_string(),
+    }
+}
+
+impl fmt::Display for InputStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Lines: {} ({} blank), {} to {} characters long", self.lines, self.blank_lines, self.shortest_line, self.longest_line)?;
+        let histogram: Vec<_> = self.histogram.iter().map(|(c, count)| format!("{}: {}", describe_char(*c), count)).collect();
+        writeln!(f, "Characters ({} distinct): {}", self.histogram.len(), histogram.join(", "))?;
+        match self.integer_range {
+            Some((min, max)) => writeln!(f, "Integers: {}, ranging from {} to {}", self.integer_count, min, max)?,
+            None => writeln!(f, "Integers: {}", self.integer_count)?,
+        }
+        if self.block_sizes.len() > 1 {
+            let sizes: Vec<_> = self.block_sizes.iter().map(|s| s.to_string()).collect();
+            writeln!(f, "Blocks: {}, of {} lines", self.block_sizes.len(), sizes.join(", "))?;
+        }
+        if let Some((width, height)) = self.grid {
+            writeln!(f, "Grid: {}x{}", width, height)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::inspect::InputStats;
+
+    #[test]
+    fn test_grid_input() {
+        let stats = InputStats::create("30373\n25512\n65332\n33549\n35390\n");
+        assert_eq!(5, stats.lines);
+        assert_eq!(0, stats.blank_lines);
+        assert_eq!(Some((5, 5)), stats.grid);
+        assert_eq!(Some(('3', 9)), stats.histogram.first().copied());
+        assert_eq!(5, stats.integer_count);
+        assert_eq!(Some((25512, 65332)), stats.integer_range);
+        assert_eq!(vec![5], stats.block_sizes);
+    }
+
+    #[test]
+    fn test_block_input() {
+        let stats = InputStats::create("100\n2000\n\n-4000\n\n5000\n6000\n7000\n");
+        assert_eq!(8, stats.lines);
+        assert_eq!(2, stats.blank_lines);
+        assert_eq!(vec![2, 1, 3], stats.block_sizes);
+        assert_eq!(Some((-4000, 7000)), stats.integer_range);
+        // Lines of different lengths are not a grid.
+        assert_eq!(None, stats.grid);
+        assert_eq!((0, 5), (stats.shortest_line, stats.longest_line));
+
+        assert_eq!("\
+Lines: 8 (2 blank), 0 to 5 characters long
+Characters (8 distinct): 0: 17, -: 1, 1: 1, 2: 1, 4: 1, 5: 1, 6: 1, 7: 1
+Integers: 6, ranging from -4000 to 7000
+Blocks: 3, of 2, 1, 3 lines
+", stats.to_string());
+    }
+
+    #[test]
+    fn test_range_input() {
+        let stats = InputStats::create(include_str!("../../examples/day04/input.txt"));
+        assert_eq!(24, stats.integer_count);
+        assert_eq!(Some((2, 9)), stats.integer_range);
+        assert_eq!(Some((-3, 5)), InputStats::create("x=-3, y=5-3\n-1--2").integer_range);
+    }
+
+    #[test]
+    fn test_empty_input() {
+        let stats = InputStats::create("");
+        assert_eq!(0, stats.lines);
+        assert_eq!(None, stats.integer_range);
+        assert_eq!("Lines: 0 (0 blank), 0 to 0 characters long\nCharacters (0 distinct): \nIntegers: 0\n", stats.to_string());
+    }
+}
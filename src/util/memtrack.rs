@@ -0,0 +1,211 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The number of trackers that can be active at the same time.
+const TRACKER_SLOTS: usize = 32;
+
+/// Wraps the system allocator, counting the bytes in use and the number of allocations. Every active tracker has a
+/// slot with its own highest number of bytes in use, so overlapping trackers (e.g. of tests running in parallel) do
+/// not reset each other's peak. Only installed as global allocator with the `memtrack` feature, as the counting is
+/// not free.
+pub struct CountingAllocator {
+    current: AtomicUsize,
+    allocations: AtomicUsize,
+    peaks: [AtomicUsize; TRACKER_SLOTS],
+    /// A bit for every slot of `peaks` in use by a tracker.
+    active_slots: AtomicUsize,
+}
+
+#[cfg(feature = "memtrack")]
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator::new();
+
+#[cfg_attr(not(feature = "memtrack"), allow(dead_code))]
+impl CountingAllocator {
+    pub const fn new() -> Self {
+        CountingAllocator {
+            current: AtomicUsize::new(0),
+            allocations: AtomicUsize::new(0),
+            peaks: [const { AtomicUsize::new(0) }; TRACKER_SLOTS],
+            active_slots: AtomicUsize::new(0),
+        }
+    }
+
+    fn allocated(&self, size: usize) {
+        let current = self.current.fetch_add(size, Ordering::Relaxed) + size;
+        let mut active = self.active_slots.load(Ordering::Acquire);
+        while active != 0 {
+            self.peaks[active.trailing_zeros() as usize].fetch_max(current, Ordering::Relaxed);
+            active &= active - 1;
+        }
+        self.allocations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn freed(&self, size: usize) {
+        self.current.fetch_sub(size, Ordering::Relaxed);
+    }
+
+    /// Starts measuring from the current state, see `Tracker::finish`. None when all tracker slots are in use.
+    pub fn track(&self) -> Option<Tracker<'_>> {
+        let mut active = self.active_slots.load(Ordering::Relaxed);
+        let slot = loop {
+            let slot = (!active).trailing_zeros() as usize;
+            if slot >= TRACKER_SLOTS {
+                return None;
+            }
+            match self.active_slots.compare_exchange_weak(active, active | 1 << slot, Ordering::AcqRel, Ordering::Relaxed) {
+                Ok(_) => break slot,
+                Err(actual) => active = actual,
+            }
+        };
+
+        let current = self.current.load(Ordering::Relaxed);
+        self.peaks[slot].store(current, Ordering::Relaxed);
+        Some(Tracker { allocator: self, slot, start: current, allocations: self.allocations.load(Ordering::Relaxed) })
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            self.allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        self.freed(layout.size());
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            self.allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            self.freed(layout.size());
+            self.allocated(new_size);
+        }
+        new_ptr
+    }
+}
+
+/// Memory used between `CountingAllocator::track` and `Tracker::finish`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct MemoryUsage {
+    /// Highest number of bytes in use on top of what was in use when tracking started.
+    pub peak: usize,
+    /// Bytes still in use on top of what was in use when tracking started.
+    pub resident: usize,
+    pub allocations: usize,
+}
+
+/// Measures the memory used from its creation, until `finish`ed. Its slot is released again when dropped.
+pub struct Tracker<'a> {
+    allocator: &'a CountingAllocator,
+    slot: usize,
+    start: usize,
+    allocations: usize,
+}
+
+impl Tracker<'_> {
+    pub fn finish(self) -> MemoryUsage {
+        MemoryUsage {
+            peak: self.allocator.peaks[self.slot].load(Ordering::Relaxed).saturating_sub(self.start),
+            resident: self.allocator.current.load(Ordering::Relaxed).saturating_sub(self.start),
+            allocations: self.allocator.allocations.load(Ordering::Relaxed) - self.allocations,
+        }
+    }
+}
+
+impl Drop for Tracker<'_> {
+    fn drop(&mut self) {
+        self.allocator.active_slots.fetch_and(!(1 << self.slot), Ordering::AcqRel);
+    }
+}
+
+/// Starts tracking the memory used by the global allocator, or None when built without the `memtrack` feature (or
+/// when too many trackers are active already). Note that allocations from other threads are counted as well.
+pub fn track() -> Option<Tracker<'static>> {
+    #[cfg(feature = "memtrack")]
+    return ALLOCATOR.track();
+    #[cfg(not(feature = "memtrack"))]
+    None
+}
+
+fn format_bytes(bytes: usize) -> String {
+    match bytes {
+        0..=1023 => format!("{} B", bytes),
+        1024..=1_048_575 => format!("{:.1} KiB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0)),
+    }
+}
+
+impl fmt::Display for MemoryUsage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "peak {}, resident {}, {} allocations", format_bytes(self.peak), format_bytes(self.resident), self.allocations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::alloc::{GlobalAlloc, Layout};
+    use crate::util::memtrack::{CountingAllocator, MemoryUsage, TRACKER_SLOTS};
+
+    #[test]
+    fn test_counting_allocator() {
+        let allocator = CountingAllocator::new();
+        let kept = unsafe { allocator.alloc(Layout::from_size_align(100, 8).unwrap()) };
+
+        let tracker = allocator.track().unwrap();
+        unsafe {
+            let layout = Layout::from_size_align(1000, 8).unwrap();
+            let a = allocator.alloc(layout);
+            let b = allocator.alloc_zeroed(Layout::from_size_align(24, 8).unwrap());
+            allocator.dealloc(a, layout);
+            let b = allocator.realloc(b, Layout::from_size_align(24, 8).unwrap(), 48);
+
+            assert_eq!(MemoryUsage { peak: 1024, resident: 48, allocations: 3 }, tracker.finish());
+            allocator.dealloc(b, Layout::from_size_align(48, 8).unwrap());
+            allocator.dealloc(kept, Layout::from_size_align(100, 8).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_overlapping_trackers() {
+        let allocator = CountingAllocator::new();
+        let layout = Layout::from_size_align(1000, 8).unwrap();
+        unsafe {
+            let outer = allocator.track().unwrap();
+            let a = allocator.alloc(layout);
+            allocator.dealloc(a, layout);
+
+            // A tracker started later has its own peak, and does not reset that of the first one.
+            let inner = allocator.track().unwrap();
+            let b = allocator.alloc(Layout::from_size_align(10, 8).unwrap());
+            assert_eq!(10, inner.finish().peak);
+            allocator.dealloc(b, Layout::from_size_align(10, 8).unwrap());
+            assert_eq!(1000, outer.finish().peak);
+        }
+
+        // Finished trackers free their slot, and there are only so many slots.
+        let trackers: Vec<_> = std::iter::from_fn(|| allocator.track()).collect();
+        assert_eq!(TRACKER_SLOTS, trackers.len());
+        drop(trackers);
+        assert!(allocator.track().is_some());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!("peak 1.5 MiB, resident 512 B, 3 allocations", MemoryUsage { peak: 1_572_864, resident: 512, allocations: 3 }.to_string());
+        assert_eq!("peak 2.0 KiB, resident 0 B, 1 allocations", MemoryUsage { peak: 2048, resident: 0, allocations: 1 }.to_string());
+    }
+}
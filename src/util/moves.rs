@@ -0,0 +1,83 @@
+// Allow dead_code since this is a util file copied across years. Later in the AoC we might use everything, or not.
+#![allow(dead_code)]
+
+use crate::util::error::AocError;
+use crate::util::number::parse_usize;
+
+/// A part of a compact move string, like "R4U4L3" or "10R5L5".
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MoveToken {
+    Letter(char),
+    Number(usize),
+}
+
+/// Splits a compact move string into letters and numbers. Whitespace is not allowed anywhere, except at the end.
+pub fn tokenize(input: &str) -> Result<Vec<MoveToken>, AocError> {
+    let input = input.trim_end();
+    let mut tokens = vec![];
+    let mut number_start = None;
+
+    for (index, c) in input.char_indices() {
+        if c.is_ascii_digit() {
+            number_start.get_or_insert(index);
+            continue;
+        }
+        if let Some(start) = number_start.take() {
+            tokens.push(MoveToken::Number(parse_usize(&input[start..index])?));
+        }
+        if !c.is_ascii_alphabetic() {
+            return Err(AocError::parse_at(1, index + 1, format!("Unexpected '{}' in the moves", c)));
+        }
+        tokens.push(MoveToken::Letter(c));
+    }
+    if let Some(start) = number_start {
+        tokens.push(MoveToken::Number(parse_usize(&input[start..])?));
+    }
+
+    Ok(tokens)
+}
+
+/// Parses a compact move string where every letter is followed by an amount, like "R4U4L3".
+pub fn letters_with_amounts(input: &str) -> Result<Vec<(char, usize)>, AocError> {
+    let tokens = tokenize(input)?;
+    tokens.chunks(2).map(|chunk| match chunk {
+        [MoveToken::Letter(letter), MoveToken::Number(amount)] => Ok((*letter, *amount)),
+        [MoveToken::Letter(letter)] => Err(AocError::parse(format!("Expected an amount after the last '{}' of the moves", letter))),
+        _ => Err(AocError::parse(format!("Expected a letter followed by an amount, got {:?}", chunk))),
+    }).collect()
+}
+
+/// Whether the input looks like a compact move string: a single word, without any whitespace in between.
+pub fn is_compact(input: &str) -> bool {
+    let input = input.trim();
+    !input.is_empty() && !input.contains(char::is_whitespace)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::error::AocError;
+    use crate::util::moves::{is_compact, letters_with_amounts, MoveToken, tokenize};
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(Ok(vec![MoveToken::Number(10), MoveToken::Letter('R'), MoveToken::Number(5), MoveToken::Letter('L')]), tokenize("10R5L\n"));
+        assert_eq!(Ok(vec![]), tokenize(""));
+        assert_eq!(Err(AocError::parse_at(1, 3, "Unexpected ' ' in the moves")), tokenize("R4 U4"));
+        assert_eq!(Err(AocError::parse_at(1, 2, "Unexpected '-' in the moves")), tokenize("R-4"));
+    }
+
+    #[test]
+    fn test_letters_with_amounts() {
+        assert_eq!(Ok(vec![('R', 4), ('U', 4), ('L', 13)]), letters_with_amounts("R4U4L13"));
+        assert_eq!(Err(AocError::parse("Expected an amount after the last 'U' of the moves")), letters_with_amounts("R4U"));
+        assert!(letters_with_amounts("4R").is_err());
+        assert!(letters_with_amounts("RU4").is_err());
+    }
+
+    #[test]
+    fn test_is_compact() {
+        assert!(is_compact("R4U4L3\n"));
+        assert!(!is_compact("R 4\nU 4\n"));
+        assert!(!is_compact("\n"));
+    }
+}
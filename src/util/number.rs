@@ -1,13 +1,14 @@
 // Allow dead_code since this is a util file copied across years. Later in the AoC we might use everything, or not.
 #![allow(dead_code)]
 
-use num_traits::Num;
+use num_traits::{CheckedAdd, CheckedMul, Num};
+use crate::util::error::AocError;
 
 macro_rules! parse_int_impl {
     ($($t:ty, $name: ident)*) => {$(
         #[allow(unused)]
-        pub fn $name(input: &str) -> Result<$t, String> {
-            input.to_string().parse().map_err(|e| format!("{}", e))
+        pub fn $name(input: &str) -> Result<$t, AocError> {
+            input.to_string().parse().map_err(|e| AocError::parse(format!("Cannot parse '{}': {}", input, e)))
         }
     )*}
 }
@@ -57,31 +58,88 @@ pub fn gcd<T: Num + Copy>(a: T, b: T) -> T {
     return gcd(b, a % b);
 }
 
+/// Division rounding up, i.e. towards positive infinity (also for negative numbers).
+pub fn ceil_div<T: Num + Copy + PartialOrd>(numerator: T, denominator: T) -> T {
+    let quotient = numerator / denominator;
+    let remainder = numerator % denominator;
+    if remainder != T::zero() && (remainder > T::zero()) == (denominator > T::zero()) {
+        quotient + T::one()
+    } else {
+        quotient
+    }
+}
+
+/// The largest integer whose square is at most `n`, using Newton's method on integers.
+pub fn isqrt<T: Num + Copy + PartialOrd>(n: T) -> T {
+    let two = T::one() + T::one();
+    if n < T::zero() {
+        panic!("Cannot take the square root of a negative number");
+    }
+    if n < two {
+        return n;
+    }
+
+    // n / 2 + 1 is at least the root for n >= 2, and the iteration decreases until it reaches it.
+    let mut root = n / two + T::one();
+    let mut next = (root + n / root) / two;
+    while next < root {
+        root = next;
+        next = (root + n / root) / two;
+    }
+    root
+}
+
+/// The n-th triangular number: 1 + 2 + ... + n.
+pub fn triangular<T: Num + Copy>(n: T) -> T {
+    n * (n + T::one()) / (T::one() + T::one())
+}
+
+/// The nearest-rank percentile: the smallest value that at least `p` percent of the values are at or below. None
+/// for no values.
+pub fn percentile<T: Ord + Copy>(values: &[T], p: usize) -> Option<T> {
+    let mut sorted = values.to_vec();
+    sorted.sort();
+    let rank = ceil_div(p.min(100) * sorted.len(), 100).max(1);
+    sorted.get(rank - 1).copied()
+}
+
 pub trait NumberExtensions<T> {
-    fn lcm(&self) -> T;
-    fn gcd(&self) -> T;
+    fn lcm(self) -> T;
+    fn gcd(self) -> T;
+    fn sum_checked(self) -> Option<T>;
+    fn product_checked(self) -> Option<T>;
 }
-impl<T> NumberExtensions<T> for Vec<T> where T: Num + Copy + Clone {
-    fn lcm(&self) -> T {
-        if let Some((first, rest)) = self.split_first() {
-            rest.iter().fold(first.clone(), |acc,v| lcm(acc, v.clone()))
+impl<I, T> NumberExtensions<T> for I where I: IntoIterator<Item=T>, T: Num + Copy + CheckedAdd + CheckedMul {
+    fn lcm(self) -> T {
+        let mut iter = self.into_iter();
+        if let Some(first) = iter.next() {
+            iter.fold(first, lcm)
         } else {
             T::zero()
         }
     }
 
-    fn gcd(&self) -> T {
-        if let Some((first, rest)) = self.split_first() {
-            rest.iter().fold(first.clone(), |acc,v| gcd(acc, v.clone()))
+    fn gcd(self) -> T {
+        let mut iter = self.into_iter();
+        if let Some(first) = iter.next() {
+            iter.fold(first, gcd)
         } else {
             T::zero()
         }
     }
+
+    fn sum_checked(self) -> Option<T> {
+        self.into_iter().try_fold(T::zero(), |acc, v| acc.checked_add(&v))
+    }
+
+    fn product_checked(self) -> Option<T> {
+        self.into_iter().try_fold(T::one(), |acc, v| acc.checked_mul(&v))
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::util::number::{gcd, lcm, NumberExtensions, parse_binary};
+    use crate::util::number::{ceil_div, gcd, isqrt, lcm, NumberExtensions, parse_binary, percentile, triangular};
 
     #[test]
     fn test_parse_binary() {
@@ -97,6 +155,8 @@ mod tests {
         assert_eq!(96, lcm(6, 32));
 
         assert_eq!(12, vec![4, 6, 3].lcm());
+        assert_eq!(12, [4, 6, 3].iter().copied().lcm());
+        assert_eq!(0, Vec::<usize>::new().lcm());
     }
 
     #[test]
@@ -105,6 +165,62 @@ mod tests {
         assert_eq!(12, gcd(36, 12));
         assert_eq!(4, gcd(36, 32));
 
-        assert_eq!(4, vec![36, 32, 48].gcd())
+        assert_eq!(4, vec![36, 32, 48].gcd());
+        assert_eq!(12, [36, 32, 48].into_iter().filter(|v| *v > 32).gcd());
+    }
+
+    #[test]
+    fn test_ceil_div() {
+        assert_eq!(3, ceil_div(7, 3));
+        assert_eq!(2, ceil_div(6, 3));
+        assert_eq!(0, ceil_div(0, 3));
+        assert_eq!(-2, ceil_div(-7, 3));
+        assert_eq!(3, ceil_div(-7, -3));
+        assert_eq!(-2, ceil_div(7, -3));
+        assert_eq!(1, ceil_div(1_u16, 5));
+    }
+
+    #[test]
+    fn test_isqrt() {
+        let roots: Vec<usize> = (0..=17).map(isqrt).collect();
+        assert_eq!(vec![0, 1, 1, 1, 2, 2, 2, 2, 2, 3, 3, 3, 3, 3, 3, 3, 4, 4], roots);
+        assert_eq!(4_294_967_295, isqrt(u64::MAX));
+        assert_eq!(1_000_000, isqrt(1_000_000_000_000_i64));
+        assert_eq!(999_999, isqrt(999_999_999_999_i64));
+    }
+
+    #[test]
+    fn test_triangular() {
+        assert_eq!(0, triangular(0));
+        assert_eq!(1, triangular(1));
+        assert_eq!(55, triangular(10));
+        assert_eq!(465_u16, triangular(30));
+    }
+
+    #[test]
+    fn test_percentile() {
+        let values = [15, 20, 35, 40, 50];
+        assert_eq!(Some(35), percentile(&values, 50));
+        assert_eq!(Some(50), percentile(&values, 90));
+        assert_eq!(Some(50), percentile(&values, 100));
+        assert_eq!(Some(15), percentile(&values, 0));
+        assert_eq!(Some(20), percentile(&[40, 20, 50, 15, 35], 30));
+        assert_eq!(None, percentile::<i32>(&[], 50));
+    }
+
+    #[test]
+    fn test_sum_checked() {
+        assert_eq!(Some(10), vec![1, 2, 3, 4].sum_checked());
+        assert_eq!(Some(0), Vec::<u8>::new().sum_checked());
+        assert_eq!(None, [200_u8, 50, 10].sum_checked());
+        assert_eq!(Some(-5), [-10_isize, 5].sum_checked());
+    }
+
+    #[test]
+    fn test_product_checked() {
+        assert_eq!(Some(24), (1..=4).product_checked());
+        assert_eq!(Some(1), Vec::<u8>::new().product_checked());
+        assert_eq!(None, [16_u8, 16].product_checked());
+        assert_eq!(None, (1..=25_usize).product_checked());
     }
 }
\ No newline at end of file
@@ -0,0 +1,98 @@
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::panic;
+use std::panic::UnwindSafe;
+use std::sync::Once;
+
+#[derive(Debug)]
+pub struct PanicError {
+    pub message: String,
+    pub backtrace: String,
+}
+
+impl fmt::Display for PanicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+thread_local! {
+    static LAST_PANIC: RefCell<Option<PanicError>> = const { RefCell::new(None) };
+    static CATCHING: Cell<bool> = const { Cell::new(false) };
+}
+
+static INSTALL_HOOK: Once = Once::new();
+
+fn payload_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Unknown panic payload".to_string()
+    }
+}
+
+// Installs (once, for the whole process) a hook recording panics of threads inside `catch_panic`. Panics of other
+// threads go to the hook that was installed before.
+fn install_hook() {
+    INSTALL_HOOK.call_once(|| {
+        let previous_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !CATCHING.with(|c| c.get()) {
+                return previous_hook(info);
+            }
+            let location = info.location().map(|l| format!(" at {}:{}", l.file(), l.line())).unwrap_or_default();
+            let error = PanicError {
+                message: format!("panicked{}: {}", location, payload_message(info.payload())),
+                backtrace: Backtrace::force_capture().to_string(),
+            };
+            LAST_PANIC.with(|p| *p.borrow_mut() = Some(error));
+        }));
+    });
+}
+
+/// Runs the given function, converting a panic into an error containing the panic message and backtrace
+/// instead of unwinding further. The default panic output is suppressed while the function runs.
+pub fn catch_panic<F, R>(f: F) -> Result<R, PanicError> where F: FnOnce() -> R + UnwindSafe {
+    install_hook();
+    let was_catching = CATCHING.with(|c| c.replace(true));
+    let result = panic::catch_unwind(f);
+    CATCHING.with(|c| c.set(was_catching));
+
+    result.map_err(|payload| LAST_PANIC.with(|p| p.borrow_mut().take())
+        .unwrap_or_else(|| PanicError { message: payload_message(payload.as_ref()), backtrace: String::new() }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use crate::util::panic::{catch_panic, CATCHING};
+
+    #[test]
+    fn test_catch_panic() {
+        assert_eq!(42, catch_panic(|| 42).unwrap());
+
+        let error = catch_panic(|| -> usize { panic!("Halpz! {}", 42) }).unwrap_err();
+        assert!(error.message.starts_with("panicked at src/util/panic.rs:"), "{}", error.message);
+        assert!(error.message.ends_with(": Halpz! 42"), "{}", error.message);
+        assert!(!error.backtrace.is_empty());
+
+        let error = catch_panic(|| "abc".parse::<usize>().unwrap()).unwrap_err();
+        assert!(error.message.contains("called `Result::unwrap()` on an `Err` value"), "{}", error.message);
+
+        // Nested and concurrent calls only catch their own panics, and leave other threads alone.
+        let nested = catch_panic(|| catch_panic(|| -> usize { panic!("inner") }).unwrap_err().message).unwrap();
+        assert!(nested.ends_with(": inner"), "{}", nested);
+        let messages: Vec<_> = (0..8).map(|i| thread::spawn(move || catch_panic(|| -> usize { panic!("thread {}", i) }).unwrap_err().message))
+            .map(|h| h.join().unwrap())
+            .collect();
+        for (i, message) in messages.iter().enumerate() {
+            assert!(message.ends_with(&format!(": thread {}", i)), "{}", message);
+        }
+        assert!(!CATCHING.with(|c| c.get()));
+        assert!(thread::spawn(|| panic!("not caught")).join().is_err());
+    }
+}
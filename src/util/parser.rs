@@ -1,90 +1,598 @@
 use std::cmp::min;
-use crate::util::number::parse_usize;
+use crate::util::error::AocError;
 
 pub struct Parser {
     input: String,
-    position: usize
+    position: usize,
+    /// The position of the first character of every line, to locate errors.
+    line_starts: Vec<usize>
 }
 
+/// A position in the input to go back to with `Parser::restore`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Checkpoint(usize);
+
 impl Parser {
     pub fn new<T>(input: T) -> Self
         where T: ToString {
-        Parser { input: input.to_string(), position: 0 }
+        let input = input.to_string();
+        let line_starts = [0].into_iter().chain(input.match_indices('\n').map(|(index, _)| index + 1)).collect();
+        Parser { input, position: 0, line_starts }
     }
 
     fn skip_whitespace(&mut self) {
         self.position += self.input.chars().skip(self.position).take_while(|c| c.is_whitespace()).count()
     }
 
-    pub fn literal(&mut self, literal: &str) -> Result<(), String> {
+    pub fn literal(&mut self, literal: &str) -> Result<(), AocError> {
         self.skip_whitespace();
 
         let actual = &self.input[self.position..min(self.position+literal.len(), self.input.len())];
         if actual != literal {
-            Err(format!("Expected '{}' to match '{}' ('{}':{})", actual, literal, self.input, self.position))
+            Err(self.error(format!("Expected '{}' to match '{}'", actual, literal)))
         } else {
             self.position += literal.len();
             Ok(())
         }
     }
 
-    pub fn one_of(&mut self, options: Vec<&'static str>) -> Result<&'static str, String> {
+    pub fn one_of<'a>(&mut self, options: Vec<&'a str>) -> Result<&'a str, AocError> {
+        self.skip_whitespace();
         for option in &options {
-            if self.literal(option).is_ok() {
+            if self.try_parse(|parser| parser.literal(option)).is_ok() {
                 return Ok(option)
             }
         }
 
-        Err(format!("Expected one of {} ('{}':{})", options.iter().map(|o| format!("'{}'",o)).collect::<Vec<_>>().join(", "), self.input, self.position))
+        Err(self.error(format!("Expected one of {}", options.iter().map(|o| format!("'{}'",o)).collect::<Vec<_>>().join(", "))))
     }
 
-    pub fn usize(&mut self) -> Result<usize, String> {
+    pub fn usize(&mut self) -> Result<usize, AocError> {
         self.skip_whitespace();
+        self.digits(10, "a number")
+    }
 
-        let mut result = 0;
+    /// A hexadecimal number like "1f" or "0x1F".
+    #[cfg(test)]
+    pub fn hex_usize(&mut self) -> Result<usize, AocError> {
+        self.try_parse(|parser| {
+            parser.skip_whitespace();
+            parser.radix_prefix(&["0x", "0X"]);
+            parser.digits(16, "a hexadecimal number")
+        })
+    }
 
-        // consume at least one numeric character
-        let numbers: Vec<_> = self.input.chars().skip(self.position)
-            .take_while(|c| c.is_numeric())
-            .collect();
-        if numbers.len() == 0 { return Err(format!("Expected to find a number. ('{}':{})", self.input, self.position)) }
+    /// A binary number like "101" or "0b101".
+    #[cfg(test)]
+    pub fn binary_usize(&mut self) -> Result<usize, AocError> {
+        self.try_parse(|parser| {
+            parser.skip_whitespace();
+            parser.radix_prefix(&["0b", "0B"]);
+            parser.digits(2, "a binary number")
+        })
+    }
 
-        for char in numbers.iter() {
-            result *= 10;
-            result += parse_usize(char.to_string().as_str())?;
-        }
+    pub fn isize(&mut self) -> Result<isize, AocError> {
+        // A '-' without a number is not consumed either.
+        self.try_parse(|parser| {
+            let sign = parser.sign();
+            Ok(sign * (parser.usize()?) as isize)
+        })
+    }
+
+    /// A decimal number like "3", "-0.25" or "1.5e3".
+    #[cfg(test)]
+    pub fn f64(&mut self) -> Result<f64, AocError> {
+        self.try_parse(|parser| {
+            let sign = parser.sign();
+            let start = parser.position;
+            let digits = |parser: &mut Parser| parser.position += parser.remaining().chars().take_while(|c| c.is_ascii_digit()).count();
+
+            digits(parser);
+            if parser.remaining().starts_with('.') {
+                parser.position += 1;
+                digits(parser);
+            }
+            let mantissa = &parser.input[start..parser.position];
+            if !mantissa.chars().any(|c| c.is_ascii_digit()) {
+                parser.position = start;
+                return Err(parser.error("Expected to find a decimal number".to_string()));
+            }
+            // The exponent only counts when it has digits, so the 'e' of e.g. "2 eggs" is left alone.
+            let exponent = parser.save();
+            if parser.remaining().starts_with(['e', 'E']) {
+                parser.position += 1;
+                if parser.remaining().starts_with(['-', '+']) {
+                    parser.position += 1;
+                }
+                if !parser.remaining().starts_with(|c: char| c.is_ascii_digit()) {
+                    parser.restore(exponent);
+                }
+                digits(parser);
+            }
 
-        self.position += numbers.len();
-        Ok(result)
+            let number: f64 = parser.input[start..parser.position].parse()
+                .map_err(|e| parser.error(format!("Invalid decimal number: {}", e)))?;
+            Ok(sign as f64 * number)
+        })
     }
 
-    pub fn isize(&mut self) -> Result<isize, String> {
+    /// Skips whitespace and consumes an optional '-', returning the sign of the number that follows.
+    fn sign(&mut self) -> isize {
         self.skip_whitespace();
-
-        let modifier = if self.input.chars().nth(self.position) == Some('-') {
+        if self.remaining().starts_with('-') {
             self.position += 1;
             -1
         } else {
             1
-        };
+        }
+    }
+
+    #[cfg(test)]
+    fn radix_prefix(&mut self, prefixes: &[&str]) {
+        if let Some(prefix) = prefixes.iter().find(|p| self.remaining().starts_with(*p)) {
+            self.position += prefix.len();
+        }
+    }
+
+    /// At least one digit in the given radix, describing the number as `what` when there are none.
+    fn digits(&mut self, radix: u32, what: &str) -> Result<usize, AocError> {
+        let length = self.remaining().chars().take_while(|c| c.is_digit(radix)).count();
+        if length == 0 {
+            return Err(self.error(format!("Expected to find {}", what)));
+        }
 
-        Ok(modifier * (self.usize()?) as isize)
+        let number = usize::from_str_radix(&self.remaining()[..length], radix)
+            .map_err(|e| self.error(format!("Invalid number '{}': {}", &self.remaining()[..length], e)))?;
+        self.position += length;
+        Ok(number)
     }
 
-    pub fn str(&mut self, len: usize) -> Result<String, String> {
+    pub fn str(&mut self, len: usize) -> Result<String, AocError> {
         self.skip_whitespace();
 
         let result: Vec<_> = self.input.chars().skip(self.position).take(len).collect();
         if result.len() != len {
-            Err(format!("Expected to read {} chars, but only got {}. ('{}':{})", len, result.len(), self.input, self.position))
+            Err(self.error(format!("Expected to read {} chars, but only got {}", len, result.len())))
         } else {
             self.position += len;
             Ok(result.iter().collect())
         }
     }
 
+    /// The characters up to (not including) the next `end`, which is left to parse next.
+    #[cfg(test)]
+    pub fn take_until(&mut self, end: char) -> Result<String, AocError> {
+        self.skip_whitespace();
+
+        match self.remaining().find(end) {
+            Some(length) => {
+                let taken = self.remaining()[..length].to_string();
+                self.position += length;
+                Ok(taken)
+            }
+            None => Err(self.error(format!("Expected to find '{}'", end)))
+        }
+    }
+
+    /// All of the input that has not been parsed yet, without the whitespace around it. Empty when exhausted.
+    pub fn rest(&mut self) -> String {
+        let rest = self.remaining().trim().to_string();
+        self.position = self.input.len();
+        rest
+    }
+
+    /// The (1-based) line and column of the next character to be parsed.
+    pub fn location(&self) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|start| *start <= self.position);
+        let column = self.input[self.line_starts[line - 1]..self.position].chars().count() + 1;
+        (line, column)
+    }
+
+    /// A parse error at the current position, with the line it is on and a caret under the next character to be
+    /// parsed appended to the message.
+    fn error(&self, message: String) -> AocError {
+        let (line, column) = self.location();
+        let text = self.input[self.line_starts[line - 1]..].lines().next().unwrap_or("");
+        AocError::parse_at(line, column, format!("{}\n  {}\n  {}^", message, text, " ".repeat(column - 1)))
+    }
+
     pub fn is_exhausted(&self) -> bool {
         let rest = &self.input[self.position..self.input.len()];
         rest.is_empty() || rest.chars().all(|c| c.is_whitespace())
     }
-}
\ No newline at end of file
+
+    /// The complete input this parser was created with.
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// The position (in bytes) of the next character to be parsed.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// The part of the input that has not been parsed yet.
+    pub fn remaining(&self) -> &str {
+        &self.input[self.position..]
+    }
+
+    /// Marks the current position, to `restore` when parsing something fails halfway.
+    pub fn save(&self) -> Checkpoint {
+        Checkpoint(self.position)
+    }
+
+    pub fn restore(&mut self, checkpoint: Checkpoint) {
+        self.position = checkpoint.0;
+    }
+
+    /// Whether the input continues with the literal (after any whitespace), without consuming anything.
+    pub fn peek_literal(&self, literal: &str) -> bool {
+        self.remaining().trim_start().starts_with(literal)
+    }
+
+    /// Tries to parse something with `f`, backtracking if it fails. Unlike `opt`, the error is kept, so
+    /// alternatives can be tried with `or_else`, and the error of the last one is reported.
+    pub fn try_parse<T, F>(&mut self, f: F) -> Result<T, AocError> where F: FnOnce(&mut Parser) -> Result<T, AocError> {
+        let checkpoint = self.save();
+        f(self).inspect_err(|_| self.restore(checkpoint))
+    }
+
+    /// Tries to parse something with `f`, returning None (and backtracking) if it fails.
+    /// Use `.unwrap_or(default)` on the result for optional sections with a default value.
+    pub fn opt<T, F>(&mut self, f: F) -> Option<T> where F: FnOnce(&mut Parser) -> Result<T, AocError> {
+        self.try_parse(f).ok()
+    }
+
+    /// Parses with `f` as many times as possible (including zero times), backtracking the failed attempt.
+    /// Stops as well when `f` succeeds without consuming any input, as it would succeed forever.
+    #[cfg(test)]
+    pub fn many0<T, F>(&mut self, mut f: F) -> Vec<T> where F: FnMut(&mut Parser) -> Result<T, AocError> {
+        let mut values = vec![];
+        loop {
+            let start = self.save();
+            match self.opt(&mut f) {
+                Some(value) => {
+                    values.push(value);
+                    if self.save() == start {
+                        break;
+                    }
+                }
+                None => break
+            }
+        }
+        values
+    }
+
+    /// Parses at least one item with `item`, and another one after every `separator`, like "79, 98" or
+    /// "498,4 -> 498,6". A separator that is not followed by an item is an error, rather than the end of the list.
+    pub fn separated_list<T, F>(&mut self, separator: &str, mut item: F) -> Result<Vec<T>, AocError> where F: FnMut(&mut Parser) -> Result<T, AocError> {
+        let mut items = vec![item(self)?];
+        while self.peek_literal(separator) {
+            self.literal(separator)?;
+            items.push(item(self)?);
+        }
+        Ok(items)
+    }
+
+    /// Like `many0`, but fails (with the error of the first attempt) when `f` does not match at least once.
+    #[cfg(test)]
+    pub fn many1<T, F>(&mut self, mut f: F) -> Result<Vec<T>, AocError> where F: FnMut(&mut Parser) -> Result<T, AocError> {
+        let first = self.try_parse(&mut f)?;
+        let mut values = vec![first];
+        values.extend(self.many0(f));
+        Ok(values)
+    }
+}
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Value {
+    Int(isize),
+    UInt(usize),
+    Word(String),
+}
+
+impl Value {
+    pub fn as_isize(&self) -> Result<isize, AocError> {
+        match self {
+            Value::Int(v) => Ok(*v),
+            Value::UInt(v) => isize::try_from(*v).map_err(AocError::parse),
+            Value::Word(w) => Err(AocError::parse(format!("Expected a number, but got word '{}'", w)))
+        }
+    }
+
+    pub fn as_usize(&self) -> Result<usize, AocError> {
+        match self {
+            Value::Int(v) => usize::try_from(*v).map_err(AocError::parse),
+            Value::UInt(v) => Ok(*v),
+            Value::Word(w) => Err(AocError::parse(format!("Expected a number, but got word '{}'", w)))
+        }
+    }
+
+    pub fn as_str(&self) -> Result<&str, AocError> {
+        match self {
+            Value::Word(w) => Ok(w.as_str()),
+            _ => Err(AocError::parse(format!("Expected a word, but got {:?}", self)))
+        }
+    }
+}
+
+enum PatternPart {
+    Literal(String),
+    Placeholder(char),
+}
+
+fn parse_pattern(pattern: &str) -> Result<Vec<PatternPart>, AocError> {
+    let mut parts = vec![];
+    let mut literal = String::new();
+    let mut chars = pattern.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '{' {
+            literal.push(c);
+            continue;
+        }
+
+        let name: String = chars.by_ref().take_while(|c| *c != '}').collect();
+        let placeholder = match name.as_str() {
+            "i" => 'i',
+            "u" => 'u',
+            "w" => 'w',
+            _ => return Err(AocError::parse(format!("Unknown placeholder '{{{}}}' in pattern '{}'", name, pattern)))
+        };
+
+        if !literal.trim().is_empty() {
+            parts.push(PatternPart::Literal(literal.trim_start().to_string()));
+        }
+        literal = String::new();
+        parts.push(PatternPart::Placeholder(placeholder));
+    }
+
+    if !literal.trim().is_empty() {
+        parts.push(PatternPart::Literal(literal.trim_start().to_string()));
+    }
+
+    Ok(parts)
+}
+
+impl Parser {
+    /// Matches the input against the given pattern, returning the values of all placeholders in order.
+    /// Supported placeholders are `{i}` (isize), `{u}` (usize), and `{w}` (a word of alphanumeric characters).
+    /// Like the other parser methods, whitespace before every literal or placeholder is skipped.
+    pub fn pattern(&mut self, pattern: &str) -> Result<Vec<Value>, AocError> {
+        let mut values = vec![];
+
+        for part in parse_pattern(pattern)? {
+            match part {
+                PatternPart::Literal(literal) => self.literal(literal.as_str())?,
+                PatternPart::Placeholder('i') => values.push(Value::Int(self.isize()?)),
+                PatternPart::Placeholder('u') => values.push(Value::UInt(self.usize()?)),
+                PatternPart::Placeholder(_) => values.push(Value::Word(self.word()?)),
+            }
+        }
+
+        Ok(values)
+    }
+
+    pub fn word(&mut self) -> Result<String, AocError> {
+        self.skip_whitespace();
+
+        let word: String = self.input.chars().skip(self.position).take_while(|c| c.is_alphanumeric()).collect();
+        if word.is_empty() {
+            Err(self.error("Expected to find a word".to_string()))
+        } else {
+            self.position += word.len();
+            Ok(word)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::error::AocError;
+    use crate::util::parser::{Parser, Value};
+
+    #[test]
+    fn test_word() {
+        let mut parser = Parser::new("Valve AA has");
+        assert_eq!(Ok("Valve".to_string()), parser.word());
+        assert_eq!(Ok("AA".to_string()), parser.word());
+        assert_eq!(Ok("has".to_string()), parser.word());
+        assert!(parser.word().is_err());
+    }
+
+    #[test]
+    fn test_pattern() {
+        let mut parser = Parser::new("Sensor at x=2, y=-18: closest beacon is at x=-2, y=15");
+        assert_eq!(Ok(vec![Value::Int(2), Value::Int(-18), Value::Int(-2), Value::Int(15)]),
+                   parser.pattern("Sensor at x={i}, y={i}: closest beacon is at x={i}, y={i}"));
+        assert!(parser.is_exhausted());
+
+        let mut parser = Parser::new("Valve BB has flow rate=13; tunnels lead to valves CC, AA");
+        assert_eq!(Ok(vec![Value::Word("BB".to_string()), Value::UInt(13)]), parser.pattern("Valve {w} has flow rate={u};"));
+        assert!(parser.literal("tunnels lead to valves").is_ok());
+    }
+
+    #[test]
+    fn test_pattern_mismatch() {
+        let mut parser = Parser::new("Sensor at x=2, z=18");
+        assert!(parser.pattern("Sensor at x={i}, y={i}").is_err());
+
+        let mut parser = Parser::new("Monkey 0:");
+        assert!(parser.pattern("Monkey {u}{x}:").is_err());
+    }
+
+    #[test]
+    fn test_error_location() {
+        let mut parser = Parser::new("Blueprint 1:\n  Each ore robot costs x ore.");
+        assert!(parser.pattern("Blueprint {u}:").is_ok());
+        assert!(parser.literal("Each ore robot costs").is_ok());
+        assert_eq!((2, 23), parser.location());
+        assert_eq!(Err(AocError::parse_at(2, 24, "Expected to find a number\n    Each ore robot costs x ore.\n                         ^")), parser.usize());
+        assert_eq!("Line 2, column 24: Expected 'x ' to match 'or'\n    Each ore robot costs x ore.\n                         ^",
+                   parser.literal("or").unwrap_err().to_string());
+    }
+
+    #[test]
+    fn test_inspection() {
+        let mut parser = Parser::new("move 1 from 2");
+        assert_eq!(0, parser.position());
+        assert_eq!("move 1 from 2", parser.remaining());
+
+        assert!(parser.literal("move").is_ok());
+        assert_eq!(Ok(1), parser.usize());
+        assert_eq!(6, parser.position());
+        assert_eq!(" from 2", parser.remaining());
+        assert_eq!("move 1 from 2", parser.input());
+    }
+
+    #[test]
+    fn test_opt() {
+        let mut parser = Parser::new("move 3 crates");
+        assert_eq!(Ok(()), parser.literal("move"));
+        assert_eq!(None, parser.opt(|p| p.literal("all")));
+        assert_eq!(4, parser.position());
+        // A partial match backtracks as well.
+        assert_eq!(None, parser.opt(|p| { p.usize()?; p.literal("boxes") }));
+        assert_eq!(4, parser.position());
+        assert_eq!(Some(3), parser.opt(|p| p.usize()));
+        assert_eq!(1, parser.opt(|p| p.usize()).unwrap_or(1));
+        assert_eq!(Ok("crates".to_string()), parser.word());
+    }
+
+    #[test]
+    fn test_save_restore() {
+        let mut parser = Parser::new("Monkey 0: items 79, 98");
+        let start = parser.save();
+        assert_eq!(Ok(vec![Value::UInt(0)]), parser.pattern("Monkey {u}:"));
+        let items = parser.save();
+        assert_eq!(Ok("items".to_string()), parser.word());
+        parser.restore(items);
+        assert_eq!(" items 79, 98", parser.remaining());
+        parser.restore(start);
+        assert_eq!(0, parser.position());
+    }
+
+    #[test]
+    fn test_peek_literal() {
+        let parser = Parser::new("  and 3 clay");
+        assert!(parser.peek_literal("and"));
+        assert!(parser.peek_literal(""));
+        assert!(!parser.peek_literal("or"));
+        assert!(!parser.peek_literal("and 3 clay."));
+        assert_eq!(0, parser.position());
+    }
+
+    #[test]
+    fn test_try_parse() {
+        let mut parser = Parser::new("humn: dvpt * -x");
+        assert_eq!(Ok("humn".to_string()), parser.str(4));
+        assert_eq!(Ok(()), parser.literal(":"));
+        // Alternatives, where the first one fails halfway: a number, or a name followed by an operator.
+        let failed = parser.try_parse(|p| p.isize());
+        assert_eq!(Err(AocError::parse_at(1, 7, "Expected to find a number\n  humn: dvpt * -x\n        ^")), failed);
+        assert_eq!(5, parser.position());
+        let name = parser.try_parse(|p| { let name = p.str(4)?; p.literal("+")?; Ok(name) })
+            .or_else(|_| parser.try_parse(|p| { let name = p.str(4)?; p.literal("*")?; Ok(name) }));
+        assert_eq!(Ok("dvpt".to_string()), name);
+
+        // A '-' without a number is not consumed.
+        assert!(parser.isize().is_err());
+        assert_eq!(" -x", parser.remaining());
+        assert_eq!(Err(AocError::parse_at(1, 14, "Expected one of '-y', '-z'\n  humn: dvpt * -x\n               ^")), parser.one_of(vec!["-y", "-z"]));
+    }
+
+    #[test]
+    fn test_radix_numbers() {
+        let mut parser = Parser::new("ff 0x1F 0b101 11 z");
+        assert_eq!(Ok(255), parser.hex_usize());
+        assert_eq!(Ok(31), parser.hex_usize());
+        assert_eq!(Ok(5), parser.binary_usize());
+        assert_eq!(Ok(3), parser.binary_usize());
+        assert_eq!(Err(AocError::parse_at(1, 18, "Expected to find a hexadecimal number\n  ff 0x1F 0b101 11 z\n                   ^")), parser.hex_usize());
+        assert_eq!(16, parser.position());
+
+        assert_eq!(Ok(1), Parser::new("12").binary_usize());
+        assert!(Parser::new("0x").hex_usize().is_err());
+        assert!(Parser::new("99999999999999999999999").usize().is_err());
+    }
+
+    #[test]
+    fn test_f64() {
+        let mut parser = Parser::new("3 -0.25 .5 1.5e3 2e-1 7 eggs -x");
+        assert_eq!(Ok(3.0), parser.f64());
+        assert_eq!(Ok(-0.25), parser.f64());
+        assert_eq!(Ok(0.5), parser.f64());
+        assert_eq!(Ok(1500.0), parser.f64());
+        assert_eq!(Ok(0.2), parser.f64());
+        assert_eq!(Ok(7.0), parser.f64());
+        assert!(parser.literal("eggs").is_ok());
+
+        // Neither the '-' nor the '.' of something that is not a number is consumed.
+        assert_eq!(Err(AocError::parse_at(1, 31, "Expected to find a decimal number\n  3 -0.25 .5 1.5e3 2e-1 7 eggs -x\n                                ^")), parser.f64());
+        assert_eq!("-x", parser.remaining().trim());
+        let mut parser = Parser::new(".x");
+        assert!(parser.f64().is_err());
+        assert_eq!(0, parser.position());
+    }
+
+    #[test]
+    fn test_separated_list() {
+        let mut parser = Parser::new("Starting items: 79, 98\nOperation");
+        assert_eq!(Ok(()), parser.literal("Starting items:"));
+        assert_eq!(Ok(vec![79, 98]), parser.separated_list(",", |p| p.usize()));
+        assert_eq!("\nOperation", parser.remaining());
+
+        let mut parser = Parser::new("498,4 -> 498,6 -> 496,6");
+        let points = parser.separated_list("->", |p| Ok((p.isize()?, { p.literal(",")?; p.isize()? })));
+        assert_eq!(Ok(vec![(498, 4), (498, 6), (496, 6)]), points);
+        assert!(parser.is_exhausted());
+
+        // A single item is a list as well, but no item or a dangling separator is not.
+        assert_eq!(Ok(vec!["AA".to_string()]), Parser::new("AA").separated_list(",", |p| p.str(2)));
+        assert_eq!(Err(AocError::parse_at(1, 1, "Expected to find a number\n  \n  ^")), Parser::new("").separated_list(",", |p| p.usize()));
+        assert_eq!(Err(AocError::parse_at(1, 8, "Expected to find a number\n  79, 98,\n         ^")), Parser::new("79, 98,").separated_list(",", |p| p.usize()));
+    }
+
+    #[test]
+    fn test_take_until() {
+        let mut parser = Parser::new("Valve AA has flow rate=0; tunnels lead to valves DD, II");
+        assert_eq!(Ok("Valve AA has flow rate=0".to_string()), parser.take_until(';'));
+        assert_eq!(Ok(()), parser.literal(";"));
+        assert_eq!(Ok(String::new()), parser.take_until('t'));
+        assert_eq!(Err(AocError::parse_at(1, 27, "Expected to find '.'\n  Valve AA has flow rate=0; tunnels lead to valves DD, II\n                            ^")), parser.take_until('.'));
+
+        assert_eq!("tunnels lead to valves DD, II", parser.rest());
+        assert!(parser.is_exhausted());
+        assert_eq!("", parser.rest());
+    }
+
+    #[test]
+    fn test_many() {
+        let mut parser = Parser::new("1, 2, 3 and 4");
+        let values = parser.many0(|p| {
+            let value = p.usize()?;
+            p.literal(",")?;
+            Ok(value)
+        });
+        assert_eq!(vec![1, 2], values);
+        assert_eq!(" 3 and 4", parser.remaining());
+
+        assert_eq!(Vec::<String>::new(), parser.many0(|p| p.word().and_then(|w| if w == "and" { Ok(w) } else { Err(AocError::parse(w)) })));
+        assert_eq!(Ok(vec![3]), parser.many1(|p| p.usize()));
+        assert!(parser.many1(|p| p.usize()).is_err());
+        assert_eq!(" and 4", parser.remaining());
+
+        // Stops when nothing is consumed, rather than looping forever.
+        assert_eq!(vec![()], parser.many0(|_| Ok(())));
+    }
+
+    #[test]
+    fn test_value_conversions() {
+        assert_eq!(Ok(12), Value::UInt(12).as_isize());
+        assert_eq!(Ok(12), Value::Int(12).as_usize());
+        assert!(Value::Int(-1).as_usize().is_err());
+        assert!(Value::Word("a".to_string()).as_isize().is_err());
+        assert_eq!(Ok("a"), Value::Word("a".to_string()).as_str());
+        assert!(Value::Int(1).as_str().is_err());
+    }
+}
@@ -0,0 +1,142 @@
+use std::io;
+use std::io::{BufRead, Write};
+use crate::util::parser::Parser;
+
+const HELP: &str = "Commands:
+    input <line>      - start parsing the given line.
+    reset             - start over parsing the current line.
+    literal <text>    - match the given literal.
+    one_of <a> <b> .. - match one of the given (space separated) literals.
+    usize | isize     - parse a number.
+    word              - parse a word of alphanumeric characters.
+    str <len>         - read the given number of characters.
+    pattern <pattern> - match a pattern with {i}, {u} and {w} placeholders.
+    exhausted         - check whether all input has been parsed.
+    state             - show the position and remaining input.
+    help              - show this help.
+    quit              - stop the playground.";
+
+/// An interactive session on a single input line, used by the `parser-repl` command.
+pub struct ReplSession {
+    parser: Option<Parser>
+}
+
+impl ReplSession {
+    pub fn new() -> Self {
+        ReplSession { parser: None }
+    }
+
+    /// Executes a single command, returning the (debug representation of the) result of the parser operation.
+    pub fn execute(&mut self, command: &str) -> Result<String, String> {
+        let (name, arg) = match command.split_once(' ') {
+            Some((name, arg)) => (name, arg),
+            None => (command.trim(), "")
+        };
+
+        match name {
+            "input" => {
+                self.parser = Some(Parser::new(arg));
+                Ok(format!("{:?}", arg))
+            }
+            "reset" => {
+                let input = self.parser()?.input().to_string();
+                self.parser = Some(Parser::new(input));
+                Ok("()".to_string())
+            }
+            "literal" => self.parser()?.literal(arg).map(|r| format!("{:?}", r)).map_err(String::from),
+            "one_of" => self.parser()?.one_of(arg.split_whitespace().collect()).map(|r| format!("{:?}", r)).map_err(String::from),
+            "usize" => self.parser()?.usize().map(|r| format!("{:?}", r)).map_err(String::from),
+            "isize" => self.parser()?.isize().map(|r| format!("{:?}", r)).map_err(String::from),
+            "word" => self.parser()?.word().map(|r| format!("{:?}", r)).map_err(String::from),
+            "str" => {
+                let len = arg.trim().parse::<usize>().map_err(|e| format!("Invalid length '{}': {}", arg.trim(), e))?;
+                self.parser()?.str(len).map(|r| format!("{:?}", r)).map_err(String::from)
+            }
+            "pattern" => self.parser()?.pattern(arg).map(|r| format!("{:?}", r)).map_err(String::from),
+            "exhausted" => Ok(format!("{:?}", self.parser()?.is_exhausted())),
+            "state" => self.parser().map(|_| "()".to_string()),
+            _ => Err(format!("Unknown command '{}', type 'help' for a list of commands", name))
+        }
+    }
+
+    fn parser(&mut self) -> Result<&mut Parser, String> {
+        self.parser.as_mut().ok_or("No input yet, start with 'input <line>'".to_string())
+    }
+
+    /// Shows the input with a marker below the current position, followed by the remaining input.
+    pub fn describe_state(&self) -> Option<String> {
+        self.parser.as_ref().map(|parser| {
+            let column = parser.input()[..parser.position()].chars().count();
+            format!("  {}\n  {}^ position {}, remaining {:?}", parser.input(), " ".repeat(column), parser.position(), parser.remaining())
+        })
+    }
+}
+
+/// Runs the playground on stdin/stdout until 'quit' or the end of the input.
+pub fn run() {
+    let mut session = ReplSession::new();
+    println!("Parser playground, type 'help' for a list of commands.");
+
+    let stdin = io::stdin();
+    loop {
+        print!("> ");
+        io::stdout().flush().ok();
+
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("Could not read from stdin: {}", e);
+                break;
+            }
+        }
+
+        let command = line.trim_end_matches(['\r', '\n']);
+        match command.trim() {
+            "" => continue,
+            "quit" | "exit" => break,
+            "help" => {
+                println!("{}", HELP);
+                continue;
+            }
+            _ => {}
+        }
+
+        match session.execute(command) {
+            Ok(result) => println!("Ok({})", result),
+            Err(e) => println!("Err({})", e),
+        }
+        if let Some(state) = session.describe_state() {
+            println!("{}", state);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::parser_repl::ReplSession;
+
+    #[test]
+    fn test_session() {
+        let mut session = ReplSession::new();
+        assert!(session.execute("word").is_err());
+        assert_eq!(None, session.describe_state());
+
+        assert_eq!(Ok("\"move 1 from 2 to 3\"".to_string()), session.execute("input move 1 from 2 to 3"));
+        assert_eq!(Ok("()".to_string()), session.execute("literal move"));
+        assert_eq!(Ok("1".to_string()), session.execute("usize"));
+        assert_eq!(Some("  move 1 from 2 to 3\n        ^ position 6, remaining \" from 2 to 3\"".to_string()), session.describe_state());
+
+        assert_eq!(Ok("\"from\"".to_string()), session.execute("one_of to from"));
+        assert!(session.execute("word").is_ok());
+        assert_eq!(Ok("[UInt(3)]".to_string()), session.execute("pattern to {u}"));
+        assert_eq!(Ok("true".to_string()), session.execute("exhausted"));
+
+        assert_eq!(Ok("()".to_string()), session.execute("reset"));
+        assert_eq!(Ok("\"move\"".to_string()), session.execute("str 4"));
+        assert!(session.execute("str x").is_err());
+        assert!(session.execute("isize").is_ok());
+        assert!(session.execute("frobnicate").is_err());
+    }
+}
@@ -0,0 +1,154 @@
+// Allow dead_code since this is a util file copied across years. Later in the AoC we might use everything, or not.
+#![allow(dead_code)]
+
+use std::ops::RangeInclusive;
+
+/// A closed interval [start, end]. An interval where end < start is empty.
+pub trait Interval<T: Ord> {
+    fn start(&self) -> &T;
+    fn end(&self) -> &T;
+
+    fn is_empty_interval(&self) -> bool {
+        self.end() < self.start()
+    }
+
+    /// Named so it does not shadow the inherent `RangeInclusive::contains`.
+    fn contains_value(&self, value: &T) -> bool {
+        self.start() <= value && value <= self.end()
+    }
+
+    /// Whether the two intervals share at least one value.
+    fn overlaps(&self, other: &Self) -> bool {
+        !self.is_empty_interval() && !other.is_empty_interval() &&
+            self.start() <= other.end() && other.start() <= self.end()
+    }
+
+    /// Whether every value in `other` is also in this interval.
+    fn contains_interval(&self, other: &Self) -> bool {
+        !other.is_empty_interval() && self.start() <= other.start() && other.end() <= self.end()
+    }
+}
+
+impl<T: Ord> Interval<T> for RangeInclusive<T> {
+    fn start(&self) -> &T {
+        RangeInclusive::start(self)
+    }
+
+    fn end(&self) -> &T {
+        RangeInclusive::end(self)
+    }
+}
+
+/// The values shared by both ranges, or None when they do not overlap.
+pub fn intersection<T: Ord + Clone>(range: &RangeInclusive<T>, other: &RangeInclusive<T>) -> Option<RangeInclusive<T>> {
+    if !range.overlaps(other) {
+        return None;
+    }
+
+    let start = range.start().max(other.start()).clone();
+    let end = range.end().min(other.end()).clone();
+    Some(start..=end)
+}
+
+/// A box of closed intervals, one for every dimension: a rectangle for N = 2, a cuboid for N = 3, and so on. The
+/// box is empty when its interval in any dimension is.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IntervalBox<T, const N: usize> {
+    pub ranges: [RangeInclusive<T>; N],
+}
+
+impl<T: Ord + Clone, const N: usize> IntervalBox<T, N> {
+    pub fn new(ranges: [RangeInclusive<T>; N]) -> Self {
+        IntervalBox { ranges }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.iter().any(|r| r.is_empty_interval())
+    }
+
+    /// Whether the point (a value for every dimension) is inside the box.
+    pub fn contains_value(&self, point: &[T; N]) -> bool {
+        self.ranges.iter().zip(point).all(|(r, v)| r.contains_value(v))
+    }
+
+    /// Whether the two boxes share at least one point.
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.ranges.iter().zip(&other.ranges).all(|(r, o)| r.overlaps(o))
+    }
+
+    /// Whether every point in `other` is also in this box.
+    pub fn contains_box(&self, other: &Self) -> bool {
+        !other.is_empty() && self.ranges.iter().zip(&other.ranges).all(|(r, o)| r.contains_interval(o))
+    }
+
+    /// The points shared by both boxes, or None when they do not overlap.
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        let ranges: Vec<_> = self.ranges.iter().zip(&other.ranges).map(|(r, o)| intersection(r, o)).collect::<Option<_>>()?;
+        Some(IntervalBox { ranges: ranges.try_into().ok()? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::RangeInclusive;
+    use crate::util::range::{intersection, Interval, IntervalBox};
+
+    #[test]
+    fn test_contains() {
+        assert!((2..=4).contains_value(&2));
+        assert!((2..=4).contains_value(&4));
+        assert!(!(2..=4).contains_value(&5));
+        assert!(!RangeInclusive::new(4, 2).contains_value(&3));
+    }
+
+    #[test]
+    fn test_overlaps() {
+        assert!(!(2..=4).overlaps(&(6..=8)));
+        assert!(!(2..=3).overlaps(&(4..=5)));
+        assert!((5..=7).overlaps(&(7..=9)));
+        assert!((7..=9).overlaps(&(5..=7)));
+        assert!((2..=8).overlaps(&(3..=7)));
+        assert!((6..=6).overlaps(&(4..=6)));
+        assert!(!RangeInclusive::new(6, 5).overlaps(&(4..=8)));
+    }
+
+    #[test]
+    fn test_contains_interval() {
+        assert!((2..=8).contains_interval(&(3..=7)));
+        assert!((4..=6).contains_interval(&(6..=6)));
+        assert!((4..=6).contains_interval(&(4..=6)));
+        assert!(!(3..=7).contains_interval(&(2..=8)));
+        assert!(!(2..=6).contains_interval(&(4..=8)));
+        assert!(!(2..=6).contains_interval(&RangeInclusive::new(5, 4)));
+    }
+
+    #[test]
+    fn test_intersection() {
+        assert_eq!(Some(4..=6), intersection(&(2..=6), &(4..=8)));
+        assert_eq!(Some(7..=7), intersection(&(5..=7), &(7..=9)));
+        assert_eq!(Some(3..=7), intersection(&(3..=7), &(2..=8)));
+        assert_eq!(None, intersection(&(2..=3), &(4..=5)));
+    }
+
+    #[test]
+    fn test_interval_box() {
+        let cuboid = IntervalBox::new([0..=4, 0..=4, 0..=4]);
+        let corner = IntervalBox::new([3..=6, 4..=8, -2..=0]);
+        assert!(cuboid.contains_value(&[4, 0, 2]));
+        assert!(!cuboid.contains_value(&[4, 5, 2]));
+        assert!(cuboid.overlaps(&corner));
+        assert_eq!(Some(IntervalBox::new([3..=4, 4..=4, 0..=0])), cuboid.intersection(&corner));
+        assert!(cuboid.contains_box(&cuboid.intersection(&corner).unwrap()));
+        assert!(!cuboid.contains_box(&corner));
+
+        // Overlapping in every dimension but one is not overlapping.
+        let beside = IntervalBox::new([1..=2, 1..=2, 5..=6]);
+        assert!(!cuboid.overlaps(&beside));
+        assert_eq!(None, cuboid.intersection(&beside));
+
+        let empty = IntervalBox::new([1..=2, RangeInclusive::new(3, 2)]);
+        assert!(empty.is_empty());
+        assert!(!IntervalBox::new([0..=4, 0..=4]).contains_box(&empty));
+        assert!(!empty.overlaps(&empty));
+    }
+}
@@ -0,0 +1,299 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{create_dir_all, OpenOptions, read_to_string, write};
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde_json::{json, Value};
+use crate::util::answer::Answer;
+use crate::util::buildinfo;
+use crate::util::hash::fnv1a;
+use crate::util::input::input_dir;
+
+pub fn results_path() -> String {
+    format!("{}/results.csv", input_dir())
+}
+
+/// A single recorded puzzle run.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RunRecord {
+    pub day: i32,
+    pub part: usize,
+    /// Seconds since the unix epoch at which the run was recorded.
+    pub timestamp: u64,
+    pub duration: Duration,
+    pub success: bool,
+    /// The source hash of the build that made the run (see `buildinfo`), absent in older records.
+    pub build: Option<String>,
+}
+
+impl RunRecord {
+    pub fn now(day: i32, part: usize, duration: Duration, success: bool) -> Self {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+        RunRecord { day, part, timestamp, duration, success, build: Some(buildinfo::SOURCE_HASH.to_string()) }
+    }
+
+    fn to_line(&self) -> String {
+        let line = format!("{},{},{},{},{}", self.day, self.part, self.timestamp, self.duration.as_micros(), self.success);
+        match &self.build {
+            Some(build) => format!("{},{}", line, build),
+            None => line,
+        }
+    }
+
+    fn from_line(line: &str) -> Result<Self, String> {
+        let parts: Vec<_> = line.trim().split(',').collect();
+        if parts.len() != 5 && parts.len() != 6 {
+            return Err(format!("Expected 5 or 6 fields in result record, but got '{}'", line));
+        }
+        let number = |s: &str| s.parse::<u64>().map_err(|e| format!("Invalid number '{}' in result record: {}", s, e));
+
+        Ok(RunRecord {
+            day: number(parts[0])? as i32,
+            part: number(parts[1])? as usize,
+            timestamp: number(parts[2])?,
+            duration: Duration::from_micros(number(parts[3])?),
+            success: parts[4].parse().map_err(|_| format!("Invalid success flag '{}' in result record", parts[4]))?,
+            build: parts.get(5).map(|b| b.to_string()),
+        })
+    }
+}
+
+/// Appends the record to the results store.
+pub fn record(run: &RunRecord) -> Result<(), String> {
+    let mut file = OpenOptions::new().create(true).append(true).open(results_path()).map_err(|e| format!("{}", e))?;
+    writeln!(file, "{}", run.to_line()).map_err(|e| format!("{}", e))
+}
+
+/// Reads all records from the results store, an absent store has no records.
+pub fn load() -> Result<Vec<RunRecord>, String> {
+    if !Path::new(&results_path()).exists() {
+        return Ok(vec![]);
+    }
+    parse_records(&read_to_string(results_path()).map_err(|e| format!("{}", e))?)
+}
+
+fn parse_records(input: &str) -> Result<Vec<RunRecord>, String> {
+    input.lines().filter(|l| !l.trim().is_empty()).map(RunRecord::from_line).collect()
+}
+
+/// Summary of the runs of a single day, as printed by `day N --format json`, with the answer of every run (None
+/// when it failed).
+pub fn runs_to_json(day: i32, runs: &[(RunRecord, Option<Answer>)]) -> Value {
+    json!({
+        "day": day,
+        "build": buildinfo::to_json(),
+        "parts": runs.iter()
+            .map(|(r, answer)| json!({
+                "part": r.part, "success": r.success, "runtime_ms": r.duration.as_secs_f64() * 1000.0,
+                "answer": answer.as_ref().map(|a| a.to_string()),
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// The directory of the intermediate results stored with `store_cached`.
+pub fn cache_dir() -> String {
+    format!("{}/cache", input_dir())
+}
+
+fn cache_path(name: &str) -> String {
+    format!("{}/{}.txt", cache_dir(), name)
+}
+
+/// A key for cached results: an FNV-1a hash over the given parts (e.g. the input and a format version).
+pub fn cache_key(parts: &[&str]) -> String {
+    let hash = fnv1a(parts.iter().flat_map(|part| part.bytes().chain([0])));
+    format!("{:016x}", hash)
+}
+
+/// Reads an intermediate result stored with `store_cached`, None when there is none or it was stored under another
+/// key (i.e. for another input), as a new entry replaces the old one.
+pub fn load_cached(name: &str, key: &str) -> Option<String> {
+    let contents = read_to_string(cache_path(name)).ok()?;
+    let (stored_key, value) = contents.split_once('\n')?;
+    (stored_key == key).then(|| value.to_string())
+}
+
+/// Stores an intermediate result in the `cache` directory next to the results store, replacing any earlier entry
+/// with the same name.
+pub fn store_cached(name: &str, key: &str, value: &str) -> Result<(), String> {
+    create_dir_all(cache_dir()).map_err(|e| format!("{}", e))?;
+    write(cache_path(name), format!("{}\n{}", key, value)).map_err(|e| format!("{}", e))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Speedup {
+    pub day: i32,
+    pub first: Duration,
+    pub latest: Duration,
+}
+
+impl Speedup {
+    pub fn factor(&self) -> f64 {
+        self.first.as_secs_f64() / self.latest.as_secs_f64().max(f64::MIN_POSITIVE)
+    }
+}
+
+/// Summary over the recorded runs, based on the latest successful run of every puzzle.
+#[derive(Clone, Debug, PartialEq)]
+pub struct YearSummary {
+    pub total_runtime: Duration,
+    /// Runtime per day (both parts), slowest first.
+    pub slowest_days: Vec<(i32, Duration)>,
+    /// Days that got faster since their first recorded run, biggest speedup first.
+    pub speedups: Vec<Speedup>,
+    /// (day, part) of puzzles without a successful recorded run.
+    pub missing: Vec<(i32, usize)>,
+}
+
+impl YearSummary {
+    pub fn create(records: &[RunRecord], days: &[i32]) -> Self {
+        // Records are appended, so later entries are newer runs.
+        let mut first: BTreeMap<(i32, usize), Duration> = BTreeMap::new();
+        let mut latest: BTreeMap<(i32, usize), Duration> = BTreeMap::new();
+        for run in records.iter().filter(|r| r.success) {
+            first.entry((run.day, run.part)).or_insert(run.duration);
+            latest.insert((run.day, run.part), run.duration);
+        }
+
+        let day_total = |map: &BTreeMap<(i32, usize), Duration>, day: i32| -> Duration {
+            map.iter().filter(|((d, _), _)| *d == day).map(|(_, duration)| *duration).sum()
+        };
+        let recorded_days: Vec<i32> = latest.keys().map(|(day, _)| *day).fold(vec![], |mut acc, day| {
+            if !acc.contains(&day) { acc.push(day); }
+            acc
+        });
+
+        let mut slowest_days: Vec<_> = recorded_days.iter().map(|day| (*day, day_total(&latest, *day))).collect();
+        slowest_days.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        let mut speedups: Vec<_> = recorded_days.iter()
+            .map(|day| Speedup { day: *day, first: day_total(&first, *day), latest: day_total(&latest, *day) })
+            .filter(|s| s.latest < s.first)
+            .collect();
+        speedups.sort_by(|a, b| b.factor().total_cmp(&a.factor()).then(a.day.cmp(&b.day)));
+
+        let missing = days.iter()
+            .flat_map(|day| [(*day, 1), (*day, 2)])
+            .filter(|key| !latest.contains_key(key))
+            .collect();
+
+        YearSummary { total_runtime: latest.values().sum(), slowest_days, speedups, missing }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "total_runtime_ms": self.total_runtime.as_secs_f64() * 1000.0,
+            "slowest_days": self.slowest_days.iter()
+                .map(|(day, duration)| json!({ "day": day, "runtime_ms": duration.as_secs_f64() * 1000.0 }))
+                .collect::<Vec<_>>(),
+            "speedups": self.speedups.iter()
+                .map(|s| json!({
+                    "day": s.day,
+                    "first_ms": s.first.as_secs_f64() * 1000.0,
+                    "latest_ms": s.latest.as_secs_f64() * 1000.0,
+                    "factor": s.factor()
+                }))
+                .collect::<Vec<_>>(),
+            "missing": self.missing.iter().map(|(day, part)| json!({ "day": day, "part": part })).collect::<Vec<_>>(),
+        })
+    }
+}
+
+impl fmt::Display for YearSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Total runtime: {:.3}ms", self.total_runtime.as_secs_f64() * 1000.0)?;
+
+        writeln!(f, "\nSlowest days:")?;
+        writeln!(f, "{:>5} | {:>12}", "Day", "Runtime (ms)")?;
+        for (day, duration) in self.slowest_days.iter().take(5) {
+            writeln!(f, "{:>5} | {:>12.3}", day, duration.as_secs_f64() * 1000.0)?;
+        }
+
+        writeln!(f, "\nBiggest speedups since first run:")?;
+        writeln!(f, "{:>5} | {:>12} | {:>12} | {:>7}", "Day", "First (ms)", "Latest (ms)", "Factor")?;
+        for speedup in self.speedups.iter().take(5) {
+            writeln!(f, "{:>5} | {:>12.3} | {:>12.3} | {:>6.1}x", speedup.day,
+                     speedup.first.as_secs_f64() * 1000.0, speedup.latest.as_secs_f64() * 1000.0, speedup.factor())?;
+        }
+
+        write!(f, "\nMissing answers: ")?;
+        if self.missing.is_empty() {
+            writeln!(f, "none")
+        } else {
+            writeln!(f, "{}", self.missing.iter().map(|(day, part)| format!("{}.{}", day, part)).collect::<Vec<_>>().join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use serde_json::json;
+    use crate::util::buildinfo;
+    use crate::util::answer::Answer;
+    use crate::util::results::{cache_key, parse_records, RunRecord, runs_to_json, Speedup, YearSummary};
+
+    fn run(day: i32, part: usize, millis: u64, success: bool) -> RunRecord {
+        RunRecord { day, part, timestamp: 1670000000, duration: Duration::from_millis(millis), success, build: None }
+    }
+
+    #[test]
+    fn test_record_lines() {
+        let record = run(3, 2, 12, true);
+        assert_eq!("3,2,1670000000,12000,true", record.to_line());
+        assert_eq!(Ok(record.clone()), RunRecord::from_line(&record.to_line()));
+        assert_eq!(Ok(vec![record.clone(), run(4, 1, 1, false)]), parse_records("3,2,1670000000,12000,true\n\n4,1,1670000000,1000,false\n"));
+        assert!(RunRecord::from_line("3,2,12000,true").is_err());
+        assert!(RunRecord::from_line("3,2,1670000000,12000,yes").is_err());
+
+        let record = RunRecord { build: Some("0123456789abcdef".to_string()), ..run(3, 2, 12, true) };
+        assert_eq!("3,2,1670000000,12000,true,0123456789abcdef", record.to_line());
+        assert_eq!(Ok(record.clone()), RunRecord::from_line(&record.to_line()));
+        assert_eq!(Some(buildinfo::SOURCE_HASH.to_string()), RunRecord::now(3, 2, Duration::ZERO, true).build);
+    }
+
+    #[test]
+    fn test_runs_to_json() {
+        assert_eq!(json!({
+            "day": 3,
+            "build": buildinfo::to_json(),
+            "parts": [
+                { "part": 1, "success": true, "runtime_ms": 12.0, "answer": "157" },
+                { "part": 2, "success": false, "runtime_ms": 0.0, "answer": null },
+            ]
+        }), runs_to_json(3, &[(run(3, 1, 12, true), Some(Answer::from(157))), (run(3, 2, 0, false), None)]));
+    }
+
+    #[test]
+    fn test_year_summary() {
+        let records = vec![
+            run(1, 1, 10, true), run(1, 2, 20, true),
+            run(2, 1, 100, true), run(2, 2, 0, false),
+            run(1, 1, 2, true), run(1, 2, 3, true),
+            run(3, 1, 50, true), run(3, 2, 60, true),
+        ];
+        let summary = YearSummary::create(&records, &[1, 2, 3]);
+
+        assert_eq!(Duration::from_millis(2 + 3 + 100 + 50 + 60), summary.total_runtime);
+        assert_eq!(vec![(3, Duration::from_millis(110)), (2, Duration::from_millis(100)), (1, Duration::from_millis(5))], summary.slowest_days);
+        assert_eq!(vec![Speedup { day: 1, first: Duration::from_millis(30), latest: Duration::from_millis(5) }], summary.speedups);
+        assert_eq!(6.0, summary.speedups[0].factor());
+        assert_eq!(vec![(2, 2)], summary.missing);
+
+        let json = summary.to_json();
+        assert_eq!(215.0, json["total_runtime_ms"]);
+        assert_eq!(2, json["missing"][0]["day"]);
+        assert_eq!(3, json["slowest_days"][0]["day"]);
+    }
+
+    #[test]
+    fn test_cache_key() {
+        assert_eq!(16, cache_key(&["input"]).len());
+        assert_eq!(cache_key(&["input", "1"]), cache_key(&["input", "1"]));
+        assert_ne!(cache_key(&["input", "1"]), cache_key(&["input", "2"]));
+        // The parts are separated, so moving characters between them changes the key.
+        assert_ne!(cache_key(&["ab", "c"]), cache_key(&["a", "bc"]));
+    }
+}
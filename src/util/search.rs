@@ -0,0 +1,470 @@
+// Allow dead_code since this is a util file copied across years. Later in the AoC we might use everything, or not.
+#![allow(dead_code)]
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+
+/// Iterative deepening depth-first search: runs a depth-limited DFS for every limit up to `max_depth`,
+/// so the first path found is a shortest one. Memory use is limited to the current path, at the cost of
+/// re-exploring the shallower levels for every limit.
+/// Returns the path from `start` up to and including the goal state.
+pub fn iddfs<S, E, I, G>(start: S, expand: E, is_goal: G, max_depth: usize) -> Option<Vec<S>>
+    where S: Clone, E: FnMut(&S) -> I, I: IntoIterator<Item=S>, G: FnMut(&S) -> bool {
+    search(start, expand, is_goal, max_depth, None::<fn(&S)>)
+}
+
+/// Same as `iddfs`, but keeps a transposition table keyed by `key`: within one depth limit, a state whose
+/// key was already reached at the same or a lower depth is not explored again. States with equal keys must
+/// be interchangeable for the remainder of the search.
+pub fn iddfs_with_table<S, E, I, G, K, H>(start: S, expand: E, is_goal: G, max_depth: usize, key: H) -> Option<Vec<S>>
+    where S: Clone, E: FnMut(&S) -> I, I: IntoIterator<Item=S>, G: FnMut(&S) -> bool, K: Hash + Eq, H: Fn(&S) -> K {
+    search(start, expand, is_goal, max_depth, Some(key))
+}
+
+fn search<S, E, I, G, K, H>(start: S, mut expand: E, mut is_goal: G, max_depth: usize, key: Option<H>) -> Option<Vec<S>>
+    where S: Clone, E: FnMut(&S) -> I, I: IntoIterator<Item=S>, G: FnMut(&S) -> bool, K: Hash + Eq, H: Fn(&S) -> K {
+    if is_goal(&start) {
+        return Some(vec![start]);
+    }
+
+    let mut table: HashMap<K, usize> = HashMap::new();
+    for limit in 1..=max_depth {
+        table.clear();
+        if let Some(key) = &key {
+            table.insert(key(&start), 0);
+        }
+
+        // An explicit stack instead of recursion, as searches can easily go a few hundred levels deep.
+        let mut path = vec![start.clone()];
+        let mut children = vec![expand(&start).into_iter().collect::<Vec<_>>().into_iter()];
+        while let Some(next) = children.last_mut().map(|c| c.next()) {
+            let Some(next) = next else {
+                children.pop();
+                path.pop();
+                continue;
+            };
+
+            let depth = path.len();
+            if is_goal(&next) {
+                path.push(next);
+                return Some(path);
+            }
+            if depth >= limit {
+                continue;
+            }
+            if let Some(key) = &key {
+                let key = key(&next);
+                if table.get(&key).is_some_and(|seen| *seen <= depth) {
+                    continue;
+                }
+                table.insert(key, depth);
+            }
+
+            children.push(expand(&next).into_iter().collect::<Vec<_>>().into_iter());
+            path.push(next);
+        }
+    }
+
+    None
+}
+
+/// The predecessor of every node reached by a search, to reconstruct the path leading to any of them. The roots
+/// (the starts of the search) have no predecessor.
+#[derive(Clone, Debug)]
+pub struct Predecessors<T> {
+    links: HashMap<T, Option<T>>,
+}
+
+impl<T> Default for Predecessors<T> {
+    fn default() -> Self {
+        Predecessors { links: HashMap::new() }
+    }
+}
+
+impl<T> Predecessors<T> where T: Hash + Eq + Clone {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_root(&mut self, node: T) {
+        self.links.insert(node, None);
+    }
+
+    /// Records the predecessor of the node, replacing an earlier one (e.g. when a shorter path was found).
+    pub fn link(&mut self, node: T, predecessor: T) {
+        self.links.insert(node, Some(predecessor));
+    }
+
+    pub fn contains(&self, node: &T) -> bool {
+        self.links.contains_key(node)
+    }
+
+    pub fn predecessor(&self, node: &T) -> Option<&T> {
+        self.links.get(node).and_then(|p| p.as_ref())
+    }
+
+    /// The path from a root up to and including the goal, None if the goal was not reached.
+    pub fn path_to(&self, goal: &T) -> Option<Vec<T>> {
+        let mut path = vec![goal.clone()];
+        let mut current = self.links.get(goal)?;
+        while let Some(node) = current {
+            // A path cannot be longer than the number of nodes, unless the links have a cycle.
+            if path.len() > self.links.len() {
+                return None;
+            }
+            path.push(node.clone());
+            // A predecessor that was never added is not a path from a root.
+            current = self.links.get(node)?;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    pub fn len(&self) -> usize {
+        self.links.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.links.is_empty()
+    }
+}
+
+/// The order in which a `WorkQueue` hands out its items.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Order {
+    /// First in, first out: a breadth-first search.
+    Fifo,
+    /// Last in, first out: a depth-first search.
+    Lifo,
+    /// Lowest priority first, in the order pushed for equal priorities.
+    Priority,
+}
+
+/// The queue and seen-set of a graph exploration in one: every item is only queued the first time it is pushed.
+/// In `Order::Priority` mode, an item is queued again when it is pushed with a lower priority before it was popped,
+/// and only handed out once, with the lowest priority it was pushed with (the decrease-key of Dijkstra's algorithm).
+pub struct WorkQueue<T> {
+    order: Order,
+    pending: VecDeque<T>,
+    prioritized: BinaryHeap<Reverse<(usize, usize)>>,
+    prioritized_items: HashMap<usize, T>,
+    pushed: usize,
+    seen: HashSet<T>,
+    // Priority mode only: the lowest priority every item was queued with, and the items popped (or marked seen).
+    best_priorities: HashMap<T, usize>,
+    settled: HashSet<T>,
+    processed: usize,
+    predecessors: Option<Predecessors<T>>,
+}
+
+impl<T> WorkQueue<T> where T: Hash + Eq + Clone {
+    pub fn new(order: Order) -> Self {
+        WorkQueue {
+            order,
+            pending: VecDeque::new(),
+            prioritized: BinaryHeap::new(),
+            prioritized_items: HashMap::new(),
+            pushed: 0,
+            seen: HashSet::new(),
+            best_priorities: HashMap::new(),
+            settled: HashSet::new(),
+            processed: 0,
+            predecessors: None,
+        }
+    }
+
+    /// Makes the queue remember which item every item was first pushed from (see `push_from`), so the path to it
+    /// can be reconstructed with `path_to`. Items pushed with `push` are the roots of those paths.
+    pub fn tracking_paths(mut self) -> Self {
+        self.predecessors = Some(Predecessors::new());
+        self
+    }
+
+    pub fn fifo() -> Self {
+        Self::new(Order::Fifo)
+    }
+
+    pub fn lifo() -> Self {
+        Self::new(Order::Lifo)
+    }
+
+    pub fn priority() -> Self {
+        Self::new(Order::Priority)
+    }
+
+    /// Queues the item, unless it was seen before. Returns whether it was queued.
+    pub fn push(&mut self, item: T) -> bool {
+        self.push_with_priority(item, 0)
+    }
+
+    /// Like `push`, with the priority used in `Order::Priority` mode (ignored otherwise). In that mode, an item that
+    /// is still queued is queued again when the priority is lower than before.
+    pub fn push_with_priority(&mut self, item: T, priority: usize) -> bool {
+        if !self.admit(&item, priority) {
+            return false;
+        }
+        if let Some(predecessors) = &mut self.predecessors {
+            predecessors.add_root(item.clone());
+        }
+        self.enqueue(item, priority);
+        true
+    }
+
+    /// Like `push`, remembering that the item was reached from `from` when tracking paths.
+    pub fn push_from(&mut self, from: &T, item: T) -> bool {
+        self.push_from_with_priority(from, item, 0)
+    }
+
+    /// Like `push_with_priority`, remembering that the item was reached from `from` when tracking paths.
+    pub fn push_from_with_priority(&mut self, from: &T, item: T, priority: usize) -> bool {
+        if !self.admit(&item, priority) {
+            return false;
+        }
+        if let Some(predecessors) = &mut self.predecessors {
+            predecessors.link(item.clone(), from.clone());
+        }
+        self.enqueue(item, priority);
+        true
+    }
+
+    /// Whether the item should be queued, marking it as seen (with its priority) when it should.
+    fn admit(&mut self, item: &T, priority: usize) -> bool {
+        match self.order {
+            Order::Fifo | Order::Lifo => self.seen.insert(item.clone()),
+            Order::Priority => {
+                if self.settled.contains(item) || self.best_priorities.get(item).is_some_and(|best| *best <= priority) {
+                    return false;
+                }
+                self.seen.insert(item.clone());
+                self.best_priorities.insert(item.clone(), priority);
+                true
+            }
+        }
+    }
+
+    fn enqueue(&mut self, item: T, priority: usize) {
+        match self.order {
+            Order::Fifo | Order::Lifo => self.pending.push_back(item),
+            Order::Priority => {
+                self.prioritized.push(Reverse((priority, self.pushed)));
+                self.prioritized_items.insert(self.pushed, item);
+            }
+        }
+        self.pushed += 1;
+    }
+
+    /// Marks the item as seen without queueing it, so later pushes of it are ignored. Returns whether it was new.
+    pub fn mark_seen(&mut self, item: T) -> bool {
+        if self.order == Order::Priority {
+            self.settled.insert(item.clone());
+        }
+        self.seen.insert(item)
+    }
+
+    pub fn is_seen(&self, item: &T) -> bool {
+        self.seen.contains(item)
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let item = match self.order {
+            Order::Fifo => self.pending.pop_front(),
+            Order::Lifo => self.pending.pop_back(),
+            Order::Priority => self.pop_prioritized(),
+        };
+        if item.is_some() {
+            self.processed += 1;
+        }
+        item
+    }
+
+    // Skips the entries of items that were queued again with a lower priority, or that were handed out already.
+    fn pop_prioritized(&mut self) -> Option<T> {
+        while let Some(Reverse((priority, index))) = self.prioritized.pop() {
+            let item = self.prioritized_items.remove(&index)?;
+            if self.settled.contains(&item) || self.best_priorities.get(&item).is_some_and(|best| *best < priority) {
+                continue;
+            }
+            self.settled.insert(item.clone());
+            return Some(item);
+        }
+        None
+    }
+
+    /// The number of queued entries; in priority mode this includes those of items queued again with a lower priority.
+    pub fn len(&self) -> usize {
+        self.pending.len() + self.prioritized.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of items popped so far.
+    pub fn processed(&self) -> usize {
+        self.processed
+    }
+
+    /// The number of distinct items pushed or marked as seen so far.
+    pub fn seen_count(&self) -> usize {
+        self.seen.len()
+    }
+
+    /// The path from a root to the goal, when tracking paths and the goal was pushed.
+    pub fn path_to(&self, goal: &T) -> Option<Vec<T>> {
+        self.predecessors.as_ref()?.path_to(goal)
+    }
+
+    pub fn predecessors(&self) -> Option<&Predecessors<T>> {
+        self.predecessors.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use crate::util::search::{iddfs, iddfs_with_table, Order, Predecessors, WorkQueue};
+
+    #[test]
+    fn test_iddfs() {
+        // Reach 10 from 1 by doubling or adding one; the shortest way is 1, 2, 4, 5, 10.
+        let expand = |n: &usize| vec![n + 1, n * 2];
+        assert_eq!(Some(vec![1, 2, 4, 5, 10]), iddfs(1, expand, |n| *n == 10, 10));
+        assert_eq!(Some(vec![3]), iddfs(3, expand, |n| *n == 3, 0));
+        assert_eq!(None, iddfs(1, expand, |n| *n == 10, 3));
+        assert_eq!(None, iddfs(1, |_| vec![], |n| *n == 10, 10));
+    }
+
+    #[test]
+    fn test_iddfs_with_table() {
+        // Walking on a 4x4 grid from a corner to the opposite corner, without a table this would visit
+        // every walk up to the found length.
+        let expand = |(x, y): &(isize, isize)| {
+            [(x - 1, *y), (x + 1, *y), (*x, y - 1), (*x, y + 1)].into_iter()
+                .filter(|(x, y)| (0..4).contains(x) && (0..4).contains(y))
+                .collect::<Vec<_>>()
+        };
+        let mut expanded_with_table = 0;
+        let path = iddfs_with_table((0, 0), |p| { expanded_with_table += 1; expand(p) }, |p| *p == (3, 3), 10, |p| *p);
+        assert_eq!(Some(7), path.map(|p| p.len()));
+
+        let mut expanded = 0;
+        let path = iddfs((0, 0), |p| { expanded += 1; expand(p) }, |p| *p == (3, 3), 10);
+        assert_eq!(Some(7), path.map(|p| p.len()));
+        assert!(expanded_with_table * 2 < expanded, "Expected the table to limit expansions, but expanded {} vs {}", expanded_with_table, expanded);
+    }
+
+    #[test]
+    fn test_work_queue_orders() {
+        let drain = |mut queue: WorkQueue<usize>| {
+            for (item, priority) in [(1, 3), (2, 1), (3, 2), (2, 0), (4, 1)] {
+                queue.push_with_priority(item, priority);
+            }
+            std::iter::from_fn(|| queue.pop()).collect::<Vec<_>>()
+        };
+        assert_eq!(vec![1, 2, 3, 4], drain(WorkQueue::fifo()));
+        assert_eq!(vec![4, 3, 2, 1], drain(WorkQueue::lifo()));
+        assert_eq!(vec![2, 4, 3, 1], drain(WorkQueue::new(Order::Priority)));
+    }
+
+    #[test]
+    fn test_work_queue_decreases_priority() {
+        // Dijkstra from a to d, where the cheapest way to c is only found after c was queued.
+        let edges = [('a', 'b', 1), ('a', 'c', 10), ('b', 'c', 2), ('c', 'd', 1), ('b', 'd', 7)];
+        let mut queue = WorkQueue::priority().tracking_paths();
+        let mut costs = HashMap::from([('a', 0)]);
+        queue.push('a');
+        let mut order = vec![];
+        while let Some(node) = queue.pop() {
+            order.push(node);
+            for (_, to, cost) in edges.iter().filter(|(from, _, _)| *from == node) {
+                let cost = costs[&node] + cost;
+                if queue.push_from_with_priority(&node, *to, cost) {
+                    costs.insert(*to, cost);
+                }
+            }
+        }
+        assert_eq!(vec!['a', 'b', 'c', 'd'], order);
+        assert_eq!((3, 4), (costs[&'c'], costs[&'d']));
+        assert_eq!(Some(vec!['a', 'b', 'c', 'd']), queue.path_to(&'d'));
+
+        // Popped items are not queued again, and neither are higher priorities.
+        assert!(!queue.push_with_priority('c', 0));
+        let mut queue = WorkQueue::priority();
+        assert!(queue.push_with_priority('x', 5));
+        assert!(!queue.push_with_priority('x', 5));
+        assert!(queue.push_with_priority('x', 2));
+        assert!(queue.mark_seen('y'));
+        assert!(!queue.push_with_priority('y', 0));
+        assert_eq!((Some('x'), None), (queue.pop(), queue.pop()));
+        assert_eq!((1, 2), (queue.processed(), queue.seen_count()));
+    }
+
+    #[test]
+    fn test_work_queue_deduplicates() {
+        let mut queue = WorkQueue::fifo();
+        assert!(queue.push("a"));
+        assert!(!queue.push("a"));
+        assert!(queue.mark_seen("b"));
+        assert!(!queue.push("b"));
+        assert!(queue.is_seen(&"b"));
+        assert_eq!((1, 2), (queue.len(), queue.seen_count()));
+
+        assert_eq!(Some("a"), queue.pop());
+        assert!(!queue.push("a"), "Items stay seen after being processed");
+        assert_eq!(None, queue.pop());
+        assert_eq!(1, queue.processed());
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_predecessors() {
+        let mut predecessors = Predecessors::new();
+        predecessors.add_root('a');
+        predecessors.link('b', 'a');
+        predecessors.link('c', 'b');
+        predecessors.link('d', 'b');
+        assert_eq!(Some(vec!['a', 'b', 'c']), predecessors.path_to(&'c'));
+        assert_eq!(Some(vec!['a']), predecessors.path_to(&'a'));
+        assert_eq!(None, predecessors.path_to(&'e'));
+
+        // A shorter way to d replaces the earlier one.
+        predecessors.link('d', 'a');
+        assert_eq!(Some(vec!['a', 'd']), predecessors.path_to(&'d'));
+        assert_eq!(Some(&'a'), predecessors.predecessor(&'d'));
+
+        // Cycles do not hang.
+        predecessors.link('a', 'c');
+        assert_eq!(None, predecessors.path_to(&'c'));
+
+        // A chain that reaches a node that was never added gives no path, rather than panicking.
+        predecessors.link('f', 'x');
+        assert!(!predecessors.contains(&'x'));
+        assert_eq!(None, predecessors.path_to(&'f'));
+    }
+
+    #[test]
+    fn test_work_queue_paths() {
+        // Breadth-first from 1 to 10 by doubling or adding one, like `test_iddfs`.
+        let mut queue = WorkQueue::fifo().tracking_paths();
+        queue.push(1);
+        while let Some(n) = queue.pop() {
+            if n == 10 {
+                break;
+            }
+            for next in [n + 1, n * 2].into_iter().filter(|next| *next <= 10) {
+                queue.push_from(&n, next);
+            }
+        }
+        assert_eq!(Some(vec![1, 2, 4, 5, 10]), queue.path_to(&10));
+        assert_eq!(Some(vec![1, 2, 3]), queue.path_to(&3));
+        assert_eq!(None, queue.path_to(&11));
+
+        // Without tracking, there are no paths.
+        let mut queue = WorkQueue::fifo();
+        queue.push(1);
+        queue.push_from(&1, 2);
+        assert_eq!(None, queue.path_to(&2));
+        assert!(queue.predecessors().is_none());
+    }
+}
@@ -0,0 +1,135 @@
+// Allow dead_code since this is a util file copied across years. Later in the AoC we might use everything, or not.
+#![allow(dead_code)]
+
+use std::ops::{Add, Mul, Sub};
+
+/// Eight u16 lanes packed together. The operations are simple per-lane loops over a fixed size array,
+/// which the compiler turns into vector instructions, without needing nightly `std::simd`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct U16x8(pub [u16; 8]);
+
+impl U16x8 {
+    pub const ZERO: U16x8 = U16x8([0; 8]);
+
+    pub const fn splat(value: u16) -> Self {
+        U16x8([value; 8])
+    }
+
+    /// A vector with `value` in the given lane and zero everywhere else.
+    pub const fn unit(lane: usize, value: u16) -> Self {
+        let mut lanes = [0; 8];
+        lanes[lane] = value;
+        U16x8(lanes)
+    }
+
+    pub fn lane(&self, lane: usize) -> u16 {
+        self.0[lane]
+    }
+
+    pub fn with_lane(mut self, lane: usize, value: u16) -> Self {
+        self.0[lane] = value;
+        self
+    }
+
+    /// Moves every lane `n` positions towards lane 0, filling the upper lanes with zeroes.
+    pub fn shift_down(&self, n: usize) -> Self {
+        let mut result = [0; 8];
+        result[..8 - n].copy_from_slice(&self.0[n..]);
+        U16x8(result)
+    }
+
+    pub fn saturating_sub(&self, other: &Self) -> Self {
+        let mut result = self.0;
+        for (r, o) in result.iter_mut().zip(other.0) {
+            *r = r.saturating_sub(o);
+        }
+        U16x8(result)
+    }
+
+    pub fn max(&self, other: &Self) -> Self {
+        let mut result = self.0;
+        for (r, o) in result.iter_mut().zip(other.0) {
+            *r = (*r).max(o);
+        }
+        U16x8(result)
+    }
+
+    /// Whether every lane is greater than or equal to the same lane in `other`.
+    pub fn all_ge(&self, other: &Self) -> bool {
+        self.0.iter().zip(other.0).fold(true, |acc, (s, o)| acc & (*s >= o))
+    }
+}
+
+impl Add for U16x8 {
+    type Output = U16x8;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        let mut result = self.0;
+        for (r, o) in result.iter_mut().zip(rhs.0) {
+            *r = r.wrapping_add(o);
+        }
+        U16x8(result)
+    }
+}
+
+impl Sub for U16x8 {
+    type Output = U16x8;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        let mut result = self.0;
+        for (r, o) in result.iter_mut().zip(rhs.0) {
+            *r = r.wrapping_sub(o);
+        }
+        U16x8(result)
+    }
+}
+
+impl Mul<u16> for U16x8 {
+    type Output = U16x8;
+
+    fn mul(self, rhs: u16) -> Self::Output {
+        let mut result = self.0;
+        for r in result.iter_mut() {
+            *r = r.wrapping_mul(rhs);
+        }
+        U16x8(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::simd::U16x8;
+
+    #[test]
+    fn test_arithmetic() {
+        let a = U16x8([1, 2, 3, 4, 5, 6, 7, 8]);
+        let b = U16x8::splat(2);
+
+        assert_eq!(U16x8([3, 4, 5, 6, 7, 8, 9, 10]), a + b);
+        assert_eq!(U16x8([u16::MAX, 0, 1, 2, 3, 4, 5, 6]), a - b);
+        assert_eq!(U16x8([0, 0, 1, 2, 3, 4, 5, 6]), a.saturating_sub(&b));
+        assert_eq!(U16x8([3, 6, 9, 12, 15, 18, 21, 24]), a * 3);
+        assert_eq!(U16x8([2, 2, 3, 4, 5, 6, 7, 8]), a.max(&b));
+    }
+
+    #[test]
+    fn test_lanes() {
+        let a = U16x8([1, 2, 3, 4, 5, 6, 7, 8]);
+
+        assert_eq!(3, a.lane(2));
+        assert_eq!(U16x8([1, 2, 0, 4, 5, 6, 7, 8]), a.with_lane(2, 0));
+        assert_eq!(U16x8([0, 0, 0, 9, 0, 0, 0, 0]), U16x8::unit(3, 9));
+        assert_eq!(U16x8([5, 6, 7, 8, 0, 0, 0, 0]), a.shift_down(4));
+        assert_eq!(a, a.shift_down(0));
+    }
+
+    #[test]
+    fn test_all_ge() {
+        let a = U16x8([1, 2, 3, 4, 5, 6, 7, 8]);
+
+        assert!(a.all_ge(&a));
+        assert!(a.all_ge(&U16x8::splat(1)));
+        assert!(!a.all_ge(&U16x8::splat(2)));
+        assert!(U16x8::splat(8).all_ge(&a));
+    }
+}
@@ -0,0 +1,99 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const UPDATE_ENV_VAR: &str = "AOC_UPDATE_SNAPSHOTS";
+
+fn snapshot_path(name: &str) -> PathBuf {
+    snapshot_path_in(&PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("snapshots"), name)
+}
+
+fn snapshot_path_in(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{}.snap", name))
+}
+
+fn updating() -> bool {
+    env::var(UPDATE_ENV_VAR).is_ok()
+}
+
+/// Compares `rendered` to the snapshot stored as `tests/snapshots/<name>.snap`.
+/// Run the tests with `AOC_UPDATE_SNAPSHOTS=1` to (re)write the snapshots instead.
+pub fn assert_snapshot(name: &str, rendered: &str) {
+    if let Err(e) = check_snapshot(&snapshot_path(name), name, rendered) {
+        panic!("{}", e);
+    }
+}
+
+fn check_snapshot(path: &Path, name: &str, rendered: &str) -> Result<(), String> {
+    if updating() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).unwrap_or_else(|e| panic!("Could not create {}: {}", dir.display(), e));
+        }
+        fs::write(path, rendered).unwrap_or_else(|e| panic!("Could not write snapshot {}: {}", path.display(), e));
+        return Ok(());
+    }
+
+    let expected = fs::read_to_string(path).map_err(|e| format!(
+        "Could not read snapshot {} ({}), run with {}=1 to create it", path.display(), e, UPDATE_ENV_VAR))?;
+
+    if expected != rendered {
+        return Err(format!("Snapshot '{}' does not match, run with {}=1 to update it\n--- expected\n{}\n--- actual\n{}",
+                           name, UPDATE_ENV_VAR, expected, rendered));
+    }
+    Ok(())
+}
+
+/// A deterministic pseudo-random generator (a 64-bit LCG) for generated test inputs, seeded so every run of a
+/// test sees the same values.
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        SeededRng { state: seed }
+    }
+
+    /// The next value, from the high bits of the state as those are the most random.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        self.state >> 33
+    }
+
+    /// The next value in `0..bound`.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+    use crate::util::snaptest::{check_snapshot, snapshot_path, snapshot_path_in, updating};
+
+    #[test]
+    fn test_snapshot_path() {
+        assert!(snapshot_path("day17_drop_1").ends_with("tests/snapshots/day17_drop_1.snap"));
+    }
+
+    #[test]
+    fn test_check_snapshot_mismatch() {
+        if updating() {
+            // Updating would just write the mismatching snapshot.
+            return;
+        }
+        let dir = env::temp_dir().join(format!("aoc-snaptest-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = snapshot_path_in(&dir, "mismatch");
+        fs::write(&path, "a tetris field").unwrap();
+
+        let result = check_snapshot(&path, "mismatch", "not a tetris field");
+        let matching = check_snapshot(&path, "mismatch", "a tetris field");
+        let missing = check_snapshot(&snapshot_path_in(&dir, "missing"), "missing", "");
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(result.is_err_and(|e| e.contains("does not match")));
+        assert_eq!(Ok(()), matching);
+        assert!(missing.is_err_and(|e| e.contains("Could not read snapshot")));
+    }
+}
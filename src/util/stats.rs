@@ -0,0 +1,374 @@
+// Allow dead_code since this is a util file copied across years. Later in the AoC we might use everything, or not.
+#![allow(dead_code)]
+
+use std::{env, fmt};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use serde_json::{json, Map, Value};
+use crate::util::buildinfo;
+
+/// Where `Stats::print` sends the stats while several days run, see `set_collector`.
+static COLLECTOR: Mutex<Option<StatsCollector>> = Mutex::new(None);
+
+/// Makes `Stats::print` report to the collector instead of printing right away, until set back to None.
+pub fn set_collector(collector: Option<StatsCollector>) {
+    *COLLECTOR.lock().unwrap() = collector;
+}
+
+/// Named counters collected while solving a puzzle, e.g. the number of visited or pruned states, and the time
+/// spent in named phases of the solution (see `Stats::phase`).
+/// Counters and phases are kept in the order they were first recorded.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Stats {
+    counters: Vec<(String, usize)>,
+    phases: Vec<(String, Duration)>,
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        Self { counters: vec![], phases: vec![] }
+    }
+
+    fn entry(&mut self, name: &str) -> &mut usize {
+        let index = match self.counters.iter().position(|(n, _)| n == name) {
+            Some(index) => index,
+            None => {
+                self.counters.push((name.to_string(), 0));
+                self.counters.len() - 1
+            }
+        };
+        &mut self.counters[index].1
+    }
+
+    pub fn add(&mut self, name: &str, amount: usize) {
+        *self.entry(name) += amount;
+    }
+
+    pub fn set(&mut self, name: &str, value: usize) {
+        *self.entry(name) = value;
+    }
+
+    /// Records the value if it is higher than the value seen so far for this counter.
+    pub fn peak(&mut self, name: &str, value: usize) {
+        let entry = self.entry(name);
+        *entry = (*entry).max(value);
+    }
+
+    pub fn get(&self, name: &str) -> Option<usize> {
+        self.counters.iter().find(|(n, _)| n == name).map(|(_, v)| *v)
+    }
+
+    pub fn entries(&self) -> Vec<(String, usize)> {
+        self.counters.clone()
+    }
+
+    /// Starts timing the named phase, until the returned timer is dropped. Time spent in a phase that was timed
+    /// before is added to it.
+    pub fn phase(&mut self, name: &str) -> PhaseTimer<'_> {
+        PhaseTimer { stats: self, name: name.to_string(), start: Instant::now() }
+    }
+
+    pub fn add_phase_time(&mut self, name: &str, duration: Duration) {
+        match self.phases.iter_mut().find(|(n, _)| n == name) {
+            Some((_, total)) => *total += duration,
+            None => self.phases.push((name.to_string(), duration)),
+        }
+    }
+
+    pub fn phase_time(&self, name: &str) -> Option<Duration> {
+        self.phases.iter().find(|(n, _)| n == name).map(|(_, d)| *d)
+    }
+
+    pub fn phases(&self) -> Vec<(String, Duration)> {
+        self.phases.clone()
+    }
+
+    /// Adds the counters and phase times of the other stats to these, e.g. to combine the stats of work done in
+    /// parallel.
+    pub fn merge(&mut self, other: &Stats) {
+        for (name, value) in &other.counters {
+            self.add(name, *value);
+        }
+        for (name, duration) in &other.phases {
+            self.add_phase_time(name, *duration);
+        }
+    }
+}
+
+/// Times a phase of a puzzle while it is alive, see `Stats::phase`. It derefs to the stats, so counters can be
+/// updated during the phase. Phases started from a timer are nested, and named like the stacks of a flame
+/// graph: "search;pairing".
+pub struct PhaseTimer<'a> {
+    stats: &'a mut Stats,
+    name: String,
+    start: Instant,
+}
+
+impl PhaseTimer<'_> {
+    pub fn phase(&mut self, name: &str) -> PhaseTimer<'_> {
+        PhaseTimer { stats: self.stats, name: format!("{};{}", self.name, name), start: Instant::now() }
+    }
+}
+
+impl Deref for PhaseTimer<'_> {
+    type Target = Stats;
+
+    fn deref(&self) -> &Self::Target {
+        self.stats
+    }
+}
+
+impl DerefMut for PhaseTimer<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.stats
+    }
+}
+
+impl Drop for PhaseTimer<'_> {
+    fn drop(&mut self) {
+        self.stats.add_phase_time(&self.name, self.start.elapsed());
+    }
+}
+
+/// Header of the rows produced by `Stats::to_csv`, `build` is the source hash of the binary (see `buildinfo`).
+pub const CSV_HEADER: &str = "day,part,variant,counter,value,build";
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+impl Stats {
+    /// Formats the counters as CSV rows (without header, see `CSV_HEADER`), one row per counter. Phases follow as
+    /// rows with counter "phase:<name>", in microseconds.
+    pub fn to_csv(&self, day: i32, part: usize, variant: &str) -> String {
+        let phases = self.phases.iter().map(|(name, duration)| (format!("phase:{}", name), duration.as_micros() as usize));
+        self.counters.iter().cloned().chain(phases)
+            .map(|(name, value)| format!("{},{},{},{},{},{}", day, part, csv_field(variant), csv_field(&name), value, buildinfo::SOURCE_HASH))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn to_json(&self, day: i32, part: usize, variant: &str) -> Value {
+        let counters: Map<String, Value> = self.counters.iter().map(|(name, value)| (name.clone(), json!(value))).collect();
+        let phases: Vec<_> = self.phases.iter()
+            .map(|(name, duration)| json!({ "name": name, "ms": duration.as_secs_f64() * 1000.0 }))
+            .collect();
+        json!({ "day": day, "part": part, "variant": variant, "counters": counters, "phases": phases, "build": buildinfo::to_json() })
+    }
+
+    /// Prints the stats in the format requested through the `AOC_FORMAT` environment variable ('csv', 'json' or
+    /// plain text), or reports them to the collector when one is set.
+    pub fn print(&self, day: i32, part: usize, variant: &str) {
+        if let Some(collector) = COLLECTOR.lock().unwrap().as_ref() {
+            collector.report(day, part, variant, self);
+            return;
+        }
+        match env::var("AOC_FORMAT").as_deref() {
+            Ok("csv") => println!("{}\n{}", CSV_HEADER, self.to_csv(day, part, variant)),
+            Ok("json") => println!("{}", self.to_json(day, part, variant)),
+            _ => println!("{}", self)
+        }
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let counters = self.counters.iter().map(|(name, value)| format!("{}: {}", name, value));
+        let phases = self.phases.iter().map(|(name, duration)| format!("phase {}: {:.3}ms", name, duration.as_secs_f64() * 1000.0));
+        write!(f, "{}", counters.chain(phases).collect::<Vec<_>>().join("\n"))
+    }
+}
+
+/// The stats of a single puzzle run, tagged with the day and part they belong to.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Report {
+    pub day: i32,
+    pub part: usize,
+    pub variant: String,
+    pub stats: Stats,
+}
+
+/// Collects the stats of puzzles that may run on different threads. Clones share the same reports, so every
+/// puzzle can get its own handle; output is ordered by day and part, regardless of the order in which the
+/// puzzles finished.
+#[derive(Clone, Debug, Default)]
+pub struct StatsCollector {
+    reports: Arc<Mutex<Vec<Report>>>,
+}
+
+impl StatsCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the stats of a puzzle. A later report of the same puzzle and variant (e.g. of another benchmark
+    /// run) replaces the earlier one.
+    pub fn report(&self, day: i32, part: usize, variant: &str, stats: &Stats) {
+        let report = Report { day, part, variant: variant.to_string(), stats: stats.clone() };
+        let mut reports = self.reports.lock().unwrap();
+        reports.retain(|r| (r.day, r.part, &r.variant) != (day, part, &report.variant));
+        reports.push(report);
+    }
+
+    pub fn reports(&self) -> Vec<Report> {
+        let mut reports = self.reports.lock().unwrap().clone();
+        reports.sort_by_key(|r| (r.day, r.part));
+        reports
+    }
+
+    /// The stats as text, every line prefixed with the day and part it came from.
+    pub fn lines(&self) -> Vec<String> {
+        self.reports().iter()
+            .flat_map(|r| r.stats.to_string().lines().map(|line| format!("[day {:02} part {}] {}", r.day, r.part, line)).collect::<Vec<_>>())
+            .collect()
+    }
+
+    /// All reports as CSV, including the header.
+    pub fn to_csv(&self) -> String {
+        let rows: Vec<_> = self.reports().iter()
+            .map(|r| r.stats.to_csv(r.day, r.part, &r.variant))
+            .filter(|rows| !rows.is_empty())
+            .collect();
+        [CSV_HEADER.to_string()].into_iter().chain(rows).collect::<Vec<_>>().join("\n")
+    }
+
+    /// Prints all reports in the format requested through `AOC_FORMAT`, like `Stats::print`.
+    pub fn print(&self) {
+        if self.reports.lock().unwrap().is_empty() {
+            return;
+        }
+        match env::var("AOC_FORMAT").as_deref() {
+            Ok("csv") => println!("{}", self.to_csv()),
+            Ok("json") => self.reports().iter().for_each(|r| println!("{}", r.stats.to_json(r.day, r.part, &r.variant))),
+            _ => println!("{}", self.lines().join("\n"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::Duration;
+    use serde_json::json;
+    use crate::util::buildinfo;
+    use crate::util::stats::{CSV_HEADER, Stats, StatsCollector};
+
+    #[test]
+    fn test_counters() {
+        let mut stats = Stats::new();
+        assert_eq!(None, stats.get("visited"));
+
+        stats.add("visited", 2);
+        stats.add("visited", 3);
+        stats.set("queue", 10);
+        stats.peak("queue", 4);
+        stats.peak("states", 4);
+        stats.peak("states", 7);
+
+        assert_eq!(Some(5), stats.get("visited"));
+        assert_eq!(Some(10), stats.get("queue"));
+        assert_eq!(Some(7), stats.get("states"));
+        assert_eq!("visited: 5\nqueue: 10\nstates: 7", format!("{}", stats));
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut stats = Stats::new();
+        stats.add("visited", 2);
+        let mut other = Stats::new();
+        other.add("pruned", 1);
+        other.add("visited", 3);
+        other.add_phase_time("search", Duration::from_millis(4));
+
+        stats.merge(&other);
+        assert_eq!(vec![("visited".to_string(), 5), ("pruned".to_string(), 1)], stats.entries());
+        assert_eq!(Some(Duration::from_millis(4)), stats.phase_time("search"));
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let mut stats = Stats::new();
+        stats.add("visited", 5);
+        stats.add("peak \"queue\"", 3);
+
+        let build = buildinfo::SOURCE_HASH;
+        assert_eq!("day,part,variant,counter,value,build", CSV_HEADER);
+        assert_eq!(format!("24,1,default,visited,5,{}\n24,1,default,\"peak \"\"queue\"\"\",3,{}", build, build), stats.to_csv(24, 1, "default"));
+        assert_eq!(format!("24,2,\"capped,20\",visited,5,{}", build), stats.to_csv(24, 2, "capped,20").lines().next().unwrap());
+        assert_eq!("", Stats::new().to_csv(1, 1, "default"));
+    }
+
+    #[test]
+    fn test_phases() {
+        let mut stats = Stats::new();
+        {
+            let mut search = stats.phase("search");
+            search.add("visited", 1);
+            thread::sleep(Duration::from_millis(2));
+            let _pairing = search.phase("pairing");
+        }
+        stats.phase("search");
+
+        assert_eq!(Some(1), stats.get("visited"));
+        let phases: Vec<_> = stats.phases().into_iter().map(|(name, _)| name).collect();
+        assert_eq!(vec!["search;pairing", "search"], phases);
+        assert!(stats.phase_time("search").unwrap() >= Duration::from_millis(2));
+        assert_eq!(None, stats.phase_time("pairing"));
+    }
+
+    #[test]
+    fn test_phase_output() {
+        let mut stats = Stats::new();
+        stats.add("visited", 5);
+        stats.add_phase_time("parse", Duration::from_micros(1500));
+        stats.add_phase_time("parse", Duration::from_micros(500));
+
+        assert_eq!("visited: 5\nphase parse: 2.000ms", stats.to_string());
+        let build = buildinfo::SOURCE_HASH;
+        assert_eq!(format!("16,1,default,visited,5,{}\n16,1,default,phase:parse,2000,{}", build, build), stats.to_csv(16, 1, "default"));
+        assert_eq!(json!({
+            "day": 16, "part": 1, "variant": "default",
+            "counters": { "visited": 5 },
+            "phases": [{ "name": "parse", "ms": 2.0 }],
+            "build": buildinfo::to_json()
+        }), stats.to_json(16, 1, "default"));
+    }
+
+    #[test]
+    fn test_collector_across_threads() {
+        let collector = StatsCollector::new();
+        thread::scope(|scope| {
+            for day in [24, 3, 11] {
+                let collector = collector.clone();
+                scope.spawn(move || {
+                    for part in [2, 1] {
+                        let mut stats = Stats::new();
+                        stats.add("visited", day as usize * part);
+                        collector.report(day, part, "default", &stats);
+                        stats.add("pruned", part);
+                        collector.report(day, part, "default", &stats);
+                    }
+                });
+            }
+        });
+
+        let tags: Vec<_> = collector.reports().iter().map(|r| (r.day, r.part)).collect();
+        assert_eq!(vec![(3, 1), (3, 2), (11, 1), (11, 2), (24, 1), (24, 2)], tags);
+        assert_eq!(Some(22), collector.reports()[3].stats.get("visited"));
+
+        // The second report of a puzzle replaced the first.
+        let lines = collector.lines();
+        assert_eq!(12, lines.len());
+        assert_eq!(vec!["[day 03 part 1] visited: 3", "[day 03 part 1] pruned: 1"], lines[..2].to_vec());
+
+        let csv = collector.to_csv();
+        assert_eq!(Some(CSV_HEADER), csv.lines().next());
+        assert_eq!(Some(format!("3,1,default,visited,3,{}", buildinfo::SOURCE_HASH).as_str()), csv.lines().nth(1));
+        assert_eq!(CSV_HEADER, StatsCollector::new().to_csv());
+    }
+}
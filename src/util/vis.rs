@@ -0,0 +1,515 @@
+// Allow dead_code since this is a util file copied across years. Later in the AoC we might use everything, or not.
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Mutex;
+use crate::util::geometry::{Bounds, Grid};
+
+/// Where to write the animation of the day being run, set by the `--visualize` flag of the day command.
+static OUTPUT: Mutex<Option<String>> = Mutex::new(None);
+static SAVED: Mutex<bool> = Mutex::new(false);
+/// Whether saved animations are kept in memory instead of written, and the last frame of the last one saved.
+static CAPTURE: Mutex<bool> = Mutex::new(false);
+static CAPTURED: Mutex<Option<Animation>> = Mutex::new(None);
+
+pub fn set_output(path: Option<String>) {
+    *OUTPUT.lock().unwrap() = path;
+    *SAVED.lock().unwrap() = false;
+}
+
+/// The path to write an animation to, None when not visualizing.
+pub fn output() -> Option<String> {
+    OUTPUT.lock().unwrap().clone()
+}
+
+/// Keeps the final frame of saved animations for `take_captured`, instead of writing them to the output.
+pub fn set_capture(capture: bool) {
+    *CAPTURE.lock().unwrap() = capture;
+    *CAPTURED.lock().unwrap() = None;
+    *SAVED.lock().unwrap() = false;
+}
+
+/// Whether the day being run should make its animation: there is an output to write it to, or it is captured.
+pub fn enabled() -> bool {
+    output().is_some() || *CAPTURE.lock().unwrap()
+}
+
+/// The final frame of the animation saved since capturing started, with its palette.
+pub fn take_captured() -> Option<Animation> {
+    CAPTURED.lock().unwrap().take()
+}
+
+/// Whether an animation was written since the output was set, so the runner can tell about days without one.
+pub fn was_saved() -> bool {
+    *SAVED.lock().unwrap()
+}
+
+/// A single image, as an index into the palette of its animation for every pixel, row by row.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Frame {
+    pub width: usize,
+    pub height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Frame {
+    pub fn new(width: usize, height: usize) -> Self {
+        Frame { width, height, pixels: vec![0; width * height] }
+    }
+
+    /// Renders the part of the grid within `bounds`, one pixel per cell. Cells without a value get color 0.
+    pub fn from_grid<T: Clone>(grid: &Grid<T>, bounds: Bounds, color: impl Fn(&T) -> u8) -> Self {
+        let mut frame = Frame::new(bounds.width, bounds.height);
+        for point in bounds.iter_row_major() {
+            if let Some(value) = grid.get(&point) {
+                frame.set((point.x - bounds.left) as usize, (point.y - bounds.top) as usize, color(&value));
+            }
+        }
+        frame
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> u8 {
+        self.pixels[y * self.width + x]
+    }
+
+    /// Sets a pixel, ignoring pixels outside the frame.
+    pub fn set(&mut self, x: usize, y: usize, color: u8) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = color;
+        }
+    }
+
+    /// Shrinks the frame, every block of factor x factor pixels becomes one. A block gets its most common color
+    /// other than 0 (the background), so thin lines and single pixels stay visible.
+    pub fn downscale(&self, factor: usize) -> Frame {
+        let factor = factor.max(1);
+        let mut frame = Frame::new(self.width.div_ceil(factor), self.height.div_ceil(factor));
+        for y in 0..frame.height {
+            for x in 0..frame.width {
+                let mut counts = [0usize; 256];
+                for py in y * factor..((y + 1) * factor).min(self.height) {
+                    for px in x * factor..((x + 1) * factor).min(self.width) {
+                        counts[self.get(px, py) as usize] += 1;
+                    }
+                }
+                let color = (1..256).max_by_key(|c| (counts[*c], *c)).filter(|c| counts[*c] > 0).unwrap_or(0);
+                frame.set(x, y, color as u8);
+            }
+        }
+        frame
+    }
+}
+
+/// Frames of equal size with a shared palette, to write as an (endlessly looping) animated GIF.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Animation {
+    palette: Vec<[u8; 3]>,
+    frames: Vec<Frame>,
+    /// Time every frame is shown, in hundredths of a second.
+    pub delay: u16,
+    /// Every pixel of a frame becomes a block of scale x scale pixels in the GIF.
+    pub scale: usize,
+}
+
+impl Animation {
+    pub fn new(palette: &[[u8; 3]]) -> Self {
+        Animation { palette: palette.to_vec(), frames: vec![], delay: 5, scale: 1 }
+    }
+
+    pub fn push(&mut self, frame: Frame) {
+        self.frames.push(frame);
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    /// A still of the final frame, shrunk (if needed) to fit within the given size.
+    pub fn thumbnail(&self, max_width: usize, max_height: usize) -> Option<Animation> {
+        let last = self.frames.last()?;
+        let factor = last.width.div_ceil(max_width.max(1)).max(last.height.div_ceil(max_height.max(1)));
+        Some(Animation { palette: self.palette.clone(), frames: vec![last.downscale(factor)], delay: self.delay, scale: 1 })
+    }
+
+    /// The final frame as text, going from ' ' for the darkest colors to '@' for the lightest. Every pixel is two
+    /// characters wide, as characters are about twice as high as they are wide.
+    pub fn render_text(&self) -> String {
+        const SHADES: &[u8] = b" .:-=+*#%@";
+        let Some(frame) = self.frames.last() else { return String::new() };
+        let shades: Vec<char> = self.palette.iter().map(|[r, g, b]| {
+            let luminance = (299 * *r as usize + 587 * *g as usize + 114 * *b as usize) / 1000;
+            SHADES[luminance * SHADES.len() / 256] as char
+        }).collect();
+        (0..frame.height)
+            .map(|y| (0..frame.width).map(|x| shades.get(frame.get(x, y) as usize).unwrap_or(&'?').to_string().repeat(2)).collect::<String>() + "\n")
+            .collect()
+    }
+
+    pub fn encode_gif(&self) -> Result<Vec<u8>, String> {
+        let first = self.frames.first().ok_or("An animation needs at least one frame")?;
+        if let Some(frame) = self.frames.iter().find(|f| (f.width, f.height) != (first.width, first.height)) {
+            return Err(format!("All frames should be {}x{}, but got one of {}x{}", first.width, first.height, frame.width, frame.height));
+        }
+        if self.palette.is_empty() || self.palette.len() > 256 {
+            return Err(format!("The palette should have 1 to 256 colors, but has {}", self.palette.len()));
+        }
+        if let Some(color) = self.frames.iter().flat_map(|f| f.pixels.iter()).find(|c| **c as usize >= self.palette.len()) {
+            return Err(format!("Color {} is not in the palette of {} colors", color, self.palette.len()));
+        }
+        let scale = self.scale.max(1);
+        let (width, height) = (first.width * scale, first.height * scale);
+        if width > u16::MAX as usize || height > u16::MAX as usize {
+            return Err(format!("A GIF can be at most {0}x{0} pixels, but this one would be {1}x{2}", u16::MAX, width, height));
+        }
+
+        // The color table holds a power of two (at least 2) colors.
+        let table_bits = (1..=8).find(|bits| 1 << bits >= self.palette.len()).unwrap();
+        let mut out = b"GIF89a".to_vec();
+        out.extend((width as u16).to_le_bytes());
+        out.extend((height as u16).to_le_bytes());
+        out.extend([0x80 | ((table_bits as u8 - 1) << 4) | (table_bits as u8 - 1), 0, 0]);
+        for index in 0..1 << table_bits {
+            out.extend(self.palette.get(index).unwrap_or(&[0, 0, 0]));
+        }
+        // Loop forever.
+        out.extend([0x21, 0xff, 0x0b]);
+        out.extend(b"NETSCAPE2.0");
+        out.extend([0x03, 0x01, 0x00, 0x00, 0x00]);
+
+        for frame in &self.frames {
+            out.extend([0x21, 0xf9, 0x04, 0x00]);
+            out.extend(self.delay.to_le_bytes());
+            out.extend([0x00, 0x00]);
+
+            out.push(0x2c);
+            out.extend([0, 0, 0, 0]);
+            out.extend((width as u16).to_le_bytes());
+            out.extend((height as u16).to_le_bytes());
+            out.push(0);
+
+            let pixels: Vec<u8> = (0..height).flat_map(|y| (0..width).map(move |x| frame.get(x / scale, y / scale))).collect();
+            let min_code_size = table_bits.max(2);
+            out.push(min_code_size as u8);
+            for block in lzw_encode(&pixels, min_code_size).chunks(255) {
+                out.push(block.len() as u8);
+                out.extend(block);
+            }
+            out.push(0);
+        }
+
+        out.push(0x3b);
+        Ok(out)
+    }
+
+    /// Writes the animation to the output set with `set_output`, if any. When capturing, only its final frame is kept.
+    pub fn save(&self) -> Result<(), String> {
+        if *CAPTURE.lock().unwrap() {
+            let last = self.frames.last().cloned().ok_or("An animation needs at least one frame")?;
+            *CAPTURED.lock().unwrap() = Some(Animation { palette: self.palette.clone(), frames: vec![last], delay: self.delay, scale: self.scale });
+            *SAVED.lock().unwrap() = true;
+            return Ok(());
+        }
+        let Some(path) = output() else { return Ok(()) };
+        fs::write(&path, self.encode_gif()?).map_err(|e| format!("Could not write {}: {}", path, e))?;
+        *SAVED.lock().unwrap() = true;
+        println!("Saved an animation of {} frames to {}", self.frames.len(), path);
+        Ok(())
+    }
+}
+
+/// A bucket of a `Histogram`: the values from `start` up to and including `end`, and how many there are.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Bucket {
+    pub start: i64,
+    pub end: i64,
+    pub count: usize,
+}
+
+/// A bar chart of how values are distributed, to print in the terminal.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Histogram {
+    buckets: Vec<Bucket>,
+}
+
+impl Histogram {
+    /// Groups the values into at most `bucket_count` buckets of equal width, from the smallest to the largest value.
+    /// The last bucket ends at the largest value, so it can be a bit narrower or wider than the others.
+    pub fn create(values: &[i64], bucket_count: usize) -> Self {
+        let (Some(&min), Some(&max)) = (values.iter().min(), values.iter().max()) else {
+            return Histogram { buckets: vec![] };
+        };
+        let count = bucket_count.max(1) as i64;
+        let width = ((max - min + count - 1) / count).max(1);
+        let last = ((max - min) / width).min(count - 1);
+        let mut buckets: Vec<_> = (0..=last)
+            .map(|index| Bucket { start: min + index * width, end: if index == last { max } else { min + (index + 1) * width - 1 }, count: 0 })
+            .collect();
+        for value in values {
+            buckets[((value - min) / width).min(last) as usize].count += 1;
+        }
+        Histogram { buckets }
+    }
+
+    pub fn buckets(&self) -> &[Bucket] {
+        &self.buckets
+    }
+
+    /// A line per bucket with its range, a bar that is `width` characters for the fullest bucket, and the count.
+    pub fn render(&self, width: usize) -> String {
+        let ranges: Vec<_> = self.buckets.iter().map(|b| format!("{}..={}", b.start, b.end)).collect();
+        let range_width = ranges.iter().map(|r| r.len()).max().unwrap_or(0);
+        let most = self.buckets.iter().map(|b| b.count).max().unwrap_or(0).max(1);
+        self.buckets.iter().zip(ranges)
+            .map(|(bucket, range)| {
+                let bar = "#".repeat((bucket.count * width).div_ceil(most));
+                format!("{:>range_width$} | {} {}\n", range, bar, bucket.count)
+            })
+            .collect()
+    }
+}
+
+/// Writes codes of a varying number of bits, least significant bit first, as GIF expects.
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: usize,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u32, width: usize) {
+        self.buffer |= code << self.bits;
+        self.bits += width;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+/// The variable-width LZW compression of GIF image data. Codes grow up to 12 bits; when the table is full, a clear
+/// code starts over with a fresh table.
+fn lzw_encode(pixels: &[u8], min_code_size: usize) -> Vec<u8> {
+    let clear = 1u32 << min_code_size;
+    let end = clear + 1;
+
+    let mut writer = BitWriter { bytes: vec![], buffer: 0, bits: 0 };
+    let mut table: HashMap<(u32, u8), u32> = HashMap::new();
+    let mut width = min_code_size + 1;
+    // The code the last table entry got, the next entry gets the one after.
+    let mut last = end;
+
+    writer.write(clear, width);
+    let Some((&first, rest)) = pixels.split_first() else {
+        writer.write(end, width);
+        return writer.finish();
+    };
+
+    let mut code = first as u32;
+    for &pixel in rest {
+        if let Some(&next) = table.get(&(code, pixel)) {
+            code = next;
+            continue;
+        }
+        writer.write(code, width);
+        if next_code(&mut last, &mut width, min_code_size) {
+            writer.write(clear, 12);
+            table.clear();
+        } else {
+            table.insert((code, pixel), last);
+        }
+        code = pixel as u32;
+    }
+    writer.write(code, width);
+    // The decoder adds a table entry after this code as well, which can widen the end code (or need a clear).
+    if next_code(&mut last, &mut width, min_code_size) {
+        writer.write(clear, 12);
+    }
+    writer.write(end, width);
+    writer.finish()
+}
+
+/// Moves on to the next code for a table entry, widening the codes when it no longer fits. Returns true when the
+/// table is full instead, after resetting to a fresh table: the caller should write a clear code (of 12 bits).
+fn next_code(last: &mut u32, width: &mut usize, min_code_size: usize) -> bool {
+    const MAX_CODE: u32 = 4095;
+    *last += 1;
+    if *last == 1 << *width {
+        *width += 1;
+    }
+    if *last == MAX_CODE {
+        *last = (1 << min_code_size) + 1;
+        *width = min_code_size + 1;
+        return true;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::geometry::{Bounds, Grid};
+    use crate::util::snaptest::SeededRng;
+    use crate::util::vis::{Animation, Bucket, Frame, Histogram, lzw_encode};
+
+    /// A plain GIF LZW decoder, to check the encoder against.
+    fn lzw_decode(data: &[u8], min_code_size: usize) -> Vec<u8> {
+        let clear = 1usize << min_code_size;
+        let literals = || (0..clear + 2).map(|c| vec![c as u8]).collect::<Vec<_>>();
+        let (mut table, mut width, mut previous): (Vec<Vec<u8>>, usize, Option<Vec<u8>>) = (literals(), min_code_size + 1, None);
+        let (mut position, mut output) = (0, vec![]);
+        loop {
+            let code = (0..width).map(|bit| ((data[(position + bit) / 8] >> ((position + bit) % 8)) & 1) as usize)
+                .enumerate().map(|(index, bit)| bit << index).sum::<usize>();
+            position += width;
+            if code == clear {
+                (table, width, previous) = (literals(), min_code_size + 1, None);
+                continue;
+            }
+            if code == clear + 1 {
+                return output;
+            }
+            let entry = match (table.get(code), &previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(previous)) => [previous.clone(), vec![previous[0]]].concat(),
+                (None, None) => panic!("Unknown code {} without a previous code", code),
+            };
+            output.extend(&entry);
+            if let Some(previous) = previous {
+                table.push([previous, vec![entry[0]]].concat());
+                if table.len() == 1 << width && width < 12 {
+                    width += 1;
+                }
+            }
+            previous = Some(entry);
+        }
+    }
+
+    #[test]
+    fn test_lzw_roundtrip() {
+        let mut rng = SeededRng::new(2022);
+        let mut noise = |colors: u64| -> u8 { rng.below(colors) as u8 };
+
+        for (length, colors, min_code_size) in [(0, 2, 2), (1, 2, 2), (100, 4, 2), (50_000, 4, 2), (50_000, 256, 8), (20_000, 16, 4)] {
+            let pixels: Vec<u8> = (0..length).map(|_| noise(colors)).collect();
+            assert_eq!(pixels, lzw_decode(&lzw_encode(&pixels, min_code_size), min_code_size), "{} pixels of {} colors", length, colors);
+        }
+
+        // Runs of a single color compress well, and fill up the table with long entries.
+        let pixels = vec![3; 100_000];
+        let encoded = lzw_encode(&pixels, 2);
+        assert!(encoded.len() < 1000);
+        assert_eq!(pixels, lzw_decode(&encoded, 2));
+    }
+
+    #[test]
+    fn test_encode_gif() {
+        let mut grid: Grid<bool> = Grid::empty();
+        grid.set((1, 1).into(), true);
+        grid.set((2, 2).into(), false);
+        let frame = Frame::from_grid(&grid, Bounds::from_size(3, 3), |lit| if *lit { 2 } else { 1 });
+        assert_eq!((0, 2, 1), (frame.get(0, 0), frame.get(1, 1), frame.get(2, 2)));
+
+        let mut animation = Animation::new(&[[0, 0, 0], [128, 128, 128], [255, 255, 255]]);
+        animation.scale = 2;
+        animation.push(frame.clone());
+        animation.push(Frame::new(3, 3));
+        let gif = animation.encode_gif().unwrap();
+
+        assert_eq!(b"GIF89a", &gif[0..6]);
+        // 6x6 pixels, with a global color table of 4 colors.
+        assert_eq!([6, 0, 6, 0, 0x91], gif[6..11]);
+        assert_eq!([0, 0, 0, 128, 128, 128, 255, 255, 255, 0, 0, 0], gif[13..25]);
+        assert_eq!(Some(&0x3b), gif.last());
+        assert_eq!(2, gif.windows(2).filter(|w| *w == [0x21, 0xf9]).count());
+
+        // The image data of the first frame decodes to the scaled up pixels.
+        let start = gif.iter().position(|b| *b == 0x2c).unwrap() + 10;
+        assert_eq!(2, gif[start]);
+        let data = &gif[start + 2..start + 2 + gif[start + 1] as usize];
+        let pixels = lzw_decode(data, 2);
+        assert_eq!(vec![0, 0, 2, 2, 0, 0], pixels[12..18]);
+        assert_eq!(36, pixels.len());
+    }
+
+    #[test]
+    fn test_invalid_animation() {
+        let mut animation = Animation::new(&[[0, 0, 0]]);
+        assert_eq!(Err("An animation needs at least one frame".to_string()), animation.encode_gif());
+        animation.push(Frame::new(2, 2));
+        animation.push(Frame::new(3, 2));
+        assert_eq!(Err("All frames should be 2x2, but got one of 3x2".to_string()), animation.encode_gif());
+
+        let mut animation = Animation::new(&[[0, 0, 0]]);
+        let mut frame = Frame::new(2, 2);
+        frame.set(1, 1, 1);
+        frame.set(5, 5, 1);
+        animation.push(frame);
+        assert_eq!(Err("Color 1 is not in the palette of 1 colors".to_string()), animation.encode_gif());
+    }
+
+    #[test]
+    fn test_thumbnail() {
+        let mut frame = Frame::new(5, 3);
+        frame.set(0, 0, 1);
+        frame.set(1, 0, 2);
+        frame.set(1, 1, 2);
+        frame.set(4, 2, 1);
+        // The most common color that is not the background wins, also at the partial blocks along the edges.
+        let small = frame.downscale(2);
+        assert_eq!((3, 2), (small.width, small.height));
+        assert_eq!(vec![2, 0, 0, 0, 0, 1], (0..2).flat_map(|y| (0..3).map(move |x| (x, y))).map(|(x, y)| small.get(x, y)).collect::<Vec<_>>());
+        assert_eq!(frame, frame.downscale(1));
+
+        let mut animation = Animation::new(&[[0, 0, 0], [128, 128, 128], [255, 255, 255]]);
+        assert_eq!(None, animation.thumbnail(2, 2));
+        animation.scale = 4;
+        animation.push(Frame::new(5, 3));
+        animation.push(frame.clone());
+        let thumbnail = animation.thumbnail(3, 3).unwrap();
+        assert_eq!(1, thumbnail.frames().len());
+        assert_eq!(small, thumbnail.frames()[0]);
+        assert_eq!(1, thumbnail.scale);
+        assert_eq!(frame, animation.thumbnail(10, 10).unwrap().frames()[0]);
+
+        assert_eq!("++@@      \n  @@      \n        ++\n", animation.render_text());
+        assert_eq!("", Animation::new(&[[0, 0, 0]]).render_text());
+    }
+
+    #[test]
+    fn test_histogram() {
+        let histogram = Histogram::create(&[6000, 4000, 11000, 24000, 10000], 4);
+        assert_eq!(&[
+            Bucket { start: 4000, end: 8999, count: 2 },
+            Bucket { start: 9000, end: 13999, count: 2 },
+            Bucket { start: 14000, end: 18999, count: 0 },
+            Bucket { start: 19000, end: 24000, count: 1 },
+        ], histogram.buckets());
+        assert_eq!(vec![
+            "  4000..=8999 | #### 2",
+            " 9000..=13999 | #### 2",
+            "14000..=18999 |  0",
+            "19000..=24000 | ## 1",
+        ], histogram.render(4).lines().collect::<Vec<_>>());
+
+        // Every value in its own bucket when there are enough of them.
+        assert_eq!(vec![1, 1, 0, 2], Histogram::create(&[1, 2, 4, 4], 10).buckets().iter().map(|b| b.count).collect::<Vec<_>>());
+        assert_eq!("", Histogram::create(&[], 4).render(10));
+
+        // The last bucket ends at the largest value, and no bucket is left empty at the end of a small range.
+        assert_eq!(&[
+            Bucket { start: 0, end: 2, count: 1 },
+            Bucket { start: 3, end: 5, count: 2 },
+            Bucket { start: 6, end: 9, count: 1 },
+        ], Histogram::create(&[0, 3, 5, 9], 3).buckets());
+        assert_eq!(vec![(0, 1), (2, 3), (4, 5), (6, 6)], Histogram::create(&[0, 1, 2, 3, 4, 5, 6], 4).buckets().iter().map(|b| (b.start, b.end)).collect::<Vec<_>>());
+        assert_eq!(&[Bucket { start: 7, end: 7, count: 2 }], Histogram::create(&[7, 7], 4).buckets());
+    }
+}
@@ -0,0 +1,230 @@
+// Allow dead_code since this is a util file copied across years. Later in the AoC we might use everything, or not.
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use crate::util::error::AocError;
+use crate::util::number::parse_isize;
+
+/// The flavours of assembly the VM can read.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Dialect {
+    /// Only `noop` and `addx V`, exactly as used by day 10.
+    Day10,
+    /// The day 10 instructions, plus labels (`name:`), `cpy <value|register> <register>` and
+    /// `jnz <value|register> <label>`.
+    Extended,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Operand {
+    Register(char),
+    Value(isize),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Instruction {
+    Noop,
+    Add(isize),
+    Copy(Operand, char),
+    // Jumps to the instruction with the given index when the operand is not 0.
+    JumpNotZero(Operand, usize),
+}
+
+impl Instruction {
+    fn cycles(&self) -> usize {
+        match self {
+            Instruction::Add(_) => 2,
+            _ => 1
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Program {
+    pub dialect: Dialect,
+    pub instructions: Vec<Instruction>,
+}
+
+fn parse_register(name: &str) -> Result<char, AocError> {
+    match name.chars().collect::<Vec<_>>()[..] {
+        [c] if c.is_ascii_lowercase() => Ok(c),
+        _ => Err(AocError::parse(format!("Invalid register '{}', expected a single letter a-z", name)))
+    }
+}
+
+fn parse_operand(operand: &str) -> Result<Operand, AocError> {
+    parse_isize(operand).map(Operand::Value).or_else(|_| parse_register(operand).map(Operand::Register))
+}
+
+fn is_label(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+pub fn parse_program(input: &str, dialect: Dialect) -> Result<Program, AocError> {
+    // Jumps can go forward, so their labels are resolved once all lines are read.
+    let mut labels: HashMap<&str, usize> = HashMap::new();
+    let mut instructions = vec![];
+    let mut jumps: Vec<(usize, usize, &str)> = vec![];
+
+    for (index, line) in input.lines().enumerate() {
+        let error = |message: &str| AocError::parse(format!("Invalid instruction '{}' ({})", line, message)).in_line(index + 1);
+        let parts: Vec<_> = line.split(' ').collect();
+
+        match (dialect, &parts[..]) {
+            (_, ["noop"]) => instructions.push(Instruction::Noop),
+            (_, ["addx", value]) => instructions.push(Instruction::Add(parse_isize(value).map_err(|e| error(&e.to_string()))?)),
+            (Dialect::Extended, [label]) if label.ends_with(':') && is_label(&label[..label.len() - 1]) => {
+                if labels.insert(&label[..label.len() - 1], instructions.len()).is_some() {
+                    return Err(error("duplicate label"));
+                }
+            }
+            (Dialect::Extended, ["cpy", from, to]) => {
+                instructions.push(Instruction::Copy(parse_operand(from).map_err(|e| error(&e.to_string()))?, parse_register(to).map_err(|e| error(&e.to_string()))?));
+            }
+            (Dialect::Extended, ["jnz", operand, label]) if is_label(label) => {
+                jumps.push((instructions.len(), index, label));
+                instructions.push(Instruction::JumpNotZero(parse_operand(operand).map_err(|e| error(&e.to_string()))?, usize::MAX));
+            }
+            (Dialect::Day10, _) => return Err(error("the day 10 dialect only supports 'noop' and 'addx V'")),
+            (Dialect::Extended, _) => return Err(error("unknown instruction")),
+        }
+    }
+
+    for (position, index, label) in jumps {
+        let target = *labels.get(label).ok_or_else(|| AocError::parse(format!("Unknown label '{}'", label)).in_line(index + 1))?;
+        if let Instruction::JumpNotZero(_, to) = &mut instructions[position] {
+            *to = target;
+        }
+    }
+
+    Ok(Program { dialect, instructions })
+}
+
+/// The state of the machine during a cycle.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MachineState {
+    registers: [isize; 26],
+    pub cycle_count: usize,
+    // Index of the instruction being executed (or next to execute, once the machine stopped).
+    pub instruction_pointer: usize,
+}
+
+impl MachineState {
+    fn new() -> Self {
+        // The X register starts with the value 1, everything else is 0.
+        let mut registers = [0; 26];
+        registers[(b'x' - b'a') as usize] = 1;
+        MachineState { registers, cycle_count: 0, instruction_pointer: 0 }
+    }
+
+    pub fn register(&self, name: char) -> isize {
+        self.registers[(name as u8 - b'a') as usize]
+    }
+
+    fn set_register(&mut self, name: char, value: isize) {
+        self.registers[(name as u8 - b'a') as usize] = value;
+    }
+
+    fn value(&self, operand: &Operand) -> isize {
+        match operand {
+            Operand::Register(name) => self.register(*name),
+            Operand::Value(value) => *value,
+        }
+    }
+}
+
+/// Runs the program until the instruction pointer leaves the program, calling the callback during every
+/// cycle with the state before the instruction takes effect. Note that programs with jumps might never stop.
+pub fn execute<C>(program: &Program, mut cycle_callback: C) -> MachineState
+    where C: FnMut(&MachineState)
+{
+    let mut state = MachineState::new();
+
+    while let Some(instruction) = program.instructions.get(state.instruction_pointer) {
+        for _ in 0..instruction.cycles() {
+            state.cycle_count += 1;
+            cycle_callback(&state);
+        }
+
+        state.instruction_pointer += 1;
+        match instruction {
+            Instruction::Noop => {}
+            Instruction::Add(value) => state.set_register('x', state.register('x') + value),
+            Instruction::Copy(from, to) => state.set_register(*to, state.value(from)),
+            Instruction::JumpNotZero(operand, target) => {
+                if state.value(operand) != 0 {
+                    state.instruction_pointer = *target;
+                }
+            }
+        }
+    }
+
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::error::AocError;
+    use crate::util::vm::{Dialect, execute, Instruction, Operand, parse_program};
+
+    const LABELLED_PROGRAM: &str = "\
+        cpy 3 a\n\
+        loop:\n\
+        addx 2\n\
+        cpy -1 b\n\
+        jnz a dec\n\
+        noop\n\
+        dec:\n\
+        cpy 0 a\n\
+        jnz a loop\n\
+    ";
+
+    #[test]
+    fn test_parse_day10() {
+        assert_eq!(Ok(vec![Instruction::Noop, Instruction::Add(3), Instruction::Add(-5)]),
+                   parse_program("noop\naddx 3\naddx -5\n", Dialect::Day10).map(|p| p.instructions));
+
+        assert!(parse_program(LABELLED_PROGRAM, Dialect::Day10).is_err());
+        assert!(parse_program("addx  3", Dialect::Day10).is_err());
+        assert!(parse_program("noop\naddx", Dialect::Day10).is_err());
+        assert_eq!(Err("Line 1: Invalid instruction 'loop:' (the day 10 dialect only supports 'noop' and 'addx V')".to_string()),
+                   parse_program("loop:", Dialect::Day10).map_err(|e| e.to_string()));
+    }
+
+    #[test]
+    fn test_parse_extended() {
+        let program = parse_program(LABELLED_PROGRAM, Dialect::Extended).unwrap();
+        assert_eq!(vec![
+            Instruction::Copy(Operand::Value(3), 'a'),
+            Instruction::Add(2),
+            Instruction::Copy(Operand::Value(-1), 'b'),
+            Instruction::JumpNotZero(Operand::Register('a'), 5),
+            Instruction::Noop,
+            Instruction::Copy(Operand::Value(0), 'a'),
+            Instruction::JumpNotZero(Operand::Register('a'), 1),
+        ], program.instructions);
+
+        assert_eq!(Err(AocError::parse("Unknown label 'nowhere'").in_line(1)), parse_program("jnz 1 nowhere", Dialect::Extended));
+        assert!(parse_program("a:\na:", Dialect::Extended).is_err());
+        assert!(parse_program("cpy 1 X", Dialect::Extended).is_err());
+        assert!(parse_program("jmp a", Dialect::Extended).is_err());
+    }
+
+    #[test]
+    fn test_execute() {
+        let program = parse_program("noop\naddx 3\naddx -5\n", Dialect::Day10).unwrap();
+        let mut xs = vec![];
+        let state = execute(&program, |s| xs.push((s.cycle_count, s.register('x'))));
+        assert_eq!(vec![(1, 1), (2, 1), (3, 1), (4, 4), (5, 4)], xs);
+        assert_eq!(-1, state.register('x'));
+        assert_eq!(5, state.cycle_count);
+
+        let program = parse_program("cpy 3 a\nloop:\naddx 2\ncpy a b\njnz 0 loop\ncpy 0 a\njnz b loop", Dialect::Extended).unwrap();
+        let state = execute(&program, |_| {});
+        // The loop runs twice: once falling through, once after jumping back as b was still 3.
+        assert_eq!(5, state.register('x'));
+        assert_eq!(0, state.register('a'));
+        assert_eq!(0, state.register('b'));
+        assert_eq!(6, state.instruction_pointer);
+    }
+}
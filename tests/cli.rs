@@ -0,0 +1,305 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use serde_json::Value;
+
+/// A fresh input directory for a single test, with the example input of day 1 as puzzle input.
+fn input_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("aoc-2022-cli-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::copy("examples/day01/input.txt", dir.join("day01.txt")).unwrap();
+    dir
+}
+
+fn run(dir: &PathBuf, args: &[&str]) -> (bool, String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_advent-of-code-2022"))
+        .args(args)
+        .current_dir(dir)
+        .env("AOC_INPUT_DIR", dir)
+        .env_remove("AOC_VERBOSE")
+        .output()
+        .unwrap();
+    (output.status.success(), String::from_utf8(output.stdout).unwrap(), String::from_utf8(output.stderr).unwrap())
+}
+
+#[test]
+fn test_day_with_json_output() {
+    let dir = input_dir("json");
+    let (success, stdout, stderr) = run(&dir, &["day", "1", "--format", "json"]);
+    assert!(success, "Running day 1 failed: {}", stderr);
+
+    // The puzzles print their answers, followed by the summary of the runs.
+    let lines: Vec<_> = stdout.lines().collect();
+    let expected: Vec<_> = ["part1", "part2"].iter()
+        .map(|part| fs::read_to_string(format!("examples/day01/{}.expected", part)).unwrap().trim().to_string())
+        .collect();
+    for (index, answer) in expected.iter().enumerate() {
+        let line = format!("Puzzle {}: {}", index + 1, answer);
+        assert!(lines.contains(&line.as_str()), "Expected '{}' in output:\n{}", line, stdout);
+    }
+
+    let summary: Value = serde_json::from_str(lines.last().unwrap()).unwrap();
+    assert_eq!(Some(1), summary["day"].as_i64());
+    assert!(summary["build"]["source_hash"].as_str().is_some_and(|hash| hash.len() == 16));
+    let parts = summary["parts"].as_array().unwrap();
+    assert_eq!(2, parts.len());
+    for (index, part) in parts.iter().enumerate() {
+        assert_eq!(Some(index as u64 + 1), part["part"].as_u64());
+        assert_eq!(Some(true), part["success"].as_bool());
+        assert!(part["runtime_ms"].as_f64().is_some_and(|ms| ms >= 0.0));
+        assert_eq!(Some(expected[index].as_str()), part["answer"].as_str());
+    }
+
+    // Runs are recorded next to the inputs, and show up in the stats.
+    let (success, stdout, _) = run(&dir, &["stats", "--json"]);
+    assert!(success);
+    let stats: Value = serde_json::from_str(&stdout).unwrap();
+    assert!(stats["missing"].as_array().unwrap().iter().all(|m| m["day"].as_i64() != Some(1)), "Day 1 not recorded: {}", stdout);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_day_with_text_output() {
+    let dir = input_dir("text");
+    let (success, stdout, _) = run(&dir, &["day", "1"]);
+    assert!(success);
+    assert!(stdout.lines().last().is_some_and(|l| serde_json::from_str::<Value>(l).is_err()));
+
+    // Without input, the day reports an error instead of running.
+    let (_, stdout, stderr) = run(&dir, &["day", "2"]);
+    assert!(stdout.is_empty() && !stderr.is_empty());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_slow_day_in_debug_build() {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+
+    let dir = input_dir("debug");
+    fs::write(dir.join("day19.txt"), "").unwrap();
+    let (_, stdout, stderr) = run(&dir, &["day", "19"]);
+    assert!(stdout.is_empty(), "Expected day 19 not to run, but got: {}", stdout);
+    assert!(stderr.contains("--allow-debug"), "Expected a warning, but got: {}", stderr);
+    assert!(!dir.join("results.csv").exists());
+
+    // Invalid options print the usage instead.
+    let (_, stdout, stderr) = run(&dir, &["day", "1", "--allow-debugging"]);
+    assert!(stdout.is_empty() && stderr.contains("Usage"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_lenient_parsing() {
+    let dir = input_dir("lenient");
+    let input = fs::read_to_string(dir.join("day01.txt")).unwrap().replace("8000", "8k").replace("4000", "four");
+    fs::write(dir.join("day01.txt"), input).unwrap();
+
+    // By default, the first line that cannot be parsed fails the puzzles.
+    let (_, stdout, stderr) = run(&dir, &["day", "1"]);
+    assert!(!stdout.contains("Puzzle 1:"), "Expected day 1 to fail, but got: {}", stdout);
+    assert!(stderr.contains("Line 5:"), "Expected the first invalid line, but got: {}", stderr);
+
+    // With --lenient, those lines are skipped and reported.
+    let (success, stdout, stderr) = run(&dir, &["day", "1", "--lenient"]);
+    assert!(success);
+    assert!(stdout.contains("Puzzle 1: 16000"), "{}", stdout);
+    assert!(stderr.contains("Skipping: Line 5:") && stderr.contains("Skipping: Line 11:"), "{}", stderr);
+    assert!(stderr.contains("Skipped 2 of 14 lines"), "{}", stderr);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_visualize() {
+    let dir = input_dir("visualize");
+    fs::copy("examples/day14/input.txt", dir.join("day14.txt")).unwrap();
+    let output = dir.join("sand.gif");
+
+    let (success, stdout, _) = run(&dir, &["day", "14", "--visualize", "--output", output.to_str().unwrap()]);
+    assert!(success);
+    assert!(stdout.contains("Saved an animation"), "{}", stdout);
+    assert!(fs::read(&output).unwrap().starts_with(b"GIF89a"));
+
+    let (_, _, stderr) = run(&dir, &["day", "1", "--visualize", "--output", output.to_str().unwrap()]);
+    assert!(stderr.contains("Day 1 has no visualization"), "{}", stderr);
+
+    // An output file without --visualize is not valid.
+    let (_, stdout, stderr) = run(&dir, &["day", "14", "--output", output.to_str().unwrap()]);
+    assert!(stdout.is_empty() && stderr.contains("Usage"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_clean() {
+    let dir = input_dir("clean");
+    fs::create_dir_all(dir.join("cache")).unwrap();
+    fs::write(dir.join("cache").join("day16-flow-map.txt"), "key\nvalue").unwrap();
+    fs::write(dir.join("results.csv"), "").unwrap();
+    fs::write(dir.join("day14.gif"), "GIF89a").unwrap();
+    fs::write(dir.join("sand.gif"), "GIF89a").unwrap();
+
+    let (success, stdout, _) = run(&dir, &["clean", "--all", "--dry-run"]);
+    assert!(success);
+    assert_eq!(3, stdout.lines().filter(|l| l.starts_with("Would remove")).count(), "{}", stdout);
+    assert!(dir.join("cache").exists() && dir.join("results.csv").exists() && dir.join("day14.gif").exists());
+
+    let (_, stdout, _) = run(&dir, &["clean", "--vis"]);
+    assert_eq!("Removed day14.gif", stdout.trim());
+    let (_, stdout, _) = run(&dir, &["clean", "--inputs", "--results"]);
+    assert_eq!(2, stdout.lines().filter(|l| l.starts_with("Removed")).count(), "{}", stdout);
+    let (_, stdout, _) = run(&dir, &["clean", "--all"]);
+    assert_eq!("Nothing to clean", stdout.trim());
+
+    // Inputs and visualizations with an explicit name are kept.
+    assert!(dir.join("day01.txt").exists() && dir.join("sand.gif").exists());
+    assert!(!dir.join("cache").exists() && !dir.join("results.csv").exists());
+
+    // Something has to be selected.
+    let (_, stdout, stderr) = run(&dir, &["clean", "--dry-run"]);
+    assert!(stdout.is_empty() && stderr.contains("Usage"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_all_days() {
+    let dir = input_dir("all");
+    let (success, stdout, stderr) = run(&dir, &["--all"]);
+    assert!(success, "Running all days failed: {}", stderr);
+
+    // Every day with an input runs, and ends up in the table with its answers.
+    assert!(stdout.contains("== Day 1 ==\nPuzzle 1: 24000\nPuzzle 2: 45000"), "{}", stdout);
+    let row = stdout.lines().find(|l| l.trim_start().starts_with("1 |")).unwrap();
+    let cells: Vec<_> = row.split('|').map(|c| c.trim()).collect();
+    assert_eq!(["1", "24000", "45000"], cells[..3]);
+    assert!(cells[3].parse::<f64>().is_ok(), "{}", row);
+
+    // The others are listed as well, without running.
+    assert!(!stdout.contains("== Day 2 =="));
+    assert!(stdout.lines().any(|l| l.trim_start().starts_with("2 |") && l.ends_with("input missing")), "{}", stdout);
+    assert!(stdout.lines().last().is_some_and(|l| l.starts_with("Total: ")));
+
+    // A failing puzzle fails the run.
+    fs::write(dir.join("day02.txt"), "A Q\n").unwrap();
+    let (success, stdout, _) = run(&dir, &["--all"]);
+    assert!(!success);
+    assert!(stdout.lines().any(|l| l.trim_start().starts_with("2 |") && l.contains("FAILED")), "{}", stdout);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_dry_run() {
+    let dir = input_dir("dry-run");
+    let (success, stdout, stderr) = run(&dir, &["day", "1", "--dry-run"]);
+    assert!(success, "Parsing day 1 failed: {}", stderr);
+    assert!(stdout.starts_with("Day 1: parsed in ") && stdout.trim_end().ends_with("ms, 5 elves, 10 food items"), "{}", stdout);
+    assert!(!stdout.contains("Puzzle"));
+    assert!(!dir.join("results.csv").exists());
+
+    fs::write(dir.join("day02.txt"), "A Q\n").unwrap();
+    let (success, stdout, _) = run(&dir, &["--all", "--dry-run"]);
+    assert!(!success);
+    let lines: Vec<_> = stdout.lines().collect();
+    assert!(lines[0].starts_with("Day 1: parsed in "), "{}", stdout);
+    assert!(lines[1].starts_with("Day 2: failed to parse after "), "{}", stdout);
+    assert_eq!("Day 3: input missing", lines[2]);
+
+    let (_, stdout, stderr) = run(&dir, &["day", "1", "--dry-run", "--format", "json"]);
+    assert!(stdout.is_empty() && stderr.contains("Usage"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_bench() {
+    let dir = input_dir("bench");
+    let (success, stdout, stderr) = run(&dir, &["bench", "1", "--runs", "3"]);
+    assert!(success, "Benchmarking day 1 failed: {}", stderr);
+
+    let rows: Vec<Vec<_>> = stdout.lines().skip(1).map(|l| l.split('|').map(|c| c.trim()).collect()).collect();
+    assert_eq!(2, rows.len(), "{}", stdout);
+    for (index, answer) in ["24000", "45000"].iter().enumerate() {
+        assert_eq!((index + 1).to_string(), rows[index][1]);
+        assert_eq!("3", rows[index][2]);
+        assert_eq!("-", rows[index][6], "Nothing was recorded yet");
+        assert_eq!(*answer, rows[index][7]);
+    }
+    assert!(!dir.join("results.csv").exists());
+
+    // The latest recorded run is shown next to the benchmark.
+    run(&dir, &["day", "1"]);
+    let (_, stdout, _) = run(&dir, &["bench", "--all", "--runs", "1"]);
+    assert!(stdout.lines().nth(1).is_some_and(|l| l.split('|').nth(6).is_some_and(|c| c.trim().parse::<f64>().is_ok())), "{}", stdout);
+
+    let (_, stdout, stderr) = run(&dir, &["bench", "1", "--runs", "0"]);
+    assert!(stdout.is_empty() && stderr.contains("Usage"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_poster() {
+    let dir = input_dir("poster");
+    fs::copy("examples/day14/input.txt", dir.join("day14.txt")).unwrap();
+    let (success, stdout, stderr) = run(&dir, &["poster", "--format", "text"]);
+    assert!(success, "Making the poster failed: {}", stderr);
+    assert!(stdout.trim_end().ends_with("Saved the poster to poster.txt"), "{}", stdout);
+
+    // Every day is on it, with the answers of the days that ran, and the visualization of day 14.
+    let poster = fs::read_to_string(dir.join("poster.txt")).unwrap();
+    assert!(poster.contains("Day 1: Calorie Counting\n  Puzzle 1: 24000\n  Puzzle 2: 45000\n  Time: "), "{}", poster);
+    assert!(poster.contains("Day 2: Rock Paper Scissors\n  Time: input missing\n"), "{}", poster);
+    let day14 = poster.split("Day 14: ").nth(1).unwrap().split("\nDay 15: ").next().unwrap();
+    assert!(day14.contains("Puzzle 1: 24\n") && day14.lines().count() > 5, "{}", day14);
+    assert!(poster.contains("Recorded runs\n-------------\nTotal runtime: "));
+    assert!(!dir.join("day14.gif").exists());
+
+    let output = dir.join("year.html");
+    let (success, _, _) = run(&dir, &["poster", "--output", output.to_str().unwrap()]);
+    assert!(success);
+    let poster = fs::read_to_string(&output).unwrap();
+    assert!(poster.starts_with("<!DOCTYPE html>") && poster.contains("data:image/gif;base64,"), "{}", poster);
+
+    let (_, stdout, _) = run(&dir, &["clean", "--vis"]);
+    assert_eq!("Removed poster.txt", stdout.trim());
+    let (_, stdout, stderr) = run(&dir, &["poster", "--format", "gif"]);
+    assert!(stdout.is_empty() && stderr.contains("Usage"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_selftest() {
+    let dir = input_dir("selftest");
+    fs::copy("examples/day17/input.txt", dir.join("day17.txt")).unwrap();
+    let (success, stdout, stderr) = run(&dir, &["selftest"]);
+    assert!(success, "The self-checks failed: {}", stderr);
+    assert!(stdout.contains("Day 17: OK, Extrapolated and dropped heights agree after 100000 drops: "), "{}", stdout);
+
+    // Day 1 has no self-check.
+    let (success, stdout, _) = run(&dir, &["selftest", "1"]);
+    assert!(!success);
+    assert_eq!("Day 1: FAILED, Day 1 has no self-check", stdout.trim());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_inspect() {
+    let dir = input_dir("inspect");
+    fs::copy("examples/day04/input.txt", dir.join("day04.txt")).unwrap();
+    let (success, stdout, stderr) = run(&dir, &["inspect", "4"]);
+    assert!(success, "Inspecting day 4 failed: {}", stderr);
+    // The dashes of the section ranges are not minus signs.
+    assert!(stdout.contains("Integers: 24, ranging from 2 to 9"), "{}", stdout);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
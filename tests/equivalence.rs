@@ -0,0 +1,77 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use serde_json::Value;
+
+// Runs every algorithm of the days that have several (see `Day::algorithms`), and checks they all give the same
+// answers. The days and their algorithms are read from `--list --json`, so new algorithms are covered as soon as
+// they are registered.
+
+fn binary(dir: &Path, args: &[&str], algorithm: Option<(u64, &str)>) -> String {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_advent-of-code-2022"));
+    command.args(args).current_dir(dir).env("AOC_INPUT_DIR", dir).env_remove("AOC_VERBOSE").env_remove("AOC_CACHE");
+    if let Some((day, algorithm)) = algorithm {
+        command.env(format!("AOC_DAY{:02}_ALGORITHM", day), algorithm);
+    }
+    let output = command.output().unwrap();
+    assert!(output.status.success(), "Running {:?} with {:?} failed: {}", args, algorithm, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// A fresh input directory for a single test.
+fn input_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("aoc-2022-equivalence-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// The days with more than one algorithm, and the names of those algorithms.
+fn days_with_algorithms() -> Vec<(u64, Vec<String>)> {
+    let list: Value = serde_json::from_str(&binary(&input_dir("list"), &["--list", "--json"], None)).unwrap();
+    list.as_array().unwrap().iter()
+        .map(|day| (day["day"].as_u64().unwrap(), day["algorithms"].as_array().unwrap().iter().map(|a| a.as_str().unwrap().to_string()).collect::<Vec<_>>()))
+        .filter(|(_, algorithms)| algorithms.len() > 1)
+        .collect()
+}
+
+/// The answers of both puzzles of the day for the given input file, None for puzzles that failed.
+fn answers(day: u64, input: &Path, algorithm: &str) -> Vec<Option<String>> {
+    let dir = input_dir(&format!("day{:02}-{}", day, algorithm));
+    fs::copy(input, dir.join(format!("day{:02}.txt", day))).unwrap();
+    let output = binary(&dir, &["day", &day.to_string(), "--format", "json", "--allow-debug"], Some((day, algorithm)));
+    fs::remove_dir_all(&dir).unwrap();
+
+    let summary: Value = serde_json::from_str(output.lines().last().unwrap()).unwrap();
+    summary["parts"].as_array().unwrap().iter().map(|part| part["answer"].as_str().map(|a| a.to_string())).collect()
+}
+
+fn assert_equivalent(input_for: impl Fn(u64) -> PathBuf) {
+    let days = days_with_algorithms();
+    assert!(!days.is_empty(), "No days with several algorithms found");
+
+    for (day, algorithms) in days {
+        let input = input_for(day);
+        if !input.exists() {
+            eprintln!("Skipping day {}, {} does not exist", day, input.display());
+            continue;
+        }
+        let expected = answers(day, &input, &algorithms[0]);
+        assert!(expected.iter().any(|a| a.is_some()), "Day {} gives no answers with {}", day, algorithms[0]);
+        for algorithm in &algorithms[1..] {
+            assert_eq!(expected, answers(day, &input, algorithm), "Day {} with {} differs from {} on {}", day, algorithm, algorithms[0], input.display());
+        }
+    }
+}
+
+#[test]
+fn test_algorithms_agree_on_examples() {
+    assert_equivalent(|day| PathBuf::from(format!("examples/day{:02}/input.txt", day)));
+}
+
+#[test]
+#[ignore = "takes minutes, run with `cargo test --release --test equivalence -- --ignored`"]
+fn test_algorithms_agree_on_inputs() {
+    let input_dir = std::env::var("AOC_INPUT_DIR").unwrap_or("resources".to_string());
+    assert_equivalent(|day| Path::new(&input_dir).join(format!("day{:02}.txt", day)));
+}